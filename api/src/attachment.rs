@@ -0,0 +1,263 @@
+use std::path::PathBuf;
+
+use axum::extract::Multipart;
+use sha2::{Digest, Sha256};
+use snafu::{OptionExt, ResultExt, ensure};
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+use validator::Validate;
+
+use crate::Result;
+use crate::config::Config;
+use crate::error::{
+    CreateFileSnafu, DbSnafu, FileTypeNotAllowedSnafu, MissingUploadFileSnafu, MultipartSnafu,
+    RemoveFileSnafu, UploadFileSnafu, ValidationSnafu,
+};
+use crate::state::AppState;
+use db::attachment::NewAttachment;
+use dto::attachment::AttachmentDto;
+use dto::entry::EntryDto;
+use vault::utils::generate_id;
+use vault::validators::flatten_errors;
+
+/// Attachments are encrypted by the client, only raw bytes are accepted
+const ALLOWED_CONTENT_TYPE: &str = "application/octet-stream";
+
+pub fn attachment_path(config: &Config, id: &str) -> PathBuf {
+    config.upload_dir.join("attachments").join(id)
+}
+
+fn attachment_tmp_path(config: &Config, id: &str) -> PathBuf {
+    config.upload_dir.join("tmp").join(id)
+}
+
+struct UploadedBlob {
+    path: PathBuf,
+    size: i64,
+    sha256: String,
+}
+
+pub async fn upload_attachment(
+    state: &AppState,
+    entry: &EntryDto,
+    mut multipart: Multipart,
+) -> Result<AttachmentDto> {
+    let quotas = &state.config.quotas;
+
+    // Limit the number of attachments per entry
+    let count = state
+        .db
+        .attachments
+        .count_by_entry(&entry.id)
+        .await
+        .context(DbSnafu)?;
+
+    ensure!(
+        count < quotas.max_attachments_per_entry,
+        ValidationSnafu {
+            msg: format!(
+                "Entry already reached the maximum attachments: {}",
+                quotas.max_attachments_per_entry
+            ),
+        }
+    );
+
+    // Limit the total size of attachments per vault
+    let used = state
+        .db
+        .attachments
+        .total_size_by_vault(&entry.vault_id)
+        .await
+        .context(DbSnafu)?;
+
+    let remaining = quotas.max_attachments_size_per_vault - used;
+    ensure!(
+        remaining > 0,
+        ValidationSnafu {
+            msg: "Vault already reached the maximum attachments size".to_string(),
+        }
+    );
+
+    let max_size = remaining.min(quotas.max_attachment_size);
+    let id = generate_id();
+    let mut cipher_filename: Option<String> = None;
+    let mut blob: Option<UploadedBlob> = None;
+
+    while let Some(field) = multipart.next_field().await.context(MultipartSnafu)? {
+        let name = field.name().unwrap_or("").to_string();
+        match name.as_str() {
+            "cipher_filename" => {
+                cipher_filename = Some(field.text().await.context(MultipartSnafu)?);
+            }
+            "file" => {
+                let res = save_upload_field(&state.config, &id, max_size, field).await;
+                match res {
+                    Ok(uploaded) => {
+                        blob = Some(uploaded);
+                    }
+                    Err(e) => {
+                        let _ = fs::remove_file(attachment_tmp_path(&state.config, &id)).await;
+                        return Err(e);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let blob = blob.context(MissingUploadFileSnafu {
+        msg: "Missing upload file",
+    })?;
+
+    let res = save_attachment(state, entry, &id, cipher_filename, &blob).await;
+    if res.is_err() {
+        let _ = fs::remove_file(&blob.path).await;
+    }
+    res
+}
+
+async fn save_upload_field(
+    config: &Config,
+    id: &str,
+    max_size: i64,
+    mut field: axum::extract::multipart::Field<'_>,
+) -> Result<UploadedBlob> {
+    if let Some(content_type) = field.content_type() {
+        ensure!(
+            content_type == ALLOWED_CONTENT_TYPE,
+            FileTypeNotAllowedSnafu
+        );
+    }
+
+    let path = attachment_tmp_path(config, id);
+    let mut file = File::create(&path)
+        .await
+        .context(CreateFileSnafu { path: path.clone() })?;
+
+    let mut hasher = Sha256::new();
+    let mut size: i64 = 0;
+
+    while let Some(chunk) = field.chunk().await.context(MultipartSnafu)? {
+        size += chunk.len() as i64;
+        ensure!(
+            size <= max_size,
+            ValidationSnafu {
+                msg: format!("Attachment exceeds the allowed size: {} bytes", max_size),
+            }
+        );
+
+        hasher.update(&chunk);
+        file.write_all(&chunk).await.context(UploadFileSnafu)?;
+    }
+
+    file.flush().await.context(UploadFileSnafu)?;
+
+    ensure!(
+        size > 0,
+        MissingUploadFileSnafu {
+            msg: "Upload file is empty",
+        }
+    );
+
+    Ok(UploadedBlob {
+        path,
+        size,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+}
+
+async fn save_attachment(
+    state: &AppState,
+    entry: &EntryDto,
+    id: &str,
+    cipher_filename: Option<String>,
+    blob: &UploadedBlob,
+) -> Result<AttachmentDto> {
+    let data = NewAttachment {
+        cipher_filename: cipher_filename.unwrap_or_default(),
+    };
+    let errors = data.validate();
+    ensure!(
+        errors.is_ok(),
+        ValidationSnafu {
+            msg: flatten_errors(&errors.unwrap_err()),
+        }
+    );
+
+    // Move the blob out of the tmp directory, addressed by its id
+    let dest = attachment_path(&state.config, id);
+    fs::rename(&blob.path, &dest)
+        .await
+        .context(CreateFileSnafu { path: dest.clone() })?;
+
+    let dto = AttachmentDto {
+        id: id.to_string(),
+        entry_id: entry.id.clone(),
+        vault_id: entry.vault_id.clone(),
+        cipher_filename: data.cipher_filename,
+        size: blob.size,
+        sha256: blob.sha256.clone(),
+        created_at: chrono::Utc::now().timestamp(),
+    };
+
+    // Uploads run concurrently, check the limits again right before saving
+    let max_count = state.config.quotas.max_attachments_per_entry;
+    let max_total_size = state.config.quotas.max_attachments_size_per_vault;
+    let res = state
+        .db
+        .transaction(move |tx| {
+            let count = tx
+                .attachments()
+                .count_by_entry(&dto.entry_id)
+                .context(DbSnafu)?;
+            ensure!(
+                count < max_count,
+                ValidationSnafu {
+                    msg: format!(
+                        "Entry already reached the maximum attachments: {}",
+                        max_count
+                    ),
+                }
+            );
+
+            let used = tx
+                .attachments()
+                .total_size_by_vault(&dto.vault_id)
+                .context(DbSnafu)?;
+            ensure!(
+                used + dto.size <= max_total_size,
+                ValidationSnafu {
+                    msg: "Vault already reached the maximum attachments size".to_string(),
+                }
+            );
+
+            tx.attachments().create(dto).context(DbSnafu)
+        })
+        .await;
+    if res.is_err() {
+        let _ = fs::remove_file(&dest).await;
+    }
+    res
+}
+
+pub async fn delete_attachment(state: &AppState, attachment: &AttachmentDto) -> Result<()> {
+    state
+        .db
+        .attachments
+        .delete(&attachment.id)
+        .await
+        .context(DbSnafu)?;
+
+    remove_attachment_file(state, &attachment.id).await
+}
+
+/// Removes the stored blob of an attachment whose record is already gone
+pub async fn remove_attachment_file(state: &AppState, id: &str) -> Result<()> {
+    let path = attachment_path(&state.config, id);
+    match fs::remove_file(&path).await {
+        Ok(_) => Ok(()),
+        // Blob may have been removed already, metadata is the source of truth
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).context(RemoveFileSnafu { path }),
+    }
+}
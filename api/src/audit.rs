@@ -0,0 +1,141 @@
+use snafu::ResultExt;
+use std::net::IpAddr;
+use tracing::error;
+
+use crate::Result;
+use crate::error::{DbSnafu, Error};
+use crate::state::AppState;
+use db::audit::NewAuditEvent;
+use dto::actor::Actor;
+use dto::api_key::ip_in_range;
+use dto::audit::AuditAction;
+
+/// Request origin information used when recording audit events
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// Client address as seen through the trusted proxies
+///
+/// Each proxy appends the address it received the request from, so the hops
+/// are walked from the right for as long as the sender is a trusted proxy.
+/// Anything the client wrote further left is never reached.
+pub fn resolve_client_ip(
+    peer: Option<IpAddr>,
+    forwarded: &[&str],
+    trusted_proxies: &[String],
+) -> Option<String> {
+    let is_trusted = |ip: &IpAddr| {
+        let ip = ip.to_string();
+        trusted_proxies.iter().any(|range| ip_in_range(&ip, range))
+    };
+
+    let mut client = peer?.to_canonical();
+    for hop in forwarded.iter().rev() {
+        if !is_trusted(&client) {
+            break;
+        }
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => client = ip.to_canonical(),
+            Err(_) => break,
+        }
+    }
+
+    Some(client.to_string())
+}
+
+pub fn new_event(
+    actor: &Actor,
+    client: &ClientInfo,
+    org_id: &str,
+    action: AuditAction,
+    target_type: &str,
+    target_id: &str,
+) -> NewAuditEvent {
+    NewAuditEvent {
+        org_id: Some(org_id.to_string()),
+        actor_id: Some(actor.id.clone()),
+        action: action.to_string(),
+        target_type: Some(target_type.to_string()),
+        target_id: Some(target_id.to_string()),
+        ip: client.ip.clone(),
+        detail: None,
+    }
+}
+
+pub async fn record_event(state: &AppState, event: NewAuditEvent) -> Result<()> {
+    let _ = state.db.audit.create(&event).await.context(DbSnafu)?;
+    Ok(())
+}
+
+/// Identifies login errors caused by bad credentials
+pub fn login_failure_reason(err: &Error) -> Option<&'static str> {
+    match err {
+        Error::InvalidPassword => Some("invalid_password"),
+        Error::Password { .. } => Some("invalid_password"),
+        Error::InactiveUser => Some("inactive_user"),
+        Error::InvalidClient => Some("invalid_org"),
+        Error::LoginLocked { .. } => Some("locked"),
+        _ => None,
+    }
+}
+
+pub async fn record_login_failure(
+    state: &AppState,
+    client: &ClientInfo,
+    username: &str,
+    reason: &str,
+) {
+    // Attribute the attempt to the org when the user exists
+    let user = state.db.users.find_by_username(username).await;
+    let (org_id, user_id) = match user {
+        Ok(Some(u)) => (Some(u.org_id), Some(u.id)),
+        _ => (None, None),
+    };
+
+    let event = NewAuditEvent {
+        org_id,
+        actor_id: None,
+        action: AuditAction::LoginFailed.to_string(),
+        target_type: Some("user".to_string()),
+        target_id: user_id,
+        ip: client.ip.clone(),
+        detail: Some(format!("username={}, reason={}", username, reason)),
+    };
+
+    if let Err(e) = record_event(state, event).await {
+        error!("Unable to record failed login: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let trusted = vec!["127.0.0.1".to_string(), "10.0.0.0/8".to_string()];
+        let proxy: IpAddr = "127.0.0.1".parse().unwrap();
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+        // Direct clients cannot choose their address
+        let ip = resolve_client_ip(Some(client), &["10.1.2.3"], &trusted);
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
+
+        // Rightmost hop not added by a trusted proxy
+        let ip = resolve_client_ip(Some(proxy), &["1.2.3.4", "203.0.113.7"], &trusted);
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
+        let ip = resolve_client_ip(Some(proxy), &["203.0.113.7", "10.0.0.5"], &trusted);
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
+
+        // Garbage stops at the proxy that passed it on
+        let ip = resolve_client_ip(Some(proxy), &["203.0.113.7", "unknown"], &trusted);
+        assert_eq!(ip.as_deref(), Some("127.0.0.1"));
+
+        let ip = resolve_client_ip(Some(proxy), &["203.0.113.7"], &[]);
+        assert_eq!(ip.as_deref(), Some("127.0.0.1"));
+        assert_eq!(resolve_client_ip(None, &["203.0.113.7"], &trusted), None);
+    }
+}
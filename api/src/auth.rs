@@ -1,19 +1,36 @@
 use snafu::ResultExt;
 use validator::Validate;
 
-use crate::token::{create_auth_token, verify_auth_token};
-use dto::actor::{Actor, ActorPayload, AuthResponse, Credentials};
+use crate::token::{EXP_DURATION, REFRESH_DURATION, create_auth_token, verify_auth_token};
+use db::audit::NewAuditEvent;
+use db::session::{NewSession, SessionRefresh};
+use db::user::User;
+use dto::actor::{Actor, ActorPayload, AuthResponse, Credentials, RefreshCredentials};
+use dto::audit::AuditAction;
+use dto::org::OrgDto;
+use dto::role::Role;
+use dto::session::NewSessionDto;
+use dto::user::UserDto;
 use password::verify_password;
 use snafu::{OptionExt, ensure};
 
+use crate::audit::{ClientInfo, login_failure_reason, record_event};
 use crate::error::{
-    DbSnafu, InactiveUserSnafu, InvalidClientSnafu, InvalidPasswordSnafu, PasswordSnafu,
+    DbSnafu, ForbiddenSnafu, InactiveUserSnafu, InvalidApiKeySnafu, InvalidAuthTokenSnafu,
+    InvalidClientSnafu, InvalidPasswordSnafu, InvalidRefreshTokenSnafu, PasswordSnafu,
     UserNotFoundSnafu, ValidationSnafu,
 };
+use crate::keys::read_keys;
+use crate::lockout::{fail_login_attempt, release_login_attempt, reserve_login_attempt};
+use crate::role::resolve_custom_roles;
 use crate::{Result, state::AppState};
 use vault::validators::flatten_errors;
 
-pub async fn authenticate(state: &AppState, credentials: &Credentials) -> Result<AuthResponse> {
+pub async fn authenticate(
+    state: &AppState,
+    credentials: &Credentials,
+    client: &ClientInfo,
+) -> Result<AuthResponse> {
     let errors = credentials.validate();
     ensure!(
         errors.is_ok(),
@@ -22,6 +39,35 @@ pub async fn authenticate(state: &AppState, credentials: &Credentials) -> Result
         }
     );
 
+    let reservation = reserve_login_attempt(state, &credentials.username, client).await?;
+
+    let (org, user) = match verify_credentials(state, credentials).await {
+        Ok(verified) => verified,
+        Err(e) => {
+            if login_failure_reason(&e).is_some() {
+                fail_login_attempt(state, client, reservation).await;
+            } else {
+                release_login_attempt(state, &credentials.username, client, false).await?;
+            }
+            return Err(e);
+        }
+    };
+
+    release_login_attempt(state, &credentials.username, client, true).await?;
+
+    // Start a new session
+    let created = state
+        .db
+        .sessions
+        .create(&org.id, &user.id, &new_session(client))
+        .await
+        .context(DbSnafu)?;
+
+    auth_response(state, user, created)
+}
+
+/// Checks the username and password, failures count towards the lockout
+async fn verify_credentials(state: &AppState, credentials: &Credentials) -> Result<(OrgDto, User)> {
     // Validate user
     let user = state
         .db
@@ -32,6 +78,9 @@ pub async fn authenticate(state: &AppState, credentials: &Credentials) -> Result
 
     let user = user.context(InvalidPasswordSnafu)?;
 
+    // Service accounts only authenticate with API keys
+    ensure!(!user.is_service_account(), InvalidPasswordSnafu);
+
     ensure!(&user.status == "active", InactiveUserSnafu);
 
     // Validate org
@@ -41,22 +90,99 @@ pub async fn authenticate(state: &AppState, credentials: &Credentials) -> Result
     // Validate password
     let _ = verify_password(&credentials.password, &user.password).context(PasswordSnafu)?;
 
-    // Generate a token
+    Ok((org, user))
+}
+
+/// Exchanges a refresh token for a new access token and refresh token
+///
+/// Refresh tokens are single use. Using one again revokes the session
+/// since either the client or an attacker holds a stolen copy.
+pub async fn refresh_auth(
+    state: &AppState,
+    credentials: &RefreshCredentials,
+    client: &ClientInfo,
+) -> Result<AuthResponse> {
+    let errors = credentials.validate();
+    ensure!(
+        errors.is_ok(),
+        ValidationSnafu {
+            msg: flatten_errors(&errors.unwrap_err()),
+        }
+    );
+
+    let result = state
+        .db
+        .sessions
+        .refresh(&credentials.refresh_token, &new_session(client))
+        .await
+        .context(DbSnafu)?;
+
+    let refreshed = match result {
+        SessionRefresh::Rotated(refreshed) => refreshed,
+        SessionRefresh::Reused(session) => {
+            let event = NewAuditEvent {
+                org_id: Some(session.org_id.clone()),
+                actor_id: None,
+                action: AuditAction::RefreshReused.to_string(),
+                target_type: Some("user".to_string()),
+                target_id: Some(session.user_id.clone()),
+                ip: client.ip.clone(),
+                detail: Some(format!("session={}", session.id)),
+            };
+            record_event(state, event).await?;
+            return InvalidRefreshTokenSnafu.fail();
+        }
+        SessionRefresh::Invalid => return InvalidRefreshTokenSnafu.fail(),
+    };
+
+    let user = state
+        .db
+        .users
+        .get(&refreshed.session.user_id)
+        .await
+        .context(DbSnafu)?;
+    let user = user.context(InvalidRefreshTokenSnafu)?;
+    ensure!(&user.status == "active", InactiveUserSnafu);
+
+    auth_response(state, user, refreshed)
+}
+
+fn new_session(client: &ClientInfo) -> NewSession {
+    NewSession {
+        ip: client.ip.clone(),
+        user_agent: client.user_agent.clone(),
+        expires_at: chrono::Utc::now().timestamp() + REFRESH_DURATION,
+    }
+}
+
+fn auth_response(state: &AppState, user: User, created: NewSessionDto) -> Result<AuthResponse> {
     let actor = ActorPayload {
         id: user.id.clone(),
-        org_id: org.id.clone(),
+        org_id: created.session.org_id.clone(),
         scope: "auth vault".to_string(),
+        session_id: Some(created.session.id.clone()),
     };
 
-    let token = create_auth_token(&actor, &state.config.jwt_secret)?;
+    let now = chrono::Utc::now().timestamp();
+    let key = read_keys(&state.keys)?.signing_key(now).clone();
+    let token = create_auth_token(&actor, &key)?;
     Ok(AuthResponse {
         user: user.into(),
         token,
+        refresh_token: created.refresh_token,
+        expires_in: EXP_DURATION,
     })
 }
 
 pub async fn authenticate_token(state: &AppState, token: &str) -> Result<Actor> {
-    let actor = verify_auth_token(token, &state.config.jwt_secret)?;
+    let actor = verify_auth_token(token, &*read_keys(&state.keys)?)?;
+
+    // Tokens of revoked or expired sessions are no longer accepted
+    let session_id = actor.session_id.clone().unwrap_or_default();
+    let session = state.db.sessions.get(&session_id).await.context(DbSnafu)?;
+    let now = chrono::Utc::now().timestamp();
+    let session = session.filter(|x| x.is_active(now) && x.user_id == actor.id);
+    let _ = session.context(InvalidAuthTokenSnafu)?;
 
     // Validate org
     let org = state.db.orgs.get(&actor.org_id).await.context(DbSnafu)?;
@@ -66,5 +192,80 @@ pub async fn authenticate_token(state: &AppState, token: &str) -> Result<Actor>
     let user = user.context(UserNotFoundSnafu)?;
     ensure!(&user.org_id == &org.id, UserNotFoundSnafu);
 
-    Ok(Actor::new(actor, user.into()))
+    user_actor(state, actor, user).await
+}
+
+/// Authenticates a service account through one of its API keys
+///
+/// The actor is limited to what both the account roles and the key allow.
+pub async fn authenticate_api_key(
+    state: &AppState,
+    key: &str,
+    client: &ClientInfo,
+) -> Result<Actor> {
+    let api_key = state.db.api_keys.authenticate(key).await.context(DbSnafu)?;
+    let api_key = api_key.context(InvalidApiKeySnafu)?;
+
+    let now = chrono::Utc::now().timestamp();
+    ensure!(!api_key.is_expired(now), InvalidApiKeySnafu);
+    ensure!(
+        api_key.allows_ip(client.ip.as_deref()),
+        ForbiddenSnafu {
+            msg: "API key not allowed from this address"
+        }
+    );
+
+    // Validate org
+    let org = state.db.orgs.get(&api_key.org_id).await.context(DbSnafu)?;
+    let org = org.context(InvalidClientSnafu)?;
+
+    let user = state
+        .db
+        .users
+        .get(&api_key.user_id)
+        .await
+        .context(DbSnafu)?;
+    let user = user.context(UserNotFoundSnafu)?;
+    ensure!(user.org_id == org.id, UserNotFoundSnafu);
+    ensure!(user.is_service_account(), InvalidApiKeySnafu);
+    ensure!(&user.status == "active", InactiveUserSnafu);
+
+    let payload = ActorPayload {
+        id: user.id.clone(),
+        org_id: org.id.clone(),
+        scope: "auth vault".to_string(),
+        session_id: None,
+    };
+    let actor = user_actor(state, payload, user).await?;
+
+    state
+        .db
+        .api_keys
+        .touch(&api_key.id, client.ip.clone())
+        .await
+        .context(DbSnafu)?;
+
+    Ok(actor.with_api_key(&api_key))
+}
+
+async fn user_actor(state: &AppState, payload: ActorPayload, user: User) -> Result<Actor> {
+    // Users also act with the roles of their groups
+    let groups = state
+        .db
+        .groups
+        .list_by_user(&user.id)
+        .await
+        .context(DbSnafu)?;
+    let group_roles: Vec<Role> = groups.into_iter().flat_map(|group| group.roles).collect();
+
+    let user: UserDto = user.into();
+    let all_roles: Vec<Role> = user
+        .roles
+        .iter()
+        .chain(group_roles.iter())
+        .cloned()
+        .collect();
+    let custom_roles = resolve_custom_roles(state, &user.org_id, &all_roles).await?;
+
+    Ok(Actor::new(payload, user, &custom_roles).with_group_roles(&group_roles, &custom_roles))
 }
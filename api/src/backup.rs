@@ -0,0 +1,256 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use snafu::{OptionExt, ResultExt, ensure};
+use tokio::fs;
+use tokio::time::{Instant, interval_at};
+use tracing::{error, info};
+
+use crate::Result;
+use crate::config::{BackupConfig, Config};
+use crate::error::{
+    BackupSnafu, CipherSnafu, CreateFileSnafu, DbSnafu, ReadFileSnafu, RemoveFileSnafu,
+};
+
+const SNAPSHOT_PREFIX: &str = "vault-";
+const SNAPSHOT_EXT: &str = ".sqlite3";
+
+/// Appended to snapshots encrypted with the backup key
+const ENCRYPTED_EXT: &str = ".enc";
+
+/// Outcome of the latest scheduled snapshot
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackupStatus {
+    pub last_backup_at: Option<i64>,
+    pub last_backup_file: Option<String>,
+    pub last_error: Option<String>,
+}
+
+pub type SharedBackupStatus = Arc<RwLock<BackupStatus>>;
+
+/// Writes a consistent copy of the live database, encrypted when a key is given
+pub async fn create_backup(db_url: &str, out: &Path, key: Option<&str>) -> Result<()> {
+    let Some(key) = key else {
+        return backup_to(db_url, out).await;
+    };
+
+    ensure!(
+        !out.exists(),
+        BackupSnafu {
+            msg: format!("Backup file already exists: {}", out.display()),
+        }
+    );
+
+    // Plain copy only lives until it is encrypted
+    let tmp = tmp_path(out);
+    backup_to(db_url, &tmp).await?;
+    let res = encrypt_file(key, &tmp, out).await;
+    let _ = fs::remove_file(&tmp).await;
+    res
+}
+
+/// Replaces the database with the backup, decrypting it first when needed
+pub async fn restore_backup(db_url: &str, from: &Path, key: Option<&str>) -> Result<()> {
+    if !is_encrypted(from) {
+        return restore_from(db_url, from).await;
+    }
+
+    let key = key.context(BackupSnafu {
+        msg: "Backup encryption key is not configured".to_string(),
+    })?;
+
+    let tmp = tmp_path(from);
+    decrypt_file(key, from, &tmp).await?;
+    let res = restore_from(db_url, &tmp).await;
+    let _ = fs::remove_file(&tmp).await;
+    res
+}
+
+/// Takes snapshots on the configured interval until the server stops
+pub fn start_backup_scheduler(config: &Config, status: SharedBackupStatus) {
+    let Some(backup) = config.backup.clone() else {
+        return;
+    };
+
+    let db_url = config.db.url.clone();
+    let period = Duration::from_secs(backup.interval_minutes * 60);
+
+    tokio::spawn(async move {
+        let mut interval = interval_at(Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+
+            let res = run_snapshot(&db_url, &backup).await;
+            let Ok(mut current) = status.write() else {
+                continue;
+            };
+
+            match res {
+                Ok(path) => {
+                    info!("Database snapshot saved: {}", path.display());
+                    current.last_backup_at = Some(chrono::Utc::now().timestamp());
+                    current.last_backup_file = Some(path.display().to_string());
+                    current.last_error = None;
+                }
+                Err(e) => {
+                    error!("Database snapshot failed: {}", e);
+                    current.last_error = Some(e.to_string());
+                }
+            }
+        }
+    });
+}
+
+async fn run_snapshot(db_url: &str, backup: &BackupConfig) -> Result<PathBuf> {
+    let now = chrono::Utc::now();
+    let mut name = format!(
+        "{}{}{}",
+        SNAPSHOT_PREFIX,
+        now.format("%Y%m%dT%H%M%SZ"),
+        SNAPSHOT_EXT
+    );
+    if backup.encryption_key.is_some() {
+        name.push_str(ENCRYPTED_EXT);
+    }
+
+    let out = backup.dir.join(name);
+    create_backup(db_url, &out, backup.encryption_key.as_deref()).await?;
+    prune_snapshots(&backup.dir, backup.retention).await?;
+
+    Ok(out)
+}
+
+/// Removes the oldest snapshots beyond the retention count
+async fn prune_snapshots(dir: &Path, retention: usize) -> Result<()> {
+    let mut entries = fs::read_dir(dir).await.context(ReadFileSnafu {
+        path: dir.to_path_buf(),
+    })?;
+
+    let mut names: Vec<String> = Vec::new();
+    while let Some(entry) = entries.next_entry().await.context(ReadFileSnafu {
+        path: dir.to_path_buf(),
+    })? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_snapshot_name(&name) {
+            names.push(name);
+        }
+    }
+
+    // Timestamped names sort from oldest to newest
+    names.sort();
+    let excess = names.len().saturating_sub(retention);
+    for name in names.into_iter().take(excess) {
+        let path = dir.join(name);
+        fs::remove_file(&path)
+            .await
+            .context(RemoveFileSnafu { path })?;
+    }
+
+    Ok(())
+}
+
+fn is_snapshot_name(name: &str) -> bool {
+    name.starts_with(SNAPSHOT_PREFIX)
+        && (name.ends_with(SNAPSHOT_EXT)
+            || name.ends_with(&format!("{}{}", SNAPSHOT_EXT, ENCRYPTED_EXT)))
+}
+
+fn is_encrypted(path: &Path) -> bool {
+    path.to_string_lossy().ends_with(ENCRYPTED_EXT)
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.tmp", path.display()))
+}
+
+async fn backup_to(db_url: &str, out: &Path) -> Result<()> {
+    let db_url = db_url.to_string();
+    let out = out.to_path_buf();
+
+    // Backup steps block while copying pages
+    let res = tokio::task::spawn_blocking(move || db::backup::backup_database(&db_url, &out))
+        .await
+        .ok()
+        .context(BackupSnafu {
+            msg: "Backup task was interrupted".to_string(),
+        })?;
+
+    res.context(DbSnafu)
+}
+
+async fn restore_from(db_url: &str, from: &Path) -> Result<()> {
+    let db_url = db_url.to_string();
+    let from = from.to_path_buf();
+
+    let res = tokio::task::spawn_blocking(move || db::backup::restore_database(&db_url, &from))
+        .await
+        .ok()
+        .context(BackupSnafu {
+            msg: "Restore task was interrupted".to_string(),
+        })?;
+
+    res.context(DbSnafu)
+}
+
+async fn encrypt_file(key: &str, from: &Path, out: &Path) -> Result<()> {
+    let data = fs::read(from).await.context(ReadFileSnafu {
+        path: from.to_path_buf(),
+    })?;
+    let encrypted = cipher::encrypt_bytes(key, &data).context(CipherSnafu)?;
+
+    fs::write(out, encrypted).await.context(CreateFileSnafu {
+        path: out.to_path_buf(),
+    })
+}
+
+async fn decrypt_file(key: &str, from: &Path, out: &Path) -> Result<()> {
+    let data = fs::read_to_string(from).await.context(ReadFileSnafu {
+        path: from.to_path_buf(),
+    })?;
+    let decrypted = cipher::decrypt_bytes(key, data.trim()).context(CipherSnafu)?;
+
+    fs::write(out, decrypted).await.context(CreateFileSnafu {
+        path: out.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_names() {
+        assert!(is_snapshot_name("vault-20250618T010203Z.sqlite3"));
+        assert!(is_snapshot_name("vault-20250618T010203Z.sqlite3.enc"));
+        assert!(!is_snapshot_name("vault-20250618T010203Z.sqlite3.tmp"));
+        assert!(!is_snapshot_name("db.sqlite3"));
+    }
+
+    #[tokio::test]
+    async fn test_prune_snapshots() {
+        let dir = std::env::temp_dir().join(format!("vault-backups-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let names = [
+            "vault-20250601T000000Z.sqlite3",
+            "vault-20250602T000000Z.sqlite3.enc",
+            "vault-20250603T000000Z.sqlite3",
+            "notes.txt",
+        ];
+        for name in names {
+            std::fs::write(dir.join(name), "x").unwrap();
+        }
+
+        prune_snapshots(&dir, 2).await.unwrap();
+
+        assert!(!dir.join(names[0]).exists());
+        assert!(dir.join(names[1]).exists());
+        assert!(dir.join(names[2]).exists());
+        assert!(dir.join(names[3]).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
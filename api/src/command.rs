@@ -1,9 +1,13 @@
-use snafu::ResultExt;
+use jsonwebtoken::Algorithm;
+use snafu::{OptionExt, ResultExt, ensure};
+use std::path::Path;
 use text_io::read;
 
 use crate::Result;
+use crate::backup::{create_backup, restore_backup};
 use crate::config::Config;
-use crate::error::{DbSnafu, PasswordPromptSnafu};
+use crate::error::{BackupSnafu, ConfigSnafu, DbSnafu, PasswordPromptSnafu, WhateverSnafu};
+use crate::keys::{read_key_file, rotate_keys, write_key_file};
 use crate::state::create_app_state;
 use db::org::NewOrg;
 use db::user::NewUser;
@@ -63,3 +67,118 @@ pub async fn run_setup(config: &Config) -> Result<()> {
     println!("Created system admin user.");
     Ok(())
 }
+
+pub async fn run_verify_audit(config: &Config) -> Result<()> {
+    let state = create_app_state(config).await?;
+
+    let broken = state.db.audit.verify_chain().await.context(DbSnafu)?;
+    if let Some(id) = &broken {
+        println!("Audit log chain is broken at event: {}", id);
+    }
+
+    ensure!(
+        broken.is_none(),
+        WhateverSnafu {
+            msg: "Audit log verification failed."
+        }
+    );
+
+    println!("Audit log chain is valid.");
+    Ok(())
+}
+
+pub async fn run_db_check(config: &Config) -> Result<()> {
+    let state = create_app_state(config).await?;
+
+    let report = state.db.integrity.check().await.context(DbSnafu)?;
+    for msg in &report.integrity_errors {
+        println!("Integrity check: {}", msg);
+    }
+    for violation in &report.foreign_key_violations {
+        println!(
+            "Foreign key violation: {} row {} references missing {}",
+            violation.table_name,
+            violation.row_id.map_or("-".to_string(), |x| x.to_string()),
+            violation.parent
+        );
+    }
+    for id in &report.orphaned_users {
+        println!("Orphaned user: {}", id);
+    }
+    for id in &report.orphaned_vaults {
+        println!("Orphaned vault: {}", id);
+    }
+    for id in &report.orphaned_entries {
+        println!("Orphaned entry: {}", id);
+    }
+
+    ensure!(
+        report.is_ok(),
+        WhateverSnafu {
+            msg: "Database check failed."
+        }
+    );
+
+    println!("Database check passed.");
+    Ok(())
+}
+
+pub async fn run_backup(config: &Config, out: &Path, encrypt: bool) -> Result<()> {
+    let key = match encrypt {
+        true => {
+            let key = config
+                .backup
+                .as_ref()
+                .and_then(|x| x.encryption_key.clone())
+                .context(BackupSnafu {
+                    msg: "Backup encryption key is not configured".to_string(),
+                })?;
+            Some(key)
+        }
+        false => None,
+    };
+
+    create_backup(&config.db.url, out, key.as_deref()).await?;
+
+    println!("Database backup saved to {}", out.display());
+    Ok(())
+}
+
+pub async fn run_restore(config: &Config, from: &Path) -> Result<()> {
+    let key = config
+        .backup
+        .as_ref()
+        .and_then(|x| x.encryption_key.clone());
+    restore_backup(&config.db.url, from, key.as_deref()).await?;
+
+    println!("Database restored from {}", from.display());
+    Ok(())
+}
+
+pub fn run_keys_rotate(config: &Config, alg: Algorithm) -> Result<()> {
+    let path = config.jwt.keys_file.as_ref().context(ConfigSnafu {
+        msg: "Keys file is not configured, set jwt.keys_file".to_string(),
+    })?;
+
+    let file = read_key_file(path)?;
+    let previous = file.keys.len();
+    let has_other_keys = config.jwt_secret.is_some() || !config.jwt.keys.is_empty();
+    let now = chrono::Utc::now().timestamp();
+    let (file, key) = rotate_keys(file, has_other_keys, alg, now)?;
+    write_key_file(path, &file)?;
+
+    let activates_at = chrono::DateTime::from_timestamp(key.activates_at, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_default();
+    println!(
+        "Added {:?} signing key {}, active from {}.",
+        key.alg, key.kid, activates_at
+    );
+    println!(
+        "Removed {} retired keys, {} keys in {}.",
+        previous + 1 - file.keys.len(),
+        file.keys.len(),
+        path.display()
+    );
+    Ok(())
+}
@@ -1,40 +1,164 @@
 use clap::{Parser, Subcommand};
+use db::db::DbOptions;
+use dto::api_key::valid_ip_range;
+use jsonwebtoken::Algorithm;
 use serde::Deserialize;
 use snafu::{ResultExt, ensure};
 use std::{fs, path::PathBuf};
 
 use crate::Result;
-use crate::error::{ConfigFileSnafu, ConfigParseSnafu, ConfigSnafu, UploadDirSnafu};
+use crate::error::{
+    BackupDirSnafu, ConfigFileSnafu, ConfigParseSnafu, ConfigSnafu, UploadDirSnafu,
+};
+use crate::keys::JwtKey;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
-    pub jwt_secret: String,
+    /// Single signing key, see `jwt` for rotating keys
+    pub jwt_secret: Option<String>,
     pub upload_dir: PathBuf,
     pub server: ServerConfig,
     pub db: DbConfig,
+    pub backup: Option<BackupConfig>,
+
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+
+    #[serde(default)]
+    pub login: LoginConfig,
+
+    #[serde(default)]
+    pub jwt: JwtConfig,
+}
+
+/// Keys signing and verifying auth tokens, in addition to `jwt_secret`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JwtConfig {
+    #[serde(default)]
+    pub keys: Vec<JwtKey>,
+
+    /// Managed by the `keys rotate` command and reloaded while running
+    pub keys_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ServerConfig {
     pub port: u16,
     pub cors: bool,
+
+    /// Proxies allowed to report the client address in `X-Forwarded-For`
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct DbConfig {
     pub url: String,
+
+    #[serde(flatten)]
+    pub options: DbOptions,
+}
+
+/// Scheduled database snapshots, disabled when not configured
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackupConfig {
+    pub dir: PathBuf,
+    pub interval_minutes: u64,
+    pub retention: usize,
+    pub encryption_key: Option<String>,
+}
+
+/// Instance-wide limits, system admins can override them per org
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct QuotaConfig {
+    pub max_orgs: i64,
+    pub max_vaults_per_org: i64,
+    pub max_users_per_org: i64,
+    pub max_entries_per_vault: i64,
+
+    /// Sizes in bytes of the encrypted attachments
+    pub max_attachment_size: i64,
+    pub max_attachments_size_per_vault: i64,
+    pub max_attachments_per_entry: i64,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            max_orgs: 10,
+            max_vaults_per_org: 10,
+            max_users_per_org: 10,
+            max_entries_per_vault: 10000,
+            max_attachment_size: 10 * 1024 * 1024,
+            max_attachments_size_per_vault: 100 * 1024 * 1024,
+            max_attachments_per_entry: 20,
+        }
+    }
+}
+
+/// Back-off and lockout after failed logins, tracked per username and per source IP
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LoginConfig {
+    /// Failures allowed before the next attempts are delayed
+    pub free_attempts: i32,
+
+    /// Delay after the first delayed failure, doubled after each further failure
+    pub base_delay_seconds: i64,
+    pub max_delay_seconds: i64,
+
+    /// Failures before the username is locked out
+    pub user_lockout_after: i32,
+
+    /// Higher than for usernames since addresses can be shared by many users
+    pub ip_lockout_after: i32,
+    pub lockout_minutes: i64,
+
+    /// Failures older than this are forgotten
+    pub reset_after_minutes: i64,
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self {
+            free_attempts: 3,
+            base_delay_seconds: 1,
+            max_delay_seconds: 60,
+            user_lockout_after: 10,
+            ip_lockout_after: 50,
+            lockout_minutes: 15,
+            reset_after_minutes: 15,
+        }
+    }
 }
 
 impl Config {
     pub fn build(filename: &PathBuf) -> Result<Self> {
         let toml_string = fs::read_to_string(filename).context(ConfigFileSnafu)?;
-        let config: Config = toml::from_str(toml_string.as_str()).context(ConfigParseSnafu)?;
+        let mut config: Config = toml::from_str(toml_string.as_str()).context(ConfigParseSnafu)?;
+
+        // An empty secret is the same as none at all
+        config.jwt_secret = config.jwt_secret.filter(|x| !x.is_empty());
 
         // Validate config values
         ensure!(
-            config.jwt_secret.len() > 0,
+            config.jwt_secret.is_some()
+                || !config.jwt.keys.is_empty()
+                || config.jwt.keys_file.is_some(),
             ConfigSnafu {
-                msg: "Jwt secret is required.".to_string()
+                msg: "Jwt secret or keys are required.".to_string()
+            }
+        );
+
+        ensure!(
+            config
+                .server
+                .trusted_proxies
+                .iter()
+                .all(|x| valid_ip_range(x)),
+            ConfigSnafu {
+                msg: "Trusted proxies must be addresses or CIDR ranges.".to_string()
             }
         );
 
@@ -62,6 +186,78 @@ impl Config {
         let upload_dir = config.upload_dir.clone().join("tmp");
         std::fs::create_dir_all(&upload_dir).context(UploadDirSnafu)?;
 
+        ensure!(
+            config.db.options.max_connections > 0,
+            ConfigSnafu {
+                msg: "Database max connections must be at least 1.".to_string()
+            }
+        );
+
+        let quotas = &config.quotas;
+        ensure!(
+            quotas.max_orgs > 0
+                && quotas.max_vaults_per_org > 0
+                && quotas.max_users_per_org > 0
+                && quotas.max_entries_per_vault > 0
+                && quotas.max_attachment_size > 0
+                && quotas.max_attachments_size_per_vault > 0
+                && quotas.max_attachments_per_entry > 0,
+            ConfigSnafu {
+                msg: "Quotas must allow at least 1 item.".to_string()
+            }
+        );
+
+        let login = &config.login;
+        ensure!(
+            login.free_attempts >= 0
+                && login.base_delay_seconds > 0
+                && login.max_delay_seconds >= login.base_delay_seconds,
+            ConfigSnafu {
+                msg: "Login delays must be positive, the maximum at least the base delay."
+                    .to_string()
+            }
+        );
+
+        ensure!(
+            login.user_lockout_after > login.free_attempts
+                && login.ip_lockout_after > login.free_attempts
+                && login.lockout_minutes > 0
+                && login.reset_after_minutes > 0,
+            ConfigSnafu {
+                msg: "Login lockouts must allow more failures than the free attempts.".to_string()
+            }
+        );
+
+        let attachments_dir = config.upload_dir.clone().join("attachments");
+        std::fs::create_dir_all(&attachments_dir).context(UploadDirSnafu)?;
+
+        if let Some(backup) = &config.backup {
+            ensure!(
+                backup.interval_minutes > 0,
+                ConfigSnafu {
+                    msg: "Backup interval is required.".to_string()
+                }
+            );
+
+            ensure!(
+                backup.retention > 0,
+                ConfigSnafu {
+                    msg: "Backup retention must keep at least one snapshot.".to_string()
+                }
+            );
+
+            if let Some(key) = &backup.encryption_key {
+                ensure!(
+                    key.len() == 32,
+                    ConfigSnafu {
+                        msg: "Backup encryption key must be 32 bytes.".to_string()
+                    }
+                );
+            }
+
+            std::fs::create_dir_all(&backup.dir).context(BackupDirSnafu)?;
+        }
+
         Ok(config)
     }
 }
@@ -80,8 +276,59 @@ pub struct CliArgs {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Runs the API server
-    Server,
+    Server {
+        /// Uses a seeded in-memory database, nothing is persisted
+        #[arg(long)]
+        ephemeral: bool,
+    },
 
     /// Sets up the admin user
     Setup,
+
+    /// Verifies the audit log hash chain
+    VerifyAudit,
+
+    /// Writes a consistent copy of the live database
+    Backup {
+        #[arg(long, value_name = "file")]
+        out: PathBuf,
+
+        /// Encrypts the backup with the configured backup key
+        #[arg(long)]
+        encrypt: bool,
+    },
+
+    /// Replaces the database with a backup, stop the server first
+    Restore {
+        #[arg(long, value_name = "file")]
+        from: PathBuf,
+    },
+
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+
+    /// JWT signing keys
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommands {
+    /// Runs integrity and foreign key checks and reports orphaned records
+    Check,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeysCommands {
+    /// Adds a new signing key to the keys file and removes the retired ones
+    Rotate {
+        /// Signing algorithm of the new key: EdDSA, ES256 or HS256
+        #[arg(long, default_value = "EdDSA")]
+        alg: Algorithm,
+    },
 }
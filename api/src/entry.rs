@@ -1,50 +1,458 @@
-use db::entry::{Entry, EntryPayload};
-use dto::entry::EntryDto;
-use snafu::{ResultExt, ensure};
+use std::collections::HashSet;
+
+use db::entry::{BulkEntriesPayload, BulkEntryOp, Entry, EntryPayload, TransferEntryPayload};
+use dto::entry::{BulkEntryResultDto, EntryDto};
+use dto::role::Permission;
+use snafu::{OptionExt, ResultExt, ensure};
+use validator::Validate;
 
 use crate::Result;
-use crate::error::{DbSnafu, ValidationSnafu};
+use crate::attachment::remove_attachment_file;
+use crate::error::{
+    DbSnafu, ForbiddenSnafu, MaxEntriesReachedSnafu, NotFoundSnafu, PreconditionFailedSnafu,
+    ValidationSnafu,
+};
+use crate::quota::org_limits;
 
 use crate::state::AppState;
+use crate::vault::vault_actor;
+use dto::actor::Actor;
 use dto::vault::VaultDto;
 use vault::utils::generate_id;
+use vault::validators::flatten_errors;
 
-const MAX_ENTRIES: i32 = 10000;
+fn new_entry_dto(vault: &VaultDto, data: &EntryPayload) -> EntryDto {
+    let today = chrono::Utc::now().timestamp();
+    EntryDto {
+        id: generate_id(),
+        vault_id: vault.id.clone(),
+        label: data.label.clone(),
+        cipher_username: data.cipher_username.clone(),
+        cipher_password: data.cipher_password.clone(),
+        cipher_notes: data.cipher_notes.clone(),
+        cipher_extra_notes: data.cipher_extra_notes.clone(),
+        status: "active".to_string(),
+        created_at: today.clone(),
+        updated_at: today,
+        last_used_at: today,
+        version: 1,
+        secret_changed_at: today,
+        rotation_days: data.rotation_days(),
+        expires_at: data.expires_at(),
+    }
+}
 
 pub async fn create_entry(state: AppState, vault: &VaultDto, data: &EntryPayload) -> Result<Entry> {
-    // Limit the number of entries per vault
-    let count = state
+    let max_entries = org_limits(&state, &vault.org_id)
+        .await?
+        .max_entries_per_vault;
+    let entry_dto = new_entry_dto(vault, data);
+
+    state
         .db
-        .entries
-        .count_by_vault(&vault.id)
+        .transaction(move |tx| {
+            // Limit the number of entries per vault
+            let count = tx
+                .entries()
+                .count_by_vault(&entry_dto.vault_id)
+                .context(DbSnafu)?;
+
+            ensure!(
+                count < max_entries,
+                MaxEntriesReachedSnafu { max: max_entries }
+            );
+
+            tx.entries().create(entry_dto).context(DbSnafu)
+        })
+        .await
+}
+
+/// Permissions needed to run all operations of a bulk request
+pub fn bulk_permissions(data: &BulkEntriesPayload) -> Vec<Permission> {
+    let mut permissions: Vec<Permission> = Vec::new();
+    for op in data.operations.iter() {
+        let permission = match op {
+            BulkEntryOp::Create { .. } => Permission::EntriesCreate,
+            BulkEntryOp::Update { .. } => Permission::EntriesEdit,
+            BulkEntryOp::Delete { .. } => Permission::EntriesDelete,
+        };
+        if !permissions.contains(&permission) {
+            permissions.push(permission);
+        }
+    }
+    permissions
+}
+
+fn validate_bulk(data: &BulkEntriesPayload) -> Result<()> {
+    let valid_res = data.validate();
+    ensure!(
+        valid_res.is_ok(),
+        ValidationSnafu {
+            msg: flatten_errors(&valid_res.unwrap_err()),
+        }
+    );
+
+    let mut errors: Vec<String> = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    for (index, op) in data.operations.iter().enumerate() {
+        let payload = match op {
+            BulkEntryOp::Create { data } | BulkEntryOp::Update { data, .. } => Some(data),
+            BulkEntryOp::Delete { .. } => None,
+        };
+        if let Some(Err(e)) = payload.map(|x| x.validate()) {
+            errors.push(format!("operations[{}]: {}", index, flatten_errors(&e)));
+        }
+
+        // Each entry may only be touched once per request
+        if let Some(id) = op.entry_id()
+            && !seen.insert(id)
+        {
+            errors.push(format!("operations[{}]: duplicate entry {}", index, id));
+        }
+    }
+
+    ensure!(
+        errors.is_empty(),
+        ValidationSnafu {
+            msg: errors.join(", "),
+        }
+    );
+
+    Ok(())
+}
+
+/// Runs all operations on the vault in one transaction, any failure rolls back all of them
+pub async fn bulk_entries(
+    state: &AppState,
+    vault: &VaultDto,
+    data: &BulkEntriesPayload,
+) -> Result<Vec<BulkEntryResultDto>> {
+    validate_bulk(data)?;
+
+    let max_entries = org_limits(state, &vault.org_id)
+        .await?
+        .max_entries_per_vault;
+
+    // Ids are generated upfront so the transaction only writes
+    let operations: Vec<(BulkEntryOp, Option<EntryDto>)> = data
+        .operations
+        .iter()
+        .map(|op| match op {
+            BulkEntryOp::Create { data } => (op.clone(), Some(new_entry_dto(vault, data))),
+            _ => (op.clone(), None),
+        })
+        .collect();
+
+    let vault_id = vault.id.clone();
+    let outcome: Result<(Vec<BulkEntryResultDto>, Vec<String>)> = state
+        .db
+        .transaction(move |tx| {
+            let creates = operations.iter().filter(|(_, x)| x.is_some()).count() as i64;
+            let deletes = operations
+                .iter()
+                .filter(|(op, _)| matches!(op, BulkEntryOp::Delete { .. }))
+                .count() as i64;
+
+            // Only growing batches are held to the limit
+            if creates > deletes {
+                let count = tx.entries().count_by_vault(&vault_id).context(DbSnafu)?;
+                ensure!(
+                    count - deletes + creates <= max_entries,
+                    MaxEntriesReachedSnafu { max: max_entries }
+                );
+            }
+
+            let mut results: Vec<BulkEntryResultDto> = Vec::with_capacity(operations.len());
+            let mut attachment_ids: Vec<String> = Vec::new();
+
+            for (index, (op, entry_dto)) in operations.into_iter().enumerate() {
+                let (id, version) = match (&op, entry_dto) {
+                    (BulkEntryOp::Create { .. }, Some(entry_dto)) => {
+                        let entry = tx.entries().create(entry_dto).context(DbSnafu)?;
+                        (entry.id, Some(entry.version))
+                    }
+                    (BulkEntryOp::Update { id, version, data }, _) => {
+                        let updated = tx
+                            .entries()
+                            .update(&vault_id, id, *version, data)
+                            .context(DbSnafu)?;
+                        ensure!(
+                            updated,
+                            PreconditionFailedSnafu {
+                                msg: format!(
+                                    "operations[{}]: entry has been modified or is not in the vault",
+                                    index
+                                ),
+                            }
+                        );
+                        (id.clone(), Some(version + 1))
+                    }
+                    (BulkEntryOp::Delete { id, version }, _) => {
+                        let ids = tx.attachments().delete_by_entry(id).context(DbSnafu)?;
+                        let deleted = tx
+                            .entries()
+                            .delete(&vault_id, id, *version)
+                            .context(DbSnafu)?;
+                        ensure!(
+                            deleted,
+                            PreconditionFailedSnafu {
+                                msg: format!(
+                                    "operations[{}]: entry has been modified or is not in the vault",
+                                    index
+                                ),
+                            }
+                        );
+                        attachment_ids.extend(ids);
+                        (id.clone(), None)
+                    }
+                    (BulkEntryOp::Create { .. }, None) => unreachable!("create without entry"),
+                };
+
+                results.push(BulkEntryResultDto {
+                    index,
+                    op: op.name().to_string(),
+                    id,
+                    version,
+                });
+            }
+
+            Ok((results, attachment_ids))
+        })
+        .await;
+    let (results, attachment_ids) = outcome?;
+
+    // Blobs are only removed once their records are gone for good
+    for id in attachment_ids.iter() {
+        remove_attachment_file(state, id).await?;
+    }
+
+    Ok(results)
+}
+
+/// Deletes the entry with its attachments if it is still at the given version
+pub async fn delete_entry(state: &AppState, vault: &VaultDto, entry: &EntryDto) -> Result<()> {
+    let vault_id = vault.id.clone();
+    let entry_id = entry.id.clone();
+    let version = entry.version;
+
+    let attachment_ids: Vec<String> = state
+        .db
+        .transaction(move |tx| -> Result<_> {
+            let ids = tx
+                .attachments()
+                .delete_by_entry(&entry_id)
+                .context(DbSnafu)?;
+            let deleted = tx
+                .entries()
+                .delete(&vault_id, &entry_id, version)
+                .context(DbSnafu)?;
+            ensure!(
+                deleted,
+                PreconditionFailedSnafu {
+                    msg: "Entry has been modified by another request",
+                }
+            );
+
+            Ok(ids)
+        })
+        .await?;
+
+    // Blobs are only removed once their records are gone for good
+    for id in attachment_ids.iter() {
+        remove_attachment_file(state, id).await?;
+    }
+
+    Ok(())
+}
+
+/// Loads the vault an entry is moved or copied to, it must be another vault of the same org
+pub async fn transfer_target(
+    state: &AppState,
+    actor: &Actor,
+    vault: &VaultDto,
+    data: &TransferEntryPayload,
+) -> Result<VaultDto> {
+    let valid_res = data.validate();
+    ensure!(
+        valid_res.is_ok(),
+        ValidationSnafu {
+            msg: flatten_errors(&valid_res.unwrap_err()),
+        }
+    );
+
+    ensure!(
+        data.target_vault_id != vault.id,
+        ValidationSnafu {
+            msg: "Target vault must be different from the current vault",
+        }
+    );
+
+    let target = state
+        .db
+        .vaults
+        .get(&data.target_vault_id)
         .await
         .context(DbSnafu)?;
 
+    // Vaults of other orgs are treated as missing
+    let target = target.filter(|x| x.org_id == vault.org_id);
+    let target = target.context(NotFoundSnafu {
+        msg: "Target vault not found",
+    })?;
+
+    // Entries are created in the target vault
+    let target_actor = vault_actor(state, actor, &target).await?;
+    ensure!(
+        target_actor.has_permissions(&vec![Permission::EntriesCreate]),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions in the target vault"
+        }
+    );
+
+    Ok(target)
+}
+
+/// Requires a re-encrypted value for every secret the entry has, so that
+/// a secret is never stored empty or dropped on the way to the other vault
+fn validate_transfer_secrets(entry: &EntryDto, data: &TransferEntryPayload) -> Result<()> {
+    let fields = [
+        (
+            "cipher_username",
+            &entry.cipher_username,
+            &data.cipher_username,
+        ),
+        (
+            "cipher_password",
+            &entry.cipher_password,
+            &data.cipher_password,
+        ),
+        ("cipher_notes", &entry.cipher_notes, &data.cipher_notes),
+        (
+            "cipher_extra_notes",
+            &entry.cipher_extra_notes,
+            &data.cipher_extra_notes,
+        ),
+    ];
+
+    let missing: Vec<&str> = fields
+        .into_iter()
+        .filter(|(_, current, value)| {
+            current.as_ref().is_some_and(|x| !x.is_empty())
+                && value.as_ref().is_none_or(|x| x.is_empty())
+        })
+        .map(|(name, _, _)| name)
+        .collect();
+
     ensure!(
-        count < MAX_ENTRIES as i64,
+        missing.is_empty(),
         ValidationSnafu {
-            msg: format!("Vault already reached the maximum entries: {}", MAX_ENTRIES),
+            msg: format!("Re-encrypted secrets are required: {}", missing.join(", ")),
         }
     );
 
+    Ok(())
+}
+
+/// Moves the entry to the target vault with secrets re-encrypted by the client
+pub async fn move_entry(
+    state: &AppState,
+    vault: &VaultDto,
+    target: &VaultDto,
+    entry: &EntryDto,
+    data: &TransferEntryPayload,
+) -> Result<Entry> {
+    validate_transfer_secrets(entry, data)?;
+
+    let max_entries = org_limits(state, &target.org_id)
+        .await?
+        .max_entries_per_vault;
+
+    let vault_id = vault.id.clone();
+    let entry_id = entry.id.clone();
+    let version = entry.version;
+    let data = data.clone();
+
+    state
+        .db
+        .transaction(move |tx| {
+            // Attachments are encrypted with the key of the source vault
+            let attachments = tx
+                .attachments()
+                .count_by_entry(&entry_id)
+                .context(DbSnafu)?;
+            ensure!(
+                attachments == 0,
+                ValidationSnafu {
+                    msg: "Entries with attachments cannot be moved",
+                }
+            );
+
+            let count = tx
+                .entries()
+                .count_by_vault(&data.target_vault_id)
+                .context(DbSnafu)?;
+            ensure!(
+                count < max_entries,
+                MaxEntriesReachedSnafu { max: max_entries }
+            );
+
+            let moved = tx
+                .entries()
+                .move_to(&vault_id, &entry_id, version, &data)
+                .context(DbSnafu)?;
+            moved.context(PreconditionFailedSnafu {
+                msg: "Entry has been modified by another request",
+            })
+        })
+        .await
+}
+
+/// Copies the entry to the target vault, attachments are not copied
+pub async fn copy_entry(
+    state: &AppState,
+    target: &VaultDto,
+    entry: &EntryDto,
+    data: &TransferEntryPayload,
+) -> Result<Entry> {
+    validate_transfer_secrets(entry, data)?;
+
+    let max_entries = org_limits(state, &target.org_id)
+        .await?
+        .max_entries_per_vault;
+
     let today = chrono::Utc::now().timestamp();
     let entry_dto = EntryDto {
         id: generate_id(),
-        vault_id: vault.id.clone(),
-        label: data.label.clone(),
+        vault_id: target.id.clone(),
+        label: entry.label.clone(),
         cipher_username: data.cipher_username.clone(),
         cipher_password: data.cipher_password.clone(),
         cipher_notes: data.cipher_notes.clone(),
         cipher_extra_notes: data.cipher_extra_notes.clone(),
         status: "active".to_string(),
-        created_at: today.clone(),
+        created_at: today,
         updated_at: today,
+        last_used_at: today,
+        version: 1,
+        // Same secret, so the copy keeps the age of the original
+        secret_changed_at: entry.secret_changed_at,
+        rotation_days: entry.rotation_days,
+        expires_at: entry.expires_at,
     };
 
     state
         .db
-        .entries
-        .create(entry_dto.clone())
+        .transaction(move |tx| {
+            let count = tx
+                .entries()
+                .count_by_vault(&entry_dto.vault_id)
+                .context(DbSnafu)?;
+            ensure!(
+                count < max_entries,
+                MaxEntriesReachedSnafu { max: max_entries }
+            );
+
+            tx.entries().create(entry_dto).context(DbSnafu)
+        })
         .await
-        .context(DbSnafu)
 }
@@ -1,11 +1,12 @@
 use std::path::PathBuf;
 
+use axum::extract::multipart::MultipartError;
 use axum::extract::rejection::JsonRejection;
 use axum::response::IntoResponse;
 use axum::{body::Body, http::StatusCode, response::Response};
 use dto::role::{InvalidPermissionsError, InvalidRolesError};
 use serde::{Deserialize, Serialize};
-use snafu::{Backtrace, ErrorCompat, Snafu};
+use snafu::{Backtrace, ErrorCompat, IntoError, Snafu};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -20,7 +21,8 @@ pub enum Error {
 
     #[snafu(display("Error parsing config file: {}", source))]
     ConfigParse {
-        source: toml::de::Error,
+        #[snafu(source(from(toml::de::Error, Box::new)))]
+        source: Box<toml::de::Error>,
         backtrace: Backtrace,
     },
 
@@ -30,12 +32,44 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display("Unable to create backup dir: {}", source))]
+    BackupDir {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Config error: {}", msg))]
     Config { msg: String },
 
+    #[snafu(display("Error reading keys file: {}", source))]
+    KeysFile {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Error parsing keys file: {}", source))]
+    KeysParse {
+        #[snafu(source(from(toml::de::Error, Box::new)))]
+        source: Box<toml::de::Error>,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Error serializing keys file: {}", source))]
+    KeysSerialize {
+        source: toml::ser::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Error writing keys file: {}", source))]
+    KeysWrite {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("{}", source))]
     Db {
-        source: db::Error,
+        #[snafu(source(from(db::Error, Box::new)))]
+        source: Box<db::Error>,
         backtrace: Backtrace,
     },
 
@@ -52,17 +86,20 @@ pub enum Error {
     #[snafu(display("Maximum number of clients reached: 10"))]
     MaxClientsReached,
 
-    #[snafu(display("Maximum number of orgs reached: 10"))]
-    MaxOrgsReached,
+    #[snafu(display("Maximum number of orgs reached: {}", max))]
+    MaxOrgsReached { max: i64 },
 
-    #[snafu(display("Maximum number of users reached: 100"))]
-    MaxUsersReached,
+    #[snafu(display("Maximum number of users reached: {}", max))]
+    MaxUsersReached { max: i64 },
 
     #[snafu(display("Maximum number of buckets reached: 50"))]
     MaxBucketsReached,
 
-    #[snafu(display("Maximum number of vaults reached: 10"))]
-    MaxVaultsReached,
+    #[snafu(display("Maximum number of vaults reached: {}", max))]
+    MaxVaultsReached { max: i64 },
+
+    #[snafu(display("Maximum number of entries per vault reached: {}", max))]
+    MaxEntriesReached { max: i64 },
 
     #[snafu(display("Maximum number of directories reached: 1000"))]
     MaxDirsReached,
@@ -96,15 +133,59 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display("Unable to read file: {:?}", path))]
+    ReadFile {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unable to remove file: {:?}", path))]
+    RemoveFile {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("{}", source))]
+    Cipher {
+        source: cipher::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Backup failed: {}", msg))]
+    Backup { msg: String },
+
+    #[snafu(display("Invalid multipart request: {}", source))]
+    Multipart {
+        source: MultipartError,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("File type not allowed"))]
     FileTypeNotAllowed,
 
     #[snafu(display("{}", msg))]
     NotFound { msg: String },
 
+    #[snafu(display("Missing If-Match header"))]
+    PreconditionRequired,
+
+    #[snafu(display("{}", msg))]
+    PreconditionFailed { msg: String },
+
     #[snafu(display("Invalid auth token"))]
     InvalidAuthToken,
 
+    #[snafu(display("Invalid API key"))]
+    InvalidApiKey,
+
+    #[snafu(display("Invalid refresh token"))]
+    InvalidRefreshToken,
+
+    #[snafu(display("Invalid or expired password reset link"))]
+    InvalidResetToken,
+
     #[snafu(display("Insufficient auth scope"))]
     InsufficientAuthScope,
 
@@ -126,6 +207,12 @@ pub enum Error {
     #[snafu(display("Invalid username or password"))]
     InvalidPassword,
 
+    #[snafu(display(
+        "Too many failed login attempts. Try again in {}.",
+        format_wait(*retry_after)
+    ))]
+    LoginLocked { retry_after: i64 },
+
     #[snafu(display("Inactive user"))]
     InactiveUser,
 
@@ -154,6 +241,16 @@ pub enum Error {
     Whatever { msg: String },
 }
 
+/// Rounds up to minutes once the wait is long enough
+fn format_wait(seconds: i64) -> String {
+    match seconds {
+        1 => "1 second".to_string(),
+        0..60 => format!("{} seconds", seconds),
+        60 => "1 minute".to_string(),
+        _ => format!("{} minutes", (seconds + 59) / 60),
+    }
+}
+
 // Allow string slices to be converted to Error
 impl From<&str> for Error {
     fn from(val: &str) -> Self {
@@ -169,14 +266,23 @@ impl From<String> for Error {
     }
 }
 
+// Allows db errors to surface from within transactions
+impl From<db::Error> for Error {
+    fn from(source: db::Error) -> Self {
+        DbSnafu.into_error(source)
+    }
+}
+
 /// Allow Error to be converted to StatusCode
 impl From<&Error> for StatusCode {
     fn from(err: &Error) -> Self {
         match err {
             Error::Validation { .. } => StatusCode::BAD_REQUEST,
-            Error::MaxOrgsReached => StatusCode::BAD_REQUEST,
+            Error::MaxOrgsReached { .. } => StatusCode::BAD_REQUEST,
             Error::MaxClientsReached => StatusCode::BAD_REQUEST,
-            Error::MaxUsersReached => StatusCode::BAD_REQUEST,
+            Error::MaxUsersReached { .. } => StatusCode::BAD_REQUEST,
+            Error::MaxVaultsReached { .. } => StatusCode::BAD_REQUEST,
+            Error::MaxEntriesReached { .. } => StatusCode::BAD_REQUEST,
             Error::MaxBucketsReached => StatusCode::BAD_REQUEST,
             Error::MaxDirsReached => StatusCode::BAD_REQUEST,
             Error::MaxFilesReached => StatusCode::BAD_REQUEST,
@@ -186,21 +292,29 @@ impl From<&Error> for StatusCode {
             Error::MissingUploadFile { .. } => StatusCode::BAD_REQUEST,
             Error::CreateFile { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Error::FileTypeNotAllowed => StatusCode::BAD_REQUEST,
+            Error::Multipart { source, .. } => source.status(),
             Error::NotFound { .. } => StatusCode::NOT_FOUND,
+            Error::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+            Error::PreconditionFailed { .. } => StatusCode::PRECONDITION_FAILED,
             Error::InvalidAuthToken => StatusCode::UNAUTHORIZED,
+            Error::InvalidApiKey => StatusCode::UNAUTHORIZED,
+            Error::InvalidRefreshToken => StatusCode::UNAUTHORIZED,
+            Error::InvalidResetToken => StatusCode::BAD_REQUEST,
             Error::InsufficientAuthScope => StatusCode::UNAUTHORIZED,
             Error::NoAuthToken => StatusCode::UNAUTHORIZED,
             Error::InvalidClient => StatusCode::UNAUTHORIZED,
             Error::RequiresAuth => StatusCode::UNAUTHORIZED,
             Error::InvalidPassword => StatusCode::UNAUTHORIZED,
+            Error::LoginLocked { .. } => StatusCode::TOO_MANY_REQUESTS,
             Error::InactiveUser => StatusCode::UNAUTHORIZED,
             Error::UserNotFound => StatusCode::UNAUTHORIZED,
             Error::InvalidRoles { .. } => StatusCode::BAD_REQUEST,
             Error::InvalidPermissions { .. } => StatusCode::BAD_REQUEST,
             #[allow(unused_variables)]
-            Error::Db { source, backtrace } => match source {
+            Error::Db { source, backtrace } => match source.as_ref() {
                 db::Error::Validation { .. } => StatusCode::BAD_REQUEST,
                 db::Error::InvalidRoles { .. } => StatusCode::BAD_REQUEST,
+                db::Error::InvalidPermissions { .. } => StatusCode::BAD_REQUEST,
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             },
             #[allow(unused_variables)]
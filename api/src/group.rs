@@ -0,0 +1,48 @@
+use snafu::{OptionExt, ResultExt, ensure};
+
+use crate::Result;
+use crate::error::{DbSnafu, ForbiddenSnafu, NotFoundSnafu};
+use crate::state::AppState;
+use dto::actor::Actor;
+use dto::group::{GroupDto, GroupMemberDto};
+
+/// Adds a user of the group org to the group
+pub async fn add_group_member(
+    state: &AppState,
+    actor: &Actor,
+    group: &GroupDto,
+    user_id: &str,
+) -> Result<GroupMemberDto> {
+    // Group roles would otherwise be a way around the roles update rules
+    ensure!(
+        actor.id.as_str() != user_id,
+        ForbiddenSnafu {
+            msg: "Updating your own group membership not allowed"
+        }
+    );
+
+    // Users of other orgs are treated as missing
+    let user = state.db.users.get(user_id).await.context(DbSnafu)?;
+    let user = user.filter(|x| x.org_id == group.org_id);
+    let user = user.context(NotFoundSnafu {
+        msg: "User not found",
+    })?;
+
+    state
+        .db
+        .groups
+        .add_member(&group.id, &user.id)
+        .await
+        .context(DbSnafu)?;
+
+    let members = state
+        .db
+        .groups
+        .list_members(&group.id)
+        .await
+        .context(DbSnafu)?;
+    let member = members.into_iter().find(|x| x.user_id == user.id);
+    member.context(NotFoundSnafu {
+        msg: "Member not found",
+    })
+}
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use tracing::error;
 
 use crate::Result;
+use crate::backup::BackupStatus;
 use db::db::DbMapper;
 
 #[derive(Serialize)]
@@ -15,6 +16,8 @@ pub struct HealthStatus {
     pub status: String,
     pub message: String,
     pub checks: HealthChecks,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<BackupStatus>,
 }
 
 #[derive(Serialize)]
@@ -47,7 +50,11 @@ pub async fn check_liveness() -> Result<LiveStatus> {
     })
 }
 
-pub async fn check_readiness(db: Arc<DbMapper>) -> Result<HealthStatus> {
+/// Backup status is informational, it does not affect readiness
+pub async fn check_readiness(
+    db: Arc<DbMapper>,
+    backup: Option<BackupStatus>,
+) -> Result<HealthStatus> {
     let checks = perform_checks(db).await?;
     let mut status = "DOWN".to_string();
     let mut message = "One or more health checks are failing".to_string();
@@ -61,6 +68,7 @@ pub async fn check_readiness(db: Arc<DbMapper>) -> Result<HealthStatus> {
         status,
         message,
         checks,
+        backup,
     })
 }
 
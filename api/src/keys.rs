@@ -0,0 +1,480 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::{Duration, SystemTime};
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+    EllipticCurveKeyType, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType,
+    PublicKeyUse,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use ring::rand::SystemRandom;
+use ring::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, ensure};
+use tokio::time::{Instant, interval_at};
+use tracing::{error, info};
+
+use crate::Result;
+use crate::config::Config;
+use crate::error::{
+    ConfigSnafu, KeysFileSnafu, KeysParseSnafu, KeysSerializeSnafu, KeysWriteSnafu,
+};
+use crate::token::EXP_DURATION;
+use vault::utils::generate_id;
+
+/// Key id of the plain `jwt_secret`, also used for tokens without a `kid` header
+pub const DEFAULT_KID: &str = "default";
+
+/// Rotated keys only start signing once every server had time to load them
+pub const ACTIVATION_DELAY: i64 = 120;
+
+/// How often running servers check the keys file for changes
+const RELOAD_SECONDS: u64 = 30;
+
+/// How long consumers may cache the published public keys
+pub const JWKS_MAX_AGE: i64 = 300;
+
+/// Key identified by the `kid` header of the tokens it signs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtKey {
+    pub kid: String,
+
+    /// HS256, EdDSA or ES256
+    #[serde(default)]
+    pub alg: Algorithm,
+
+    /// Shared secret for HS256, base64 PKCS#8 private key otherwise
+    pub secret: String,
+
+    /// Signs new tokens from this time until a newer key activates
+    #[serde(default)]
+    pub activates_at: i64,
+}
+
+/// Contents of the keys file managed by the `keys rotate` command
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyFile {
+    #[serde(default)]
+    pub keys: Vec<JwtKey>,
+}
+
+/// Key parsed once for signing and verifying
+#[derive(Clone)]
+pub struct LoadedKey {
+    pub kid: String,
+    pub alg: Algorithm,
+    pub activates_at: i64,
+    pub encoding: EncodingKey,
+    pub decoding: DecodingKey,
+
+    /// Public key published in the JWKS, none for shared secrets
+    pub jwk: Option<Jwk>,
+}
+
+impl LoadedKey {
+    pub fn load(key: &JwtKey) -> Result<Self> {
+        let (encoding, decoding, params) = match key.alg {
+            Algorithm::HS256 => (
+                EncodingKey::from_secret(key.secret.as_bytes()),
+                DecodingKey::from_secret(key.secret.as_bytes()),
+                None,
+            ),
+            Algorithm::EdDSA => {
+                let der = private_key_der(key)?;
+                let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+                    .ok()
+                    .context(invalid_key(key))?;
+                let public = pair.public_key().as_ref();
+                let params = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(public),
+                });
+                (
+                    EncodingKey::from_ed_der(&der),
+                    DecodingKey::from_ed_der(public),
+                    Some(params),
+                )
+            }
+            Algorithm::ES256 => {
+                let der = private_key_der(key)?;
+                let rng = SystemRandom::new();
+                let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &der, &rng)
+                    .ok()
+                    .context(invalid_key(key))?;
+
+                // Uncompressed point, 0x04 followed by both coordinates
+                let public = pair.public_key().as_ref();
+                let params = AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                    key_type: EllipticCurveKeyType::EC,
+                    curve: EllipticCurve::P256,
+                    x: URL_SAFE_NO_PAD.encode(&public[1..33]),
+                    y: URL_SAFE_NO_PAD.encode(&public[33..65]),
+                });
+                (
+                    EncodingKey::from_ec_der(&der),
+                    DecodingKey::from_ec_der(public),
+                    Some(params),
+                )
+            }
+            alg => {
+                return ConfigSnafu {
+                    msg: format!("Unsupported JWT key algorithm {:?}: {}", alg, key.kid),
+                }
+                .fail();
+            }
+        };
+
+        let jwk = params.map(|algorithm| Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(match key.alg {
+                    Algorithm::ES256 => KeyAlgorithm::ES256,
+                    _ => KeyAlgorithm::EdDSA,
+                }),
+                key_id: Some(key.kid.clone()),
+                ..Default::default()
+            },
+            algorithm,
+        });
+
+        Ok(Self {
+            kid: key.kid.clone(),
+            alg: key.alg,
+            activates_at: key.activates_at,
+            encoding,
+            decoding,
+            jwk,
+        })
+    }
+}
+
+fn invalid_key(key: &JwtKey) -> ConfigSnafu<String> {
+    ConfigSnafu {
+        msg: format!("Invalid {:?} JWT key: {}", key.alg, key.kid),
+    }
+}
+
+fn private_key_der(key: &JwtKey) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(key.secret.trim())
+        .ok()
+        .context(invalid_key(key))
+}
+
+/// New secret for the algorithm, in the format `JwtKey` expects
+pub fn generate_secret(alg: Algorithm) -> Result<String> {
+    let rng = SystemRandom::new();
+    let document = match alg {
+        Algorithm::HS256 => return Ok(cipher::random_hex(32)),
+        Algorithm::EdDSA => Ed25519KeyPair::generate_pkcs8(&rng).ok(),
+        Algorithm::ES256 => {
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).ok()
+        }
+        _ => None,
+    };
+    let document = document.context(ConfigSnafu {
+        msg: format!("Unable to generate a {:?} JWT key", alg),
+    })?;
+    Ok(STANDARD.encode(document.as_ref()))
+}
+
+/// All keys accepted for verification, the newest active one signs
+#[derive(Clone)]
+pub struct KeySet {
+    keys: Vec<LoadedKey>,
+}
+
+pub type SharedKeySet = Arc<RwLock<KeySet>>;
+
+impl KeySet {
+    pub fn new(mut keys: Vec<JwtKey>) -> Result<Self> {
+        ensure!(
+            !keys.is_empty(),
+            ConfigSnafu {
+                msg: "At least one JWT key is required.".to_string()
+            }
+        );
+
+        let mut kids: HashSet<&str> = HashSet::new();
+        for key in &keys {
+            ensure!(
+                !key.kid.is_empty() && !key.secret.is_empty(),
+                ConfigSnafu {
+                    msg: "JWT keys require a kid and a secret.".to_string()
+                }
+            );
+            ensure!(
+                kids.insert(&key.kid),
+                ConfigSnafu {
+                    msg: format!("Duplicate JWT key id: {}", key.kid)
+                }
+            );
+        }
+
+        keys.sort_by_key(|x| x.activates_at);
+        let keys = keys
+            .iter()
+            .map(LoadedKey::load)
+            .collect::<Result<Vec<LoadedKey>>>()?;
+        Ok(Self { keys })
+    }
+
+    /// Keys from the plain secret, the config and the keys file combined
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut keys: Vec<JwtKey> = Vec::new();
+        if let Some(secret) = config.jwt_secret.as_ref().filter(|x| !x.is_empty()) {
+            keys.push(JwtKey {
+                kid: DEFAULT_KID.to_string(),
+                alg: Algorithm::HS256,
+                secret: secret.clone(),
+                activates_at: 0,
+            });
+        }
+        keys.extend(config.jwt.keys.iter().cloned());
+        if let Some(path) = &config.jwt.keys_file {
+            keys.extend(read_key_file(path)?.keys);
+        }
+        Self::new(keys)
+    }
+
+    /// Newest key already active, the oldest one if none is active yet
+    pub fn signing_key(&self, now: i64) -> &LoadedKey {
+        self.keys
+            .iter()
+            .rev()
+            .find(|x| x.activates_at <= now)
+            .unwrap_or(&self.keys[0])
+    }
+
+    pub fn verification_key(&self, kid: &str) -> Option<&LoadedKey> {
+        self.keys.iter().find(|x| x.kid == kid)
+    }
+
+    /// Public keys of the asymmetric keys, including the ones not active yet
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.keys.iter().filter_map(|x| x.jwk.clone()).collect(),
+        }
+    }
+}
+
+/// A missing file has no keys yet
+pub fn read_key_file(path: &Path) -> Result<KeyFile> {
+    if !path.exists() {
+        return Ok(KeyFile::default());
+    }
+    let contents = std::fs::read_to_string(path).context(KeysFileSnafu)?;
+    toml::from_str(&contents).context(KeysParseSnafu)
+}
+
+/// Replaces the file at once so servers never read a partial keyset
+pub fn write_key_file(path: &Path, file: &KeyFile) -> Result<()> {
+    let contents = toml::to_string(file).context(KeysSerializeSnafu)?;
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, contents).context(KeysWriteSnafu)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = std::fs::Permissions::from_mode(0o600);
+        std::fs::set_permissions(&tmp, permissions).context(KeysWriteSnafu)?;
+    }
+
+    std::fs::rename(&tmp, path).context(KeysWriteSnafu)
+}
+
+/// Adds a new signing key and drops the keys no live token can use anymore
+///
+/// A key stops signing when the next one activates. Tokens it signed expire
+/// within `EXP_DURATION`, after that it is removed. The first key of an
+/// empty file signs right away unless an older key is still signing.
+pub fn rotate_keys(
+    file: KeyFile,
+    has_other_keys: bool,
+    alg: Algorithm,
+    now: i64,
+) -> Result<(KeyFile, JwtKey)> {
+    let activates_at = match file.keys.is_empty() && !has_other_keys {
+        true => now,
+        false => now + ACTIVATION_DELAY,
+    };
+    let new_key = JwtKey {
+        kid: generate_id(),
+        alg,
+        secret: generate_secret(alg)?,
+        activates_at,
+    };
+
+    let mut keys = file.keys;
+    keys.sort_by_key(|x| x.activates_at);
+
+    // Kept while the key after it activated too recently
+    let mut retained: Vec<JwtKey> = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        let replaced_at = keys.get(i + 1).map(|x| x.activates_at);
+        match replaced_at {
+            Some(replaced_at) if replaced_at + EXP_DURATION < now => continue,
+            _ => retained.push(key.clone()),
+        }
+    }
+    retained.push(new_key.clone());
+
+    Ok((KeyFile { keys: retained }, new_key))
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|x| x.modified()).ok()
+}
+
+/// Reloads the keyset whenever the keys file changes, until the server stops
+pub fn start_key_reloader(config: &Config, keys: SharedKeySet) {
+    let Some(path) = config.jwt.keys_file.clone() else {
+        return;
+    };
+
+    let config = config.clone();
+    let period = Duration::from_secs(RELOAD_SECONDS);
+
+    tokio::spawn(async move {
+        let mut last_modified = modified_at(&path);
+        let mut interval = interval_at(Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+
+            let modified = modified_at(&path);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            // Keep the current keys when the new ones are unusable
+            let res = KeySet::from_config(&config);
+            match res {
+                Ok(loaded) => {
+                    let Ok(mut current) = keys.write() else {
+                        continue;
+                    };
+                    *current = loaded;
+                    info!("JWT keys reloaded from {}", path.display());
+                }
+                Err(e) => error!("Unable to reload JWT keys: {}", e),
+            }
+        }
+    });
+}
+
+/// Current keyset, do not hold the guard across an await
+pub fn read_keys(keys: &SharedKeySet) -> Result<RwLockReadGuard<'_, KeySet>> {
+    keys.read().ok().context(ConfigSnafu {
+        msg: "JWT keys are unavailable.".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(kid: &str, activates_at: i64) -> JwtKey {
+        JwtKey {
+            kid: kid.to_string(),
+            alg: Algorithm::HS256,
+            secret: format!("secret-{}", kid),
+            activates_at,
+        }
+    }
+
+    #[test]
+    fn test_signing_key() {
+        let keys = KeySet::new(vec![key("b", 2000), key("a", 0), key("c", 5000)]).unwrap();
+        assert_eq!(keys.signing_key(1000).kid, "a");
+        assert_eq!(keys.signing_key(3000).kid, "b");
+        assert_eq!(keys.signing_key(5000).kid, "c");
+        assert!(keys.verification_key("a").is_some());
+        assert!(keys.verification_key("x").is_none());
+
+        assert!(KeySet::new(vec![]).is_err());
+        assert!(KeySet::new(vec![key("a", 0), key("a", 10)]).is_err());
+    }
+
+    #[test]
+    fn test_empty_secret() {
+        let mut config = crate::state::create_test_app_state().config;
+        config.jwt_secret = Some("".to_string());
+        config.jwt.keys = vec![key("a", 0)];
+
+        let keys = KeySet::from_config(&config).unwrap();
+        assert_eq!(keys.signing_key(1000).kid, "a");
+        assert!(keys.verification_key(DEFAULT_KID).is_none());
+    }
+
+    #[test]
+    fn test_rotate_keys() {
+        // First key of a fresh install signs right away
+        let (file, first) = rotate_keys(KeyFile::default(), false, Algorithm::HS256, 1000).unwrap();
+        assert_eq!(file.keys.len(), 1);
+        assert_eq!(first.activates_at, 1000);
+        assert_eq!(first.secret.len(), 64);
+
+        // Later keys wait for the servers to load them
+        let (file, second) = rotate_keys(file, false, Algorithm::EdDSA, 2000).unwrap();
+        assert_eq!(file.keys.len(), 2);
+        assert_eq!(second.activates_at, 2000 + ACTIVATION_DELAY);
+
+        // The first key goes once its tokens expired
+        let now = second.activates_at + EXP_DURATION + 1;
+        let (file, third) = rotate_keys(file, false, Algorithm::ES256, now).unwrap();
+        let kids: Vec<&str> = file.keys.iter().map(|x| x.kid.as_str()).collect();
+        assert_eq!(kids, vec![second.kid.as_str(), third.kid.as_str()]);
+
+        // The plain secret keeps signing until the first rotated key activates
+        let (_, key) = rotate_keys(KeyFile::default(), true, Algorithm::EdDSA, 1000).unwrap();
+        assert_eq!(key.activates_at, 1000 + ACTIVATION_DELAY);
+    }
+
+    #[test]
+    fn test_jwks() {
+        let mut keys = vec![key("a", 0)];
+        for (kid, alg) in [("b", Algorithm::EdDSA), ("c", Algorithm::ES256)] {
+            keys.push(JwtKey {
+                kid: kid.to_string(),
+                alg,
+                secret: generate_secret(alg).unwrap(),
+                activates_at: 1000,
+            });
+        }
+        let keys = KeySet::new(keys).unwrap();
+
+        // Shared secrets are never published
+        let jwks = keys.jwks();
+        assert_eq!(jwks.keys.len(), 2);
+        assert!(jwks.find("a").is_none());
+        assert!(matches!(
+            jwks.find("b").unwrap().algorithm,
+            AlgorithmParameters::OctetKeyPair(_)
+        ));
+        assert!(matches!(
+            jwks.find("c").unwrap().algorithm,
+            AlgorithmParameters::EllipticCurve(_)
+        ));
+
+        let invalid = JwtKey {
+            kid: "d".to_string(),
+            alg: Algorithm::EdDSA,
+            secret: "not a key".to_string(),
+            activates_at: 0,
+        };
+        assert!(KeySet::new(vec![invalid]).is_err());
+
+        let mut unsupported = key("e", 0);
+        unsupported.alg = Algorithm::RS256;
+        assert!(KeySet::new(vec![unsupported]).is_err());
+    }
+}
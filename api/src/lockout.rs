@@ -0,0 +1,248 @@
+use snafu::{ResultExt, ensure};
+use tracing::error;
+
+use crate::Result;
+use crate::audit::{ClientInfo, record_event};
+use crate::config::LoginConfig;
+use crate::error::{DbSnafu, LoginLockedSnafu};
+use crate::state::AppState;
+use db::audit::NewAuditEvent;
+use db::login_attempt::LoginFailure;
+use dto::audit::AuditAction;
+use dto::login_attempt::{IP_SCOPE, LoginAttemptDto, USER_SCOPE};
+
+/// Usernames and source addresses are throttled independently
+///
+/// The address is the one resolved through the trusted proxies, clients
+/// cannot dodge the limit or lock out others with forwarded headers.
+fn login_subjects(username: &str, client: &ClientInfo) -> Vec<(&'static str, String)> {
+    let mut subjects = vec![(USER_SCOPE, username.to_string())];
+    if let Some(ip) = &client.ip {
+        subjects.push((IP_SCOPE, ip.clone()));
+    }
+    subjects
+}
+
+/// Seconds before the next attempt is allowed after the given failures
+///
+/// The first failures are free, the next ones double the delay each time
+/// until the lockout threshold is reached.
+fn login_delay(config: &LoginConfig, failures: i32, lockout_after: i32) -> Option<i64> {
+    if failures >= lockout_after {
+        return Some(config.lockout_minutes * 60);
+    }
+    if failures <= config.free_attempts {
+        return None;
+    }
+
+    let exponent = (failures - config.free_attempts - 1).min(30) as u32;
+    let delay = config.base_delay_seconds.saturating_mul(1 << exponent);
+    Some(delay.min(config.max_delay_seconds))
+}
+
+fn lockout_after(config: &LoginConfig, scope: &str) -> i32 {
+    match scope {
+        USER_SCOPE => config.user_lockout_after,
+        _ => config.ip_lockout_after,
+    }
+}
+
+/// Failures counted ahead of the password check
+pub struct LoginReservation {
+    /// Attempts that reached the lockout, recorded once the login fails
+    lockouts: Vec<(LoginAttemptDto, i64)>,
+}
+
+/// Counts the attempt as failed before the password is checked
+///
+/// The lockout check and the count run in one transaction, so parallel
+/// guesses cannot all pass the check before any of them is counted. Attempts
+/// that do not fail hand the count back with `release_login_attempt`.
+pub async fn reserve_login_attempt(
+    state: &AppState,
+    username: &str,
+    client: &ClientInfo,
+) -> Result<LoginReservation> {
+    let config = state.config.login.clone();
+    let subjects = login_subjects(username, client);
+    let now = chrono::Utc::now().timestamp();
+    let failure = LoginFailure {
+        failed_at: now,
+        reset_before: now - config.reset_after_minutes * 60,
+    };
+
+    let lockouts = state
+        .db
+        .transaction(move |tx| -> Result<_> {
+            // Rejects the attempt while the username or the source address is locked
+            for (scope, subject) in &subjects {
+                let attempt = tx.login_attempts().get(scope, subject).context(DbSnafu)?;
+                if let Some(attempt) = attempt {
+                    let retry_after = attempt.retry_after(now);
+                    ensure!(retry_after == 0, LoginLockedSnafu { retry_after });
+                }
+            }
+
+            let mut lockouts = Vec::new();
+            for (scope, subject) in &subjects {
+                let attempt = tx
+                    .login_attempts()
+                    .record_failure(scope, subject, &failure)
+                    .context(DbSnafu)?;
+
+                let lockout_after = lockout_after(&config, scope);
+                let Some(delay) = login_delay(&config, attempt.failures, lockout_after) else {
+                    continue;
+                };
+                tx.login_attempts()
+                    .lock(scope, subject, now + delay)
+                    .context(DbSnafu)?;
+
+                if attempt.failures >= lockout_after {
+                    lockouts.push((attempt, now + delay));
+                }
+            }
+            Ok(lockouts)
+        })
+        .await?;
+
+    Ok(LoginReservation { lockouts })
+}
+
+/// Keeps the counted failure and records the lockouts it caused
+pub async fn fail_login_attempt(
+    state: &AppState,
+    client: &ClientInfo,
+    reservation: LoginReservation,
+) {
+    for (attempt, locked_until) in reservation.lockouts {
+        record_lockout(state, client, &attempt, locked_until).await;
+    }
+}
+
+/// Hands back the failure counted for an attempt that did not fail
+///
+/// A successful login forgets the failures of the username. Failures from
+/// the address are kept, a single known password must not reset the counter
+/// of an address trying many usernames.
+pub async fn release_login_attempt(
+    state: &AppState,
+    username: &str,
+    client: &ClientInfo,
+    succeeded: bool,
+) -> Result<()> {
+    let config = state.config.login.clone();
+    let subjects = login_subjects(username, client);
+
+    state
+        .db
+        .transaction(move |tx| -> Result<_> {
+            for (scope, subject) in &subjects {
+                if succeeded && *scope == USER_SCOPE {
+                    tx.login_attempts().clear(scope, subject).context(DbSnafu)?;
+                    continue;
+                }
+
+                let attempt = tx.login_attempts().get(scope, subject).context(DbSnafu)?;
+                let Some(attempt) = attempt else {
+                    continue;
+                };
+                let failures = attempt.failures - 1;
+                if failures <= 0 {
+                    tx.login_attempts().clear(scope, subject).context(DbSnafu)?;
+                    continue;
+                }
+
+                // The lock follows the remaining failures, counted from the last one
+                let lockout_after = lockout_after(&config, scope);
+                let locked_until = login_delay(&config, failures, lockout_after)
+                    .map(|delay| attempt.last_failed_at + delay);
+                tx.login_attempts()
+                    .release(scope, subject, locked_until)
+                    .context(DbSnafu)?;
+            }
+            Ok(())
+        })
+        .await
+}
+
+/// Forgets the failures of the username and lifts its lock
+pub async fn clear_failed_logins(state: &AppState, username: &str) -> Result<()> {
+    let _ = state
+        .db
+        .login_attempts
+        .clear(USER_SCOPE, username)
+        .await
+        .context(DbSnafu)?;
+    Ok(())
+}
+
+/// Failed logins of the username, none when there are no recent failures
+pub async fn user_lockout(state: &AppState, username: &str) -> Result<Option<LoginAttemptDto>> {
+    state
+        .db
+        .login_attempts
+        .get(USER_SCOPE, username)
+        .await
+        .context(DbSnafu)
+}
+
+/// Lockouts are recorded against the org of the user so admins can see them
+async fn record_lockout(
+    state: &AppState,
+    client: &ClientInfo,
+    attempt: &LoginAttemptDto,
+    locked_until: i64,
+) {
+    let user = match attempt.scope.as_str() {
+        USER_SCOPE => state.db.users.find_by_username(&attempt.subject).await,
+        _ => Ok(None),
+    };
+    let (org_id, user_id) = match user {
+        Ok(Some(u)) => (Some(u.org_id), Some(u.id)),
+        _ => (None, None),
+    };
+
+    let event = NewAuditEvent {
+        org_id,
+        actor_id: None,
+        action: AuditAction::LoginLocked.to_string(),
+        target_type: user_id.as_ref().map(|_| "user".to_string()),
+        target_id: user_id,
+        ip: client.ip.clone(),
+        detail: Some(format!(
+            "{}={}, failures={}, locked_until={}",
+            attempt.scope, attempt.subject, attempt.failures, locked_until
+        )),
+    };
+
+    if let Err(e) = record_event(state, event).await {
+        error!("Unable to record login lockout: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_delay() {
+        let config = LoginConfig::default();
+        let lockout_after = config.user_lockout_after;
+
+        assert_eq!(login_delay(&config, 1, lockout_after), None);
+        assert_eq!(login_delay(&config, 3, lockout_after), None);
+        assert_eq!(login_delay(&config, 4, lockout_after), Some(1));
+        assert_eq!(login_delay(&config, 5, lockout_after), Some(2));
+        assert_eq!(login_delay(&config, 9, lockout_after), Some(32));
+        assert_eq!(login_delay(&config, 10, lockout_after), Some(15 * 60));
+
+        // Delays never exceed the maximum before the lockout
+        let config = LoginConfig {
+            max_delay_seconds: 10,
+            ..LoginConfig::default()
+        };
+        assert_eq!(login_delay(&config, 8, 100), Some(10));
+        assert_eq!(login_delay(&config, 99, 100), Some(10));
+    }
+}
@@ -4,15 +4,26 @@ use run::run_command;
 use snafu::ErrorCompat;
 use std::process;
 
+mod attachment;
+mod audit;
 mod auth;
+mod backup;
 mod command;
 mod config;
 mod entry;
 mod error;
+mod group;
 mod health;
+mod keys;
+mod lockout;
 mod org;
+mod password_reset;
+mod quota;
+mod role;
 mod run;
 mod schema;
+mod service_account;
+mod session;
 mod state;
 mod token;
 mod user;
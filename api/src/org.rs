@@ -2,14 +2,11 @@ use snafu::{OptionExt, ResultExt, ensure};
 use validator::Validate;
 
 use crate::Result;
-use crate::error::{DbSnafu, MaxOrgsReachedSnafu, ValidationSnafu};
+use crate::error::{DbSnafu, MaxOrgsReachedSnafu, PreconditionFailedSnafu, ValidationSnafu};
 use crate::state::AppState;
 use db::org::{NewOrg, Org, UpdateOrg};
 use vault::validators::flatten_errors;
 
-// Can't have too many orgs
-const MAX_ORGS: i32 = 10;
-
 pub async fn create_org(state: &AppState, data: &NewOrg, admin: bool) -> Result<Org> {
     let valid_res = data.validate();
     ensure!(
@@ -19,14 +16,26 @@ pub async fn create_org(state: &AppState, data: &NewOrg, admin: bool) -> Result<
         }
     );
 
-    // Limit the number of orgs because we are poor!
-    let count = state.db.orgs.count().await.context(DbSnafu)?;
-    ensure!(count < MAX_ORGS as i64, MaxOrgsReachedSnafu,);
+    let data = data.clone();
+    let max_orgs = state.config.quotas.max_orgs;
+    state
+        .db
+        .transaction(move |tx| {
+            // Limit the number of orgs because we are poor!
+            let count = tx.orgs().count().context(DbSnafu)?;
+            ensure!(count < max_orgs, MaxOrgsReachedSnafu { max: max_orgs });
 
-    state.db.orgs.create(data, admin).await.context(DbSnafu)
+            tx.orgs().create(&data, admin).context(DbSnafu)
+        })
+        .await
 }
 
-pub async fn update_org(state: &AppState, id: &str, data: &UpdateOrg) -> Result<bool> {
+pub async fn update_org(
+    state: &AppState,
+    id: &str,
+    version: i32,
+    data: &UpdateOrg,
+) -> Result<bool> {
     let valid_res = data.validate();
     ensure!(
         valid_res.is_ok(),
@@ -35,37 +44,56 @@ pub async fn update_org(state: &AppState, id: &str, data: &UpdateOrg) -> Result<
         }
     );
 
-    state.db.orgs.update(id, data).await.context(DbSnafu)
+    state
+        .db
+        .orgs
+        .update(id, version, data)
+        .await
+        .context(DbSnafu)
 }
 
-pub async fn delete_org(state: &AppState, id: &str) -> Result<()> {
-    let org = state.db.orgs.get(id).await.context(DbSnafu)?;
-    let org = org.context(ValidationSnafu {
-        msg: "Org not found".to_string(),
-    })?;
+pub async fn delete_org(state: &AppState, id: &str, version: i32) -> Result<()> {
+    let id = id.to_string();
+    state
+        .db
+        .transaction(move |tx| {
+            let org = tx.orgs().get(&id).context(DbSnafu)?;
+            let org = org.context(ValidationSnafu {
+                msg: "Org not found".to_string(),
+            })?;
 
-    ensure!(
-        !org.admin,
-        ValidationSnafu {
-            msg: "Cannot delete admin org".to_string(),
-        }
-    );
+            ensure!(
+                !org.admin,
+                ValidationSnafu {
+                    msg: "Cannot delete admin org".to_string(),
+                }
+            );
 
-    let vault_count = state.db.vaults.count_by_org(id).await.context(DbSnafu)?;
-    ensure!(
-        vault_count == 0,
-        ValidationSnafu {
-            msg: "Org still has vaults".to_string(),
-        }
-    );
+            let vault_count = tx.vaults().count_by_org(&id).context(DbSnafu)?;
+            ensure!(
+                vault_count == 0,
+                ValidationSnafu {
+                    msg: "Org still has vaults".to_string(),
+                }
+            );
 
-    let users_count = state.db.users.count_by_org(id).await.context(DbSnafu)?;
-    ensure!(
-        users_count == 0,
-        ValidationSnafu {
-            msg: "Org still has users".to_string(),
-        }
-    );
+            let users_count = tx.users().count_by_org(&id).context(DbSnafu)?;
+            ensure!(
+                users_count == 0,
+                ValidationSnafu {
+                    msg: "Org still has users".to_string(),
+                }
+            );
+
+            let deleted = tx.orgs().delete(&id, version).context(DbSnafu)?;
+            ensure!(
+                deleted,
+                PreconditionFailedSnafu {
+                    msg: "Org has been modified by another request",
+                }
+            );
 
-    state.db.orgs.delete(id).await.context(DbSnafu)
+            Ok(())
+        })
+        .await
 }
@@ -0,0 +1,114 @@
+use snafu::{OptionExt, ResultExt, ensure};
+use validator::Validate;
+
+use crate::Result;
+use crate::audit::{ClientInfo, new_event, record_event};
+use crate::error::{
+    BadRequestSnafu, DbSnafu, InactiveUserSnafu, InvalidResetTokenSnafu, ValidationSnafu,
+};
+use crate::lockout::clear_failed_logins;
+use crate::state::AppState;
+use crate::token::RESET_DURATION;
+use db::audit::NewAuditEvent;
+use db::password_reset::{NewPasswordReset, RedeemPasswordReset};
+use db::user::UpdateUserPassword;
+use dto::actor::Actor;
+use dto::audit::AuditAction;
+use dto::password_reset::NewPasswordResetDto;
+use dto::user::UserDto;
+use vault::validators::flatten_errors;
+
+/// Issues a one-time link for the user to choose a new password
+///
+/// Only the latest link of a user works, issuing one invalidates the others.
+pub async fn issue_password_reset(
+    state: &AppState,
+    actor: &Actor,
+    client: &ClientInfo,
+    user: &UserDto,
+) -> Result<NewPasswordResetDto> {
+    // Service accounts only authenticate with API keys
+    ensure!(
+        user.kind != "service",
+        BadRequestSnafu {
+            msg: "Service accounts have no password"
+        }
+    );
+
+    let data = NewPasswordReset {
+        created_by: Some(actor.id.clone()),
+        expires_at: chrono::Utc::now().timestamp() + RESET_DURATION,
+    };
+    let created = state
+        .db
+        .password_resets
+        .create(&user.org_id, &user.id, &data)
+        .await
+        .context(DbSnafu)?;
+
+    let mut event = new_event(
+        actor,
+        client,
+        &user.org_id,
+        AuditAction::UserResetLink,
+        "user",
+        &user.id,
+    );
+    event.detail = Some(format!("reset={}", created.reset.id));
+    record_event(state, event).await?;
+
+    Ok(created)
+}
+
+/// Sets the password chosen through a reset link, the link is used up
+pub async fn redeem_password_reset(
+    state: &AppState,
+    data: &RedeemPasswordReset,
+    client: &ClientInfo,
+) -> Result<()> {
+    // Validate first so that a rejected password does not use up the link
+    let errors = data.validate();
+    ensure!(
+        errors.is_ok(),
+        ValidationSnafu {
+            msg: flatten_errors(&errors.unwrap_err()),
+        }
+    );
+
+    // The link is only used up once the password is changed
+    let token = data.token.clone();
+    let new_data = UpdateUserPassword {
+        password: data.password.clone(),
+    };
+    let (reset, user) = state
+        .db
+        .transaction(move |tx| -> Result<_> {
+            let reset = tx.password_resets().redeem(&token).context(DbSnafu)?;
+            let reset = reset.context(InvalidResetTokenSnafu)?;
+
+            let user = tx.users().get(&reset.user_id).context(DbSnafu)?;
+            let user = user.context(InvalidResetTokenSnafu)?;
+            ensure!(&user.status == "active", InactiveUserSnafu);
+
+            tx.users()
+                .update_password(&user.id, &new_data)
+                .context(DbSnafu)?;
+
+            // Existing sessions were granted under the old password
+            tx.sessions().revoke_all(&user.id, None).context(DbSnafu)?;
+            Ok((reset, user))
+        })
+        .await?;
+    clear_failed_logins(state, &user.username).await?;
+
+    let event = NewAuditEvent {
+        org_id: Some(reset.org_id.clone()),
+        actor_id: Some(user.id.clone()),
+        action: AuditAction::PasswordReset.to_string(),
+        target_type: Some("user".to_string()),
+        target_id: Some(user.id.clone()),
+        ip: client.ip.clone(),
+        detail: Some(format!("reset={}", reset.id)),
+    };
+    record_event(state, event).await
+}
@@ -0,0 +1,41 @@
+use snafu::ResultExt;
+
+use crate::Result;
+use crate::error::DbSnafu;
+use crate::state::AppState;
+use dto::quota::{OrgUsageDto, UsageDto};
+
+/// Limits in effect for an org, overrides win over the config defaults
+#[derive(Debug, Clone, Copy)]
+pub struct OrgLimits {
+    pub max_vaults: i64,
+    pub max_users: i64,
+    pub max_entries_per_vault: i64,
+}
+
+pub async fn org_limits(state: &AppState, org_id: &str) -> Result<OrgLimits> {
+    let defaults = &state.config.quotas;
+    let quota = state.db.quotas.get(org_id).await.context(DbSnafu)?;
+    let quota = quota.unwrap_or_default();
+
+    Ok(OrgLimits {
+        max_vaults: quota.max_vaults.unwrap_or(defaults.max_vaults_per_org),
+        max_users: quota.max_users.unwrap_or(defaults.max_users_per_org),
+        max_entries_per_vault: quota
+            .max_entries_per_vault
+            .unwrap_or(defaults.max_entries_per_vault),
+    })
+}
+
+pub async fn org_usage(state: &AppState, org_id: &str) -> Result<OrgUsageDto> {
+    let limits = org_limits(state, org_id).await?;
+    let usage = state.db.quotas.usage(org_id).await.context(DbSnafu)?;
+
+    Ok(OrgUsageDto {
+        org_id: org_id.to_string(),
+        vaults: UsageDto::new(usage.vaults, limits.max_vaults),
+        users: UsageDto::new(usage.users, limits.max_users),
+        entries_per_vault: UsageDto::new(usage.max_vault_entries, limits.max_entries_per_vault),
+        total_entries: usage.entries,
+    })
+}
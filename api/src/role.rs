@@ -0,0 +1,53 @@
+use snafu::{ResultExt, ensure};
+
+use crate::Result;
+use crate::error::{DbSnafu, InvalidRolesSnafu, ValidationSnafu};
+use crate::state::AppState;
+use dto::role::{Role, RoleDto, to_roles};
+
+/// Custom roles can only be assigned within the org that defines them
+pub async fn validate_org_roles(state: &AppState, org_id: &str, roles: &str) -> Result<()> {
+    let roles: Vec<String> = roles.split(",").map(|item| item.to_string()).collect();
+    let roles = to_roles(roles).context(InvalidRolesSnafu)?;
+
+    let ids: Vec<String> = roles
+        .iter()
+        .filter_map(|role| role.custom_id())
+        .map(|id| id.to_string())
+        .collect();
+    let defined = org_custom_roles(state, org_id, &ids).await?;
+
+    let unknown: Vec<String> = ids
+        .into_iter()
+        .filter(|id| !defined.iter().any(|x| &x.id == id))
+        .collect();
+    ensure!(
+        unknown.is_empty(),
+        ValidationSnafu {
+            msg: format!("Invalid roles: {}", unknown.join(", ")),
+        }
+    );
+    Ok(())
+}
+
+/// Definitions of the custom roles among the given roles
+pub async fn resolve_custom_roles(
+    state: &AppState,
+    org_id: &str,
+    roles: &[Role],
+) -> Result<Vec<RoleDto>> {
+    let ids: Vec<String> = roles
+        .iter()
+        .filter_map(|role| role.custom_id())
+        .map(|id| id.to_string())
+        .collect();
+    org_custom_roles(state, org_id, &ids).await
+}
+
+async fn org_custom_roles(state: &AppState, org_id: &str, ids: &[String]) -> Result<Vec<RoleDto>> {
+    let roles = state.db.roles.list_by_ids(ids).await.context(DbSnafu)?;
+    Ok(roles
+        .into_iter()
+        .filter(|x| x.org_id.as_deref() == Some(org_id))
+        .collect())
+}
@@ -1,14 +1,27 @@
 use crate::Result;
-use crate::command::run_setup;
+use crate::command::{
+    run_backup, run_db_check, run_keys_rotate, run_restore, run_setup, run_verify_audit,
+};
 use crate::config::CliArgs;
 use crate::config::Commands;
 use crate::config::Config;
+use crate::config::DbCommands;
+use crate::config::KeysCommands;
 use crate::web::server::run_web_server;
 
 pub async fn run_command(args: CliArgs) -> Result<()> {
     let config = Config::build(&args.config)?;
     match args.command {
-        Commands::Server => run_web_server(&config).await,
+        Commands::Server { ephemeral } => run_web_server(&config, ephemeral).await,
         Commands::Setup => run_setup(&config).await,
+        Commands::VerifyAudit => run_verify_audit(&config).await,
+        Commands::Backup { out, encrypt } => run_backup(&config, &out, encrypt).await,
+        Commands::Restore { from } => run_restore(&config, &from).await,
+        Commands::Db { command } => match command {
+            DbCommands::Check => run_db_check(&config).await,
+        },
+        Commands::Keys { command } => match command {
+            KeysCommands::Rotate { alg } => run_keys_rotate(&config, alg),
+        },
     }
 }
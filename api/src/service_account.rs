@@ -0,0 +1,54 @@
+use snafu::{OptionExt, ResultExt, ensure};
+
+use crate::Result;
+use crate::error::{DbSnafu, MaxUsersReachedSnafu, NotFoundSnafu};
+use crate::quota::org_limits;
+use crate::role::validate_org_roles;
+use crate::state::AppState;
+use db::api_key::NewApiKey;
+use db::user::{NewServiceAccount, User};
+use dto::api_key::NewApiKeyDto;
+use dto::user::UserDto;
+
+/// Service accounts count against the user limit of the org
+pub async fn create_service_account(
+    state: &AppState,
+    org_id: &str,
+    data: &NewServiceAccount,
+) -> Result<User> {
+    let max_users = org_limits(state, org_id).await?.max_users;
+    let count = state.db.users.count_by_org(org_id).await.context(DbSnafu)?;
+    ensure!(count < max_users, MaxUsersReachedSnafu { max: max_users });
+    validate_org_roles(state, org_id, &data.roles).await?;
+
+    state
+        .db
+        .users
+        .create_service_account(org_id, data)
+        .await
+        .context(DbSnafu)
+}
+
+/// Issues a key for the service account, scoped to a vault of its org if given
+pub async fn create_api_key(
+    state: &AppState,
+    account: &UserDto,
+    data: &NewApiKey,
+) -> Result<NewApiKeyDto> {
+    if let Some(vault_id) = &data.vault_id {
+        // Vaults of other orgs are treated as missing
+        let vault = state.db.vaults.get(vault_id).await.context(DbSnafu)?;
+        let _ = vault
+            .filter(|x| x.org_id == account.org_id)
+            .context(NotFoundSnafu {
+                msg: "Vault not found",
+            })?;
+    }
+
+    state
+        .db
+        .api_keys
+        .create(&account.org_id, &account.id, data)
+        .await
+        .context(DbSnafu)
+}
@@ -0,0 +1,84 @@
+use snafu::{OptionExt, ResultExt, ensure};
+
+use crate::Result;
+use crate::audit::{ClientInfo, new_event, record_event};
+use crate::error::{BadRequestSnafu, DbSnafu, NotFoundSnafu};
+use crate::state::AppState;
+use dto::actor::Actor;
+use dto::audit::AuditAction;
+use dto::user::UserDto;
+use vault::utils::valid_id;
+
+/// Revokes one of the active sessions of the user
+pub async fn revoke_session(
+    state: &AppState,
+    actor: &Actor,
+    client: &ClientInfo,
+    user: &UserDto,
+    session_id: &str,
+) -> Result<()> {
+    ensure!(
+        valid_id(session_id),
+        BadRequestSnafu {
+            msg: "Invalid session id"
+        }
+    );
+
+    // Sessions of other users are treated as missing
+    let session = state.db.sessions.get(session_id).await.context(DbSnafu)?;
+    let session = session
+        .filter(|x| x.user_id == user.id)
+        .context(NotFoundSnafu {
+            msg: "Session not found",
+        })?;
+
+    // Revoking twice is not an error
+    let _ = state
+        .db
+        .sessions
+        .revoke(&session.id)
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        actor,
+        client,
+        &user.org_id,
+        AuditAction::SessionRevoke,
+        "session",
+        &session.id,
+    );
+    record_event(state, event).await
+}
+
+/// Revokes all sessions of the user, except the current one of the actor
+pub async fn revoke_other_sessions(
+    state: &AppState,
+    actor: &Actor,
+    client: &ClientInfo,
+    user: &UserDto,
+) -> Result<usize> {
+    let except_id = match actor.user.id == user.id {
+        true => actor.session_id.clone(),
+        false => None,
+    };
+    let count = state
+        .db
+        .sessions
+        .revoke_all(&user.id, except_id)
+        .await
+        .context(DbSnafu)?;
+
+    let mut event = new_event(
+        actor,
+        client,
+        &user.org_id,
+        AuditAction::SessionRevoke,
+        "user",
+        &user.id,
+    );
+    event.detail = Some(format!("count={}", count));
+    record_event(state, event).await?;
+
+    Ok(count)
+}
@@ -1,46 +1,81 @@
 use axum::extract::FromRef;
-use std::sync::Arc;
+use snafu::ResultExt;
+use std::sync::{Arc, RwLock};
 
+use crate::backup::SharedBackupStatus;
+use crate::error::DbSnafu;
+use crate::keys::{KeySet, SharedKeySet};
 use crate::{Result, config::Config};
 use db::db::{DbMapper, create_db_mapper};
+use db::ephemeral::create_ephemeral_db_mapper;
+use db::seed::{DemoSeed, seed_demo};
 
 #[derive(Clone, FromRef)]
 pub struct AppState {
     pub config: Config,
     pub db: Arc<DbMapper>,
+    pub backup: SharedBackupStatus,
+    pub keys: SharedKeySet,
 }
 
 pub async fn create_app_state(config: &Config) -> Result<AppState> {
-    let db = create_db_mapper(config.db.url.as_str());
+    let keys = KeySet::from_config(config)?;
+    let db = create_db_mapper(config.db.url.as_str(), &config.db.options);
     Ok(AppState {
         config: config.clone(),
         db: Arc::new(db),
+        backup: SharedBackupStatus::default(),
+        keys: Arc::new(RwLock::new(keys)),
     })
 }
 
+/// Uses a seeded in-memory database instead of the configured one
+pub async fn create_ephemeral_app_state(config: &Config) -> Result<(AppState, DemoSeed)> {
+    let keys = KeySet::from_config(config)?;
+    let db = create_ephemeral_db_mapper().context(DbSnafu)?;
+    let seed = seed_demo(&db).await.context(DbSnafu)?;
+
+    let state = AppState {
+        config: config.clone(),
+        db: Arc::new(db),
+        backup: SharedBackupStatus::default(),
+        keys: Arc::new(RwLock::new(keys)),
+    };
+    Ok((state, seed))
+}
+
 #[cfg(test)]
 pub fn create_test_app_state() -> AppState {
     use std::path::PathBuf;
 
-    use crate::config::{DbConfig, ServerConfig};
-    use db::db::create_test_db_mapper;
+    use crate::config::{DbConfig, JwtConfig, LoginConfig, QuotaConfig, ServerConfig};
+    use db::db::{DbOptions, create_test_db_mapper};
 
     let config = Config {
-        jwt_secret: "0196d1dbbfd87819b9183f14ac3ed485".to_string(),
+        jwt_secret: Some("0196d1dbbfd87819b9183f14ac3ed485".to_string()),
         upload_dir: PathBuf::new(),
         server: ServerConfig {
             port: 43700,
             cors: false,
+            trusted_proxies: vec!["127.0.0.1".to_string()],
         },
         db: DbConfig {
             url: "-url".to_string(),
+            options: DbOptions::default(),
         },
+        backup: None,
+        quotas: QuotaConfig::default(),
+        login: LoginConfig::default(),
+        jwt: JwtConfig::default(),
     };
 
+    let keys = KeySet::from_config(&config).unwrap();
     let db = create_test_db_mapper();
 
     AppState {
         config,
         db: Arc::new(db),
+        backup: SharedBackupStatus::default(),
+        keys: Arc::new(RwLock::new(keys)),
     }
 }
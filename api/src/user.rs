@@ -1,13 +1,29 @@
 use snafu::{OptionExt, ResultExt, ensure};
 use validator::Validate;
 
-use crate::error::{DbSnafu, PasswordSnafu, ValidationSnafu, WhateverSnafu};
+use crate::error::{DbSnafu, MaxUsersReachedSnafu, PasswordSnafu, ValidationSnafu, WhateverSnafu};
+use crate::quota::org_limits;
+use crate::role::validate_org_roles;
 use crate::state::AppState;
 use crate::{Error, Result};
-use db::user::{ChangeCurrentPassword, UpdateUserPassword};
+use db::user::{ChangeCurrentPassword, NewUser, UpdateUserPassword, User};
 use password::verify_password;
 use vault::validators::flatten_errors;
 
+pub async fn create_user(state: &AppState, org_id: &str, data: &NewUser) -> Result<User> {
+    let max_users = org_limits(state, org_id).await?.max_users;
+    let count = state.db.users.count_by_org(org_id).await.context(DbSnafu)?;
+    ensure!(count < max_users, MaxUsersReachedSnafu { max: max_users });
+    validate_org_roles(state, org_id, &data.roles).await?;
+
+    state
+        .db
+        .users
+        .create(org_id, data, false)
+        .await
+        .context(DbSnafu)
+}
+
 pub async fn change_current_password(
     state: &AppState,
     user_id: &str,
@@ -1,16 +1,66 @@
-use snafu::{ResultExt, ensure};
+use snafu::{OptionExt, ResultExt, ensure};
 use validator::Validate;
 
 use crate::Result;
-use crate::error::{DbSnafu, MaxVaultsReachedSnafu, ValidationSnafu};
+use crate::error::{
+    DbSnafu, MaxVaultsReachedSnafu, NotFoundSnafu, PreconditionFailedSnafu, ValidationSnafu,
+};
+use crate::quota::org_limits;
 use crate::state::AppState;
-use db::vault::NewVault;
-use dto::vault::VaultDto;
+use db::member::SaveVaultMember;
+use db::vault::{NewVault, UpdateVault};
+use dto::actor::Actor;
+use dto::vault::{VaultDto, VaultGroupDto, VaultMemberDto, VaultRole};
 use vault::validators::flatten_errors;
 
-const MAX_VAULTS_PER_ORG: i32 = 10;
+/// Creates the vault with its creator as the first manager
+///
+/// System admins are not made members, they see every vault anyway.
+pub async fn create_vault(
+    state: &AppState,
+    actor: &Actor,
+    org_id: &str,
+    data: &NewVault,
+) -> Result<VaultDto> {
+    let valid_res = data.validate();
+    ensure!(
+        valid_res.is_ok(),
+        ValidationSnafu {
+            msg: flatten_errors(&valid_res.unwrap_err()),
+        }
+    );
 
-pub async fn create_vault(state: &AppState, org_id: &str, data: &NewVault) -> Result<VaultDto> {
+    let max_vaults = org_limits(state, org_id).await?.max_vaults;
+    let org_id = org_id.to_string();
+    let data = data.clone();
+    let manager_id = (!actor.is_system_admin()).then(|| actor.id.clone());
+    state
+        .db
+        .transaction(move |tx| {
+            // Limit the number of vaults per org
+            let count = tx.vaults().count_by_org(&org_id).context(DbSnafu)?;
+            ensure!(
+                count < max_vaults,
+                MaxVaultsReachedSnafu { max: max_vaults }
+            );
+
+            let vault = tx.vaults().create(&org_id, &data).context(DbSnafu)?;
+            if let Some(user_id) = manager_id {
+                tx.members()
+                    .save(&vault.id, &user_id, &VaultRole::Manager)
+                    .context(DbSnafu)?;
+            }
+            Ok(vault)
+        })
+        .await
+}
+
+pub async fn update_vault(
+    state: &AppState,
+    id: &str,
+    version: i32,
+    data: &UpdateVault,
+) -> Result<bool> {
     let valid_res = data.validate();
     ensure!(
         valid_res.is_ok(),
@@ -19,28 +69,178 @@ pub async fn create_vault(state: &AppState, org_id: &str, data: &NewVault) -> Re
         }
     );
 
-    // Limit the number of vaults per org
-    let count = state
+    state
         .db
         .vaults
-        .count_by_org(org_id)
+        .update(id, version, data)
+        .await
+        .context(DbSnafu)
+}
+
+pub async fn delete_vault(state: &AppState, id: &str, version: i32) -> Result<()> {
+    let id = id.to_string();
+    state
+        .db
+        .transaction(move |tx| {
+            // Do not delete if there are still entries inside
+            let entries_count = tx.entries().count_by_vault(&id).context(DbSnafu)?;
+            ensure!(
+                entries_count == 0,
+                ValidationSnafu {
+                    msg: "Cannot delete vault with entries inside".to_string(),
+                }
+            );
+
+            let deleted = tx.vaults().delete(&id, version).context(DbSnafu)?;
+            ensure!(
+                deleted,
+                PreconditionFailedSnafu {
+                    msg: "Vault has been modified by another request",
+                }
+            );
+
+            Ok(())
+        })
+        .await
+}
+
+/// Same actor limited to its role in the vault, non members get a not found
+///
+/// The role is the highest one granted to the actor or to any of its groups.
+/// System admins are not members of any vault and keep their permissions.
+pub async fn vault_actor(state: &AppState, actor: &Actor, vault: &VaultDto) -> Result<Actor> {
+    // Vault scoped API keys see no other vault
+    if let Some(vault_id) = &actor.vault_scope {
+        ensure!(
+            vault_id == &vault.id,
+            NotFoundSnafu {
+                msg: "Vault not found",
+            }
+        );
+    }
+
+    if actor.is_system_admin() {
+        return Ok(actor.clone());
+    }
+
+    let role = state
+        .db
+        .members
+        .effective_role(&vault.id, &actor.id)
         .await
         .context(DbSnafu)?;
+    let role = role.context(NotFoundSnafu {
+        msg: "Vault not found",
+    })?;
+
+    Ok(actor.for_vault(&role))
+}
+
+/// Vaults granted to the actor, `None` when every vault of the org is visible
+pub async fn granted_vault_ids(state: &AppState, actor: &Actor) -> Result<Option<Vec<String>>> {
+    let ids = match actor.is_system_admin() {
+        true => None,
+        false => Some(
+            state
+                .db
+                .members
+                .list_vault_ids(&actor.id)
+                .await
+                .context(DbSnafu)?,
+        ),
+    };
+
+    // Vault scoped API keys see no other vault
+    match &actor.vault_scope {
+        Some(vault_id) => {
+            let allowed = ids.is_none_or(|ids| ids.contains(vault_id));
+            Ok(Some(
+                allowed.then(|| vault_id.clone()).into_iter().collect(),
+            ))
+        }
+        None => Ok(ids),
+    }
+}
+
+/// Grants the org user access to the vault or changes its role
+pub async fn save_vault_member(
+    state: &AppState,
+    vault: &VaultDto,
+    user_id: &str,
+    data: &SaveVaultMember,
+) -> Result<VaultMemberDto> {
+    let valid_res = data.validate();
+    ensure!(
+        valid_res.is_ok(),
+        ValidationSnafu {
+            msg: flatten_errors(&valid_res.unwrap_err()),
+        }
+    );
+
+    // Users of other orgs are treated as missing
+    let user = state.db.users.get(user_id).await.context(DbSnafu)?;
+    let user = user.filter(|x| x.org_id == vault.org_id);
+    let user = user.context(NotFoundSnafu {
+        msg: "User not found",
+    })?;
 
-    ensure!(count < MAX_VAULTS_PER_ORG as i64, MaxVaultsReachedSnafu);
+    let role = VaultRole::try_from(data.role.as_str()).expect("validated vault role");
+    state
+        .db
+        .members
+        .save(&vault.id, &user.id, &role)
+        .await
+        .context(DbSnafu)?;
 
-    state.db.vaults.create(org_id, data).await.context(DbSnafu)
+    let member = state
+        .db
+        .members
+        .get(&vault.id, &user.id)
+        .await
+        .context(DbSnafu)?;
+    member.context(NotFoundSnafu {
+        msg: "Member not found",
+    })
 }
 
-pub async fn delete_vault(state: &AppState, id: &str) -> Result<()> {
-    // Do not delete if there are still entries inside
-    let entries_count = state.db.entries.count_by_vault(id).await.context(DbSnafu)?;
+/// Grants a group of the vault org access to the vault or changes its role
+pub async fn save_vault_group(
+    state: &AppState,
+    vault: &VaultDto,
+    group_id: &str,
+    data: &SaveVaultMember,
+) -> Result<VaultGroupDto> {
+    let valid_res = data.validate();
     ensure!(
-        entries_count == 0,
+        valid_res.is_ok(),
         ValidationSnafu {
-            msg: "Cannot delete vault with entries inside".to_string(),
+            msg: flatten_errors(&valid_res.unwrap_err()),
         }
     );
 
-    state.db.vaults.delete(id).await.context(DbSnafu)
+    // Groups of other orgs are treated as missing
+    let group = state.db.groups.get(group_id).await.context(DbSnafu)?;
+    let group = group.filter(|x| x.org_id == vault.org_id);
+    let group = group.context(NotFoundSnafu {
+        msg: "Group not found",
+    })?;
+
+    let role = VaultRole::try_from(data.role.as_str()).expect("validated vault role");
+    state
+        .db
+        .members
+        .save_group(&vault.id, &group.id, &role)
+        .await
+        .context(DbSnafu)?;
+
+    let grants = state
+        .db
+        .members
+        .list_groups(&vault.id)
+        .await
+        .context(DbSnafu)?;
+    let grant = grants.into_iter().find(|x| x.group_id == group.id);
+    grant.context(NotFoundSnafu {
+        msg: "Group not found",
+    })
 }
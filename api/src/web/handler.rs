@@ -1,39 +1,86 @@
 use axum::{
     Extension,
-    extract::{Json, Query, State, rejection::JsonRejection},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Body,
+    extract::{Json, Multipart, Path, Query, State, rejection::JsonRejection},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
 };
 use core::result::Result as CoreResult;
 use serde::Serialize;
 use snafu::{OptionExt, ResultExt, ensure};
+use tokio::fs::File;
+use tokio_util::io::ReaderStream;
 
 use crate::{
-    auth::authenticate,
-    entry::create_entry,
-    error::{DbSnafu, ErrorResponse, ForbiddenSnafu, JsonRejectionSnafu, Result, WhateverSnafu},
+    attachment::{attachment_path, delete_attachment, upload_attachment},
+    audit::{ClientInfo, login_failure_reason, new_event, record_event, record_login_failure},
+    auth::{authenticate, refresh_auth},
+    entry::{
+        bulk_entries, bulk_permissions, copy_entry, create_entry, delete_entry, move_entry,
+        transfer_target,
+    },
+    error::{
+        BadRequestSnafu, DbSnafu, ErrorResponse, ForbiddenSnafu, JsonRejectionSnafu, NotFoundSnafu,
+        PreconditionFailedSnafu, Result, WhateverSnafu,
+    },
+    group::add_group_member,
     health::{check_liveness, check_readiness},
+    keys::{JWKS_MAX_AGE, read_keys},
+    lockout::{clear_failed_logins, user_lockout},
     org::{create_org, delete_org, update_org},
+    password_reset::{issue_password_reset, redeem_password_reset},
+    quota::org_usage,
+    role::validate_org_roles,
+    service_account::{create_api_key, create_service_account},
+    session::{revoke_other_sessions, revoke_session},
     state::AppState,
-    user::change_current_password,
-    vault::{create_vault, delete_vault},
-    web::response::JsonResponse,
+    user::{change_current_password, create_user},
+    vault::{
+        create_vault, delete_vault, granted_vault_ids, save_vault_group, save_vault_member,
+        update_vault,
+    },
+    web::{
+        params::{
+            ApiKeyParams, GroupMemberParams, SessionParams, UserSessionParams, VaultGroupParams,
+            VaultMemberParams,
+        },
+        precondition::check_if_match,
+        response::JsonResponse,
+    },
 };
 use db::{
-    entry::{EntryPayload, ListEntriesParams},
+    api_key::NewApiKey,
+    audit::{ListAuditEventsParams, NewAuditEvent},
+    entry::{
+        BulkEntriesPayload, BulkEntryOp, EntryPayload, ListDueEntriesParams, ListEntriesParams,
+        TransferEntryPayload,
+    },
+    group::{NewGroup, UpdateGroup},
+    member::SaveVaultMember,
     org::{NewOrg, UpdateOrg},
-    user::{ChangeCurrentPassword, NewUser, UpdateUserPassword, UpdateUserRoles, UpdateUserStatus},
-    vault::NewVault,
+    password_reset::RedeemPasswordReset,
+    quota::UpdateOrgQuota,
+    role::{NewRole, UpdateRole},
+    user::{
+        ChangeCurrentPassword, NewServiceAccount, NewUser, UpdateUserPassword, UpdateUserRoles,
+        UpdateUserStatus,
+    },
+    vault::{NewVault, UpdateVault},
 };
 use dto::{
-    actor::{Actor, Credentials},
-    entry::EntryDto,
+    actor::{Actor, Credentials, RefreshCredentials},
+    attachment::AttachmentDto,
+    audit::AuditAction,
+    entry::{EntryDto, EntryFavoriteDto},
+    group::GroupDto,
     org::OrgDto,
-    pagination::PaginatedDto,
-    role::Permission,
+    pagination::{CursorPaginatedDto, PaginatedDto},
+    quota::OrgQuotaDto,
+    role::{Permission, RoleDto},
     user::UserDto,
     vault::VaultDto,
 };
+use vault::utils::valid_id;
 
 #[derive(Serialize)]
 pub struct AppMeta {
@@ -43,16 +90,102 @@ pub struct AppMeta {
 
 pub async fn authenticate_handler(
     State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
     payload: CoreResult<Json<Credentials>, JsonRejection>,
 ) -> Result<JsonResponse> {
     let credentials = payload.context(JsonRejectionSnafu {
         msg: "Invalid credentials payload",
     })?;
 
-    let res = authenticate(&state, &credentials).await?;
+    let res = match authenticate(&state, &credentials, &client).await {
+        Ok(res) => res,
+        Err(e) => {
+            if let Some(reason) = login_failure_reason(&e) {
+                record_login_failure(&state, &client, &credentials.username, reason).await;
+            }
+            return Err(e);
+        }
+    };
+
+    let event = NewAuditEvent {
+        org_id: Some(res.user.org_id.clone()),
+        actor_id: Some(res.user.id.clone()),
+        action: AuditAction::LoginSucceeded.to_string(),
+        target_type: Some("user".to_string()),
+        target_id: Some(res.user.id.clone()),
+        ip: client.ip.clone(),
+        detail: None,
+    };
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::new(serde_json::to_string(&res).unwrap()))
+}
+
+pub async fn refresh_token_handler(
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    payload: CoreResult<Json<RefreshCredentials>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let credentials = payload.context(JsonRejectionSnafu {
+        msg: "Invalid refresh token payload",
+    })?;
+
+    let res = refresh_auth(&state, &credentials, &client).await?;
+
     Ok(JsonResponse::new(serde_json::to_string(&res).unwrap()))
 }
 
+/// Sets the password chosen through an admin-issued reset link
+pub async fn redeem_password_reset_handler(
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    payload: CoreResult<Json<RedeemPasswordReset>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid password reset payload",
+    })?;
+
+    redeem_password_reset(&state, &data, &client).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn logout_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+) -> Result<JsonResponse> {
+    // API keys have no session to end
+    let session_id = actor.session_id.clone().context(BadRequestSnafu {
+        msg: "No session to log out from",
+    })?;
+
+    let _ = state
+        .db
+        .sessions
+        .revoke(&session_id)
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &actor.org_id,
+        AuditAction::Logout,
+        "user",
+        &actor.user.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
 pub async fn profile_handler(Extension(actor): Extension<Actor>) -> Result<JsonResponse> {
     Ok(JsonResponse::new(
         serde_json::to_string(&actor.user).unwrap(),
@@ -69,9 +202,64 @@ pub async fn user_authz_handler(Extension(actor): Extension<Actor>) -> Result<Js
     Ok(JsonResponse::new(serde_json::to_string(&actor).unwrap()))
 }
 
+pub async fn list_favorites_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesView];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let mut items = state
+        .db
+        .favorites
+        .list(&actor.user.id, &actor.org_id)
+        .await
+        .context(DbSnafu)?;
+
+    // Entries of vaults the user was removed from are hidden
+    if let Some(ids) = granted_vault_ids(&state, &actor).await? {
+        items.retain(|x| ids.contains(&x.vault_id));
+    }
+
+    Ok(JsonResponse::new(serde_json::to_string(&items).unwrap()))
+}
+
+pub async fn list_recents_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesView];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let mut items = state
+        .db
+        .recents
+        .list(&actor.user.id, &actor.org_id)
+        .await
+        .context(DbSnafu)?;
+
+    // Entries of vaults the user was removed from are hidden
+    if let Some(ids) = granted_vault_ids(&state, &actor).await? {
+        items.retain(|x| ids.contains(&x.vault_id));
+    }
+
+    Ok(JsonResponse::new(serde_json::to_string(&items).unwrap()))
+}
+
 pub async fn change_password_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
     payload: CoreResult<Json<ChangeCurrentPassword>, JsonRejection>,
 ) -> Result<JsonResponse> {
     let data = payload.context(JsonRejectionSnafu {
@@ -80,6 +268,65 @@ pub async fn change_password_handler(
 
     let _ = change_current_password(&state, &actor.user.id, &data).await?;
 
+    // Log out everywhere else, the current session stays
+    let _ = state
+        .db
+        .sessions
+        .revoke_all(&actor.user.id, actor.session_id.clone())
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &actor.org_id,
+        AuditAction::UserChangePassword,
+        "user",
+        &actor.user.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn list_sessions_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+) -> Result<JsonResponse> {
+    let sessions = state
+        .db
+        .sessions
+        .list(&actor.user.id)
+        .await
+        .context(DbSnafu)?;
+
+    Ok(JsonResponse::new(serde_json::to_string(&sessions).unwrap()))
+}
+
+pub async fn revoke_session_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Path(params): Path<SessionParams>,
+) -> Result<JsonResponse> {
+    revoke_session(&state, &actor, &client, &actor.user, &params.session_id).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn revoke_other_sessions_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+) -> Result<JsonResponse> {
+    let _ = revoke_other_sessions(&state, &actor, &client, &actor.user).await?;
+
     Ok(JsonResponse::with_status(
         StatusCode::NO_CONTENT,
         "".to_string(),
@@ -93,6 +340,20 @@ pub async fn home_handler() -> impl IntoResponse {
     })
 }
 
+/// Public keys for verifying access tokens without calling the API
+pub async fn jwks_handler(State(state): State<AppState>) -> Result<Response> {
+    let jwks = read_keys(&state.keys)?.jwks();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", JWKS_MAX_AGE),
+        )
+        .body(Body::from(serde_json::to_string(&jwks).unwrap()))
+        .unwrap())
+}
+
 pub async fn not_found_handler(State(_state): State<AppState>) -> impl IntoResponse {
     (
         StatusCode::NOT_FOUND,
@@ -110,7 +371,12 @@ pub async fn health_live_handler() -> Result<JsonResponse> {
 }
 
 pub async fn health_ready_handler(State(state): State<AppState>) -> Result<JsonResponse> {
-    let health = check_readiness(state.db).await?;
+    // Only report backups when the scheduler is enabled
+    let backup = match &state.config.backup {
+        Some(_) => state.backup.read().ok().map(|x| x.clone()),
+        None => None,
+    };
+    let health = check_readiness(state.db, backup).await?;
     let status = if health.is_healthy() {
         StatusCode::OK
     } else {
@@ -166,13 +432,14 @@ pub async fn create_org_handler(
 }
 
 pub async fn get_org_handler(Extension(client): Extension<OrgDto>) -> Result<JsonResponse> {
-    Ok(JsonResponse::new(serde_json::to_string(&client).unwrap()))
+    Ok(JsonResponse::new(serde_json::to_string(&client).unwrap()).with_etag(client.version))
 }
 
 pub async fn update_org_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
     Extension(org): Extension<OrgDto>,
+    headers: HeaderMap,
     payload: CoreResult<Json<UpdateOrg>, JsonRejection>,
 ) -> Result<JsonResponse> {
     let permissions = vec![Permission::OrgsEdit];
@@ -183,35 +450,103 @@ pub async fn update_org_handler(
         }
     );
 
+    check_if_match(&headers, org.version)?;
+
     let data = payload.context(JsonRejectionSnafu {
         msg: "Invalid request payload",
     })?;
 
     // No changes, just return the org
     if data.name.is_none() {
-        return Ok(JsonResponse::new(serde_json::to_string(&org).unwrap()));
+        return Ok(JsonResponse::new(serde_json::to_string(&org).unwrap()).with_etag(org.version));
     }
 
-    let updated = update_org(&state, org.id.as_str(), &data).await?;
-    if !updated {
-        // No changes, just return the org
-        return Ok(JsonResponse::new(serde_json::to_string(&org).unwrap()));
-    }
+    let updated = update_org(&state, org.id.as_str(), org.version, &data).await?;
+    ensure!(
+        updated,
+        PreconditionFailedSnafu {
+            msg: "Org has been modified by another request",
+        }
+    );
 
     let updated_org = state.db.orgs.get(org.id.as_str()).await.context(DbSnafu)?;
     let updated_org = updated_org.context(WhateverSnafu {
         msg: "Unable to find updated org",
     })?;
 
-    Ok(JsonResponse::new(
-        serde_json::to_string(&updated_org).unwrap(),
-    ))
+    Ok(
+        JsonResponse::new(serde_json::to_string(&updated_org).unwrap())
+            .with_etag(updated_org.version),
+    )
+}
+
+pub async fn get_org_usage_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::OrgsView];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let usage = org_usage(&state, &org.id).await?;
+    Ok(JsonResponse::new(serde_json::to_string(&usage).unwrap()))
+}
+
+pub async fn get_org_quota_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::OrgsManage];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let quota = state.db.quotas.get(&org.id).await.context(DbSnafu)?;
+    let quota = quota.unwrap_or(OrgQuotaDto {
+        org_id: org.id,
+        ..Default::default()
+    });
+
+    Ok(JsonResponse::new(serde_json::to_string(&quota).unwrap()))
+}
+
+pub async fn update_org_quota_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+    payload: CoreResult<Json<UpdateOrgQuota>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::OrgsManage];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let quota = state.db.quotas.set(&org.id, &data).await.context(DbSnafu)?;
+
+    Ok(JsonResponse::new(serde_json::to_string(&quota).unwrap()))
 }
 
 pub async fn delete_org_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
     Extension(org): Extension<OrgDto>,
+    headers: HeaderMap,
 ) -> Result<JsonResponse> {
     let permissions = vec![Permission::OrgsDelete];
     ensure!(
@@ -220,6 +555,8 @@ pub async fn delete_org_handler(
             msg: "Insufficient permissions"
         }
     );
+
+    check_if_match(&headers, org.version)?;
     ensure!(
         !org.admin,
         ForbiddenSnafu {
@@ -227,7 +564,7 @@ pub async fn delete_org_handler(
         }
     );
 
-    let _ = delete_org(&state, &org.id).await?;
+    delete_org(&state, &org.id, org.version).await?;
 
     Ok(JsonResponse::with_status(
         StatusCode::NO_CONTENT,
@@ -247,20 +584,26 @@ pub async fn list_vaults_handler(
             msg: "Insufficient permissions"
         }
     );
-    let vaults = state.db.vaults.list(&org.id).await.context(DbSnafu)?;
+    let mut vaults = state.db.vaults.list(&org.id).await.context(DbSnafu)?;
+    if let Some(ids) = granted_vault_ids(&state, &actor).await? {
+        vaults.retain(|x| ids.contains(&x.id));
+    }
     Ok(JsonResponse::new(serde_json::to_string(&vaults).unwrap()))
 }
 
 pub async fn get_vault_handler(Extension(vault): Extension<VaultDto>) -> Result<JsonResponse> {
-    Ok(JsonResponse::new(serde_json::to_string(&vault).unwrap()))
+    Ok(JsonResponse::new(serde_json::to_string(&vault).unwrap()).with_etag(vault.version))
 }
 
-pub async fn delete_vault_handler(
+pub async fn update_vault_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
     Extension(vault): Extension<VaultDto>,
+    headers: HeaderMap,
+    payload: CoreResult<Json<UpdateVault>, JsonRejection>,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::VaultsDelete];
+    let permissions = vec![Permission::VaultsEdit];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
@@ -268,21 +611,54 @@ pub async fn delete_vault_handler(
         }
     );
 
-    let _ = delete_vault(&state, vault.id.as_str()).await?;
+    check_if_match(&headers, vault.version)?;
 
-    Ok(JsonResponse::with_status(
-        StatusCode::NO_CONTENT,
-        "".to_string(),
-    ))
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let updated = update_vault(&state, vault.id.as_str(), vault.version, &data).await?;
+    ensure!(
+        updated,
+        PreconditionFailedSnafu {
+            msg: "Vault has been modified by another request",
+        }
+    );
+
+    let updated_vault = state
+        .db
+        .vaults
+        .get(vault.id.as_str())
+        .await
+        .context(DbSnafu)?;
+    let updated_vault = updated_vault.context(WhateverSnafu {
+        msg: "Unable to find updated vault",
+    })?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::VaultUpdate,
+        "vault",
+        &vault.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(
+        JsonResponse::new(serde_json::to_string(&updated_vault).unwrap())
+            .with_etag(updated_vault.version),
+    )
 }
 
-pub async fn create_vault_handler(
+pub async fn delete_vault_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
-    Extension(org): Extension<OrgDto>,
-    payload: CoreResult<Json<NewVault>, JsonRejection>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    headers: HeaderMap,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::VaultsCreate];
+    let permissions = vec![Permission::VaultsDelete];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
@@ -290,42 +666,52 @@ pub async fn create_vault_handler(
         }
     );
 
-    let data = payload.context(JsonRejectionSnafu {
-        msg: "Invalid request payload",
-    })?;
+    check_if_match(&headers, vault.version)?;
 
-    let vault = create_vault(&state, &org.id, &data).await?;
+    delete_vault(&state, vault.id.as_str(), vault.version).await?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::VaultDelete,
+        "vault",
+        &vault.id,
+    );
+    record_event(&state, event).await?;
 
     Ok(JsonResponse::with_status(
-        StatusCode::CREATED,
-        serde_json::to_string(&vault).unwrap(),
+        StatusCode::NO_CONTENT,
+        "".to_string(),
     ))
 }
 
-pub async fn list_users_handler(
+pub async fn list_vault_members_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
-    Extension(org): Extension<OrgDto>,
+    Extension(vault): Extension<VaultDto>,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::UsersList];
+    let permissions = vec![Permission::VaultsManage];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
             msg: "Insufficient permissions"
         }
     );
-    let users = state.db.users.list(&org.id).await.context(DbSnafu)?;
-    let dto: Vec<UserDto> = users.into_iter().map(|x| x.into()).collect();
-    Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()))
+
+    let members = state.db.members.list(&vault.id).await.context(DbSnafu)?;
+    Ok(JsonResponse::new(serde_json::to_string(&members).unwrap()))
 }
 
-pub async fn create_user_handler(
+pub async fn save_vault_member_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
-    Extension(org): Extension<OrgDto>,
-    payload: CoreResult<Json<NewUser>, JsonRejection>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Path(params): Path<VaultMemberParams>,
+    payload: CoreResult<Json<SaveVaultMember>, JsonRejection>,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::UsersCreate];
+    let permissions = vec![Permission::VaultsManage];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
@@ -333,35 +719,41 @@ pub async fn create_user_handler(
         }
     );
 
+    ensure!(
+        valid_id(&params.user_id),
+        BadRequestSnafu {
+            msg: "Invalid user id"
+        }
+    );
+
     let data = payload.context(JsonRejectionSnafu {
         msg: "Invalid request payload",
     })?;
 
-    let user = state
-        .db
-        .users
-        .create(&org.id, &data, false)
-        .await
-        .context(DbSnafu)?;
-    let dto: UserDto = user.into();
+    let member = save_vault_member(&state, &vault, &params.user_id, &data).await?;
 
-    Ok(JsonResponse::with_status(
-        StatusCode::CREATED,
-        serde_json::to_string(&dto).unwrap(),
-    ))
-}
+    let mut event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::VaultGrant,
+        "vault",
+        &vault.id,
+    );
+    event.detail = Some(format!("user_id={}, role={}", member.user_id, member.role));
+    record_event(&state, event).await?;
 
-pub async fn get_user_handler(Extension(user): Extension<UserDto>) -> Result<JsonResponse> {
-    Ok(JsonResponse::new(serde_json::to_string(&user).unwrap()))
+    Ok(JsonResponse::new(serde_json::to_string(&member).unwrap()))
 }
 
-pub async fn update_user_status_handler(
+pub async fn delete_vault_member_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
-    Extension(user): Extension<UserDto>,
-    payload: CoreResult<Json<UpdateUserStatus>, JsonRejection>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Path(params): Path<VaultMemberParams>,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::UsersEdit];
+    let permissions = vec![Permission::VaultsManage];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
@@ -369,15 +761,266 @@ pub async fn update_user_status_handler(
         }
     );
 
-    // Do not allow updating your own user
+    let deleted = state
+        .db
+        .members
+        .delete(&vault.id, &params.user_id)
+        .await
+        .context(DbSnafu)?;
     ensure!(
-        &actor.user.id != &user.id,
-        ForbiddenSnafu {
-            msg: "Updating your own user account not allowed"
+        deleted,
+        NotFoundSnafu {
+            msg: "Member not found"
         }
     );
 
-    let data = payload.context(JsonRejectionSnafu {
+    let mut event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::VaultRevoke,
+        "vault",
+        &vault.id,
+    );
+    event.detail = Some(format!("user_id={}", params.user_id));
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn list_vault_groups_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(vault): Extension<VaultDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::VaultsManage];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let groups = state
+        .db
+        .members
+        .list_groups(&vault.id)
+        .await
+        .context(DbSnafu)?;
+    Ok(JsonResponse::new(serde_json::to_string(&groups).unwrap()))
+}
+
+pub async fn save_vault_group_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Path(params): Path<VaultGroupParams>,
+    payload: CoreResult<Json<SaveVaultMember>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::VaultsManage];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    ensure!(
+        valid_id(&params.group_id),
+        BadRequestSnafu {
+            msg: "Invalid group id"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let grant = save_vault_group(&state, &vault, &params.group_id, &data).await?;
+
+    let mut event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::VaultGrant,
+        "vault",
+        &vault.id,
+    );
+    event.detail = Some(format!("group_id={}, role={}", grant.group_id, grant.role));
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::new(serde_json::to_string(&grant).unwrap()))
+}
+
+pub async fn delete_vault_group_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Path(params): Path<VaultGroupParams>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::VaultsManage];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let deleted = state
+        .db
+        .members
+        .delete_group(&vault.id, &params.group_id)
+        .await
+        .context(DbSnafu)?;
+    ensure!(
+        deleted,
+        NotFoundSnafu {
+            msg: "Group not found"
+        }
+    );
+
+    let mut event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::VaultRevoke,
+        "vault",
+        &vault.id,
+    );
+    event.detail = Some(format!("group_id={}", params.group_id));
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn create_vault_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(org): Extension<OrgDto>,
+    payload: CoreResult<Json<NewVault>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::VaultsCreate];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let vault = create_vault(&state, &actor, &org.id, &data).await?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &org.id,
+        AuditAction::VaultCreate,
+        "vault",
+        &vault.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&vault).unwrap(),
+    ))
+}
+
+pub async fn list_users_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersList];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+    let users = state.db.users.list(&org.id).await.context(DbSnafu)?;
+    let dto: Vec<UserDto> = users.into_iter().map(|x| x.into()).collect();
+    Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()))
+}
+
+pub async fn create_user_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(org): Extension<OrgDto>,
+    payload: CoreResult<Json<NewUser>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersCreate];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let user = create_user(&state, &org.id, &data).await?;
+    let dto: UserDto = user.into();
+
+    let event = new_event(
+        &actor,
+        &client,
+        &org.id,
+        AuditAction::UserCreate,
+        "user",
+        &dto.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&dto).unwrap(),
+    ))
+}
+
+pub async fn get_user_handler(Extension(user): Extension<UserDto>) -> Result<JsonResponse> {
+    Ok(JsonResponse::new(serde_json::to_string(&user).unwrap()).with_etag(user.version))
+}
+
+pub async fn update_user_status_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(user): Extension<UserDto>,
+    payload: CoreResult<Json<UpdateUserStatus>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    // Do not allow updating your own user
+    ensure!(
+        &actor.user.id != &user.id,
+        ForbiddenSnafu {
+            msg: "Updating your own user account not allowed"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
         msg: "Invalid request payload",
     })?;
 
@@ -389,6 +1032,24 @@ pub async fn update_user_status_handler(
         .await
         .context(DbSnafu)?;
 
+    // Existing sessions were granted under the old status
+    let _ = state
+        .db
+        .sessions
+        .revoke_all(&user.id, None)
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &user.org_id,
+        AuditAction::UserUpdateStatus,
+        "user",
+        &user.id,
+    );
+    record_event(&state, event).await?;
+
     // Re-query and show
     let updated_user = state.db.users.get(&user.id).await.context(DbSnafu)?;
     let updated_user = updated_user.context(WhateverSnafu {
@@ -402,6 +1063,7 @@ pub async fn update_user_status_handler(
 pub async fn update_user_roles_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
     Extension(user): Extension<UserDto>,
     payload: CoreResult<Json<UpdateUserRoles>, JsonRejection>,
 ) -> Result<JsonResponse> {
@@ -425,6 +1087,8 @@ pub async fn update_user_roles_handler(
         msg: "Invalid request payload",
     })?;
 
+    validate_org_roles(&state, &user.org_id, &data.roles).await?;
+
     // Ideally, should not update if roles do not change
     let _ = state
         .db
@@ -433,6 +1097,24 @@ pub async fn update_user_roles_handler(
         .await
         .context(DbSnafu)?;
 
+    // Existing sessions were granted under the old roles
+    let _ = state
+        .db
+        .sessions
+        .revoke_all(&user.id, None)
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &user.org_id,
+        AuditAction::UserUpdateRoles,
+        "user",
+        &user.id,
+    );
+    record_event(&state, event).await?;
+
     // Re-query and show
     let updated_user = state.db.users.get(&user.id).await.context(DbSnafu)?;
     let updated_user = updated_user.context(WhateverSnafu {
@@ -446,6 +1128,7 @@ pub async fn update_user_roles_handler(
 pub async fn reset_user_password_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
     Extension(user): Extension<UserDto>,
     payload: CoreResult<Json<UpdateUserPassword>, JsonRejection>,
 ) -> Result<JsonResponse> {
@@ -476,6 +1159,24 @@ pub async fn reset_user_password_handler(
         .await
         .context(DbSnafu)?;
 
+    // Existing sessions were granted under the old password
+    let _ = state
+        .db
+        .sessions
+        .revoke_all(&user.id, None)
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &user.org_id,
+        AuditAction::UserResetPassword,
+        "user",
+        &user.id,
+    );
+    record_event(&state, event).await?;
+
     // Re-query and show
     let updated_user = state.db.users.get(&user.id).await.context(DbSnafu)?;
     let updated_user = updated_user.context(WhateverSnafu {
@@ -486,12 +1187,14 @@ pub async fn reset_user_password_handler(
     Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()))
 }
 
-pub async fn delete_user_handler(
+/// Issues a one-time link for the user to choose a new password
+pub async fn create_password_reset_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
     Extension(user): Extension<UserDto>,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::UsersDelete];
+    let permissions = vec![Permission::UsersEdit];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
@@ -499,29 +1202,28 @@ pub async fn delete_user_handler(
         }
     );
 
-    // Do not allow deleting your own user account
+    // Do not allow updating your own user
     ensure!(
-        &actor.user.id != &user.id,
+        actor.user.id != user.id,
         ForbiddenSnafu {
-            msg: "Deleting your own user account not allowed"
+            msg: "Updating your own user account not allowed"
         }
     );
 
-    let _ = state.db.users.delete(&user.id).await.context(DbSnafu)?;
+    let created = issue_password_reset(&state, &actor, &client, &user).await?;
 
     Ok(JsonResponse::with_status(
-        StatusCode::NO_CONTENT,
-        "".to_string(),
+        StatusCode::CREATED,
+        serde_json::to_string(&created).unwrap(),
     ))
 }
 
-pub async fn list_entries_handler(
+pub async fn get_user_lockout_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
-    Extension(vault): Extension<VaultDto>,
-    query: Query<ListEntriesParams>,
+    Extension(user): Extension<UserDto>,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::EntriesList, Permission::EntriesView];
+    let permissions = vec![Permission::UsersView];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
@@ -529,32 +1231,21 @@ pub async fn list_entries_handler(
         }
     );
 
-    let entries = state
-        .db
-        .entries
-        .list(&vault.id, &query)
-        .await
-        .context(DbSnafu)?;
-
-    // Generate download urls for each files
-    let items: Vec<EntryDto> = entries.data.into_iter().map(|f| f.into()).collect();
+    let attempt = user_lockout(&state, &user.username).await?;
+    let attempt = attempt.context(NotFoundSnafu {
+        msg: "No failed logins",
+    })?;
 
-    let listing = PaginatedDto::new(
-        items,
-        entries.meta.page,
-        entries.meta.per_page,
-        entries.meta.total_records,
-    );
-    Ok(JsonResponse::new(serde_json::to_string(&listing).unwrap()))
+    Ok(JsonResponse::new(serde_json::to_string(&attempt).unwrap()))
 }
 
-pub async fn create_entry_handler(
+pub async fn unlock_user_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
-    Extension(vault): Extension<VaultDto>,
-    payload: CoreResult<Json<EntryPayload>, JsonRejection>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(user): Extension<UserDto>,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::EntriesCreate];
+    let permissions = vec![Permission::UsersEdit];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
@@ -562,31 +1253,30 @@ pub async fn create_entry_handler(
         }
     );
 
-    let data = payload.context(JsonRejectionSnafu {
-        msg: "Invalid request payload",
-    })?;
+    clear_failed_logins(&state, &user.username).await?;
 
-    let entry = create_entry(state, &vault, &data).await?;
-    let dto: EntryDto = entry.into();
+    let event = new_event(
+        &actor,
+        &client,
+        &user.org_id,
+        AuditAction::UserUnlock,
+        "user",
+        &user.id,
+    );
+    record_event(&state, event).await?;
 
     Ok(JsonResponse::with_status(
-        StatusCode::CREATED,
-        serde_json::to_string(&dto).unwrap(),
+        StatusCode::NO_CONTENT,
+        "".to_string(),
     ))
 }
 
-pub async fn get_entry_handler(Extension(entry): Extension<EntryDto>) -> Result<JsonResponse> {
-    Ok(JsonResponse::new(serde_json::to_string(&entry).unwrap()))
-}
-
-#[axum::debug_handler]
-pub async fn update_entry_handler(
+pub async fn list_user_sessions_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
-    Extension(entry): Extension<EntryDto>,
-    payload: CoreResult<Json<EntryPayload>, JsonRejection>,
+    Extension(user): Extension<UserDto>,
 ) -> Result<JsonResponse> {
-    let permissions = vec![Permission::EntriesEdit];
+    let permissions = vec![Permission::SessionsList];
     ensure!(
         actor.has_permissions(&permissions),
         ForbiddenSnafu {
@@ -594,17 +1284,967 @@ pub async fn update_entry_handler(
         }
     );
 
-    let data = payload.context(JsonRejectionSnafu {
-        msg: "Invalid request payload",
-    })?;
+    let sessions = state.db.sessions.list(&user.id).await.context(DbSnafu)?;
 
-    let _ = state
-        .db
-        .entries
-        .update(&entry.id, &data)
+    Ok(JsonResponse::new(serde_json::to_string(&sessions).unwrap()))
+}
+
+pub async fn revoke_user_session_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(user): Extension<UserDto>,
+    Path(params): Path<UserSessionParams>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::SessionsRevoke];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    revoke_session(&state, &actor, &client, &user, &params.session_id).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn revoke_user_sessions_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(user): Extension<UserDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::SessionsRevoke];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let _ = revoke_other_sessions(&state, &actor, &client, &user).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn delete_user_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(user): Extension<UserDto>,
+    headers: HeaderMap,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersDelete];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    check_if_match(&headers, user.version)?;
+
+    // Do not allow deleting your own user account
+    ensure!(
+        &actor.user.id != &user.id,
+        ForbiddenSnafu {
+            msg: "Deleting your own user account not allowed"
+        }
+    );
+
+    let deleted = state
+        .db
+        .users
+        .delete(&user.id, user.version)
+        .await
+        .context(DbSnafu)?;
+    ensure!(
+        deleted,
+        PreconditionFailedSnafu {
+            msg: "User has been modified by another request",
+        }
+    );
+
+    let event = new_event(
+        &actor,
+        &client,
+        &user.org_id,
+        AuditAction::UserDelete,
+        "user",
+        &user.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn list_groups_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersList];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let groups = state.db.groups.list(&org.id).await.context(DbSnafu)?;
+    Ok(JsonResponse::new(serde_json::to_string(&groups).unwrap()))
+}
+
+pub async fn create_group_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(org): Extension<OrgDto>,
+    payload: CoreResult<Json<NewGroup>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersCreate];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+    validate_org_roles(&state, &org.id, &data.roles).await?;
+
+    let group = state
+        .db
+        .groups
+        .create(&org.id, &data)
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &org.id,
+        AuditAction::GroupCreate,
+        "group",
+        &group.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&group).unwrap(),
+    ))
+}
+
+pub async fn get_group_handler(Extension(group): Extension<GroupDto>) -> Result<JsonResponse> {
+    Ok(JsonResponse::new(serde_json::to_string(&group).unwrap()).with_etag(group.version))
+}
+
+pub async fn update_group_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(group): Extension<GroupDto>,
+    headers: HeaderMap,
+    payload: CoreResult<Json<UpdateGroup>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    check_if_match(&headers, group.version)?;
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+    if let Some(roles) = &data.roles {
+        validate_org_roles(&state, &group.org_id, roles).await?;
+    }
+
+    let updated = state
+        .db
+        .groups
+        .update(&group.id, group.version, &data)
+        .await
+        .context(DbSnafu)?;
+    ensure!(
+        updated,
+        PreconditionFailedSnafu {
+            msg: "Group has been modified by another request",
+        }
+    );
+
+    let updated_group = state.db.groups.get(&group.id).await.context(DbSnafu)?;
+    let updated_group = updated_group.context(WhateverSnafu {
+        msg: "Unable to find updated group",
+    })?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &group.org_id,
+        AuditAction::GroupUpdate,
+        "group",
+        &group.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(
+        JsonResponse::new(serde_json::to_string(&updated_group).unwrap())
+            .with_etag(updated_group.version),
+    )
+}
+
+pub async fn delete_group_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(group): Extension<GroupDto>,
+    headers: HeaderMap,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersDelete];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    check_if_match(&headers, group.version)?;
+
+    // Memberships and vault grants of the group go away with it
+    let deleted = state
+        .db
+        .groups
+        .delete(&group.id, group.version)
+        .await
+        .context(DbSnafu)?;
+    ensure!(
+        deleted,
+        PreconditionFailedSnafu {
+            msg: "Group has been modified by another request",
+        }
+    );
+
+    let event = new_event(
+        &actor,
+        &client,
+        &group.org_id,
+        AuditAction::GroupDelete,
+        "group",
+        &group.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn list_group_members_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(group): Extension<GroupDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersList];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let members = state
+        .db
+        .groups
+        .list_members(&group.id)
+        .await
+        .context(DbSnafu)?;
+    Ok(JsonResponse::new(serde_json::to_string(&members).unwrap()))
+}
+
+pub async fn add_group_member_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(group): Extension<GroupDto>,
+    Path(params): Path<GroupMemberParams>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    ensure!(
+        valid_id(&params.user_id),
+        BadRequestSnafu {
+            msg: "Invalid user id"
+        }
+    );
+
+    let member = add_group_member(&state, &actor, &group, &params.user_id).await?;
+
+    let mut event = new_event(
+        &actor,
+        &client,
+        &group.org_id,
+        AuditAction::GroupAddMember,
+        "group",
+        &group.id,
+    );
+    event.detail = Some(format!("user_id={}", member.user_id));
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::new(serde_json::to_string(&member).unwrap()))
+}
+
+pub async fn remove_group_member_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(group): Extension<GroupDto>,
+    Path(params): Path<GroupMemberParams>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let removed = state
+        .db
+        .groups
+        .remove_member(&group.id, &params.user_id)
+        .await
+        .context(DbSnafu)?;
+    ensure!(
+        removed,
+        NotFoundSnafu {
+            msg: "Member not found"
+        }
+    );
+
+    let mut event = new_event(
+        &actor,
+        &client,
+        &group.org_id,
+        AuditAction::GroupRemoveMember,
+        "group",
+        &group.id,
+    );
+    event.detail = Some(format!("user_id={}", params.user_id));
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn list_roles_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersList];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let roles = state.db.roles.list(&org.id).await.context(DbSnafu)?;
+    Ok(JsonResponse::new(serde_json::to_string(&roles).unwrap()))
+}
+
+pub async fn create_role_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(org): Extension<OrgDto>,
+    payload: CoreResult<Json<NewRole>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersCreate];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let role = state
+        .db
+        .roles
+        .create(&org.id, &data)
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &org.id,
+        AuditAction::RoleCreate,
+        "role",
+        &role.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&role).unwrap(),
+    ))
+}
+
+pub async fn get_role_handler(Extension(role): Extension<RoleDto>) -> Result<JsonResponse> {
+    Ok(JsonResponse::new(serde_json::to_string(&role).unwrap()).with_etag(role.version))
+}
+
+pub async fn update_role_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(org): Extension<OrgDto>,
+    Extension(role): Extension<RoleDto>,
+    headers: HeaderMap,
+    payload: CoreResult<Json<UpdateRole>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+    ensure!(
+        !role.builtin,
+        ForbiddenSnafu {
+            msg: "Built-in roles cannot be modified"
+        }
+    );
+
+    check_if_match(&headers, role.version)?;
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let updated = state
+        .db
+        .roles
+        .update(&role.id, role.version, &data)
+        .await
+        .context(DbSnafu)?;
+    ensure!(
+        updated,
+        PreconditionFailedSnafu {
+            msg: "Role has been modified by another request",
+        }
+    );
+
+    let updated_role = state.db.roles.get(&role.id).await.context(DbSnafu)?;
+    let updated_role = updated_role.context(WhateverSnafu {
+        msg: "Unable to find updated role",
+    })?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &org.id,
+        AuditAction::RoleUpdate,
+        "role",
+        &role.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(
+        JsonResponse::new(serde_json::to_string(&updated_role).unwrap())
+            .with_etag(updated_role.version),
+    )
+}
+
+pub async fn delete_role_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(org): Extension<OrgDto>,
+    Extension(role): Extension<RoleDto>,
+    headers: HeaderMap,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersDelete];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+    ensure!(
+        !role.builtin,
+        ForbiddenSnafu {
+            msg: "Built-in roles cannot be deleted"
+        }
+    );
+
+    check_if_match(&headers, role.version)?;
+
+    // Refused while users or groups still hold the role
+    let deleted = state
+        .db
+        .roles
+        .delete(&role.id, role.version)
+        .await
+        .context(DbSnafu)?;
+    ensure!(
+        deleted,
+        PreconditionFailedSnafu {
+            msg: "Role has been modified by another request",
+        }
+    );
+
+    let event = new_event(
+        &actor,
+        &client,
+        &org.id,
+        AuditAction::RoleDelete,
+        "role",
+        &role.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn list_service_accounts_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersList];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+    let users = state.db.users.list(&org.id).await.context(DbSnafu)?;
+    let dto: Vec<UserDto> = users
+        .into_iter()
+        .filter(|x| x.is_service_account())
+        .map(|x| x.into())
+        .collect();
+    Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()))
+}
+
+pub async fn create_service_account_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(org): Extension<OrgDto>,
+    payload: CoreResult<Json<NewServiceAccount>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersCreate];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let user = create_service_account(&state, &org.id, &data).await?;
+    let dto: UserDto = user.into();
+
+    let event = new_event(
+        &actor,
+        &client,
+        &org.id,
+        AuditAction::ServiceAccountCreate,
+        "user",
+        &dto.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&dto).unwrap(),
+    ))
+}
+
+pub async fn list_api_keys_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDto>,
+) -> Result<JsonResponse> {
+    let keys = state.db.api_keys.list(&user.id).await.context(DbSnafu)?;
+    Ok(JsonResponse::new(serde_json::to_string(&keys).unwrap()))
+}
+
+pub async fn create_api_key_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(user): Extension<UserDto>,
+    payload: CoreResult<Json<NewApiKey>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersCreate];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    // The full key is only ever returned here
+    let created = create_api_key(&state, &user, &data).await?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &user.org_id,
+        AuditAction::ApiKeyCreate,
+        "api_key",
+        &created.api_key.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&created).unwrap(),
+    ))
+}
+
+pub async fn revoke_api_key_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(user): Extension<UserDto>,
+    Path(params): Path<ApiKeyParams>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::UsersEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    ensure!(
+        valid_id(&params.key_id),
+        BadRequestSnafu {
+            msg: "Invalid API key id"
+        }
+    );
+
+    let api_key = state
+        .db
+        .api_keys
+        .get(&params.key_id)
+        .await
+        .context(DbSnafu)?;
+    let api_key = api_key
+        .filter(|x| x.user_id == user.id)
+        .context(NotFoundSnafu {
+            msg: "API key not found",
+        })?;
+
+    // Revoking twice is not an error
+    let _ = state
+        .db
+        .api_keys
+        .revoke(&api_key.id)
+        .await
+        .context(DbSnafu)?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &user.org_id,
+        AuditAction::ApiKeyRevoke,
+        "api_key",
+        &api_key.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn list_entries_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(vault): Extension<VaultDto>,
+    query: Query<ListEntriesParams>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesList, Permission::EntriesView];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    if query.is_cursor_mode() {
+        let entries = state
+            .db
+            .entries
+            .list_by_cursor(&vault.id, &query)
+            .await
+            .context(DbSnafu)?;
+
+        let items: Vec<EntryDto> = entries.data.into_iter().map(|f| f.into()).collect();
+        let listing = CursorPaginatedDto::new(items, entries.meta);
+        return Ok(JsonResponse::new(serde_json::to_string(&listing).unwrap()));
+    }
+
+    let entries = state
+        .db
+        .entries
+        .list(&vault.id, &query)
+        .await
+        .context(DbSnafu)?;
+
+    // Generate download urls for each files
+    let items: Vec<EntryDto> = entries.data.into_iter().map(|f| f.into()).collect();
+
+    let listing = PaginatedDto::new(
+        items,
+        entries.meta.page,
+        entries.meta.per_page,
+        entries.meta.total_records,
+    );
+    Ok(JsonResponse::new(serde_json::to_string(&listing).unwrap()))
+}
+
+pub async fn create_entry_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    payload: CoreResult<Json<EntryPayload>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesCreate];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let entry = create_entry(state.clone(), &vault, &data).await?;
+    let dto: EntryDto = entry.into();
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::EntryCreate,
+        "entry",
+        &dto.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&dto).unwrap(),
+    ))
+}
+
+pub async fn bulk_entries_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    payload: CoreResult<Json<BulkEntriesPayload>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let permissions = bulk_permissions(&data);
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let results = bulk_entries(&state, &vault, &data).await?;
+
+    for result in results.iter() {
+        let action = match data.operations[result.index] {
+            BulkEntryOp::Create { .. } => AuditAction::EntryCreate,
+            BulkEntryOp::Update { .. } => AuditAction::EntryUpdate,
+            BulkEntryOp::Delete { .. } => AuditAction::EntryDelete,
+        };
+        let event = new_event(&actor, &client, &vault.org_id, action, "entry", &result.id);
+        record_event(&state, event).await?;
+    }
+
+    Ok(JsonResponse::new(serde_json::to_string(&results).unwrap()))
+}
+
+pub async fn get_entry_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(entry): Extension<EntryDto>,
+) -> Result<JsonResponse> {
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::EntryView,
+        "entry",
+        &entry.id,
+    );
+    record_event(&state, event).await?;
+
+    state
+        .db
+        .entries
+        .mark_used(&entry.id)
+        .await
+        .context(DbSnafu)?;
+
+    state
+        .db
+        .recents
+        .record(&actor.user.id, &entry.id)
+        .await
+        .context(DbSnafu)?;
+
+    Ok(JsonResponse::new(serde_json::to_string(&entry).unwrap()).with_etag(entry.version))
+}
+
+pub async fn get_favorite_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(entry): Extension<EntryDto>,
+) -> Result<JsonResponse> {
+    let favorite = state
+        .db
+        .favorites
+        .is_favorite(&actor.user.id, &entry.id)
+        .await
+        .context(DbSnafu)?;
+
+    let dto = EntryFavoriteDto {
+        entry_id: entry.id,
+        favorite,
+    };
+    Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()))
+}
+
+pub async fn toggle_favorite_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(entry): Extension<EntryDto>,
+) -> Result<JsonResponse> {
+    let favorite = state
+        .db
+        .favorites
+        .toggle(&actor.user.id, &entry.id)
         .await
         .context(DbSnafu)?;
 
+    let dto = EntryFavoriteDto {
+        entry_id: entry.id,
+        favorite,
+    };
+    Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()))
+}
+
+#[axum::debug_handler]
+pub async fn update_entry_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(entry): Extension<EntryDto>,
+    headers: HeaderMap,
+    payload: CoreResult<Json<EntryPayload>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    check_if_match(&headers, entry.version)?;
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    // Another request may have updated the entry since it was loaded
+    let updated = state
+        .db
+        .entries
+        .update(&entry.id, entry.version, &data)
+        .await
+        .context(DbSnafu)?;
+
+    ensure!(
+        updated,
+        PreconditionFailedSnafu {
+            msg: "Entry has been modified by another request",
+        }
+    );
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::EntryUpdate,
+        "entry",
+        &entry.id,
+    );
+    record_event(&state, event).await?;
+
     // Re-query and show
     let updated_entry = state.db.entries.get(&entry.id).await.context(DbSnafu)?;
     let updated_entry = updated_entry.context(WhateverSnafu {
@@ -612,13 +2252,97 @@ pub async fn update_entry_handler(
     })?;
     let dto: EntryDto = updated_entry.into();
 
-    Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()))
+    Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()).with_etag(dto.version))
+}
+
+pub async fn move_entry_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(entry): Extension<EntryDto>,
+    headers: HeaderMap,
+    payload: CoreResult<Json<TransferEntryPayload>, JsonRejection>,
+) -> Result<JsonResponse> {
+    // Removed from one vault and created in the other
+    let permissions = vec![Permission::EntriesCreate, Permission::EntriesDelete];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    check_if_match(&headers, entry.version)?;
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let target = transfer_target(&state, &actor, &vault, &data).await?;
+    let moved = move_entry(&state, &vault, &target, &entry, &data).await?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::EntryMove,
+        "entry",
+        &moved.id,
+    );
+    record_event(&state, event).await?;
+
+    let dto: EntryDto = moved.into();
+    Ok(JsonResponse::new(serde_json::to_string(&dto).unwrap()).with_etag(dto.version))
+}
+
+pub async fn copy_entry_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(entry): Extension<EntryDto>,
+    payload: CoreResult<Json<TransferEntryPayload>, JsonRejection>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesCreate];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let data = payload.context(JsonRejectionSnafu {
+        msg: "Invalid request payload",
+    })?;
+
+    let target = transfer_target(&state, &actor, &vault, &data).await?;
+    let copied = copy_entry(&state, &target, &entry, &data).await?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::EntryCopy,
+        "entry",
+        &copied.id,
+    );
+    record_event(&state, event).await?;
+
+    let dto: EntryDto = copied.into();
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&dto).unwrap(),
+    ))
 }
 
 pub async fn delete_entry_handler(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
     Extension(entry): Extension<EntryDto>,
+    headers: HeaderMap,
 ) -> Result<JsonResponse> {
     let permissions = vec![Permission::EntriesDelete];
     ensure!(
@@ -628,11 +2352,202 @@ pub async fn delete_entry_handler(
         }
     );
 
-    // Delete record
-    let _ = state.db.entries.delete(&entry.id).await.context(DbSnafu)?;
+    check_if_match(&headers, entry.version)?;
+
+    delete_entry(&state, &vault, &entry).await?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::EntryDelete,
+        "entry",
+        &entry.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::NO_CONTENT,
+        "".to_string(),
+    ))
+}
+
+pub async fn list_attachments_handler(
+    State(state): State<AppState>,
+    Extension(entry): Extension<EntryDto>,
+) -> Result<JsonResponse> {
+    let attachments = state
+        .db
+        .attachments
+        .list_by_entry(&entry.id)
+        .await
+        .context(DbSnafu)?;
+
+    Ok(JsonResponse::new(
+        serde_json::to_string(&attachments).unwrap(),
+    ))
+}
+
+pub async fn upload_attachment_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(entry): Extension<EntryDto>,
+    multipart: Multipart,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let attachment = upload_attachment(&state, &entry, multipart).await?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::AttachmentUpload,
+        "attachment",
+        &attachment.id,
+    );
+    record_event(&state, event).await?;
+
+    Ok(JsonResponse::with_status(
+        StatusCode::CREATED,
+        serde_json::to_string(&attachment).unwrap(),
+    ))
+}
+
+pub async fn get_attachment_handler(
+    Extension(attachment): Extension<AttachmentDto>,
+) -> Result<JsonResponse> {
+    Ok(JsonResponse::new(
+        serde_json::to_string(&attachment).unwrap(),
+    ))
+}
+
+pub async fn download_attachment_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(attachment): Extension<AttachmentDto>,
+) -> Result<Response<Body>> {
+    let path = attachment_path(&state.config, &attachment.id);
+    let file = File::open(&path).await.ok().context(NotFoundSnafu {
+        msg: "Attachment file not found",
+    })?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::AttachmentDownload,
+        "attachment",
+        &attachment.id,
+    );
+    record_event(&state, event).await?;
+
+    // Stream the encrypted blob as-is, decryption happens in the client
+    let body = Body::from_stream(ReaderStream::new(file));
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, attachment.size)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", attachment.id),
+        )
+        .header("X-Content-Sha256", attachment.sha256.as_str())
+        .body(body)
+        .unwrap())
+}
+
+pub async fn delete_attachment_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(client): Extension<ClientInfo>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(attachment): Extension<AttachmentDto>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesEdit];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    delete_attachment(&state, &attachment).await?;
+
+    let event = new_event(
+        &actor,
+        &client,
+        &vault.org_id,
+        AuditAction::AttachmentDelete,
+        "attachment",
+        &attachment.id,
+    );
+    record_event(&state, event).await?;
 
     Ok(JsonResponse::with_status(
         StatusCode::NO_CONTENT,
         "".to_string(),
     ))
 }
+
+pub async fn list_due_entries_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+    query: Query<ListDueEntriesParams>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::EntriesList];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let mut items = state
+        .db
+        .entries
+        .list_due(&org.id, &query)
+        .await
+        .context(DbSnafu)?;
+    if let Some(ids) = granted_vault_ids(&state, &actor).await? {
+        items.retain(|x| ids.contains(&x.vault_id));
+    }
+
+    Ok(JsonResponse::new(serde_json::to_string(&items).unwrap()))
+}
+
+pub async fn list_audit_events_handler(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+    query: Query<ListAuditEventsParams>,
+) -> Result<JsonResponse> {
+    let permissions = vec![Permission::AuditList, Permission::AuditView];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    let events = state
+        .db
+        .audit
+        .list(&org.id, &query)
+        .await
+        .context(DbSnafu)?;
+
+    Ok(JsonResponse::new(serde_json::to_string(&events).unwrap()))
+}
@@ -1,26 +1,72 @@
 use axum::{
-    Extension,
+    Extension, RequestExt,
     body::Body,
-    extract::{Path, Request, State},
+    extract::{ConnectInfo, Path, Request, State},
     http::header,
     middleware::Next,
     response::Response,
 };
 use snafu::{OptionExt, ResultExt, ensure};
+use std::net::SocketAddr;
 
 use crate::{
     Result,
-    auth::authenticate_token,
+    audit::{ClientInfo, resolve_client_ip},
+    auth::{authenticate_api_key, authenticate_token},
     error::{
         BadRequestSnafu, DbSnafu, ForbiddenSnafu, InsufficientAuthScopeSnafu,
         InvalidAuthTokenSnafu, NotFoundSnafu,
     },
     state::AppState,
+    vault::vault_actor,
+};
+use dto::{
+    actor::Actor,
+    attachment::AttachmentDto,
+    entry::EntryDto,
+    org::OrgDto,
+    role::{Permission, Role},
+    user::UserDto,
 };
-use dto::{actor::Actor, entry::EntryDto, org::OrgDto, role::Permission, user::UserDto};
 use vault::utils::valid_id;
 
-use super::params::{EntryParams, OrgParams, UserParams, VaultParams};
+use super::params::{
+    AttachmentParams, EntryParams, GroupParams, OrgParams, RoleParams, UserParams, VaultParams,
+};
+
+pub async fn client_info_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response<Body> {
+    let peer = request
+        .extract_parts::<ConnectInfo<SocketAddr>>()
+        .await
+        .ok()
+        .map(|info| info.0.ip());
+
+    // Requests proxied by the website carry the original client address
+    let forwarded: Vec<&str> = request
+        .headers()
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+    let ip = resolve_client_ip(peer, &forwarded, &state.config.server.trusted_proxies);
+
+    let user_agent = request
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|header| header.to_str().ok())
+        .map(|value| value.chars().take(250).collect::<String>())
+        .filter(|value| !value.is_empty());
+
+    let client = ClientInfo { ip, user_agent };
+
+    request.extensions_mut().insert(client);
+    next.run(request).await
+}
 
 pub async fn auth_middleware(
     State(state): State<AppState>,
@@ -39,10 +85,19 @@ pub async fn auth_middleware(
 
     if let Some(auth_header) = auth_header {
         // At this point, authentication must be verified
-        ensure!(auth_header.starts_with("Bearer "), InvalidAuthTokenSnafu);
-        let token = auth_header.replace("Bearer ", "");
-
-        actor = authenticate_token(&state, &token).await?;
+        if let Some(key) = auth_header.strip_prefix("ApiKey ") {
+            let client = request
+                .extensions()
+                .get::<ClientInfo>()
+                .cloned()
+                .unwrap_or_default();
+            actor = authenticate_api_key(&state, key, &client).await?;
+        } else {
+            ensure!(auth_header.starts_with("Bearer "), InvalidAuthTokenSnafu);
+            let token = auth_header.replace("Bearer ", "");
+
+            actor = authenticate_token(&state, &token).await?;
+        }
     }
 
     // Forward to the next middleware/handler passing the actor information
@@ -121,6 +176,22 @@ pub async fn prevent_admin_org_middleware(
     Ok(next.run(request).await)
 }
 
+/// API keys cannot be used to issue more keys or accounts
+pub async fn prevent_api_key_middleware(
+    Extension(actor): Extension<Actor>,
+    request: Request,
+    next: Next,
+) -> Result<Response<Body>> {
+    ensure!(
+        !actor.is_api_key(),
+        ForbiddenSnafu {
+            msg: "API keys cannot manage service accounts"
+        }
+    );
+
+    Ok(next.run(request).await)
+}
+
 pub async fn vault_middleware(
     State(state): State<AppState>,
     Extension(actor): Extension<Actor>,
@@ -170,7 +241,11 @@ pub async fn vault_middleware(
         );
     }
 
+    // Only members see the vault, their permissions follow the vault role
+    let actor = vault_actor(&state, &actor, &vault).await?;
+
     // Forward to the next middleware/handler passing the vault information
+    request.extensions_mut().insert(actor);
     request.extensions_mut().insert(vault);
     let response = next.run(request).await;
     Ok(response)
@@ -220,6 +295,132 @@ pub async fn user_middleware(
     Ok(response)
 }
 
+pub async fn service_account_middleware(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+    Path(params): Path<UserParams>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response<Body>> {
+    let permissions = vec![Permission::UsersList, Permission::UsersView];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    ensure!(
+        valid_id(&params.user_id),
+        BadRequestSnafu {
+            msg: "Invalid user id"
+        }
+    );
+
+    // Regular users are not service accounts
+    let user = state.db.users.get(&params.user_id).await.context(DbSnafu)?;
+    let user = user.filter(|x| x.org_id == org.id && x.is_service_account());
+    let user = user.context(NotFoundSnafu {
+        msg: "Service account not found",
+    })?;
+
+    let user: UserDto = user.into();
+
+    // Forward to the next middleware/handler passing the user information
+    request.extensions_mut().insert(user);
+    let response = next.run(request).await;
+    Ok(response)
+}
+
+pub async fn group_middleware(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+    Path(params): Path<GroupParams>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response<Body>> {
+    let permissions = vec![Permission::UsersList, Permission::UsersView];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    ensure!(
+        valid_id(&params.group_id),
+        BadRequestSnafu {
+            msg: "Invalid group id"
+        }
+    );
+
+    let group = state
+        .db
+        .groups
+        .get(&params.group_id)
+        .await
+        .context(DbSnafu)?;
+    let group = group.context(NotFoundSnafu {
+        msg: "Group not found",
+    })?;
+
+    ensure!(
+        group.org_id == org.id,
+        NotFoundSnafu {
+            msg: "Group not found"
+        }
+    );
+
+    // Forward to the next middleware/handler passing the group information
+    request.extensions_mut().insert(group);
+    let response = next.run(request).await;
+    Ok(response)
+}
+
+pub async fn role_middleware(
+    State(state): State<AppState>,
+    Extension(actor): Extension<Actor>,
+    Extension(org): Extension<OrgDto>,
+    Path(params): Path<RoleParams>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response<Body>> {
+    let permissions = vec![Permission::UsersList, Permission::UsersView];
+    ensure!(
+        actor.has_permissions(&permissions),
+        ForbiddenSnafu {
+            msg: "Insufficient permissions"
+        }
+    );
+
+    // Built-in roles are keyed by name, custom roles by id
+    ensure!(
+        Role::try_from(params.role_id.as_str()).is_ok(),
+        BadRequestSnafu {
+            msg: "Invalid role id"
+        }
+    );
+
+    let role = state.db.roles.get(&params.role_id).await.context(DbSnafu)?;
+    let role = role.context(NotFoundSnafu {
+        msg: "Role not found",
+    })?;
+
+    ensure!(
+        role.org_id.as_ref().is_none_or(|org_id| org_id == &org.id),
+        NotFoundSnafu {
+            msg: "Role not found"
+        }
+    );
+
+    // Forward to the next middleware/handler passing the role information
+    request.extensions_mut().insert(role);
+    let response = next.run(request).await;
+    Ok(response)
+}
+
 pub async fn entry_middleware(
     state: State<AppState>,
     Extension(actor): Extension<Actor>,
@@ -234,6 +435,7 @@ pub async fn entry_middleware(
         }
     );
 
+    // Already limited to the vault role by the vault middleware
     let permissions = vec![Permission::EntriesList, Permission::EntriesView];
     ensure!(
         actor.has_permissions(&permissions),
@@ -263,3 +465,41 @@ pub async fn entry_middleware(
     let response = next.run(request).await;
     Ok(response)
 }
+
+pub async fn attachment_middleware(
+    state: State<AppState>,
+    Extension(entry): Extension<EntryDto>,
+    Path(params): Path<AttachmentParams>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response<Body>> {
+    ensure!(
+        valid_id(&params.attachment_id),
+        BadRequestSnafu {
+            msg: "Invalid attachment id"
+        }
+    );
+
+    let attachment = state
+        .db
+        .attachments
+        .get(&params.attachment_id)
+        .await
+        .context(DbSnafu)?;
+
+    let attachment: AttachmentDto = attachment.context(NotFoundSnafu {
+        msg: "Attachment not found",
+    })?;
+
+    ensure!(
+        attachment.entry_id == entry.id && attachment.entry_id == params.entry_id,
+        NotFoundSnafu {
+            msg: "Attachment not found"
+        }
+    );
+
+    // Forward to the next middleware/handler passing the attachment information
+    request.extensions_mut().insert(attachment);
+    let response = next.run(request).await;
+    Ok(response)
+}
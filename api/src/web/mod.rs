@@ -1,6 +1,7 @@
 pub mod handler;
 pub mod middleware;
 pub mod params;
+pub mod precondition;
 pub mod response;
 pub mod routes;
 pub mod server;
@@ -13,6 +13,60 @@ pub struct UserParams {
     pub user_id: String,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct GroupParams {
+    #[allow(dead_code)]
+    pub org_id: String,
+
+    pub group_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RoleParams {
+    #[allow(dead_code)]
+    pub org_id: String,
+
+    pub role_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SessionParams {
+    pub session_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UserSessionParams {
+    #[allow(dead_code)]
+    pub org_id: String,
+
+    #[allow(dead_code)]
+    pub user_id: String,
+
+    pub session_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ApiKeyParams {
+    #[allow(dead_code)]
+    pub org_id: String,
+
+    #[allow(dead_code)]
+    pub user_id: String,
+
+    pub key_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GroupMemberParams {
+    #[allow(dead_code)]
+    pub org_id: String,
+
+    #[allow(dead_code)]
+    pub group_id: String,
+
+    pub user_id: String,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct VaultParams {
     #[allow(dead_code)]
@@ -21,6 +75,28 @@ pub struct VaultParams {
     pub vault_id: String,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct VaultMemberParams {
+    #[allow(dead_code)]
+    pub org_id: String,
+
+    #[allow(dead_code)]
+    pub vault_id: String,
+
+    pub user_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VaultGroupParams {
+    #[allow(dead_code)]
+    pub org_id: String,
+
+    #[allow(dead_code)]
+    pub vault_id: String,
+
+    pub group_id: String,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct EntryParams {
     #[allow(dead_code)]
@@ -30,3 +106,16 @@ pub struct EntryParams {
 
     pub entry_id: String,
 }
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AttachmentParams {
+    #[allow(dead_code)]
+    pub org_id: String,
+
+    #[allow(dead_code)]
+    pub vault_id: String,
+
+    pub entry_id: String,
+
+    pub attachment_id: String,
+}
@@ -0,0 +1,59 @@
+use axum::http::{HeaderMap, header};
+use snafu::{OptionExt, ensure};
+
+use crate::Result;
+use crate::error::{PreconditionFailedSnafu, PreconditionRequiredSnafu};
+
+/// Strong entity tag for a resource version
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Requires the If-Match header to match the current version of the resource,
+/// preventing lost updates when two users edit the same resource
+pub fn check_if_match(headers: &HeaderMap, version: i32) -> Result<()> {
+    let value = headers
+        .get(header::IF_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .context(PreconditionRequiredSnafu)?;
+
+    let current = etag(version);
+    let matched = value
+        .split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag == current);
+
+    ensure!(
+        matched,
+        PreconditionFailedSnafu {
+            msg: "Resource has been modified by another request",
+        }
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn headers_with(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_check_if_match() {
+        assert!(check_if_match(&headers_with("\"3\""), 3).is_ok());
+        assert!(check_if_match(&headers_with("\"2\", \"3\""), 3).is_ok());
+        assert!(check_if_match(&headers_with("*"), 3).is_ok());
+
+        assert!(check_if_match(&headers_with("\"2\""), 3).is_err());
+        assert!(check_if_match(&headers_with("3"), 3).is_err());
+        assert!(check_if_match(&headers_with("W/\"3\""), 3).is_err());
+        assert!(check_if_match(&HeaderMap::new(), 3).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use axum::response::IntoResponse;
 use axum::{body::Body, http::StatusCode, response::Response};
 
+use super::precondition::etag;
+
 #[derive(Debug)]
 pub struct JsonResponse {
     pub status_code: StatusCode,
     pub data: String,
+    pub etag: Option<String>,
 }
 
 impl JsonResponse {
@@ -12,20 +15,35 @@ impl JsonResponse {
         JsonResponse {
             status_code: StatusCode::OK,
             data,
+            etag: None,
         }
     }
 
     pub fn with_status(status_code: StatusCode, data: String) -> Self {
-        JsonResponse { status_code, data }
+        JsonResponse {
+            status_code,
+            data,
+            etag: None,
+        }
+    }
+
+    /// Exposes the resource version for conditional requests
+    pub fn with_etag(mut self, version: i32) -> Self {
+        self.etag = Some(etag(version));
+        self
     }
 }
 
 impl IntoResponse for JsonResponse {
     fn into_response(self) -> Response<Body> {
-        Response::builder()
+        let mut builder = Response::builder()
             .status(self.status_code)
-            .header("Content-Type", "application/json")
-            .body(Body::from(self.data))
-            .unwrap()
+            .header("Content-Type", "application/json");
+
+        if let Some(etag) = self.etag {
+            builder = builder.header("ETag", etag);
+        }
+
+        builder.body(Body::from(self.data)).unwrap()
     }
 }
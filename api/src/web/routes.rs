@@ -1,22 +1,43 @@
 use axum::{
-    Router, middleware,
-    routing::{any, get, post},
+    Router,
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{any, delete, get, post, put},
 };
 
 use super::{
     handler::{
-        authenticate_handler, change_password_handler, create_entry_handler, create_org_handler,
-        create_user_handler, create_vault_handler, delete_entry_handler, delete_org_handler,
-        delete_user_handler, delete_vault_handler, get_entry_handler, get_org_handler,
-        get_user_handler, get_vault_handler, health_live_handler, health_ready_handler,
-        home_handler, list_entries_handler, list_orgs_handler, list_users_handler,
-        list_vaults_handler, not_found_handler, profile_handler, reset_user_password_handler,
-        update_entry_handler, update_org_handler, update_user_roles_handler,
-        update_user_status_handler, user_authz_handler, user_permissions_handler,
+        add_group_member_handler, authenticate_handler, bulk_entries_handler,
+        change_password_handler, copy_entry_handler, create_api_key_handler, create_entry_handler,
+        create_group_handler, create_org_handler, create_password_reset_handler,
+        create_role_handler, create_service_account_handler, create_user_handler,
+        create_vault_handler, delete_attachment_handler, delete_entry_handler,
+        delete_group_handler, delete_org_handler, delete_role_handler, delete_user_handler,
+        delete_vault_group_handler, delete_vault_handler, delete_vault_member_handler,
+        download_attachment_handler, get_attachment_handler, get_entry_handler,
+        get_favorite_handler, get_group_handler, get_org_handler, get_org_quota_handler,
+        get_org_usage_handler, get_role_handler, get_user_handler, get_user_lockout_handler,
+        get_vault_handler, health_live_handler, health_ready_handler, home_handler, jwks_handler,
+        list_api_keys_handler, list_attachments_handler, list_audit_events_handler,
+        list_due_entries_handler, list_entries_handler, list_favorites_handler,
+        list_group_members_handler, list_groups_handler, list_orgs_handler, list_recents_handler,
+        list_roles_handler, list_service_accounts_handler, list_sessions_handler,
+        list_user_sessions_handler, list_users_handler, list_vault_groups_handler,
+        list_vault_members_handler, list_vaults_handler, logout_handler, move_entry_handler,
+        not_found_handler, profile_handler, redeem_password_reset_handler, refresh_token_handler,
+        remove_group_member_handler, reset_user_password_handler, revoke_api_key_handler,
+        revoke_other_sessions_handler, revoke_session_handler, revoke_user_session_handler,
+        revoke_user_sessions_handler, save_vault_group_handler, save_vault_member_handler,
+        toggle_favorite_handler, unlock_user_handler, update_entry_handler, update_group_handler,
+        update_org_handler, update_org_quota_handler, update_role_handler,
+        update_user_roles_handler, update_user_status_handler, update_vault_handler,
+        upload_attachment_handler, user_authz_handler, user_permissions_handler,
     },
     middleware::{
-        auth_middleware, entry_middleware, org_middleware, prevent_admin_org_middleware,
-        require_auth_middleware, user_middleware, vault_middleware,
+        attachment_middleware, auth_middleware, client_info_middleware, entry_middleware,
+        group_middleware, org_middleware, prevent_admin_org_middleware, prevent_api_key_middleware,
+        require_auth_middleware, role_middleware, service_account_middleware, user_middleware,
+        vault_middleware,
     },
 };
 use crate::state::AppState;
@@ -26,6 +47,10 @@ pub fn all_routes(state: AppState) -> Router {
         .merge(public_routes(state.clone()))
         .merge(private_routes(state.clone()))
         .fallback(any(not_found_handler))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            client_info_middleware,
+        ))
         .with_state(state)
 }
 
@@ -34,7 +59,10 @@ fn public_routes(state: AppState) -> Router<AppState> {
         .route("/", get(home_handler))
         .route("/health/liveness", get(health_live_handler))
         .route("/health/readiness", get(health_ready_handler))
+        .route("/.well-known/jwks.json", get(jwks_handler))
         .route("/auth/token", post(authenticate_handler))
+        .route("/auth/refresh", post(refresh_token_handler))
+        .route("/auth/reset_password", post(redeem_password_reset_handler))
         .with_state(state)
 }
 
@@ -65,7 +93,16 @@ pub fn user_routes(state: AppState) -> Router<AppState> {
         .route("/", get(profile_handler))
         .route("/permissions", get(user_permissions_handler))
         .route("/authz", get(user_authz_handler))
+        .route("/favorites", get(list_favorites_handler))
+        .route("/recents", get(list_recents_handler))
         .route("/change_password", post(change_password_handler))
+        .route("/logout", post(logout_handler))
+        .route("/sessions", get(list_sessions_handler))
+        .route(
+            "/sessions/revoke_others",
+            post(revoke_other_sessions_handler),
+        )
+        .route("/sessions/{session_id}", delete(revoke_session_handler))
         .with_state(state)
 }
 
@@ -77,7 +114,20 @@ fn inner_org_routes(state: AppState) -> Router<AppState> {
                 .patch(update_org_handler)
                 .delete(delete_org_handler),
         )
+        .route("/audit", get(list_audit_events_handler))
+        .route("/due_entries", get(list_due_entries_handler))
+        .route("/usage", get(get_org_usage_handler))
+        .route(
+            "/quota",
+            get(get_org_quota_handler).put(update_org_quota_handler),
+        )
         .nest("/users", org_users_routes(state.clone()))
+        .nest("/groups", org_groups_routes(state.clone()))
+        .nest("/roles", org_roles_routes(state.clone()))
+        .nest(
+            "/service_accounts",
+            org_service_accounts_routes(state.clone()),
+        )
         .nest("/vaults", org_vaults_routes(state.clone()))
         .layer(middleware::from_fn_with_state(
             state.clone(),
@@ -108,6 +158,15 @@ fn inner_user_routes(state: AppState) -> Router<AppState> {
         .route("/update_status", post(update_user_status_handler))
         .route("/update_roles", post(update_user_roles_handler))
         .route("/reset_password", post(reset_user_password_handler))
+        .route("/reset_link", post(create_password_reset_handler))
+        .route("/lockout", get(get_user_lockout_handler))
+        .route("/unlock", post(unlock_user_handler))
+        .route("/sessions", get(list_user_sessions_handler))
+        .route("/sessions/revoke_all", post(revoke_user_sessions_handler))
+        .route(
+            "/sessions/{session_id}",
+            delete(revoke_user_session_handler),
+        )
         .layer(middleware::from_fn_with_state(
             state.clone(),
             user_middleware,
@@ -115,6 +174,92 @@ fn inner_user_routes(state: AppState) -> Router<AppState> {
         .with_state(state)
 }
 
+fn org_groups_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_groups_handler).post(create_group_handler))
+        .nest("/{group_id}", inner_group_routes(state.clone()))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            prevent_admin_org_middleware,
+        ))
+        .with_state(state)
+}
+
+fn inner_group_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_group_handler)
+                .patch(update_group_handler)
+                .delete(delete_group_handler),
+        )
+        .route("/members", get(list_group_members_handler))
+        .route(
+            "/members/{user_id}",
+            put(add_group_member_handler).delete(remove_group_member_handler),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            group_middleware,
+        ))
+        .with_state(state)
+}
+
+fn org_roles_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_roles_handler).post(create_role_handler))
+        .nest("/{role_id}", inner_role_routes(state.clone()))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            prevent_admin_org_middleware,
+        ))
+        .with_state(state)
+}
+
+fn org_service_accounts_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(list_service_accounts_handler).post(create_service_account_handler),
+        )
+        .nest("/{user_id}", inner_service_account_routes(state.clone()))
+        .layer(middleware::from_fn(prevent_api_key_middleware))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            prevent_admin_org_middleware,
+        ))
+        .with_state(state)
+}
+
+fn inner_service_account_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/keys",
+            get(list_api_keys_handler).post(create_api_key_handler),
+        )
+        .route("/keys/{key_id}", delete(revoke_api_key_handler))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            service_account_middleware,
+        ))
+        .with_state(state)
+}
+
+fn inner_role_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_role_handler)
+                .patch(update_role_handler)
+                .delete(delete_role_handler),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            role_middleware,
+        ))
+        .with_state(state)
+}
+
 fn org_vaults_routes(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", get(list_vaults_handler).post(create_vault_handler))
@@ -128,7 +273,22 @@ fn org_vaults_routes(state: AppState) -> Router<AppState> {
 
 fn inner_vault_routes(state: AppState) -> Router<AppState> {
     Router::new()
-        .route("/", get(get_vault_handler).delete(delete_vault_handler))
+        .route(
+            "/",
+            get(get_vault_handler)
+                .patch(update_vault_handler)
+                .delete(delete_vault_handler),
+        )
+        .route("/members", get(list_vault_members_handler))
+        .route(
+            "/members/{user_id}",
+            put(save_vault_member_handler).delete(delete_vault_member_handler),
+        )
+        .route("/groups", get(list_vault_groups_handler))
+        .route(
+            "/groups/{group_id}",
+            put(save_vault_group_handler).delete(delete_vault_group_handler),
+        )
         .nest("/entries", entry_routes(state.clone()))
         .layer(middleware::from_fn_with_state(
             state.clone(),
@@ -140,6 +300,7 @@ fn inner_vault_routes(state: AppState) -> Router<AppState> {
 fn entry_routes(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", get(list_entries_handler).post(create_entry_handler))
+        .route("/bulk", post(bulk_entries_handler))
         .nest("/{entry_id}", inner_entry_routes(state.clone()))
         .with_state(state)
 }
@@ -152,9 +313,45 @@ fn inner_entry_routes(state: AppState) -> Router<AppState> {
                 .patch(update_entry_handler)
                 .delete(delete_entry_handler),
         )
+        .route(
+            "/favorite",
+            get(get_favorite_handler).post(toggle_favorite_handler),
+        )
+        .route("/move", post(move_entry_handler))
+        .route("/copy", post(copy_entry_handler))
+        .nest("/attachments", attachment_routes(state.clone()))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             entry_middleware,
         ))
         .with_state(state)
 }
+
+fn attachment_routes(state: AppState) -> Router<AppState> {
+    // Leave some room for the multipart boundaries and the filename field
+    let body_limit = (state.config.quotas.max_attachment_size + 64 * 1024) as usize;
+
+    Router::new()
+        .route(
+            "/",
+            get(list_attachments_handler)
+                .post(upload_attachment_handler)
+                .layer(DefaultBodyLimit::max(body_limit)),
+        )
+        .nest("/{attachment_id}", inner_attachment_routes(state.clone()))
+        .with_state(state)
+}
+
+fn inner_attachment_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_attachment_handler).delete(delete_attachment_handler),
+        )
+        .route("/download", get(download_attachment_handler))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            attachment_middleware,
+        ))
+        .with_state(state)
+}
@@ -1,4 +1,5 @@
 use axum::{Router, body::Body, middleware, response::Response};
+use std::net::SocketAddr;
 use std::time::Duration;
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
@@ -7,19 +8,41 @@ use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
 use tracing::{Level, error, info};
 
 use crate::Result;
+use crate::backup::start_backup_scheduler;
 use crate::config::Config;
 use crate::error::{ErrorInfo, ErrorResponse};
-use crate::state::create_app_state;
+use crate::keys::start_key_reloader;
+use crate::state::{create_app_state, create_ephemeral_app_state};
 use crate::web::routes::all_routes;
+use db::seed::{DEMO_KEY, DEMO_PASSWORD};
 
 #[cfg(test)]
 use axum_test::TestServer;
 
-pub async fn run_web_server(config: &Config) -> Result<()> {
+pub async fn run_web_server(config: &Config, ephemeral: bool) -> Result<()> {
     let port = config.server.port;
     let allow_cors = config.server.cors;
 
-    let state = create_app_state(config).await?;
+    let state = if ephemeral {
+        let (state, seed) = create_ephemeral_app_state(config).await?;
+        info!(
+            "Ephemeral mode, data is lost on shutdown. Demo users: {} (password: {}), demo key: {}",
+            seed.users
+                .iter()
+                .map(|x| format!("{} ({})", x.username, x.roles))
+                .collect::<Vec<String>>()
+                .join(", "),
+            DEMO_PASSWORD,
+            DEMO_KEY
+        );
+        state
+    } else {
+        let state = create_app_state(config).await?;
+        // Snapshots only make sense for the configured database
+        start_backup_scheduler(config, state.backup.clone());
+        state
+    };
+    start_key_reloader(config, state.keys.clone());
 
     let mut routes_all = Router::new()
         .merge(all_routes(state))
@@ -43,10 +66,13 @@ pub async fn run_web_server(config: &Config) -> Result<()> {
     info!("HTTP server running on {}", addr);
 
     let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, routes_all.into_make_service())
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+    axum::serve(
+        listener,
+        routes_all.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .unwrap();
 
     info!("HTTP server stopped");
 
@@ -105,26 +131,105 @@ async fn shutdown_signal() {
 
 #[cfg(test)]
 mod tests {
+    use crate::config::QuotaConfig;
+    use crate::state::AppState;
+    use db::seed::DemoSeed;
     use db::{
+        entry::TEST_ENTRY_ID,
         org::{TEST_ADMIN_ORG_ID, TEST_ORG_ID},
         user::{TEST_ADMIN_USER_ID, TEST_USER_ID},
         vault::TEST_VAULT_ID,
     };
 
     use super::*;
+    use axum::extract::connect_info::MockConnectInfo;
+    use axum::http::{HeaderValue, StatusCode, header};
     use dto::{
-        entry::EntryDto, org::OrgDto, pagination::PaginatedDto, user::UserDto, vault::VaultDto,
+        api_key::{ApiKeyDto, NewApiKeyDto},
+        attachment::AttachmentDto,
+        audit::AuditEventDto,
+        entry::{BulkEntryResultDto, EntryDto, EntryFavoriteDto, EntryLinkDto, EntryRotationDto},
+        group::{GroupDto, GroupMemberDto},
+        org::OrgDto,
+        pagination::{Cursor, CursorPaginatedDto, PaginatedDto},
+        quota::{OrgQuotaDto, OrgUsageDto},
+        role::RoleDto,
+        session::SessionDto,
+        user::UserDto,
+        vault::{VaultDto, VaultGroupDto, VaultMemberDto, VaultRole},
     };
     use serde_json::json;
 
     fn create_test_app() -> TestServer {
         use crate::state::create_test_app_state;
 
-        let state = create_test_app_state();
+        build_test_server(create_test_app_state())
+    }
+
+    /// Test server backed by a real in-memory database with demo data
+    async fn create_seeded_test_app() -> (TestServer, DemoSeed) {
+        use crate::state::{create_ephemeral_app_state, create_test_app_state};
+
+        let config = create_test_app_state().config;
+        let (state, seed) = create_ephemeral_app_state(&config).await.unwrap();
+        (build_test_server(state), seed)
+    }
+
+    /// Seeded test server storing attachments in a fresh temporary directory
+    async fn create_attachment_test_app(
+        name: &str,
+        quotas: QuotaConfig,
+    ) -> (TestServer, DemoSeed, std::path::PathBuf) {
+        let (state, seed, dir) = create_attachment_test_state(name, quotas).await;
+        (build_test_server(state), seed, dir)
+    }
+
+    /// Seeded app state storing attachments in a fresh temporary directory
+    async fn create_attachment_test_state(
+        name: &str,
+        quotas: QuotaConfig,
+    ) -> (AppState, DemoSeed, std::path::PathBuf) {
+        use crate::state::{create_ephemeral_app_state, create_test_app_state};
+
+        let dir = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("tmp")).unwrap();
+        std::fs::create_dir_all(dir.join("attachments")).unwrap();
+
+        let mut config = create_test_app_state().config;
+        config.upload_dir = dir.clone();
+        config.quotas = quotas;
+        let (state, seed) = create_ephemeral_app_state(&config).await.unwrap();
+        (state, seed, dir)
+    }
+
+    fn count_files(dir: &std::path::Path) -> usize {
+        std::fs::read_dir(dir).unwrap().count()
+    }
+
+    async fn login_seeded_user(server: &TestServer, username: &str) -> String {
+        let auth: serde_json::Value = server
+            .post("/auth/token")
+            .json(&json!({
+                "username": username,
+                "password": DEMO_PASSWORD,
+            }))
+            .await
+            .json();
+        auth["token"].as_str().unwrap().to_string()
+    }
 
+    fn build_test_server(state: AppState) -> TestServer {
+        // Requests arrive from the website, a trusted proxy
+        build_test_server_from(state, SocketAddr::from(([127, 0, 0, 1], 40000)))
+    }
+
+    /// Test server receiving every request from the given address
+    fn build_test_server_from(state: AppState, peer: SocketAddr) -> TestServer {
         let app = Router::new()
             .merge(all_routes(state))
-            .layer(middleware::map_response(response_mapper));
+            .layer(middleware::map_response(response_mapper))
+            .layer(MockConnectInfo(peer));
 
         TestServer::builder()
             .save_cookies()
@@ -136,9 +241,11 @@ mod tests {
     }
 
     fn create_test_user_auth_token() -> Result<String> {
+        use crate::keys::read_keys;
         use crate::state::create_test_app_state;
         use crate::token::create_auth_token;
         use db::org::TEST_ORG_ID;
+        use db::session::TEST_SESSION_ID;
         use db::user::TEST_USER_ID;
         use dto::actor::ActorPayload;
 
@@ -148,15 +255,18 @@ mod tests {
             id: TEST_USER_ID.to_string(),
             org_id: TEST_ORG_ID.to_string(),
             scope: "auth vault".to_string(),
+            session_id: Some(TEST_SESSION_ID.to_string()),
         };
 
-        create_auth_token(&actor, &state.config.jwt_secret)
+        create_auth_token(&actor, read_keys(&state.keys)?.signing_key(0))
     }
 
     fn create_test_admin_auth_token() -> Result<String> {
+        use crate::keys::read_keys;
         use crate::state::create_test_app_state;
         use crate::token::create_auth_token;
         use db::org::TEST_ADMIN_ORG_ID;
+        use db::session::TEST_ADMIN_SESSION_ID;
         use db::user::TEST_ADMIN_USER_ID;
         use dto::actor::ActorPayload;
 
@@ -166,9 +276,10 @@ mod tests {
             id: TEST_ADMIN_USER_ID.to_string(),
             org_id: TEST_ADMIN_ORG_ID.to_string(),
             scope: "auth vault".to_string(),
+            session_id: Some(TEST_ADMIN_SESSION_ID.to_string()),
         };
 
-        create_auth_token(&actor, &state.config.jwt_secret)
+        create_auth_token(&actor, read_keys(&state.keys)?.signing_key(0))
     }
 
     #[tokio::test]
@@ -187,6 +298,25 @@ mod tests {
         response.assert_status_ok();
     }
 
+    #[tokio::test]
+    async fn test_jwks() {
+        let server = create_test_app();
+        let response = server.get("/.well-known/jwks.json").await;
+
+        response.assert_status_ok();
+        assert!(
+            response
+                .header("cache-control")
+                .to_str()
+                .unwrap()
+                .contains("max-age")
+        );
+
+        // The test keys are shared secrets, nothing to publish
+        let jwks = response.json::<serde_json::Value>();
+        assert_eq!(jwks["keys"], json!([]));
+    }
+
     #[tokio::test]
     async fn test_login_invalid() {
         let server = create_test_app();
@@ -538,6 +668,76 @@ mod tests {
         assert_eq!(listing.meta.total_records, 0);
     }
 
+    #[tokio::test]
+    async fn test_list_user_entries_by_cursor_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries?after=&with_total=true",
+            TEST_ORG_ID, TEST_VAULT_ID,
+        );
+        let listing: CursorPaginatedDto<EntryDto> = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+
+        assert_eq!(listing.meta.total_records, Some(0));
+        assert!(listing.meta.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_user_entries_invalid_cursor_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries?after=invalid-cursor",
+            TEST_ORG_ID, TEST_VAULT_ID,
+        );
+        let response = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .expect_failure()
+            .await;
+
+        response.assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_list_user_entries_sorted_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries?sort=label&order=asc",
+            TEST_ORG_ID, TEST_VAULT_ID,
+        );
+        let listing: PaginatedDto<EntryDto> = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+
+        assert_eq!(listing.meta.total_records, 0);
+    }
+
+    #[tokio::test]
+    async fn test_list_user_entries_cursor_sort_mismatch_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let cursor = Cursor::new("created_at", "1749954250", TEST_ENTRY_ID).encode();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries?after={}&sort=label",
+            TEST_ORG_ID, TEST_VAULT_ID, cursor,
+        );
+        let response = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .expect_failure()
+            .await;
+
+        response.assert_status_bad_request();
+    }
+
     #[tokio::test]
     async fn test_list_user_entries_as_admin() {
         // System Admins cannot view vault entries
@@ -552,4 +752,1769 @@ mod tests {
 
         response.assert_status_forbidden();
     }
+
+    #[tokio::test]
+    async fn test_get_user_entry_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            TEST_ORG_ID, TEST_VAULT_ID, TEST_ENTRY_ID
+        );
+        let entry: EntryDto = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+
+        assert_eq!(entry.id.as_str(), TEST_ENTRY_ID);
+    }
+
+    #[tokio::test]
+    async fn test_get_user_entry_etag_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            TEST_ORG_ID, TEST_VAULT_ID, TEST_ENTRY_ID
+        );
+        let response = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await;
+
+        response.assert_header("ETag", "\"1\"");
+    }
+
+    #[tokio::test]
+    async fn test_update_user_entry_without_if_match_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            TEST_ORG_ID, TEST_VAULT_ID, TEST_ENTRY_ID
+        );
+        let response = server
+            .patch(url.as_str())
+            .authorization_bearer(token.as_str())
+            .json(&json!({
+                "label": "updated-entry",
+            }))
+            .expect_failure()
+            .await;
+
+        response.assert_status(StatusCode::PRECONDITION_REQUIRED);
+    }
+
+    #[tokio::test]
+    async fn test_update_user_entry_stale_version_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            TEST_ORG_ID, TEST_VAULT_ID, TEST_ENTRY_ID
+        );
+        let response = server
+            .patch(url.as_str())
+            .authorization_bearer(token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"2\""))
+            .json(&json!({
+                "label": "updated-entry",
+            }))
+            .expect_failure()
+            .await;
+
+        response.assert_status(StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_update_user_entry_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            TEST_ORG_ID, TEST_VAULT_ID, TEST_ENTRY_ID
+        );
+        let response = server
+            .patch(url.as_str())
+            .authorization_bearer(token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&json!({
+                "label": "updated-entry",
+            }))
+            .await;
+
+        response.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_entry_stale_version_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            TEST_ORG_ID, TEST_VAULT_ID, TEST_ENTRY_ID
+        );
+        let response = server
+            .delete(url.as_str())
+            .authorization_bearer(token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"2\""))
+            .expect_failure()
+            .await;
+
+        response.assert_status(StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_list_entry_attachments_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}/attachments",
+            TEST_ORG_ID, TEST_VAULT_ID, TEST_ENTRY_ID
+        );
+        let attachments: Vec<AttachmentDto> = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+
+        assert_eq!(attachments.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_entry_attachment_not_found_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}/attachments/{}",
+            TEST_ORG_ID, TEST_VAULT_ID, TEST_ENTRY_ID, "0196d1c7a1b27c3d9e8f7a6b5c4d3e2f"
+        );
+        let response = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .expect_failure()
+            .await;
+
+        response.assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_upload_download_delete_attachment() {
+        use axum_test::multipart::{MultipartForm, Part};
+
+        let (server, seed, dir) =
+            create_attachment_test_app("attachments-upload", QuotaConfig::default()).await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let viewer_token = login_seeded_user(&server, "viewer").await;
+        let entry = &seed.entries[0];
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}/attachments",
+            seed.org.id, entry.vault_id, entry.id
+        );
+
+        let content = b"encrypted-attachment-content".to_vec();
+        let form = || {
+            MultipartForm::new()
+                .add_text("cipher_filename", "encrypted-name")
+                .add_part("file", Part::bytes(content.clone()))
+        };
+
+        // Viewers can only read
+        server
+            .post(url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .multipart(form())
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        let response = server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .multipart(form())
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let attachment: AttachmentDto = response.json();
+        assert_eq!(attachment.size, content.len() as i64);
+        assert_eq!(attachment.cipher_filename, "encrypted-name");
+        assert_eq!(count_files(&dir.join("tmp")), 0);
+        assert_eq!(count_files(&dir.join("attachments")), 1);
+
+        let attachments: Vec<AttachmentDto> = server
+            .get(url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .await
+            .json();
+        assert_eq!(attachments.len(), 1);
+
+        let attachment_url = format!("{}/{}", url, attachment.id);
+        let response = server
+            .get(&format!("{}/download", attachment_url))
+            .authorization_bearer(viewer_token.as_str())
+            .await;
+        assert_eq!(response.as_bytes().as_ref(), content.as_slice());
+        assert_eq!(
+            response.header("X-Content-Sha256").to_str().unwrap(),
+            attachment.sha256
+        );
+
+        server
+            .delete(attachment_url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+        server
+            .delete(attachment_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .get(attachment_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_not_found();
+        assert_eq!(count_files(&dir.join("attachments")), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_delete_entry_with_attachments() {
+        use crate::entry::delete_entry;
+        use crate::error::Error;
+        use axum_test::multipart::{MultipartForm, Part};
+
+        let (state, seed, dir) =
+            create_attachment_test_state("attachments-entry-delete", QuotaConfig::default()).await;
+        let server = build_test_server(state.clone());
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let entry = &seed.entries[0];
+        let vault = seed.vaults.iter().find(|x| x.id == entry.vault_id).unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            seed.org.id, entry.vault_id, entry.id
+        );
+        let attachments_url = format!("{}/attachments", url);
+
+        server
+            .post(attachments_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .multipart(
+                MultipartForm::new()
+                    .add_text("cipher_filename", "encrypted-name")
+                    .add_part("file", Part::bytes(b"encrypted".to_vec())),
+            )
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        server
+            .patch(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&json!({ "label": "updated-entry" }))
+            .await
+            .assert_status_ok();
+
+        // Updated after the entry was loaded, nothing is deleted
+        let stale: EntryDto = entry.clone().into();
+        let res = delete_entry(&state, vault, &stale).await;
+        assert!(matches!(res, Err(Error::PreconditionFailed { .. })));
+        let attachments: Vec<AttachmentDto> = server
+            .get(attachments_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(count_files(&dir.join("attachments")), 1);
+
+        server
+            .delete(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"2\""))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        assert_eq!(count_files(&dir.join("attachments")), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_attachment_uploads() {
+        use axum_test::multipart::{MultipartForm, Part};
+
+        let quotas = QuotaConfig {
+            max_attachment_size: 1024,
+            max_attachments_per_entry: 3,
+            ..QuotaConfig::default()
+        };
+        let max_size = quotas.max_attachment_size as usize;
+        let max_count = quotas.max_attachments_per_entry as usize;
+        let (server, seed, dir) = create_attachment_test_app("attachments-rejected", quotas).await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let entry = &seed.entries[0];
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}/attachments",
+            seed.org.id, entry.vault_id, entry.id
+        );
+        let form = |part: Part| {
+            MultipartForm::new()
+                .add_text("cipher_filename", "encrypted-name")
+                .add_part("file", part)
+        };
+
+        // Attachments are encrypted by the client, plain files are refused
+        server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .multipart(form(Part::bytes(b"hello".to_vec()).mime_type("text/plain")))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        let too_large = vec![0u8; max_size + 1];
+        let response = server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .multipart(form(Part::bytes(too_large)))
+            .expect_failure()
+            .await;
+        response.assert_status_bad_request();
+        assert!(response.text().contains("exceeds the allowed size"));
+
+        server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .multipart(form(Part::bytes(Vec::new())))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        // Nothing left behind by the rejected uploads
+        assert_eq!(count_files(&dir.join("tmp")), 0);
+        assert_eq!(count_files(&dir.join("attachments")), 0);
+
+        for _ in 0..max_count {
+            server
+                .post(url.as_str())
+                .authorization_bearer(admin_token.as_str())
+                .multipart(form(Part::bytes(b"encrypted".to_vec())))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+        let response = server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .multipart(form(Part::bytes(b"encrypted".to_vec())))
+            .expect_failure()
+            .await;
+        response.assert_status_bad_request();
+        assert!(response.text().contains("maximum attachments"));
+        assert_eq!(count_files(&dir.join("tmp")), 0);
+        assert_eq!(count_files(&dir.join("attachments")), max_count);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_list_user_audit_events_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!("/orgs/{}/audit", TEST_ORG_ID);
+        let listing: PaginatedDto<AuditEventDto> = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+
+        assert_eq!(listing.meta.total_records, 0);
+    }
+
+    #[tokio::test]
+    async fn test_list_admin_audit_events_as_user() {
+        let server = create_test_app();
+        let token = create_test_user_auth_token().unwrap();
+        let url = format!("/orgs/{}/audit", TEST_ADMIN_ORG_ID);
+        let response = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .expect_failure()
+            .await;
+
+        response.assert_status_forbidden();
+    }
+
+    #[tokio::test]
+    async fn test_list_seeded_entries_as_editor() {
+        let (server, seed) = create_seeded_test_app().await;
+        let token = login_seeded_user(&server, "editor").await;
+
+        let vault = seed.vaults.iter().find(|x| x.name == "Team").unwrap();
+        let url = format!("/orgs/{}/vaults/{}/entries", seed.org.id, vault.id);
+        let listing: PaginatedDto<EntryDto> = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+
+        assert_eq!(listing.meta.total_records, 3);
+
+        let entry = listing.data.iter().find(|x| x.label == "GitHub").unwrap();
+        let username = entry.cipher_username.as_ref().unwrap();
+        assert_eq!(
+            cipher::decrypt(DEMO_KEY, username).unwrap(),
+            "ferriseal-bot"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_seeded_entry_as_viewer() {
+        let (server, seed) = create_seeded_test_app().await;
+        let token = login_seeded_user(&server, "viewer").await;
+
+        let vault = &seed.vaults[0];
+        let url = format!("/orgs/{}/vaults/{}/entries", seed.org.id, vault.id);
+        let response = server
+            .post(url.as_str())
+            .authorization_bearer(token.as_str())
+            .json(&json!({
+                "label": "Read only",
+            }))
+            .expect_failure()
+            .await;
+
+        response.assert_status_forbidden();
+    }
+
+    #[tokio::test]
+    async fn test_toggle_seeded_favorite() {
+        let (server, seed) = create_seeded_test_app().await;
+        let token = login_seeded_user(&server, "viewer").await;
+
+        let entry = &seed.entries[0];
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}/favorite",
+            seed.org.id, entry.vault_id, entry.id
+        );
+        let favorite: EntryFavoriteDto = server
+            .post(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+        assert!(favorite.favorite);
+
+        let items: Vec<EntryLinkDto> = server
+            .get("/user/favorites")
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, entry.id);
+
+        let favorite: EntryFavoriteDto = server
+            .post(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+        assert!(!favorite.favorite);
+    }
+
+    #[tokio::test]
+    async fn test_list_seeded_recents() {
+        let (server, seed) = create_seeded_test_app().await;
+        let token = login_seeded_user(&server, "editor").await;
+
+        let entry = &seed.entries[2];
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            seed.org.id, entry.vault_id, entry.id
+        );
+        server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .assert_status_ok();
+
+        let items: Vec<EntryLinkDto> = server
+            .get("/user/recents")
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, entry.id);
+        assert_eq!(items[0].vault_name, "Team");
+    }
+
+    #[tokio::test]
+    async fn test_list_seeded_due_entries() {
+        let (server, seed) = create_seeded_test_app().await;
+        let token = login_seeded_user(&server, "viewer").await;
+
+        let url = format!("/orgs/{}/due_entries", seed.org.id);
+        let items: Vec<EntryRotationDto> = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+        assert!(items.is_empty());
+
+        let url = format!("/orgs/{}/due_entries?within_days=100", seed.org.id);
+        let items: Vec<EntryRotationDto> = server
+            .get(url.as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+        assert_eq!(items.len(), 3);
+        assert!(items.iter().all(|x| x.vault_name == "Team"));
+    }
+
+    #[tokio::test]
+    async fn test_seeded_org_quota_and_usage() {
+        let (server, seed) = create_seeded_test_app().await;
+        let admin_token = login_seeded_user(&server, "admin").await;
+        let viewer_token = login_seeded_user(&server, "viewer").await;
+
+        let url = format!("/orgs/{}/quota", seed.org.id);
+        let quota: OrgQuotaDto = server
+            .put(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "max_vaults": 2 }))
+            .await
+            .json();
+        assert_eq!(quota.max_vaults, Some(2));
+
+        server
+            .put(url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .json(&json!({ "max_vaults": 20 }))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        // Seeded org already has two vaults
+        let url = format!("/orgs/{}/vaults", seed.org.id);
+        server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "name": "Extra", "test_cipher": "extra" }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        let url = format!("/orgs/{}/usage", seed.org.id);
+        let usage: OrgUsageDto = server
+            .get(url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .await
+            .json();
+        assert_eq!((usage.vaults.used, usage.vaults.limit), (2, 2));
+        assert_eq!((usage.users.used, usage.users.limit), (3, 10));
+        assert_eq!(usage.entries_per_vault.used, 3);
+        assert_eq!(usage.total_entries, 5);
+    }
+
+    #[tokio::test]
+    async fn test_update_seeded_vault() {
+        let (server, seed) = create_seeded_test_app().await;
+        let admin_token = login_seeded_user(&server, "admin").await;
+        let editor_token = login_seeded_user(&server, "editor").await;
+        let url = format!("/orgs/{}/vaults/{}", seed.org.id, seed.vaults[1].id);
+        server
+            .patch(url.as_str())
+            .authorization_bearer(editor_token.as_str())
+            .json(&json!({ "description": "Shared logins" }))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        server
+            .patch(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("*"))
+            .json(&json!({ "color": "teal" }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        let response = server
+            .patch(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&json!({
+                "name": "Team Shared",
+                "description": "Shared logins",
+                "icon": "users",
+                "color": "#00d1b2"
+            }))
+            .await;
+        response.assert_status_ok();
+        let updated: VaultDto = response.json();
+        assert_eq!(updated.name, "Team Shared");
+        assert_eq!(updated.icon, Some("users".to_string()));
+        assert_eq!(updated.color, Some("#00d1b2".to_string()));
+        assert_eq!(updated.version, 2);
+
+        // Stale version
+        server
+            .patch(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&json!({ "description": "" }))
+            .expect_failure()
+            .await
+            .assert_status(StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_seeded_entries() {
+        let (server, seed) = create_seeded_test_app().await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let editor_token = login_seeded_user(&server, "editor").await;
+
+        let vault = seed.vaults.iter().find(|x| x.name == "Team").unwrap();
+        let team_entries: Vec<_> = seed
+            .entries
+            .iter()
+            .filter(|x| x.vault_id == vault.id)
+            .collect();
+        let url = format!("/orgs/{}/vaults/{}/entries/bulk", seed.org.id, vault.id);
+
+        // Editors may create but not delete
+        server
+            .post(url.as_str())
+            .authorization_bearer(editor_token.as_str())
+            .json(&json!({ "operations": [
+                { "op": "create", "label": "Imported" },
+                { "op": "delete", "id": team_entries[0].id, "version": 1 },
+            ]}))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "operations": [
+                { "op": "create", "label": "ok" },
+                { "op": "delete", "id": team_entries[0].id, "version": 1 },
+                { "op": "delete", "id": team_entries[0].id, "version": 1 },
+            ]}))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        // Stale version rolls back the whole batch
+        server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "operations": [
+                { "op": "create", "label": "Imported" },
+                { "op": "delete", "id": team_entries[0].id, "version": 5 },
+            ]}))
+            .expect_failure()
+            .await
+            .assert_status(StatusCode::PRECONDITION_FAILED);
+
+        let results: Vec<BulkEntryResultDto> = server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "operations": [
+                { "op": "create", "label": "Imported", "cipher_password": "secret" },
+                { "op": "update", "id": team_entries[1].id, "version": 1, "label": "Renamed" },
+                { "op": "delete", "id": team_entries[0].id, "version": 1 },
+            ]}))
+            .await
+            .json();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].version, Some(1));
+        assert_eq!(results[1].version, Some(2));
+        assert_eq!(results[2].version, None);
+
+        let url = format!("/orgs/{}/vaults/{}/entries", seed.org.id, vault.id);
+        let entries: PaginatedDto<EntryDto> = server
+            .get(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(entries.meta.total_records, team_entries.len() as i64);
+        assert!(entries.data.iter().any(|x| x.label == "Imported"));
+        assert!(entries.data.iter().all(|x| x.id != team_entries[0].id));
+    }
+
+    #[tokio::test]
+    async fn test_move_and_copy_seeded_entry() {
+        let (server, seed) = create_seeded_test_app().await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let editor_token = login_seeded_user(&server, "editor").await;
+
+        let vault = seed.vaults.iter().find(|x| x.name == "Team").unwrap();
+        let target = seed
+            .vaults
+            .iter()
+            .find(|x| x.org_id == vault.org_id && x.id != vault.id)
+            .unwrap();
+        let entry = seed
+            .entries
+            .iter()
+            .find(|x| x.vault_id == vault.id)
+            .unwrap();
+        let url = format!(
+            "/orgs/{}/vaults/{}/entries/{}",
+            seed.org.id, vault.id, entry.id
+        );
+        let move_url = format!("{}/move", url);
+        let copy_url = format!("{}/copy", url);
+        let payload = json!({
+            "target_vault_id": target.id,
+            "cipher_username": "reencrypted-username",
+            "cipher_password": "reencrypted",
+            "cipher_notes": "reencrypted-notes",
+        });
+
+        // Editors may copy but not move
+        server
+            .post(move_url.as_str())
+            .authorization_bearer(editor_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&payload)
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        server
+            .post(move_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&json!({ "target_vault_id": vault.id }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        // Secrets of the entry are never dropped or stored empty
+        let partial = json!({
+            "target_vault_id": target.id,
+            "cipher_password": "reencrypted",
+            "cipher_notes": "",
+        });
+        for url in [move_url.as_str(), copy_url.as_str()] {
+            server
+                .post(url)
+                .authorization_bearer(admin_token.as_str())
+                .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+                .json(&partial)
+                .expect_failure()
+                .await
+                .assert_status_bad_request();
+        }
+
+        let copied: EntryDto = server
+            .post(copy_url.as_str())
+            .authorization_bearer(editor_token.as_str())
+            .json(&payload)
+            .await
+            .json();
+        assert_ne!(copied.id, entry.id);
+        assert_eq!(copied.vault_id, target.id);
+        assert_eq!(copied.label, entry.label);
+        assert_eq!(copied.cipher_password, Some("reencrypted".to_string()));
+
+        let moved: EntryDto = server
+            .post(move_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&payload)
+            .await
+            .json();
+        assert_eq!(moved.id, entry.id);
+        assert_eq!(moved.vault_id, target.id);
+        assert_eq!(moved.version, 2);
+
+        // No longer reachable through the source vault
+        server
+            .get(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_session_refresh_and_revocation() {
+        let (server, seed) = create_seeded_test_app().await;
+        let sysadmin_token = login_seeded_user(&server, "admin").await;
+        let viewer = seed.user("viewer").unwrap();
+
+        let auth: serde_json::Value = server
+            .post("/auth/token")
+            .json(&json!({ "username": "viewer", "password": DEMO_PASSWORD }))
+            .await
+            .json();
+        let token = auth["token"].as_str().unwrap().to_string();
+        let refresh_token = auth["refresh_token"].as_str().unwrap().to_string();
+        assert_eq!(auth["expires_in"].as_i64(), Some(15 * 60));
+
+        let refreshed: serde_json::Value = server
+            .post("/auth/refresh")
+            .json(&json!({ "refresh_token": refresh_token }))
+            .await
+            .json();
+        let new_token = refreshed["token"].as_str().unwrap().to_string();
+        assert_ne!(
+            refreshed["refresh_token"].as_str(),
+            Some(refresh_token.as_str())
+        );
+
+        // Access tokens of the same session stay valid after a refresh
+        server
+            .get("/user")
+            .authorization_bearer(token.as_str())
+            .await
+            .assert_status_ok();
+
+        // Reusing a rotated refresh token ends the whole session
+        server
+            .post("/auth/refresh")
+            .json(&json!({ "refresh_token": refresh_token }))
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+        server
+            .get("/user")
+            .authorization_bearer(new_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+
+        let token = login_seeded_user(&server, "viewer").await;
+        server
+            .post("/user/logout")
+            .authorization_bearer(token.as_str())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .get("/user")
+            .authorization_bearer(token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+
+        // Role changes log the user out everywhere
+        let token = login_seeded_user(&server, "viewer").await;
+        let url = format!("/orgs/{}/users/{}/update_roles", seed.org.id, viewer.id);
+        server
+            .post(url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "roles": "Viewer" }))
+            .await
+            .assert_status_ok();
+        server
+            .get("/user")
+            .authorization_bearer(token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+
+        // Changing the password keeps only the current session
+        let current_token = login_seeded_user(&server, "viewer").await;
+        let other_token = login_seeded_user(&server, "viewer").await;
+        server
+            .post("/user/change_password")
+            .authorization_bearer(current_token.as_str())
+            .json(&json!({
+                "current_password": DEMO_PASSWORD,
+                "new_password": "another-strong-password",
+            }))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .get("/user")
+            .authorization_bearer(current_token.as_str())
+            .await
+            .assert_status_ok();
+        server
+            .get("/user")
+            .authorization_bearer(other_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+    }
+
+    #[tokio::test]
+    async fn test_manage_sessions() {
+        let (server, seed) = create_seeded_test_app().await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let editor_token = login_seeded_user(&server, "editor").await;
+        let viewer = seed.user("viewer").unwrap();
+
+        let token = login_seeded_user(&server, "viewer").await;
+        let other_token = login_seeded_user(&server, "viewer").await;
+        let sessions: Vec<SessionDto> = server
+            .get("/user/sessions")
+            .authorization_bearer(token.as_str())
+            .await
+            .json();
+        assert_eq!(sessions.len(), 2);
+
+        let other: serde_json::Value = server
+            .get("/user/authz")
+            .authorization_bearer(other_token.as_str())
+            .await
+            .json();
+        let other_id = other["session_id"].as_str().unwrap();
+        server
+            .delete(format!("/user/sessions/{}", other_id).as_str())
+            .authorization_bearer(token.as_str())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .get("/user")
+            .authorization_bearer(other_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+
+        // Sessions of other users are not found
+        let admin: serde_json::Value = server
+            .get("/user/authz")
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        let admin_session = admin["session_id"].as_str().unwrap();
+        server
+            .delete(format!("/user/sessions/{}", admin_session).as_str())
+            .authorization_bearer(token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_not_found();
+
+        let other_token = login_seeded_user(&server, "viewer").await;
+        server
+            .post("/user/sessions/revoke_others")
+            .authorization_bearer(token.as_str())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .get("/user")
+            .authorization_bearer(other_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+
+        // Org admins manage the sessions of their users
+        let url = format!("/orgs/{}/users/{}/sessions", seed.org.id, viewer.id);
+        server
+            .get(url.as_str())
+            .authorization_bearer(editor_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        let sessions: Vec<SessionDto> = server
+            .get(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].user_id, viewer.id);
+
+        server
+            .post(format!("{}/revoke_all", url).as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .get("/user")
+            .authorization_bearer(token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+    }
+
+    #[tokio::test]
+    async fn test_vault_members_limit_access() {
+        let (server, seed) = create_seeded_test_app().await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let viewer_token = login_seeded_user(&server, "viewer").await;
+
+        let vault = seed.vaults.iter().find(|x| x.name == "Team").unwrap();
+        let viewer = seed.user("viewer").unwrap();
+        let vault_url = format!("/orgs/{}/vaults/{}", seed.org.id, vault.id);
+        let members_url = format!("{}/members", vault_url);
+        let member_url = format!("{}/{}", members_url, viewer.id);
+
+        // Only vault managers see the members
+        server
+            .get(members_url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        let members: Vec<VaultMemberDto> = server
+            .get(members_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(members.len(), 3);
+
+        server
+            .put(member_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "role": "owner" }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        server
+            .delete(member_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        // Revoked vaults are hidden from the user
+        server
+            .get(vault_url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_not_found();
+
+        let url = format!("/orgs/{}/vaults", seed.org.id);
+        let vaults: Vec<VaultDto> = server
+            .get(url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .await
+            .json();
+        assert_eq!(vaults.len(), 1);
+        assert_ne!(vaults[0].id, vault.id);
+
+        let member: VaultMemberDto = server
+            .put(member_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "role": "editor" }))
+            .await
+            .json();
+        assert_eq!(member.role, VaultRole::Editor);
+
+        // Vault roles never exceed the org role, viewers still cannot create
+        let url = format!("{}/entries", vault_url);
+        server
+            .post(url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .json(&json!({ "label": "Contractor" }))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        server
+            .get(vault_url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_created_vault_managed_by_creator() {
+        let (server, seed) = create_seeded_test_app().await;
+        let sysadmin_token = login_seeded_user(&server, "admin").await;
+        let viewer_token = login_seeded_user(&server, "viewer").await;
+        let admin = seed.user("orgadmin").unwrap();
+
+        // Org admins create vaults through a custom role
+        let url = format!("/orgs/{}/roles", seed.org.id);
+        let role: RoleDto = server
+            .post(url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "name": "Vault Creator", "permissions": "vaults.create" }))
+            .await
+            .json();
+        let url = format!("/orgs/{}/users/{}/update_roles", seed.org.id, admin.id);
+        server
+            .post(url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "roles": format!("Admin,{}", role.id) }))
+            .await
+            .assert_status_ok();
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+
+        let url = format!("/orgs/{}/vaults", seed.org.id);
+        let response = server
+            .post(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "name": "Created", "test_cipher": "created" }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let vault: VaultDto = response.json();
+
+        let vault_url = format!("{}/{}", url, vault.id);
+        let found: VaultDto = server
+            .get(vault_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(found.id, vault.id);
+
+        let members: Vec<VaultMemberDto> = server
+            .get(&format!("{}/members", vault_url))
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].user_id, admin.id);
+        assert_eq!(members[0].role, VaultRole::Manager);
+
+        // Nobody else was granted access
+        server
+            .get(vault_url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_group_roles_and_vault_grants() {
+        let (server, seed) = create_seeded_test_app().await;
+        let sysadmin_token = login_seeded_user(&server, "admin").await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let viewer_token = login_seeded_user(&server, "viewer").await;
+
+        let vault = seed.vaults.iter().find(|x| x.name == "Team").unwrap();
+        let viewer = seed.user("viewer").unwrap();
+        let groups_url = format!("/orgs/{}/groups", seed.org.id);
+
+        // Org admins see groups but only system admins manage them
+        server
+            .post(groups_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "name": "Support", "roles": "Editor" }))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        server
+            .post(groups_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "name": "Support", "roles": "SystemAdmin" }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        let response = server
+            .post(groups_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "name": "Support", "roles": "Editor" }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let group: GroupDto = response.json();
+
+        let groups: Vec<GroupDto> = server
+            .get(groups_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(groups.len(), 1);
+
+        let group_url = format!("{}/{}", groups_url, group.id);
+        let member: GroupMemberDto = server
+            .put(format!("{}/members/{}", group_url, viewer.id).as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .await
+            .json();
+        assert_eq!(member.username, "viewer");
+
+        // Group roles are added to the roles of the user
+        let permissions: Vec<String> = server
+            .get("/user/permissions")
+            .authorization_bearer(viewer_token.as_str())
+            .await
+            .json();
+        assert!(permissions.contains(&"entries.create".to_string()));
+
+        // The vault role still limits the user until the group is granted more
+        let entries_url = format!("/orgs/{}/vaults/{}/entries", seed.org.id, vault.id);
+        server
+            .post(entries_url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .json(&json!({ "label": "Contractor" }))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        let url = format!(
+            "/orgs/{}/vaults/{}/groups/{}",
+            seed.org.id, vault.id, group.id
+        );
+        let grant: VaultGroupDto = server
+            .put(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "role": "editor" }))
+            .await
+            .json();
+        assert_eq!(grant.role, VaultRole::Editor);
+
+        server
+            .post(entries_url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .json(&json!({ "label": "Contractor" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        // Deleting the group takes its roles and grants away
+        server
+            .delete(group_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        let permissions: Vec<String> = server
+            .get("/user/permissions")
+            .authorization_bearer(viewer_token.as_str())
+            .await
+            .json();
+        assert!(!permissions.contains(&"entries.create".to_string()));
+
+        let url = format!("/orgs/{}/vaults/{}/groups", seed.org.id, vault.id);
+        let grants: Vec<VaultGroupDto> = server
+            .get(url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert!(grants.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_service_account_api_keys() {
+        use crate::state::{create_ephemeral_app_state, create_test_app_state};
+
+        let config = create_test_app_state().config;
+        let (state, seed) = create_ephemeral_app_state(&config).await.unwrap();
+        let server = build_test_server(state.clone());
+        let sysadmin_token = login_seeded_user(&server, "admin").await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+        let viewer_token = login_seeded_user(&server, "viewer").await;
+
+        let accounts_url = format!("/orgs/{}/service_accounts", seed.org.id);
+        server
+            .post(accounts_url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .json(&json!({ "username": "deploybot", "roles": "Editor" }))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        let response = server
+            .post(accounts_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "username": "deploybot", "roles": "Editor" }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let account: UserDto = response.json();
+        assert_eq!(account.kind, "service");
+
+        let accounts: Vec<UserDto> = server
+            .get(accounts_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(accounts.len(), 1);
+
+        // Service accounts access vaults through grants like everyone else
+        let vault = &seed.vaults[0];
+        let other_vault = &seed.vaults[1];
+        for item in [vault, other_vault] {
+            let url = format!(
+                "/orgs/{}/vaults/{}/members/{}",
+                seed.org.id, item.id, account.id
+            );
+            server
+                .put(url.as_str())
+                .authorization_bearer(admin_token.as_str())
+                .json(&json!({ "role": "editor" }))
+                .await
+                .assert_status_ok();
+        }
+
+        let keys_url = format!("{}/{}/keys", accounts_url, account.id);
+        let response = server
+            .post(keys_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({
+                "name": "CI",
+                "permissions": "orgs.view,vaults.list,vaults.view,entries.list,entries.view",
+                "vault_id": vault.id,
+                "allowed_ips": "10.0.0.0/8",
+            }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let created: NewApiKeyDto = response.json();
+        let auth = format!("ApiKey {}", created.key);
+        let auth = HeaderValue::from_str(&auth).unwrap();
+
+        let entries_url = format!("/orgs/{}/vaults/{}/entries", seed.org.id, vault.id);
+        server
+            .get(entries_url.as_str())
+            .add_header(header::AUTHORIZATION, auth.clone())
+            .add_header("X-Forwarded-For", HeaderValue::from_static("10.1.2.3"))
+            .await
+            .assert_status_ok();
+
+        // Only the scoped vault, only from allowed addresses
+        let url = format!("/orgs/{}/vaults/{}/entries", seed.org.id, other_vault.id);
+        server
+            .get(url.as_str())
+            .add_header(header::AUTHORIZATION, auth.clone())
+            .add_header("X-Forwarded-For", HeaderValue::from_static("10.1.2.3"))
+            .expect_failure()
+            .await
+            .assert_status_not_found();
+        server
+            .get(entries_url.as_str())
+            .add_header(header::AUTHORIZATION, auth.clone())
+            .add_header("X-Forwarded-For", HeaderValue::from_static("192.168.1.2"))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        // Clients connecting directly cannot claim an allowed address
+        let direct =
+            build_test_server_from(state.clone(), SocketAddr::from(([203, 0, 113, 9], 40000)));
+        direct
+            .get(entries_url.as_str())
+            .add_header(header::AUTHORIZATION, auth.clone())
+            .add_header("X-Forwarded-For", HeaderValue::from_static("10.1.2.3"))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        let direct = build_test_server_from(state, SocketAddr::from(([10, 9, 8, 7], 40000)));
+        direct
+            .get(entries_url.as_str())
+            .add_header(header::AUTHORIZATION, auth.clone())
+            .add_header("X-Forwarded-For", HeaderValue::from_static("192.168.1.2"))
+            .await
+            .assert_status_ok();
+
+        // Read-only key of an editor account cannot write
+        server
+            .post(entries_url.as_str())
+            .add_header(header::AUTHORIZATION, auth.clone())
+            .add_header("X-Forwarded-For", HeaderValue::from_static("10.1.2.3"))
+            .json(&json!({ "label": "Deploy" }))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        // Keys cannot issue more keys
+        server
+            .get(accounts_url.as_str())
+            .add_header(header::AUTHORIZATION, auth.clone())
+            .add_header("X-Forwarded-For", HeaderValue::from_static("10.1.2.3"))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        let keys: Vec<ApiKeyDto> = server
+            .get(keys_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].last_used_ip.as_deref(), Some("10.1.2.3"));
+
+        let url = format!("{}/{}", keys_url, created.api_key.id);
+        server
+            .delete(url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        server
+            .get(entries_url.as_str())
+            .add_header(header::AUTHORIZATION, auth)
+            .add_header("X-Forwarded-For", HeaderValue::from_static("10.1.2.3"))
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+
+        // No password login for service accounts
+        server
+            .post("/auth/token")
+            .json(&json!({ "username": "deploybot", "password": DEMO_PASSWORD }))
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+    }
+
+    #[tokio::test]
+    async fn test_custom_roles() {
+        let (server, seed) = create_seeded_test_app().await;
+        let sysadmin_token = login_seeded_user(&server, "admin").await;
+        let admin_token = login_seeded_user(&server, "orgadmin").await;
+
+        let viewer = seed.user("viewer").unwrap();
+        let roles_url = format!("/orgs/{}/roles", seed.org.id);
+
+        // Built-in roles are listed but read-only
+        let roles: Vec<RoleDto> = server
+            .get(roles_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .await
+            .json();
+        assert_eq!(roles.len(), 3);
+        assert!(roles.iter().all(|x| x.builtin));
+
+        server
+            .delete(format!("{}/Admin", roles_url).as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        server
+            .post(roles_url.as_str())
+            .authorization_bearer(admin_token.as_str())
+            .json(&json!({ "name": "Auditor", "permissions": "audit.list,audit.view" }))
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        server
+            .post(roles_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "name": "Auditor", "permissions": "orgs.delete" }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        let response = server
+            .post(roles_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "name": "Auditor", "permissions": "audit.list,audit.view" }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let role: RoleDto = response.json();
+
+        // Assigning the custom role resolves its permissions
+        let url = format!("/orgs/{}/users/{}/update_roles", seed.org.id, viewer.id);
+        let user: UserDto = server
+            .post(url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "roles": format!("Viewer,{}", role.id) }))
+            .await
+            .json();
+        assert_eq!(user.roles.len(), 2);
+
+        // Role changes end the sessions of the user
+        let viewer_token = login_seeded_user(&server, "viewer").await;
+        let permissions: Vec<String> = server
+            .get("/user/permissions")
+            .authorization_bearer(viewer_token.as_str())
+            .await
+            .json();
+        assert!(permissions.contains(&"audit.view".to_string()));
+
+        // Only roles defined by the org can be assigned
+        let url = format!("/orgs/{}/groups", seed.org.id);
+        server
+            .post(url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "name": "Support", "roles": vault::utils::generate_id() }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        // Editing the role changes what its holders may do
+        let role_url = format!("{}/{}", roles_url, role.id);
+        let updated: RoleDto = server
+            .patch(role_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&json!({ "permissions": "audit.list" }))
+            .await
+            .json();
+        assert_eq!(updated.version, 2);
+
+        let permissions: Vec<String> = server
+            .get("/user/permissions")
+            .authorization_bearer(viewer_token.as_str())
+            .await
+            .json();
+        assert!(!permissions.contains(&"audit.view".to_string()));
+
+        // Assigned roles cannot be deleted
+        server
+            .delete(role_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"2\""))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        let url = format!("/orgs/{}/users/{}/update_roles", seed.org.id, viewer.id);
+        server
+            .post(url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .json(&json!({ "roles": "Viewer" }))
+            .await
+            .assert_status_ok();
+
+        server
+            .delete(role_url.as_str())
+            .authorization_bearer(sysadmin_token.as_str())
+            .add_header(header::IF_MATCH, HeaderValue::from_static("\"2\""))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_login_lockout() {
+        use crate::config::LoginConfig;
+        use crate::state::{create_ephemeral_app_state, create_test_app_state};
+
+        let mut config = create_test_app_state().config;
+        config.login = LoginConfig {
+            free_attempts: 2,
+            user_lockout_after: 3,
+            ..LoginConfig::default()
+        };
+        let (state, seed) = create_ephemeral_app_state(&config).await.unwrap();
+        let server = build_test_server(state);
+        let sysadmin_token = login_seeded_user(&server, "admin").await;
+        let viewer = seed.user("viewer").unwrap();
+
+        // Failures from another client than the later logins
+        for _ in 0..3 {
+            server
+                .post("/auth/token")
+                .add_header("X-Forwarded-For", HeaderValue::from_static("203.0.113.7"))
+                .json(&json!({ "username": "viewer", "password": "wrong-password" }))
+                .expect_failure()
+                .await
+                .assert_status_unauthorized();
+        }
+
+        // Even the right password is rejected until the lockout ends
+        let response = server
+            .post("/auth/token")
+            .json(&json!({ "username": "viewer", "password": DEMO_PASSWORD }))
+            .expect_failure()
+            .await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.text().contains("Try again in 15 minutes"));
+
+        let url = format!("/orgs/{}/users/{}", seed.org.id, viewer.id);
+        let lockout: serde_json::Value = server
+            .get(&format!("{}/lockout", url))
+            .authorization_bearer(sysadmin_token.as_str())
+            .await
+            .json();
+        assert_eq!(lockout["failures"].as_i64(), Some(3));
+        assert!(lockout["locked_until"].as_i64().is_some());
+
+        server
+            .post(&format!("{}/unlock", url))
+            .authorization_bearer(sysadmin_token.as_str())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        login_seeded_user(&server, "viewer").await;
+
+        // Successful logins start counting over
+        server
+            .get(&format!("{}/lockout", url))
+            .authorization_bearer(sysadmin_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_parallel_login_guesses() {
+        use crate::config::LoginConfig;
+        use crate::state::{create_ephemeral_app_state, create_test_app_state};
+        use std::rc::Rc;
+        use tokio::task::{JoinSet, LocalSet};
+
+        let mut config = create_test_app_state().config;
+        config.login = LoginConfig {
+            free_attempts: 2,
+            ..LoginConfig::default()
+        };
+        let (state, _) = create_ephemeral_app_state(&config).await.unwrap();
+        let server = Rc::new(build_test_server(state));
+
+        // Requests of the test server are not Send, they run side by side locally
+        let local = LocalSet::new();
+        let mut guesses = JoinSet::new();
+        for i in 0..10 {
+            let server = server.clone();
+            guesses.spawn_local_on(
+                async move {
+                    let password = format!("wrong-password-{}", i);
+                    server
+                        .post("/auth/token")
+                        .json(&json!({ "username": "viewer", "password": password }))
+                        .expect_failure()
+                        .await
+                        .status_code()
+                },
+                &local,
+            );
+        }
+        let statuses = local.run_until(guesses.join_all()).await;
+
+        // Each guess is counted before the next one is checked
+        let guessed = statuses
+            .iter()
+            .filter(|x| **x == StatusCode::UNAUTHORIZED)
+            .count();
+        let delayed = statuses
+            .iter()
+            .filter(|x| **x == StatusCode::TOO_MANY_REQUESTS)
+            .count();
+        assert_eq!(guessed, 3);
+        assert_eq!(delayed, 7);
+    }
+
+    #[tokio::test]
+    async fn test_ip_lockout_ignores_spoofed_addresses() {
+        use crate::config::LoginConfig;
+        use crate::state::{create_ephemeral_app_state, create_test_app_state};
+
+        let mut config = create_test_app_state().config;
+        config.login = LoginConfig {
+            free_attempts: 2,
+            ip_lockout_after: 3,
+            ..LoginConfig::default()
+        };
+        let (state, _) = create_ephemeral_app_state(&config).await.unwrap();
+        let server = build_test_server(state.clone());
+        let direct = build_test_server_from(state, SocketAddr::from(([203, 0, 113, 9], 40000)));
+
+        // Spraying passwords while claiming a new address each time
+        let spoofed = ["198.51.100.1", "198.51.100.2", "198.51.100.3"];
+        for (username, ip) in ["viewer", "editor", "orgadmin"].iter().zip(spoofed) {
+            direct
+                .post("/auth/token")
+                .add_header("X-Forwarded-For", HeaderValue::from_static(ip))
+                .json(&json!({ "username": username, "password": "wrong-password" }))
+                .expect_failure()
+                .await
+                .assert_status_unauthorized();
+        }
+
+        let response = direct
+            .post("/auth/token")
+            .add_header("X-Forwarded-For", HeaderValue::from_static("198.51.100.4"))
+            .json(&json!({ "username": "viewer", "password": DEMO_PASSWORD }))
+            .expect_failure()
+            .await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.text().contains("Try again in 15 minutes"));
+
+        // The claimed addresses were not locked out
+        server
+            .post("/auth/token")
+            .add_header("X-Forwarded-For", HeaderValue::from_static("198.51.100.1"))
+            .json(&json!({ "username": "viewer", "password": DEMO_PASSWORD }))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_link() {
+        use crate::state::{create_ephemeral_app_state, create_test_app_state};
+
+        let config = create_test_app_state().config;
+        let (state, seed) = create_ephemeral_app_state(&config).await.unwrap();
+        let server = build_test_server(state.clone());
+        let sysadmin_token = login_seeded_user(&server, "admin").await;
+        let viewer_token = login_seeded_user(&server, "viewer").await;
+        let viewer = seed.user("viewer").unwrap();
+
+        let url = format!("/orgs/{}/users/{}/reset_link", seed.org.id, viewer.id);
+        server
+            .post(url.as_str())
+            .authorization_bearer(viewer_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_forbidden();
+
+        let issue = || async {
+            let response = server
+                .post(url.as_str())
+                .authorization_bearer(sysadmin_token.as_str())
+                .await;
+            response.assert_status(StatusCode::CREATED);
+            let created: serde_json::Value = response.json();
+            created["token"].as_str().unwrap().to_string()
+        };
+        let first = issue().await;
+        let second = issue().await;
+
+        // Only the latest link works
+        server
+            .post("/auth/reset_password")
+            .json(&json!({ "token": first, "password": "viewer-new-password" }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        // A rejected password keeps the link usable
+        server
+            .post("/auth/reset_password")
+            .json(&json!({ "token": second, "password": "short" }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        // So does a failure after the link was found
+        let status = |status: &str| db::user::UpdateUserStatus {
+            status: status.to_string(),
+        };
+        let users = &state.db.users;
+        users
+            .update_status(&viewer.id, &status("inactive"))
+            .await
+            .unwrap();
+        server
+            .post("/auth/reset_password")
+            .json(&json!({ "token": second, "password": "viewer-new-password" }))
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+        users
+            .update_status(&viewer.id, &status("active"))
+            .await
+            .unwrap();
+
+        server
+            .post("/auth/reset_password")
+            .json(&json!({ "token": second, "password": "viewer-new-password" }))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .post("/auth/reset_password")
+            .json(&json!({ "token": second, "password": "viewer-other-password" }))
+            .expect_failure()
+            .await
+            .assert_status_bad_request();
+
+        // Sessions from the old password end
+        server
+            .get("/user")
+            .authorization_bearer(viewer_token.as_str())
+            .expect_failure()
+            .await
+            .assert_status_unauthorized();
+        server
+            .post("/auth/token")
+            .json(&json!({ "username": "viewer", "password": "viewer-new-password" }))
+            .await
+            .assert_status_ok();
+    }
 }
@@ -15,12 +15,17 @@ const DEFAULT_ENC_METHOD: &'static str = "xch";
 /// Encrypts data with a random key which is encrypted with the master key
 /// Result format: enc_method:key_nonce:key_data|enc_method:input_nonce:input_data
 pub fn encrypt(key: &str, data: &str) -> Result<String> {
+    encrypt_bytes(key, data.as_bytes())
+}
+
+/// Encrypts binary data, same format as `encrypt`
+pub fn encrypt_bytes(key: &str, data: &[u8]) -> Result<String> {
     // Create a random key and encrypt it with the main key
     let random_key = XChaCha20Poly1305::generate_key(OsRng);
     let cipher_key = xchacha20_encrypt(key.as_bytes(), &random_key)?;
 
     // Now that we have a random key encrypted, encypt the data with it
-    let cipher_data = xchacha20_encrypt(&random_key, data.as_bytes())?;
+    let cipher_data = xchacha20_encrypt(&random_key, data)?;
     Ok(format!("{}|{}", cipher_key, cipher_data))
 }
 
@@ -41,6 +46,12 @@ fn xchacha20_encrypt(key: &[u8], data: &[u8]) -> Result<String> {
 }
 
 pub fn decrypt(key: &str, data: &str) -> Result<String> {
+    let result = decrypt_bytes(key, data)?;
+    Ok(String::from_utf8_lossy(&result).to_string())
+}
+
+/// Decrypts data encrypted by `encrypt_bytes`
+pub fn decrypt_bytes(key: &str, data: &str) -> Result<Vec<u8>> {
     let mut chunks = data.split('|');
     let key_part = chunks.next().context(CipherSnafu {
         msg: "Cipher text format must be valid",
@@ -54,8 +65,7 @@ pub fn decrypt(key: &str, data: &str) -> Result<String> {
     let plain_key = decrypt_part(key.as_bytes(), key_part)?;
 
     // Decrypt the data using the stored key
-    let result = decrypt_part(&plain_key, data_part)?;
-    Ok(String::from_utf8_lossy(&result).to_string())
+    decrypt_part(&plain_key, data_part)
 }
 
 fn decrypt_part(key: &[u8], data: &str) -> Result<Vec<u8>> {
@@ -109,4 +119,14 @@ mod tests {
         let plain_back = decrypt(key, &crypted).unwrap();
         assert_eq!(plain, plain_back);
     }
+
+    #[test]
+    fn test_encrypt_decrypt_bytes() {
+        let key = "371d6394db654411b64a3366d407d8f7";
+        let plain: Vec<u8> = (0..=255).collect();
+
+        let crypted = encrypt_bytes(key, &plain).unwrap();
+        let plain_back = decrypt_bytes(key, &crypted).unwrap();
+        assert_eq!(plain, plain_back);
+    }
 }
@@ -1,7 +1,9 @@
 pub mod encryption;
 pub mod error;
+pub mod random;
 
 // Re-export error types for convenience
 pub use error::{Error, Result};
 
 pub use encryption::*;
+pub use random::random_hex;
@@ -0,0 +1,21 @@
+use chacha20poly1305::aead::{OsRng, rand_core::RngCore};
+
+/// Hex encoded random bytes from the OS generator, suitable for secrets
+pub fn random_hex(num_bytes: usize) -> String {
+    let mut bytes = vec![0u8; num_bytes];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_hex() {
+        let value = random_hex(32);
+        assert_eq!(value.len(), 64);
+        assert!(value.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(value, random_hex(32));
+    }
+}
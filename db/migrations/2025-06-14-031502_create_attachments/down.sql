@@ -0,0 +1,3 @@
+DROP INDEX attachments_entry_id_idx;
+DROP INDEX attachments_vault_id_idx;
+DROP TABLE attachments;
@@ -0,0 +1,13 @@
+CREATE TABLE attachments (
+    id CHAR(32) PRIMARY KEY NOT NULL,
+    entry_id CHAR(32) NOT NULL,
+    vault_id CHAR(32) NOT NULL,
+    cipher_filename TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    sha256 CHAR(64) NOT NULL,
+    created_at BIGINT NOT NULL,
+    FOREIGN KEY (entry_id) REFERENCES entries(id),
+    FOREIGN KEY (vault_id) REFERENCES vaults(id)
+);
+CREATE INDEX attachments_entry_id_idx ON attachments(entry_id);
+CREATE INDEX attachments_vault_id_idx ON attachments(vault_id);
@@ -0,0 +1,6 @@
+DROP TRIGGER audit_events_no_update;
+DROP TRIGGER audit_events_no_delete;
+DROP INDEX audit_events_prev_hash_idx;
+DROP INDEX audit_events_org_id_created_at_idx;
+DROP INDEX audit_events_created_at_idx;
+DROP TABLE audit_events;
@@ -0,0 +1,28 @@
+CREATE TABLE audit_events (
+    id CHAR(32) PRIMARY KEY NOT NULL,
+    org_id CHAR(32) NULL,
+    actor_id CHAR(32) NULL,
+    action VARCHAR(50) NOT NULL,
+    target_type VARCHAR(50) NULL,
+    target_id VARCHAR(250) NULL,
+    ip VARCHAR(50) NULL,
+    detail TEXT NULL,
+    prev_hash CHAR(64) NOT NULL,
+    hash CHAR(64) NOT NULL,
+    seq BIGINT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE UNIQUE INDEX audit_events_seq_idx ON audit_events(seq);
+CREATE UNIQUE INDEX audit_events_prev_hash_idx ON audit_events(prev_hash);
+CREATE INDEX audit_events_org_id_created_at_idx ON audit_events(org_id, created_at);
+CREATE INDEX audit_events_created_at_idx ON audit_events(created_at);
+
+-- Audit events are append-only
+CREATE TRIGGER audit_events_no_update BEFORE UPDATE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit events are append-only');
+END;
+CREATE TRIGGER audit_events_no_delete BEFORE DELETE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit events are append-only');
+END;
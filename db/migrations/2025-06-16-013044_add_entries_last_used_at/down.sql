@@ -0,0 +1,2 @@
+DROP INDEX entries_vault_id_last_used_at_idx;
+ALTER TABLE entries DROP COLUMN last_used_at;
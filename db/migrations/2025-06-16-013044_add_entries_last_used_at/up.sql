@@ -0,0 +1,3 @@
+ALTER TABLE entries ADD COLUMN last_used_at BIGINT NOT NULL DEFAULT 0;
+UPDATE entries SET last_used_at = updated_at;
+CREATE INDEX entries_vault_id_last_used_at_idx ON entries(vault_id, last_used_at);
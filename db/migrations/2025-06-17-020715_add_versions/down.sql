@@ -0,0 +1,4 @@
+ALTER TABLE entries DROP COLUMN version;
+ALTER TABLE vaults DROP COLUMN version;
+ALTER TABLE users DROP COLUMN version;
+ALTER TABLE orgs DROP COLUMN version;
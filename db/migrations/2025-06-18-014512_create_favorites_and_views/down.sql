@@ -0,0 +1,2 @@
+DROP TABLE entry_views;
+DROP TABLE entry_favorites;
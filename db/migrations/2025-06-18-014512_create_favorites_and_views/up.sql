@@ -0,0 +1,21 @@
+CREATE TABLE entry_favorites (
+    user_id CHAR(32) NOT NULL,
+    entry_id CHAR(32) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (user_id, entry_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+);
+CREATE INDEX entry_favorites_entry_id_idx ON entry_favorites(entry_id);
+
+-- Only the latest view per user and entry is kept
+CREATE TABLE entry_views (
+    user_id CHAR(32) NOT NULL,
+    entry_id CHAR(32) NOT NULL,
+    viewed_at BIGINT NOT NULL,
+    PRIMARY KEY (user_id, entry_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+);
+CREATE INDEX entry_views_entry_id_idx ON entry_views(entry_id);
+CREATE INDEX entry_views_user_id_viewed_at_idx ON entry_views(user_id, viewed_at);
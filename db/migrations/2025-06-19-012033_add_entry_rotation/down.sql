@@ -0,0 +1,4 @@
+ALTER TABLE vaults DROP COLUMN rotation_days;
+ALTER TABLE entries DROP COLUMN expires_at;
+ALTER TABLE entries DROP COLUMN rotation_days;
+ALTER TABLE entries DROP COLUMN secret_changed_at;
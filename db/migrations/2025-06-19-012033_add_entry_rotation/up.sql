@@ -0,0 +1,5 @@
+ALTER TABLE entries ADD COLUMN secret_changed_at BIGINT NOT NULL DEFAULT 0;
+UPDATE entries SET secret_changed_at = updated_at;
+ALTER TABLE entries ADD COLUMN rotation_days INTEGER;
+ALTER TABLE entries ADD COLUMN expires_at BIGINT;
+ALTER TABLE vaults ADD COLUMN rotation_days INTEGER;
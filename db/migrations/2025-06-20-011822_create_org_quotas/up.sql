@@ -0,0 +1,9 @@
+-- Per-org overrides, NULL falls back to the instance defaults
+CREATE TABLE org_quotas (
+    org_id CHAR(32) PRIMARY KEY NOT NULL,
+    max_vaults INTEGER,
+    max_users INTEGER,
+    max_entries_per_vault INTEGER,
+    updated_at BIGINT NOT NULL,
+    FOREIGN KEY (org_id) REFERENCES orgs(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+ALTER TABLE vaults DROP COLUMN color;
+ALTER TABLE vaults DROP COLUMN icon;
+ALTER TABLE vaults DROP COLUMN description;
@@ -0,0 +1,3 @@
+ALTER TABLE vaults ADD COLUMN description TEXT;
+ALTER TABLE vaults ADD COLUMN icon TEXT;
+ALTER TABLE vaults ADD COLUMN color TEXT;
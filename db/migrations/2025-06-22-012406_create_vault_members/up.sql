@@ -0,0 +1,27 @@
+CREATE TABLE vault_members (
+    vault_id CHAR(32) NOT NULL,
+    user_id CHAR(32) NOT NULL,
+    role VARCHAR(20) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (vault_id, user_id),
+    FOREIGN KEY (vault_id) REFERENCES vaults(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX vault_members_user_id_idx ON vault_members(user_id);
+
+-- Keep the access users had before grants, based on their org roles
+INSERT INTO vault_members (vault_id, user_id, role, created_at)
+SELECT
+    vaults.id,
+    users.id,
+    CASE
+        WHEN ',' || users.roles || ',' LIKE '%,Admin,%' THEN 'manager'
+        WHEN ',' || users.roles || ',' LIKE '%,Editor,%' THEN 'editor'
+        ELSE 'viewer'
+    END,
+    CAST(strftime('%s', 'now') AS INTEGER)
+FROM vaults
+INNER JOIN users ON users.org_id = vaults.org_id
+WHERE ',' || users.roles || ',' LIKE '%,Admin,%'
+    OR ',' || users.roles || ',' LIKE '%,Editor,%'
+    OR ',' || users.roles || ',' LIKE '%,Viewer,%';
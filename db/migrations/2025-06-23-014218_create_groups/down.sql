@@ -0,0 +1,3 @@
+DROP TABLE vault_groups;
+DROP TABLE group_users;
+DROP TABLE groups;
@@ -0,0 +1,32 @@
+CREATE TABLE groups (
+    id CHAR(32) PRIMARY KEY NOT NULL,
+    org_id CHAR(32) NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    roles VARCHAR(250) NOT NULL,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (org_id) REFERENCES orgs(id) ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX groups_org_id_name_idx ON groups(org_id, name);
+
+CREATE TABLE group_users (
+    group_id CHAR(32) NOT NULL,
+    user_id CHAR(32) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (group_id, user_id),
+    FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX group_users_user_id_idx ON group_users(user_id);
+
+CREATE TABLE vault_groups (
+    vault_id CHAR(32) NOT NULL,
+    group_id CHAR(32) NOT NULL,
+    role VARCHAR(20) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (vault_id, group_id),
+    FOREIGN KEY (vault_id) REFERENCES vaults(id) ON DELETE CASCADE,
+    FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
+);
+CREATE INDEX vault_groups_group_id_idx ON vault_groups(group_id);
@@ -0,0 +1,11 @@
+CREATE TABLE roles (
+    id CHAR(32) PRIMARY KEY NOT NULL,
+    org_id CHAR(32) NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    permissions VARCHAR(500) NOT NULL,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (org_id) REFERENCES orgs(id) ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX roles_org_id_name_idx ON roles(org_id, name);
@@ -0,0 +1,22 @@
+-- Service accounts are users that only authenticate with API keys
+ALTER TABLE users ADD COLUMN kind VARCHAR(20) NOT NULL DEFAULT 'user';
+
+CREATE TABLE api_keys (
+    id CHAR(32) PRIMARY KEY NOT NULL,
+    org_id CHAR(32) NOT NULL,
+    user_id CHAR(32) NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    key_hash CHAR(64) NOT NULL,
+    permissions VARCHAR(500) NOT NULL,
+    vault_id CHAR(32),
+    allowed_ips VARCHAR(500),
+    expires_at BIGINT,
+    last_used_at BIGINT,
+    last_used_ip VARCHAR(50),
+    created_at BIGINT NOT NULL,
+    revoked_at BIGINT,
+    FOREIGN KEY (org_id) REFERENCES orgs(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (vault_id) REFERENCES vaults(id) ON DELETE CASCADE
+);
+CREATE INDEX api_keys_user_id_idx ON api_keys(user_id);
@@ -0,0 +1,25 @@
+-- Login sessions, keyed by the jti claim of the access tokens
+CREATE TABLE sessions (
+    id CHAR(32) PRIMARY KEY NOT NULL,
+    org_id CHAR(32) NOT NULL,
+    user_id CHAR(32) NOT NULL,
+    refresh_hash CHAR(64) NOT NULL,
+    ip VARCHAR(50),
+    user_agent VARCHAR(250),
+    created_at BIGINT NOT NULL,
+    last_used_at BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL,
+    revoked_at BIGINT,
+    FOREIGN KEY (org_id) REFERENCES orgs(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX sessions_user_id_idx ON sessions(user_id);
+
+-- Refresh tokens already exchanged, presenting one again revokes the session
+CREATE TABLE used_refresh_tokens (
+    refresh_hash CHAR(64) PRIMARY KEY NOT NULL,
+    session_id CHAR(32) NOT NULL,
+    used_at BIGINT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+CREATE INDEX used_refresh_tokens_session_id_idx ON used_refresh_tokens(session_id);
@@ -0,0 +1,10 @@
+-- Failed logins per username and per source IP, cleared after a successful login
+CREATE TABLE login_attempts (
+    scope VARCHAR(10) NOT NULL,
+    subject VARCHAR(100) NOT NULL,
+    failures INTEGER NOT NULL,
+    first_failed_at BIGINT NOT NULL,
+    last_failed_at BIGINT NOT NULL,
+    locked_until BIGINT,
+    PRIMARY KEY (scope, subject)
+);
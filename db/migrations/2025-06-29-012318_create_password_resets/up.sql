@@ -0,0 +1,12 @@
+-- One-time password reset links issued by admins, at most one pending per user
+CREATE TABLE password_resets (
+    id CHAR(32) PRIMARY KEY NOT NULL,
+    org_id CHAR(32) NOT NULL,
+    user_id CHAR(32) NOT NULL UNIQUE,
+    token_hash CHAR(64) NOT NULL,
+    created_by CHAR(32),
+    created_at BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL,
+    FOREIGN KEY (org_id) REFERENCES orgs(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
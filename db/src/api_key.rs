@@ -0,0 +1,454 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::prelude::*;
+use diesel::{QueryDsl, SelectableHelper};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, ensure};
+use validator::{Validate, ValidationError};
+
+use crate::Result;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu, ValidationSnafu};
+use crate::role::validate_permissions;
+use crate::schema::api_keys::{self, dsl};
+use dto::api_key::{ApiKeyDto, NewApiKeyDto, valid_ip_range};
+use dto::role::to_permissions;
+use vault::utils::generate_id;
+use vault::validators::flatten_errors;
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::api_keys)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct ApiKey {
+    id: String,
+    org_id: String,
+    user_id: String,
+    name: String,
+    key_hash: String,
+    permissions: String,
+    vault_id: Option<String>,
+    allowed_ips: Option<String>,
+    expires_at: Option<i64>,
+    last_used_at: Option<i64>,
+    last_used_ip: Option<String>,
+    created_at: i64,
+    revoked_at: Option<i64>,
+}
+
+impl From<ApiKey> for ApiKeyDto {
+    fn from(key: ApiKey) -> Self {
+        let list = key
+            .permissions
+            .split(",")
+            .map(|item| item.to_string())
+            .collect();
+        let permissions = to_permissions(&list).expect("Invalid permissions");
+        let allowed_ips = match key.allowed_ips {
+            Some(ips) => ips.split(",").map(|item| item.to_string()).collect(),
+            None => vec![],
+        };
+        ApiKeyDto {
+            id: key.id,
+            org_id: key.org_id,
+            user_id: key.user_id,
+            name: key.name,
+            permissions,
+            vault_id: key.vault_id,
+            allowed_ips,
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+            last_used_ip: key.last_used_ip,
+            created_at: key.created_at,
+            revoked_at: key.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct NewApiKey {
+    #[validate(length(min = 1, max = 50))]
+    #[validate(custom(function = "vault::validators::anyname"))]
+    pub name: String,
+
+    /// Comma separated permissions, ex: entries.list,entries.view
+    #[validate(length(min = 1, max = 500))]
+    pub permissions: String,
+
+    /// Restricts the key to a single vault
+    #[validate(custom(function = "vault::validators::uuid"))]
+    pub vault_id: Option<String>,
+
+    /// Comma separated addresses or CIDR ranges, ex: 10.0.0.0/8,192.168.1.10
+    #[validate(length(min = 1, max = 500))]
+    #[validate(custom(function = "ip_ranges"))]
+    pub allowed_ips: Option<String>,
+
+    pub expires_at: Option<i64>,
+}
+
+fn ip_ranges(value: &str) -> std::result::Result<(), ValidationError> {
+    match value.split(",").all(valid_ip_range) {
+        true => Ok(()),
+        false => Err(ValidationError::new("ip_ranges")),
+    }
+}
+
+pub(crate) fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+#[async_trait]
+pub trait ApiKeyRepoable: Send + Sync {
+    /// Keys of the service account, newest first, revoked keys included
+    async fn list(&self, user_id: &str) -> Result<Vec<ApiKeyDto>>;
+
+    /// Issues a new key, the full key is only available from the result
+    async fn create(&self, org_id: &str, user_id: &str, data: &NewApiKey) -> Result<NewApiKeyDto>;
+
+    async fn get(&self, id: &str) -> Result<Option<ApiKeyDto>>;
+
+    /// Finds the key matching the full key value unless revoked
+    ///
+    /// Expiry and address restrictions are left to the caller.
+    async fn authenticate(&self, key: &str) -> Result<Option<ApiKeyDto>>;
+
+    /// Records when and from where the key was last used
+    async fn touch(&self, id: &str, ip: Option<String>) -> Result<()>;
+
+    /// Revoked keys are kept for the record but can no longer be used
+    async fn revoke(&self, id: &str) -> Result<bool>;
+}
+
+pub struct ApiKeyRepo {
+    db_pool: Pool,
+}
+
+impl ApiKeyRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+
+    async fn find(&self, id: &str) -> Result<Option<ApiKey>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let kid = id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::api_keys
+                    .find(kid)
+                    .select(ApiKey::as_select())
+                    .first::<ApiKey>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let item = select_res.context(DbQuerySnafu {
+            table: "api_keys".to_string(),
+        })?;
+
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepoable for ApiKeyRepo {
+    async fn list(&self, user_id: &str) -> Result<Vec<ApiKeyDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let user_id = user_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::api_keys
+                    .filter(dsl::user_id.eq(&user_id))
+                    .select(ApiKey::as_select())
+                    .order(dsl::created_at.desc())
+                    .then_order_by(dsl::id.desc())
+                    .load::<ApiKey>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "api_keys".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|item| item.into()).collect())
+    }
+
+    async fn create(&self, org_id: &str, user_id: &str, data: &NewApiKey) -> Result<NewApiKeyDto> {
+        let errors = data.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+        validate_permissions(&data.permissions)?;
+
+        let today = chrono::Utc::now().timestamp();
+        ensure!(
+            data.expires_at.is_none_or(|expires_at| expires_at > today),
+            ValidationSnafu {
+                msg: "Expiry must be in the future".to_string(),
+            }
+        );
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let secret = cipher::random_hex(32);
+        let api_key = ApiKey {
+            id: generate_id(),
+            org_id: org_id.to_string(),
+            user_id: user_id.to_string(),
+            name: data.name.clone(),
+            key_hash: hash_secret(&secret),
+            permissions: data.permissions.clone(),
+            vault_id: data.vault_id.clone(),
+            allowed_ips: data.allowed_ips.clone(),
+            expires_at: data.expires_at,
+            last_used_at: None,
+            last_used_ip: None,
+            created_at: today,
+            revoked_at: None,
+        };
+
+        let key_copy = api_key.clone();
+        let insert_res = db
+            .interact(move |conn| {
+                diesel::insert_into(api_keys::table)
+                    .values(&key_copy)
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = insert_res.context(DbQuerySnafu {
+            table: "api_keys".to_string(),
+        })?;
+
+        let key = format!("{}.{}", api_key.id, secret);
+        Ok(NewApiKeyDto {
+            api_key: api_key.into(),
+            key,
+        })
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<ApiKeyDto>> {
+        let item = self.find(id).await?;
+        Ok(item.map(|item| item.into()))
+    }
+
+    async fn authenticate(&self, key: &str) -> Result<Option<ApiKeyDto>> {
+        let Some((id, secret)) = key.split_once('.') else {
+            return Ok(None);
+        };
+        if !vault::utils::valid_id(id) {
+            return Ok(None);
+        }
+
+        let item = self.find(id).await?;
+        let found = item.filter(|x| x.revoked_at.is_none() && x.key_hash == hash_secret(secret));
+        Ok(found.map(|item| item.into()))
+    }
+
+    async fn touch(&self, id: &str, ip: Option<String>) -> Result<()> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let kid = id.to_string();
+        let today = chrono::Utc::now().timestamp();
+        let update_res = db
+            .interact(move |conn| {
+                diesel::update(dsl::api_keys)
+                    .filter(dsl::id.eq(kid.as_str()))
+                    .set((dsl::last_used_at.eq(today), dsl::last_used_ip.eq(ip)))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = update_res.context(DbQuerySnafu {
+            table: "api_keys".to_string(),
+        })?;
+
+        Ok(())
+    }
+
+    async fn revoke(&self, id: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let kid = id.to_string();
+        let today = chrono::Utc::now().timestamp();
+        let update_res = db
+            .interact(move |conn| {
+                diesel::update(dsl::api_keys)
+                    .filter(dsl::id.eq(kid.as_str()))
+                    .filter(dsl::revoked_at.is_null())
+                    .set(dsl::revoked_at.eq(today))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = update_res.context(DbQuerySnafu {
+            table: "api_keys".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct ApiKeyTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl ApiKeyRepoable for ApiKeyTestRepo {
+    async fn list(&self, _user_id: &str) -> Result<Vec<ApiKeyDto>> {
+        Ok(vec![])
+    }
+
+    async fn create(
+        &self,
+        _org_id: &str,
+        _user_id: &str,
+        _data: &NewApiKey,
+    ) -> Result<NewApiKeyDto> {
+        Err("Not supported".into())
+    }
+
+    async fn get(&self, _id: &str) -> Result<Option<ApiKeyDto>> {
+        Ok(None)
+    }
+
+    async fn authenticate(&self, _key: &str) -> Result<Option<ApiKeyDto>> {
+        Ok(None)
+    }
+
+    async fn touch(&self, _id: &str, _ip: Option<String>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn revoke(&self, _id: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
+    use crate::user::NewServiceAccount;
+    use dto::role::Permission;
+
+    #[tokio::test]
+    async fn test_api_key_lifecycle() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let data = NewServiceAccount {
+            username: "deploybot".to_string(),
+            roles: "Viewer".to_string(),
+        };
+        let account = db
+            .users
+            .create_service_account(&seed.org.id, &data)
+            .await
+            .unwrap();
+        assert!(account.is_service_account());
+
+        let vault_id = seed.vaults[0].id.clone();
+        let data = NewApiKey {
+            name: "CI".to_string(),
+            permissions: "entries.list,entries.view".to_string(),
+            vault_id: Some(vault_id.clone()),
+            allowed_ips: Some("10.0.0.0/8,192.168.1.10".to_string()),
+            expires_at: None,
+        };
+        let created = db
+            .api_keys
+            .create(&seed.org.id, &account.id, &data)
+            .await
+            .unwrap();
+        assert_eq!(
+            created.api_key.permissions,
+            vec![Permission::EntriesList, Permission::EntriesView]
+        );
+        assert_eq!(created.api_key.allowed_ips.len(), 2);
+        assert!(created.key.starts_with(&format!("{}.", created.api_key.id)));
+
+        let found = db.api_keys.authenticate(&created.key).await.unwrap();
+        assert_eq!(found.unwrap().vault_id, Some(vault_id));
+
+        // Wrong secret or malformed keys never match
+        let wrong = format!("{}.{}", created.api_key.id, "0".repeat(64));
+        assert!(db.api_keys.authenticate(&wrong).await.unwrap().is_none());
+        assert!(db.api_keys.authenticate("garbage").await.unwrap().is_none());
+
+        db.api_keys
+            .touch(&created.api_key.id, Some("10.1.2.3".to_string()))
+            .await
+            .unwrap();
+        let items = db.api_keys.list(&account.id).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert!(items[0].last_used_at.is_some());
+        assert_eq!(items[0].last_used_ip.as_deref(), Some("10.1.2.3"));
+
+        assert!(db.api_keys.revoke(&created.api_key.id).await.unwrap());
+        assert!(!db.api_keys.revoke(&created.api_key.id).await.unwrap());
+        assert!(
+            db.api_keys
+                .authenticate(&created.key)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_api_key() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user_id = seed.user("viewer").unwrap().id.clone();
+
+        let data = NewApiKey {
+            name: "CI".to_string(),
+            permissions: "orgs.create".to_string(),
+            vault_id: None,
+            allowed_ips: None,
+            expires_at: None,
+        };
+        assert!(
+            db.api_keys
+                .create(&seed.org.id, &user_id, &data)
+                .await
+                .is_err()
+        );
+
+        let data = NewApiKey {
+            permissions: "entries.list".to_string(),
+            allowed_ips: Some("localhost".to_string()),
+            ..data
+        };
+        assert!(
+            db.api_keys
+                .create(&seed.org.id, &user_id, &data)
+                .await
+                .is_err()
+        );
+
+        let data = NewApiKey {
+            allowed_ips: None,
+            expires_at: Some(chrono::Utc::now().timestamp() - 60),
+            ..data
+        };
+        assert!(
+            db.api_keys
+                .create(&seed.org.id, &user_id, &data)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::dsl::{count_star, sql};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Nullable};
+use diesel::{QueryDsl, SelectableHelper};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use validator::Validate;
+
+use crate::Result;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
+use crate::schema::attachments::{self, dsl};
+use crate::tx::SqliteTx;
+use dto::attachment::AttachmentDto;
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable, Serialize)]
+#[diesel(table_name = crate::schema::attachments)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Attachment {
+    pub id: String,
+    pub entry_id: String,
+    pub vault_id: String,
+    pub cipher_filename: String,
+    pub size: i64,
+    pub sha256: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct NewAttachment {
+    #[validate(length(min = 1, max = 1000))]
+    pub cipher_filename: String,
+}
+
+impl From<AttachmentDto> for Attachment {
+    fn from(dto: AttachmentDto) -> Self {
+        Attachment {
+            id: dto.id,
+            entry_id: dto.entry_id,
+            vault_id: dto.vault_id,
+            cipher_filename: dto.cipher_filename,
+            size: dto.size,
+            sha256: dto.sha256,
+            created_at: dto.created_at,
+        }
+    }
+}
+
+impl From<Attachment> for AttachmentDto {
+    fn from(attachment: Attachment) -> Self {
+        AttachmentDto {
+            id: attachment.id,
+            entry_id: attachment.entry_id,
+            vault_id: attachment.vault_id,
+            cipher_filename: attachment.cipher_filename,
+            size: attachment.size,
+            sha256: attachment.sha256,
+            created_at: attachment.created_at,
+        }
+    }
+}
+
+#[async_trait]
+pub trait AttachmentRepoable: Send + Sync {
+    async fn list_by_entry(&self, entry_id: &str) -> Result<Vec<AttachmentDto>>;
+
+    async fn create(&self, dto: AttachmentDto) -> Result<AttachmentDto>;
+
+    async fn get(&self, id: &str) -> Result<Option<AttachmentDto>>;
+
+    async fn count_by_entry(&self, entry_id: &str) -> Result<i64>;
+
+    async fn total_size_by_vault(&self, vault_id: &str) -> Result<i64>;
+
+    async fn delete(&self, id: &str) -> Result<()>;
+}
+
+pub struct AttachmentRepo {
+    db_pool: Pool,
+}
+
+impl AttachmentRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl AttachmentRepoable for AttachmentRepo {
+    async fn list_by_entry(&self, entry_id: &str) -> Result<Vec<AttachmentDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let eid = entry_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::attachments
+                    .filter(dsl::entry_id.eq(eid.as_str()))
+                    .select(Attachment::as_select())
+                    .order(dsl::created_at.asc())
+                    .load::<Attachment>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "attachments".to_string(),
+        })?;
+
+        let dtos: Vec<AttachmentDto> = items.into_iter().map(|item| item.into()).collect();
+        Ok(dtos)
+    }
+
+    async fn create(&self, dto: AttachmentDto) -> Result<AttachmentDto> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        db.interact(move |conn| SqliteTx { conn }.create(dto))
+            .await
+            .context(DbInteractSnafu)?
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<AttachmentDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let aid = id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::attachments
+                    .find(aid)
+                    .select(Attachment::as_select())
+                    .first::<Attachment>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let item = select_res.context(DbQuerySnafu {
+            table: "attachments".to_string(),
+        })?;
+
+        Ok(item.map(|item| item.into()))
+    }
+
+    async fn count_by_entry(&self, entry_id: &str) -> Result<i64> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let eid = entry_id.to_string();
+        db.interact(move |conn| SqliteTx { conn }.count_by_entry(&eid))
+            .await
+            .context(DbInteractSnafu)?
+    }
+
+    async fn total_size_by_vault(&self, vault_id: &str) -> Result<i64> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let vid = vault_id.to_string();
+        db.interact(move |conn| SqliteTx { conn }.total_size_by_vault(&vid))
+            .await
+            .context(DbInteractSnafu)?
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let aid = id.to_string();
+        let delete_res = db
+            .interact(move |conn| {
+                diesel::delete(dsl::attachments.filter(dsl::id.eq(aid.as_str()))).execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = delete_res.context(DbQuerySnafu {
+            table: "attachments".to_string(),
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Attachment operations available inside a transaction
+pub trait AttachmentTx {
+    fn create(&mut self, dto: AttachmentDto) -> Result<AttachmentDto>;
+
+    fn count_by_entry(&mut self, entry_id: &str) -> Result<i64>;
+
+    fn total_size_by_vault(&mut self, vault_id: &str) -> Result<i64>;
+
+    /// Removes the attachment records of the entry, returns their ids
+    fn delete_by_entry(&mut self, entry_id: &str) -> Result<Vec<String>>;
+}
+
+impl AttachmentTx for SqliteTx<'_> {
+    fn create(&mut self, dto: AttachmentDto) -> Result<AttachmentDto> {
+        let attachment: Attachment = dto.into();
+
+        diesel::insert_into(attachments::table)
+            .values(&attachment)
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "attachments".to_string(),
+            })?;
+
+        Ok(attachment.into())
+    }
+
+    fn count_by_entry(&mut self, entry_id: &str) -> Result<i64> {
+        dsl::attachments
+            .filter(dsl::entry_id.eq(entry_id))
+            .select(count_star())
+            .get_result::<i64>(self.conn)
+            .context(DbQuerySnafu {
+                table: "attachments".to_string(),
+            })
+    }
+
+    fn total_size_by_vault(&mut self, vault_id: &str) -> Result<i64> {
+        let total = dsl::attachments
+            .filter(dsl::vault_id.eq(vault_id))
+            .select(sql::<Nullable<BigInt>>("SUM(size)"))
+            .get_result::<Option<i64>>(self.conn)
+            .context(DbQuerySnafu {
+                table: "attachments".to_string(),
+            })?;
+
+        Ok(total.unwrap_or(0))
+    }
+
+    fn delete_by_entry(&mut self, entry_id: &str) -> Result<Vec<String>> {
+        let ids = dsl::attachments
+            .filter(dsl::entry_id.eq(entry_id))
+            .select(dsl::id)
+            .load::<String>(self.conn)
+            .context(DbQuerySnafu {
+                table: "attachments".to_string(),
+            })?;
+
+        diesel::delete(dsl::attachments.filter(dsl::entry_id.eq(entry_id)))
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "attachments".to_string(),
+            })?;
+
+        Ok(ids)
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct AttachmentTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl AttachmentRepoable for AttachmentTestRepo {
+    async fn list_by_entry(&self, _entry_id: &str) -> Result<Vec<AttachmentDto>> {
+        Ok(vec![])
+    }
+
+    async fn create(&self, _dto: AttachmentDto) -> Result<AttachmentDto> {
+        Err("Not supported".into())
+    }
+
+    async fn get(&self, _id: &str) -> Result<Option<AttachmentDto>> {
+        Ok(None)
+    }
+
+    async fn count_by_entry(&self, _entry_id: &str) -> Result<i64> {
+        Ok(0)
+    }
+
+    async fn total_size_by_vault(&self, _vault_id: &str) -> Result<i64> {
+        Ok(0)
+    }
+
+    async fn delete(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "test")]
+impl AttachmentTx for crate::tx::TestTx {
+    fn create(&mut self, _dto: AttachmentDto) -> Result<AttachmentDto> {
+        Err("Not supported".into())
+    }
+
+    fn count_by_entry(&mut self, _entry_id: &str) -> Result<i64> {
+        Ok(0)
+    }
+
+    fn total_size_by_vault(&mut self, _vault_id: &str) -> Result<i64> {
+        Ok(0)
+    }
+
+    fn delete_by_entry(&mut self, _entry_id: &str) -> Result<Vec<String>> {
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_attachment() {
+        let data = NewAttachment {
+            cipher_filename: "xch:nonce:data".to_string(),
+        };
+        assert!(data.validate().is_ok());
+
+        let data = NewAttachment {
+            cipher_filename: "".to_string(),
+        };
+        assert!(data.validate().is_err());
+    }
+}
@@ -0,0 +1,496 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use diesel::{QueryDsl, SelectableHelper};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, ensure};
+use validator::Validate;
+
+use crate::Result;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu, ValidationSnafu};
+use crate::schema::audit_events::{self, dsl};
+use dto::audit::AuditEventDto;
+use dto::pagination::PaginatedDto;
+use vault::utils::generate_id;
+use vault::validators::flatten_errors;
+
+/// The first event in the chain points to this hash
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable, Serialize)]
+#[diesel(table_name = crate::schema::audit_events)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AuditEvent {
+    pub id: String,
+    pub org_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    pub detail: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+    pub seq: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NewAuditEvent {
+    pub org_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct ListAuditEventsParams {
+    #[validate(range(min = 1, max = 1000))]
+    pub page: Option<i32>,
+
+    #[validate(range(min = 1, max = 50))]
+    pub per_page: Option<i32>,
+
+    #[validate(length(min = 0, max = 50))]
+    pub action: Option<String>,
+
+    #[validate(length(min = 0, max = 32))]
+    pub actor_id: Option<String>,
+
+    #[validate(length(min = 0, max = 50))]
+    pub target_type: Option<String>,
+
+    #[validate(length(min = 0, max = 250))]
+    pub target_id: Option<String>,
+
+    pub from: Option<i64>,
+
+    pub to: Option<i64>,
+}
+
+impl From<AuditEvent> for AuditEventDto {
+    fn from(event: AuditEvent) -> Self {
+        AuditEventDto {
+            id: event.id,
+            org_id: event.org_id,
+            actor_id: event.actor_id,
+            action: event.action,
+            target_type: event.target_type,
+            target_id: event.target_id,
+            ip: event.ip,
+            detail: event.detail,
+            prev_hash: event.prev_hash,
+            hash: event.hash,
+            created_at: event.created_at,
+        }
+    }
+}
+
+/// Length-prefixed so that no two sets of field values hash the same
+fn hash_field(hasher: &mut Sha256, value: Option<&str>) {
+    match value {
+        Some(value) => {
+            hasher.update([1]);
+            hasher.update((value.len() as u64).to_be_bytes());
+            hasher.update(value.as_bytes());
+        }
+        None => hasher.update([0]),
+    }
+}
+
+impl AuditEvent {
+    /// Computes the chain hash from the previous hash and the event fields
+    pub fn compute_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hash_field(&mut hasher, Some(&self.prev_hash));
+        hash_field(&mut hasher, Some(&self.id));
+        hash_field(&mut hasher, self.org_id.as_deref());
+        hash_field(&mut hasher, self.actor_id.as_deref());
+        hash_field(&mut hasher, Some(&self.action));
+        hash_field(&mut hasher, self.target_type.as_deref());
+        hash_field(&mut hasher, self.target_id.as_deref());
+        hash_field(&mut hasher, self.ip.as_deref());
+        hash_field(&mut hasher, self.detail.as_deref());
+        hasher.update(self.seq.to_be_bytes());
+        hasher.update(self.created_at.to_be_bytes());
+
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Walks the chain in order and returns the id of the first tampered event
+pub fn find_broken_link(events: &[AuditEvent]) -> Option<String> {
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for event in events.iter() {
+        if event.prev_hash != prev_hash || event.hash != event.compute_hash() {
+            return Some(event.id.clone());
+        }
+        prev_hash = event.hash.clone();
+    }
+    None
+}
+
+const MAX_PER_PAGE: i32 = 50;
+
+type BoxedAuditQuery<'a> = audit_events::BoxedQuery<'a, Sqlite>;
+
+fn filtered_query<'a>(org_id: &'a str, params: &'a ListAuditEventsParams) -> BoxedAuditQuery<'a> {
+    let mut query = dsl::audit_events.into_boxed();
+    query = query.filter(dsl::org_id.eq(org_id));
+
+    if let Some(action) = &params.action
+        && !action.is_empty()
+    {
+        query = query.filter(dsl::action.eq(action));
+    }
+    if let Some(actor_id) = &params.actor_id
+        && !actor_id.is_empty()
+    {
+        query = query.filter(dsl::actor_id.eq(actor_id));
+    }
+    if let Some(target_type) = &params.target_type
+        && !target_type.is_empty()
+    {
+        query = query.filter(dsl::target_type.eq(target_type));
+    }
+    if let Some(target_id) = &params.target_id
+        && !target_id.is_empty()
+    {
+        query = query.filter(dsl::target_id.eq(target_id));
+    }
+    if let Some(from) = params.from {
+        query = query.filter(dsl::created_at.ge(from));
+    }
+    if let Some(to) = params.to {
+        query = query.filter(dsl::created_at.le(to));
+    }
+    query
+}
+
+#[async_trait]
+pub trait AuditRepoable: Send + Sync {
+    async fn list(
+        &self,
+        org_id: &str,
+        params: &ListAuditEventsParams,
+    ) -> Result<PaginatedDto<AuditEventDto>>;
+
+    async fn create(&self, data: &NewAuditEvent) -> Result<AuditEventDto>;
+
+    async fn verify_chain(&self) -> Result<Option<String>>;
+}
+
+pub struct AuditRepo {
+    db_pool: Pool,
+}
+
+impl AuditRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+
+    pub async fn listing_count(&self, org_id: &str, params: &ListAuditEventsParams) -> Result<i64> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let oid = org_id.to_string();
+        let params_copy = params.clone();
+
+        let count_res = db
+            .interact(move |conn| {
+                filtered_query(&oid, &params_copy)
+                    .select(count_star())
+                    .get_result::<i64>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let count = count_res.context(DbQuerySnafu {
+            table: "audit_events".to_string(),
+        })?;
+
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl AuditRepoable for AuditRepo {
+    async fn list(
+        &self,
+        org_id: &str,
+        params: &ListAuditEventsParams,
+    ) -> Result<PaginatedDto<AuditEventDto>> {
+        let errors = params.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let total_records = self.listing_count(org_id, params).await?;
+        let mut page: i32 = 1;
+        let mut per_page: i32 = MAX_PER_PAGE;
+        let mut offset: i64 = 0;
+
+        if let Some(per_page_param) = params.per_page
+            && per_page_param > 0
+            && per_page_param <= MAX_PER_PAGE
+        {
+            per_page = per_page_param;
+        }
+
+        let total_pages: i64 = (total_records as f64 / per_page as f64).ceil() as i64;
+
+        if let Some(p) = params.page {
+            let p64 = p as i64;
+            if p64 > 0 && p64 <= total_pages {
+                page = p;
+                offset = (p64 - 1) * per_page as i64;
+            }
+        }
+
+        // Do not query if we already know there are no records
+        if total_pages == 0 {
+            return Ok(PaginatedDto::new(Vec::new(), page, per_page, total_records));
+        }
+
+        let oid = org_id.to_string();
+        let params_copy = params.clone();
+        let select_res = db
+            .interact(move |conn| {
+                filtered_query(&oid, &params_copy)
+                    .limit(per_page as i64)
+                    .offset(offset)
+                    .select(AuditEvent::as_select())
+                    .order((dsl::created_at.desc(), dsl::id.desc()))
+                    .load::<AuditEvent>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "audit_events".to_string(),
+        })?;
+
+        let dtos: Vec<AuditEventDto> = items.into_iter().map(|item| item.into()).collect();
+        Ok(PaginatedDto::new(dtos, page, per_page, total_records))
+    }
+
+    async fn create(&self, data: &NewAuditEvent) -> Result<AuditEventDto> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let data_copy = data.clone();
+        let insert_res = db
+            .interact(move |conn| {
+                // Lock the table for writing so that the chain cannot fork
+                conn.immediate_transaction(|conn| {
+                    // The sequence, unlike the clock, never goes backwards
+                    let last = dsl::audit_events
+                        .select((dsl::seq, dsl::hash))
+                        .order(dsl::seq.desc())
+                        .first::<(i64, String)>(conn)
+                        .optional()?;
+                    let (last_seq, last_hash) = match last {
+                        Some((seq, hash)) => (seq, hash),
+                        None => (0, GENESIS_HASH.to_string()),
+                    };
+
+                    let mut event = AuditEvent {
+                        id: generate_id(),
+                        org_id: data_copy.org_id,
+                        actor_id: data_copy.actor_id,
+                        action: data_copy.action,
+                        target_type: data_copy.target_type,
+                        target_id: data_copy.target_id,
+                        ip: data_copy.ip,
+                        detail: data_copy.detail,
+                        prev_hash: last_hash,
+                        hash: "".to_string(),
+                        seq: last_seq + 1,
+                        created_at: chrono::Utc::now().timestamp(),
+                    };
+                    event.hash = event.compute_hash();
+
+                    diesel::insert_into(audit_events::table)
+                        .values(&event)
+                        .execute(conn)?;
+
+                    Ok::<AuditEvent, diesel::result::Error>(event)
+                })
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let event = insert_res.context(DbQuerySnafu {
+            table: "audit_events".to_string(),
+        })?;
+
+        Ok(event.into())
+    }
+
+    async fn verify_chain(&self) -> Result<Option<String>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let select_res = db
+            .interact(move |conn| {
+                dsl::audit_events
+                    .select(AuditEvent::as_select())
+                    .order(dsl::seq.asc())
+                    .load::<AuditEvent>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "audit_events".to_string(),
+        })?;
+
+        Ok(find_broken_link(&items))
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct AuditTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl AuditRepoable for AuditTestRepo {
+    async fn list(
+        &self,
+        _org_id: &str,
+        _params: &ListAuditEventsParams,
+    ) -> Result<PaginatedDto<AuditEventDto>> {
+        Ok(PaginatedDto::new(vec![], 1, 10, 0))
+    }
+
+    async fn create(&self, data: &NewAuditEvent) -> Result<AuditEventDto> {
+        let mut event = AuditEvent {
+            id: generate_id(),
+            org_id: data.org_id.clone(),
+            actor_id: data.actor_id.clone(),
+            action: data.action.clone(),
+            target_type: data.target_type.clone(),
+            target_id: data.target_id.clone(),
+            ip: data.ip.clone(),
+            detail: data.detail.clone(),
+            prev_hash: GENESIS_HASH.to_string(),
+            hash: "".to_string(),
+            seq: 1,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        event.hash = event.compute_hash();
+        Ok(event.into())
+    }
+
+    async fn verify_chain(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_chain(count: usize) -> Vec<AuditEvent> {
+        let mut events: Vec<AuditEvent> = Vec::new();
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for i in 0..count {
+            let mut event = AuditEvent {
+                id: generate_id(),
+                org_id: Some("org".to_string()),
+                actor_id: Some("actor".to_string()),
+                action: "entries.view".to_string(),
+                target_type: Some("entry".to_string()),
+                target_id: Some(format!("entry-{}", i)),
+                ip: Some("127.0.0.1".to_string()),
+                detail: None,
+                prev_hash: prev_hash.clone(),
+                hash: "".to_string(),
+                seq: i as i64 + 1,
+                created_at: 1700000000 + i as i64,
+            };
+            event.hash = event.compute_hash();
+            prev_hash = event.hash.clone();
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn test_valid_chain() {
+        let events = create_chain(5);
+        assert_eq!(find_broken_link(&events), None);
+        assert_eq!(find_broken_link(&[]), None);
+    }
+
+    #[test]
+    fn test_modified_event() {
+        let mut events = create_chain(5);
+        events[2].ip = Some("10.0.0.1".to_string());
+        assert_eq!(find_broken_link(&events), Some(events[2].id.clone()));
+    }
+
+    #[test]
+    fn test_removed_event() {
+        let mut events = create_chain(5);
+        let removed = events.remove(1);
+        assert_eq!(find_broken_link(&events), Some(events[1].id.clone()));
+        assert_ne!(removed.id, events[1].id);
+    }
+
+    #[test]
+    fn test_field_boundaries() {
+        let events = create_chain(1);
+        let mut moved = events[0].clone();
+        moved.target_type = Some("entry|entry-0".to_string());
+        moved.target_id = None;
+        assert_ne!(moved.compute_hash(), events[0].hash);
+
+        let mut empty = events[0].clone();
+        empty.detail = Some("".to_string());
+        assert_ne!(empty.compute_hash(), events[0].hash);
+    }
+
+    #[tokio::test]
+    async fn test_clock_going_backwards() {
+        use crate::db::{DbOptions, create_db_pool};
+        use crate::ephemeral::apply_schema;
+
+        let database_url = format!("file:/ferriseal-{}?vfs=memdb", generate_id());
+        let mut conn = SqliteConnection::establish(&database_url).unwrap();
+        apply_schema(&mut conn).unwrap();
+        let repo = AuditRepo::new(create_db_pool(&database_url, &DbOptions::default()));
+
+        let data = NewAuditEvent {
+            action: "entries.view".to_string(),
+            ..NewAuditEvent::default()
+        };
+        let first = repo.create(&data).await.unwrap();
+
+        // Written while the clock was an hour ahead
+        let mut ahead = create_chain(1).remove(0);
+        ahead.prev_hash = first.hash.clone();
+        ahead.seq = 2;
+        ahead.created_at = chrono::Utc::now().timestamp() + 3600;
+        ahead.hash = ahead.compute_hash();
+        diesel::insert_into(audit_events::table)
+            .values(&ahead)
+            .execute(&mut conn)
+            .unwrap();
+
+        let third = repo.create(&data).await.unwrap();
+        let fourth = repo.create(&data).await.unwrap();
+        assert_eq!(third.prev_hash, ahead.hash);
+        assert_eq!(fourth.prev_hash, third.hash);
+        assert_eq!(repo.verify_chain().await.unwrap(), None);
+    }
+}
@@ -0,0 +1,269 @@
+use std::ffi::{CStr, CString, c_int};
+use std::path::Path;
+use std::ptr;
+use std::thread;
+use std::time::Duration;
+
+use libsqlite3_sys as ffi;
+use snafu::{OptionExt, ensure};
+
+use crate::Result;
+use crate::error::BackupSnafu;
+
+/// Pages copied per step, writers can use the source database in between
+const PAGES_PER_STEP: c_int = 256;
+
+/// Wait time when the source or destination is locked by another connection
+const BUSY_WAIT: Duration = Duration::from_millis(50);
+
+/// Copies the live database into a new file using SQLite's online backup API
+pub fn backup_database(database_url: &str, out: &Path) -> Result<()> {
+    ensure!(
+        !out.exists(),
+        BackupSnafu {
+            msg: format!("Backup file already exists: {}", out.display()),
+        }
+    );
+
+    let src = RawDb::open(&sqlite_uri(database_url), ffi::SQLITE_OPEN_READONLY)?;
+    let dest = RawDb::open(
+        &path_str(out)?,
+        ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+    )?;
+
+    copy_database(&src, &dest)
+}
+
+/// Replaces the contents of the database with the backup file
+///
+/// Other connections must not be writing while the restore runs.
+pub fn restore_database(database_url: &str, from: &Path) -> Result<()> {
+    ensure!(
+        from.exists(),
+        BackupSnafu {
+            msg: format!("Backup file not found: {}", from.display()),
+        }
+    );
+
+    let src = RawDb::open(&path_str(from)?, ffi::SQLITE_OPEN_READONLY)?;
+    src.check_integrity()?;
+
+    let dest = RawDb::open(
+        &sqlite_uri(database_url),
+        ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+    )?;
+
+    copy_database(&src, &dest)
+}
+
+/// Same url handling as diesel so both point to the same file
+fn sqlite_uri(database_url: &str) -> String {
+    if database_url.starts_with("sqlite://") {
+        database_url.replacen("sqlite://", "file:", 1)
+    } else {
+        database_url.to_string()
+    }
+}
+
+fn path_str(path: &Path) -> Result<String> {
+    let value = path.to_str().context(BackupSnafu {
+        msg: format!("Invalid backup path: {}", path.display()),
+    })?;
+    Ok(value.to_string())
+}
+
+fn copy_database(src: &RawDb, dest: &RawDb) -> Result<()> {
+    let main = c"main";
+
+    // SAFETY: both handles are open for the lifetime of the backup object,
+    // which is always released by sqlite3_backup_finish below.
+    unsafe {
+        let backup =
+            ffi::sqlite3_backup_init(dest.handle, main.as_ptr(), src.handle, main.as_ptr());
+        if backup.is_null() {
+            return Err(dest.error());
+        }
+
+        loop {
+            let rc = ffi::sqlite3_backup_step(backup, PAGES_PER_STEP);
+            match rc {
+                ffi::SQLITE_OK => {}
+                ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => thread::sleep(BUSY_WAIT),
+                _ => break,
+            }
+        }
+
+        // Reports the first error encountered by any of the steps
+        let rc = ffi::sqlite3_backup_finish(backup);
+        if rc != ffi::SQLITE_OK {
+            return Err(dest.error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Raw connection outside of the pool, closed when dropped
+struct RawDb {
+    handle: *mut ffi::sqlite3,
+}
+
+impl RawDb {
+    fn open(filename: &str, flags: c_int) -> Result<Self> {
+        let filename = CString::new(filename).ok().context(BackupSnafu {
+            msg: "Database path must not contain null bytes".to_string(),
+        })?;
+
+        let mut handle: *mut ffi::sqlite3 = ptr::null_mut();
+        // SAFETY: handle is written by sqlite3_open_v2 and closed on drop
+        // even when opening fails, as required by SQLite.
+        let rc = unsafe {
+            ffi::sqlite3_open_v2(
+                filename.as_ptr(),
+                &mut handle,
+                flags | ffi::SQLITE_OPEN_URI,
+                ptr::null(),
+            )
+        };
+
+        let db = RawDb { handle };
+        if rc != ffi::SQLITE_OK {
+            return Err(db.error());
+        }
+
+        Ok(db)
+    }
+
+    /// Refuses to restore from a corrupted or non-database file
+    fn check_integrity(&self) -> Result<()> {
+        let sql = c"PRAGMA quick_check";
+        let mut stmt: *mut ffi::sqlite3_stmt = ptr::null_mut();
+
+        // SAFETY: the statement is finalized before returning
+        unsafe {
+            let rc =
+                ffi::sqlite3_prepare_v2(self.handle, sql.as_ptr(), -1, &mut stmt, ptr::null_mut());
+            if rc != ffi::SQLITE_OK {
+                return Err(self.error());
+            }
+
+            let rc = ffi::sqlite3_step(stmt);
+            let result = if rc == ffi::SQLITE_ROW {
+                let text = ffi::sqlite3_column_text(stmt, 0);
+                if text.is_null() {
+                    String::new()
+                } else {
+                    CStr::from_ptr(text as *const _)
+                        .to_string_lossy()
+                        .to_string()
+                }
+            } else {
+                String::new()
+            };
+            ffi::sqlite3_finalize(stmt);
+
+            ensure!(
+                result == "ok",
+                BackupSnafu {
+                    msg: "Backup file failed the integrity check".to_string(),
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    fn error(&self) -> crate::Error {
+        // SAFETY: sqlite3_errmsg accepts any handle including null
+        let msg = unsafe {
+            let msg = ffi::sqlite3_errmsg(self.handle);
+            if msg.is_null() {
+                "Unknown database error".to_string()
+            } else {
+                CStr::from_ptr(msg).to_string_lossy().to_string()
+            }
+        };
+
+        BackupSnafu { msg }.build()
+    }
+}
+
+impl Drop for RawDb {
+    fn drop(&mut self) {
+        // SAFETY: closing a null handle is a harmless no-op
+        unsafe {
+            ffi::sqlite3_close(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+    use diesel::prelude::*;
+    use diesel::sql_types::BigInt;
+
+    #[derive(QueryableByName)]
+    struct Count {
+        #[diesel(sql_type = BigInt)]
+        total: i64,
+    }
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn count_items(path: &Path) -> i64 {
+        let mut conn = SqliteConnection::establish(path.to_str().unwrap()).unwrap();
+        let row = diesel::sql_query("SELECT COUNT(*) AS total FROM items")
+            .get_result::<Count>(&mut conn)
+            .unwrap();
+        row.total
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let source = temp_file("source.sqlite3");
+        let backup = temp_file("backup.sqlite3");
+        let restored = temp_file("restored.sqlite3");
+
+        let mut conn = SqliteConnection::establish(source.to_str().unwrap()).unwrap();
+        conn.batch_execute(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT);
+            INSERT INTO items (name) VALUES ('one'), ('two');",
+        )
+        .unwrap();
+
+        let url = format!("sqlite://{}", source.display());
+        backup_database(&url, &backup).unwrap();
+        assert_eq!(count_items(&backup), 2);
+
+        // Never overwrite an existing backup
+        assert!(backup_database(&url, &backup).is_err());
+
+        let restored_url = format!("sqlite://{}", restored.display());
+        restore_database(&restored_url, &backup).unwrap();
+        assert_eq!(count_items(&restored), 2);
+
+        for path in [source, backup, restored] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_restore_rejects_invalid_file() {
+        let invalid = temp_file("invalid.sqlite3");
+        let target = temp_file("target.sqlite3");
+        std::fs::write(&invalid, "not a database").unwrap();
+
+        let url = format!("sqlite://{}", target.display());
+        assert!(restore_database(&url, &invalid).is_err());
+
+        for path in [invalid, target] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
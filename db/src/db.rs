@@ -1,17 +1,111 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-use deadpool_diesel::sqlite::{Manager, Pool, Runtime};
+use deadpool_diesel::sqlite::{Hook, HookError, Manager, Pool, Runtime};
+use diesel::SqliteConnection;
+use diesel::connection::SimpleConnection;
+use serde::Deserialize;
 
 use crate::{
+    api_key::{ApiKeyRepo, ApiKeyRepoable},
+    attachment::{AttachmentRepo, AttachmentRepoable},
+    audit::{AuditRepo, AuditRepoable},
     entry::{EntryRepo, EntryRepoable},
+    favorite::{FavoriteRepo, FavoriteRepoable},
+    group::{GroupRepo, GroupRepoable},
+    integrity::{IntegrityRepo, IntegrityRepoable},
+    login_attempt::{LoginAttemptRepo, LoginAttemptRepoable},
+    member::{MemberRepo, MemberRepoable},
     org::{OrgRepo, OrgRepoable},
+    password_reset::{PasswordResetRepo, PasswordResetRepoable},
+    quota::{QuotaRepo, QuotaRepoable},
+    recent::{RecentRepo, RecentRepoable},
+    role::{RoleRepo, RoleRepoable},
+    session::{SessionRepo, SessionRepoable},
+    tx::{TxRepo, TxRepoable},
     user::{UserRepo, UserRepoable},
     vault::{VaultRepo, VaultRepoable},
 };
 
-pub fn create_db_pool(database_url: &str) -> Pool {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    Delete,
+    #[default]
+    Wal,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    #[default]
+    Normal,
+    Full,
+    Extra,
+}
+
+/// Pool size and pragmas applied to every new connection
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DbOptions {
+    pub max_connections: usize,
+    pub busy_timeout_ms: u32,
+    pub journal_mode: JournalMode,
+    pub synchronous: Synchronous,
+}
+
+impl Default for DbOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 8,
+            busy_timeout_ms: 5000,
+            journal_mode: JournalMode::default(),
+            synchronous: Synchronous::default(),
+        }
+    }
+}
+
+impl DbOptions {
+    /// Foreign keys are always enforced, SQLite leaves them off by default
+    pub fn pragmas(&self) -> String {
+        let journal_mode = match self.journal_mode {
+            JournalMode::Delete => "DELETE",
+            JournalMode::Wal => "WAL",
+        };
+        let synchronous = match self.synchronous {
+            Synchronous::Off => "OFF",
+            Synchronous::Normal => "NORMAL",
+            Synchronous::Full => "FULL",
+            Synchronous::Extra => "EXTRA",
+        };
+
+        format!(
+            "PRAGMA foreign_keys = ON;
+            PRAGMA busy_timeout = {};
+            PRAGMA journal_mode = {};
+            PRAGMA synchronous = {};",
+            self.busy_timeout_ms, journal_mode, synchronous
+        )
+    }
+}
+
+pub fn create_db_pool(database_url: &str, options: &DbOptions) -> Pool {
     let manager = Manager::new(database_url, Runtime::Tokio1);
-    Pool::builder(manager).max_size(8).build().unwrap()
+    let pragmas = options.pragmas();
+
+    Pool::builder(manager)
+        .max_size(options.max_connections)
+        .post_create(Hook::async_fn(move |conn, _| {
+            let pragmas = pragmas.clone();
+            Box::pin(async move {
+                conn.interact(move |conn| conn.batch_execute(&pragmas))
+                    .await
+                    .map_err(|e| HookError::message(e.to_string()))?
+                    .map_err(|e| HookError::message(e.to_string()))
+            })
+        }))
+        .build()
+        .unwrap()
 }
 
 pub struct DbMapper {
@@ -19,22 +113,72 @@ pub struct DbMapper {
     pub orgs: Arc<dyn OrgRepoable>,
     pub entries: Arc<dyn EntryRepoable>,
     pub users: Arc<dyn UserRepoable>,
+    pub attachments: Arc<dyn AttachmentRepoable>,
+    pub favorites: Arc<dyn FavoriteRepoable>,
+    pub recents: Arc<dyn RecentRepoable>,
+    pub members: Arc<dyn MemberRepoable>,
+    pub groups: Arc<dyn GroupRepoable>,
+    pub roles: Arc<dyn RoleRepoable>,
+    pub api_keys: Arc<dyn ApiKeyRepoable>,
+    pub sessions: Arc<dyn SessionRepoable>,
+    pub login_attempts: Arc<dyn LoginAttemptRepoable>,
+    pub password_resets: Arc<dyn PasswordResetRepoable>,
+    pub quotas: Arc<dyn QuotaRepoable>,
+    pub audit: Arc<dyn AuditRepoable>,
+    pub integrity: Arc<dyn IntegrityRepoable>,
+    pub tx: Arc<dyn TxRepoable>,
+
+    /// Keeps an in-memory database alive while the pool reconnects
+    _keep_alive: Option<Mutex<SqliteConnection>>,
+}
+
+pub fn create_db_mapper(database_url: &str, options: &DbOptions) -> DbMapper {
+    let pool = create_db_pool(database_url, options);
+    create_pool_mapper(pool, None)
 }
 
-pub fn create_db_mapper(database_url: &str) -> DbMapper {
-    let pool = create_db_pool(database_url);
+pub(crate) fn create_pool_mapper(pool: Pool, keep_alive: Option<SqliteConnection>) -> DbMapper {
     DbMapper {
         vaults: Arc::new(VaultRepo::new(pool.clone())),
         orgs: Arc::new(OrgRepo::new(pool.clone())),
         entries: Arc::new(EntryRepo::new(pool.clone())),
         users: Arc::new(UserRepo::new(pool.clone())),
+        attachments: Arc::new(AttachmentRepo::new(pool.clone())),
+        favorites: Arc::new(FavoriteRepo::new(pool.clone())),
+        recents: Arc::new(RecentRepo::new(pool.clone())),
+        members: Arc::new(MemberRepo::new(pool.clone())),
+        groups: Arc::new(GroupRepo::new(pool.clone())),
+        roles: Arc::new(RoleRepo::new(pool.clone())),
+        api_keys: Arc::new(ApiKeyRepo::new(pool.clone())),
+        sessions: Arc::new(SessionRepo::new(pool.clone())),
+        login_attempts: Arc::new(LoginAttemptRepo::new(pool.clone())),
+        password_resets: Arc::new(PasswordResetRepo::new(pool.clone())),
+        quotas: Arc::new(QuotaRepo::new(pool.clone())),
+        audit: Arc::new(AuditRepo::new(pool.clone())),
+        integrity: Arc::new(IntegrityRepo::new(pool.clone())),
+        tx: Arc::new(TxRepo::new(pool.clone())),
+        _keep_alive: keep_alive.map(Mutex::new),
     }
 }
 
 #[cfg(feature = "test")]
 pub fn create_test_db_mapper() -> DbMapper {
+    use crate::api_key::ApiKeyTestRepo;
+    use crate::attachment::AttachmentTestRepo;
+    use crate::audit::AuditTestRepo;
     use crate::entry::EntryTestRepo;
+    use crate::favorite::FavoriteTestRepo;
+    use crate::group::GroupTestRepo;
+    use crate::integrity::IntegrityTestRepo;
+    use crate::login_attempt::LoginAttemptTestRepo;
+    use crate::member::MemberTestRepo;
     use crate::org::OrgTestRepo;
+    use crate::password_reset::PasswordResetTestRepo;
+    use crate::quota::QuotaTestRepo;
+    use crate::recent::RecentTestRepo;
+    use crate::role::RoleTestRepo;
+    use crate::session::SessionTestRepo;
+    use crate::tx::TxTestRepo;
     use crate::user::UserTestRepo;
     use crate::vault::VaultTestRepo;
 
@@ -43,5 +187,20 @@ pub fn create_test_db_mapper() -> DbMapper {
         orgs: Arc::new(OrgTestRepo {}),
         entries: Arc::new(EntryTestRepo {}),
         users: Arc::new(UserTestRepo {}),
+        attachments: Arc::new(AttachmentTestRepo {}),
+        favorites: Arc::new(FavoriteTestRepo {}),
+        recents: Arc::new(RecentTestRepo {}),
+        members: Arc::new(MemberTestRepo {}),
+        groups: Arc::new(GroupTestRepo {}),
+        roles: Arc::new(RoleTestRepo {}),
+        api_keys: Arc::new(ApiKeyTestRepo {}),
+        sessions: Arc::new(SessionTestRepo {}),
+        login_attempts: Arc::new(LoginAttemptTestRepo {}),
+        password_resets: Arc::new(PasswordResetTestRepo {}),
+        quotas: Arc::new(QuotaTestRepo {}),
+        audit: Arc::new(AuditTestRepo {}),
+        integrity: Arc::new(IntegrityTestRepo {}),
+        tx: Arc::new(TxTestRepo {}),
+        _keep_alive: None,
     }
 }
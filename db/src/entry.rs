@@ -3,16 +3,22 @@ use async_trait::async_trait;
 use deadpool_diesel::sqlite::Pool;
 use diesel::dsl::count_star;
 use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
 use diesel::{QueryDsl, SelectableHelper};
 use serde::{Deserialize, Serialize};
-use snafu::{ResultExt, ensure};
+use snafu::{OptionExt, ResultExt, ensure};
 use validator::Validate;
 
 use crate::Result;
 use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu, ValidationSnafu};
 use crate::schema::entries::{self, dsl};
-use dto::entry::EntryDto;
-use dto::pagination::PaginatedDto;
+use crate::schema::vaults;
+use crate::tx::SqliteTx;
+use dto::entry::{
+    EntryDto, EntryLinkDto, EntryRotationDto, EntrySort, ROTATION_WARNING_DAYS, RotationStatus,
+    rotation_due_at,
+};
+use dto::pagination::{Cursor, CursorMetaDto, CursorPaginatedDto, PaginatedDto, SortOrder};
 use vault::validators::flatten_errors;
 
 #[derive(Debug, Clone, Queryable, Selectable, Insertable, Serialize)]
@@ -29,6 +35,11 @@ pub struct Entry {
     pub status: String,
     pub created_at: i64,
     pub updated_at: i64,
+    pub last_used_at: i64,
+    pub version: i32,
+    pub secret_changed_at: i64,
+    pub rotation_days: Option<i32>,
+    pub expires_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Validate, Serialize, Deserialize)]
@@ -39,6 +50,82 @@ pub struct EntryPayload {
     pub cipher_password: Option<String>,
     pub cipher_notes: Option<String>,
     pub cipher_extra_notes: Option<String>,
+
+    /// Days between password changes, 0 removes the interval
+    #[validate(range(min = 0, max = 3650))]
+    pub rotation_days: Option<i32>,
+
+    /// Unix timestamp when the secret expires, 0 removes the expiry
+    #[validate(range(min = 0))]
+    pub expires_at: Option<i64>,
+}
+
+impl EntryPayload {
+    pub fn rotation_days(&self) -> Option<i32> {
+        self.rotation_days.filter(|x| *x > 0)
+    }
+
+    pub fn expires_at(&self) -> Option<i64> {
+        self.expires_at.filter(|x| *x > 0)
+    }
+}
+
+/// Entry moved or copied to another vault
+///
+/// Vaults may use different keys, so the client sends the secrets
+/// re-encrypted with the key of the target vault.
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct TransferEntryPayload {
+    #[validate(custom(function = "vault::validators::uuid"))]
+    pub target_vault_id: String,
+    pub cipher_username: Option<String>,
+    pub cipher_password: Option<String>,
+    pub cipher_notes: Option<String>,
+    pub cipher_extra_notes: Option<String>,
+}
+
+/// One operation of a bulk request on a single vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkEntryOp {
+    Create {
+        #[serde(flatten)]
+        data: EntryPayload,
+    },
+    Update {
+        id: String,
+        version: i32,
+        #[serde(flatten)]
+        data: EntryPayload,
+    },
+    Delete {
+        id: String,
+        version: i32,
+    },
+}
+
+impl BulkEntryOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BulkEntryOp::Create { .. } => "create",
+            BulkEntryOp::Update { .. } => "update",
+            BulkEntryOp::Delete { .. } => "delete",
+        }
+    }
+
+    /// Existing entry targeted by the operation
+    pub fn entry_id(&self) -> Option<&str> {
+        match self {
+            BulkEntryOp::Create { .. } => None,
+            BulkEntryOp::Update { id, .. } | BulkEntryOp::Delete { id, .. } => Some(id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct BulkEntriesPayload {
+    #[validate(length(min = 1, max = 500))]
+    pub operations: Vec<BulkEntryOp>,
 }
 
 #[derive(Debug, Clone, AsChangeset)]
@@ -50,6 +137,14 @@ pub struct UpdateEntry {
     pub cipher_notes: Option<String>,
     pub cipher_extra_notes: Option<String>,
     pub updated_at: i64,
+    pub version: i32,
+
+    /// Only set when the password actually changed
+    pub secret_changed_at: Option<i64>,
+
+    /// Outer None keeps the current value, inner None clears it
+    pub rotation_days: Option<Option<i32>>,
+    pub expires_at: Option<Option<i64>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Validate)]
@@ -62,6 +157,42 @@ pub struct ListEntriesParams {
 
     #[validate(length(min = 0, max = 50))]
     pub keyword: Option<String>,
+
+    /// Cursor mode, returns entries after the cursor, empty for the first page
+    #[validate(length(min = 0, max = 200))]
+    pub after: Option<String>,
+
+    /// Cursor mode, returns entries before the cursor
+    #[validate(length(min = 1, max = 200))]
+    pub before: Option<String>,
+
+    /// Cursor mode, also compute the total number of entries
+    pub with_total: Option<bool>,
+
+    pub sort: Option<EntrySort>,
+
+    /// Defaults to ascending for labels and descending for timestamps
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+pub struct ListDueEntriesParams {
+    /// Also include entries due within the next days, defaults to the warning period
+    #[validate(range(min = 0, max = 365))]
+    pub within_days: Option<i64>,
+}
+
+impl ListEntriesParams {
+    /// Whether the client requested cursor based pagination
+    pub fn is_cursor_mode(&self) -> bool {
+        self.after.is_some() || self.before.is_some()
+    }
+
+    pub fn sort_order(&self) -> (EntrySort, SortOrder) {
+        let sort = self.sort.unwrap_or_default();
+        let order = self.order.unwrap_or(sort.default_order());
+        (sort, order)
+    }
 }
 
 /// Convert EntryDto to Entry
@@ -78,6 +209,11 @@ impl From<EntryDto> for Entry {
             status: entry.status,
             created_at: entry.created_at,
             updated_at: entry.updated_at,
+            last_used_at: entry.last_used_at,
+            version: entry.version,
+            secret_changed_at: entry.secret_changed_at,
+            rotation_days: entry.rotation_days,
+            expires_at: entry.expires_at,
         }
     }
 }
@@ -96,6 +232,66 @@ impl From<Entry> for EntryDto {
             status: entry.status,
             created_at: entry.created_at,
             updated_at: entry.updated_at,
+            last_used_at: entry.last_used_at,
+            version: entry.version,
+            secret_changed_at: entry.secret_changed_at,
+            rotation_days: entry.rotation_days,
+            expires_at: entry.expires_at,
+        }
+    }
+}
+
+/// Entry with its vault name, used by favorites and recently viewed lists
+#[derive(Debug, Clone, Queryable)]
+pub struct EntryLink {
+    pub id: String,
+    pub vault_id: String,
+    pub vault_name: String,
+    pub label: String,
+    pub timestamp: i64,
+}
+
+/// Entry with the rotation settings of its vault
+#[derive(Debug, Clone, Queryable)]
+pub struct EntryRotation {
+    pub id: String,
+    pub vault_id: String,
+    pub vault_name: String,
+    pub label: String,
+    pub secret_changed_at: i64,
+    pub rotation_days: Option<i32>,
+    pub vault_rotation_days: Option<i32>,
+    pub expires_at: Option<i64>,
+}
+
+impl EntryRotation {
+    /// None when the entry has no rotation policy
+    pub fn into_dto(self, now: i64) -> Option<EntryRotationDto> {
+        let rotation_days = self.rotation_days.or(self.vault_rotation_days);
+        let due_at = rotation_due_at(self.secret_changed_at, rotation_days, self.expires_at)?;
+
+        Some(EntryRotationDto {
+            id: self.id,
+            vault_id: self.vault_id,
+            vault_name: self.vault_name,
+            label: self.label,
+            secret_changed_at: self.secret_changed_at,
+            rotation_days,
+            expires_at: self.expires_at,
+            due_at,
+            status: RotationStatus::new(due_at, now),
+        })
+    }
+}
+
+impl From<EntryLink> for EntryLinkDto {
+    fn from(link: EntryLink) -> Self {
+        Self {
+            id: link.id,
+            vault_id: link.vault_id,
+            vault_name: link.vault_name,
+            label: link.label,
+            timestamp: link.timestamp,
         }
     }
 }
@@ -107,14 +303,31 @@ pub trait EntryRepoable: Send + Sync {
     async fn list(&self, vault_id: &str, params: &ListEntriesParams)
     -> Result<PaginatedDto<Entry>>;
 
+    async fn list_by_cursor(
+        &self,
+        vault_id: &str,
+        params: &ListEntriesParams,
+    ) -> Result<CursorPaginatedDto<Entry>>;
+
     async fn create(&self, entry_dto: EntryDto) -> Result<Entry>;
 
     async fn get(&self, id: &str) -> Result<Option<Entry>>;
 
-    async fn update(&self, id: &str, data: &EntryPayload) -> Result<bool>;
+    /// Updates only when the entry is still at the given version
+    async fn update(&self, id: &str, version: i32, data: &EntryPayload) -> Result<bool>;
 
     async fn count_by_vault(&self, vault_id: &str) -> Result<i64>;
 
+    /// Records that the entry was opened by a user
+    async fn mark_used(&self, id: &str) -> Result<()>;
+
+    /// Overdue and soon expiring entries of the org, most urgent first
+    async fn list_due(
+        &self,
+        org_id: &str,
+        params: &ListDueEntriesParams,
+    ) -> Result<Vec<EntryRotationDto>>;
+
     async fn delete(&self, id: &str) -> Result<()>;
 }
 
@@ -202,6 +415,7 @@ impl EntryRepoable for EntryRepo {
         }
 
         let params_copy = params.clone();
+        let (sort, order) = params.sort_order();
         let select_res = db
             .interact(move |conn| {
                 let mut query = dsl::entries.into_boxed();
@@ -213,11 +427,10 @@ impl EntryRepoable for EntryRepo {
                         query = query.filter(dsl::label.like(pattern));
                     }
                 }
-                query
+                sorted_query(query, sort, order, None)
                     .limit(per_page as i64)
                     .offset(offset)
                     .select(Entry::as_select())
-                    .order(dsl::created_at.desc())
                     .load::<Entry>(conn)
             })
             .await
@@ -230,27 +443,110 @@ impl EntryRepoable for EntryRepo {
         Ok(PaginatedDto::new(items, page, per_page, total_records))
     }
 
-    async fn create(&self, entry_dto: EntryDto) -> Result<Entry> {
-        let file_db_pool = self.db_pool.clone();
-        let db = file_db_pool.get().await.context(DbPoolSnafu)?;
+    async fn list_by_cursor(
+        &self,
+        vault_id: &str,
+        params: &ListEntriesParams,
+    ) -> Result<CursorPaginatedDto<Entry>> {
+        let errors = params.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+
+        ensure!(
+            params.after.is_none() || params.before.is_none(),
+            ValidationSnafu {
+                msg: "Only one of after or before cursor is allowed".to_string(),
+            }
+        );
+
+        let (sort, order) = params.sort_order();
+        let after = decode_cursor(params.after.as_deref(), sort)?;
+        let before = decode_cursor(params.before.as_deref(), sort)?;
+
+        let mut per_page: i32 = MAX_PER_PAGE;
+        if let Some(per_page_param) = params.per_page
+            && per_page_param > 0
+            && per_page_param <= MAX_PER_PAGE
+        {
+            per_page = per_page_param;
+        }
+
+        let mut total_records: Option<i64> = None;
+        if params.with_total.unwrap_or(false) {
+            total_records = Some(self.listing_count(vault_id, params).await?);
+        }
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let vid = vault_id.to_string();
+        let keyword = params.keyword.clone();
+        let backwards = before.is_some();
+        let cursor = if backwards { before } else { after.clone() };
 
-        let entry: Entry = entry_dto.clone().into();
-        let entry_copy = entry.clone();
+        // Walk backwards from the before cursor, reversed afterwards
+        let scan_order = if backwards { order.reverse() } else { order };
 
-        let insert_res = db
+        let select_res = db
             .interact(move |conn| {
-                diesel::insert_into(entries::table)
-                    .values(&entry_copy)
-                    .execute(conn)
+                let mut query = dsl::entries.into_boxed();
+                query = query.filter(dsl::vault_id.eq(vid.as_str()));
+
+                if let Some(keyword) = keyword
+                    && !keyword.is_empty()
+                {
+                    let pattern = format!("%{}%", keyword);
+                    query = query.filter(dsl::label.like(pattern));
+                }
+
+                // Fetch an extra row to know if there are more entries
+                sorted_query(query, sort, scan_order, cursor)
+                    .limit(per_page as i64 + 1)
+                    .select(Entry::as_select())
+                    .load::<Entry>(conn)
             })
             .await
             .context(DbInteractSnafu)?;
 
-        let _ = insert_res.context(DbQuerySnafu {
+        let mut items = select_res.context(DbQuerySnafu {
             table: "entries".to_string(),
         })?;
 
-        Ok(entry)
+        let has_more = items.len() > per_page as usize;
+        items.truncate(per_page as usize);
+
+        if backwards {
+            items.reverse();
+        }
+
+        let first = items.first().map(|x| entry_cursor(x, sort).encode());
+        let last = items.last().map(|x| entry_cursor(x, sort).encode());
+
+        let (next_cursor, prev_cursor) = if backwards {
+            (last, if has_more { first } else { None })
+        } else {
+            (if has_more { last } else { None }, after.and(first))
+        };
+
+        let meta = CursorMetaDto {
+            per_page,
+            next_cursor,
+            prev_cursor,
+            total_records,
+        };
+
+        Ok(CursorPaginatedDto::new(items, meta))
+    }
+
+    async fn create(&self, entry_dto: EntryDto) -> Result<Entry> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        db.interact(move |conn| SqliteTx { conn }.create(entry_dto))
+            .await
+            .context(DbInteractSnafu)?
     }
 
     async fn get(&self, id: &str) -> Result<Option<Entry>> {
@@ -275,7 +571,7 @@ impl EntryRepoable for EntryRepo {
         Ok(item)
     }
 
-    async fn update(&self, id: &str, data: &EntryPayload) -> Result<bool> {
+    async fn update(&self, id: &str, version: i32, data: &EntryPayload) -> Result<bool> {
         let errors = data.validate();
         ensure!(
             errors.is_ok(),
@@ -284,25 +580,13 @@ impl EntryRepoable for EntryRepo {
             }
         );
 
-        let today = chrono::Utc::now().timestamp();
-        let payload = UpdateEntry {
-            label: data.label.clone(),
-            cipher_username: data.cipher_username.clone(),
-            cipher_password: data.cipher_password.clone(),
-            cipher_notes: data.cipher_notes.clone(),
-            cipher_extra_notes: data.cipher_extra_notes.clone(),
-            updated_at: today,
-        };
-
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
         let id = id.to_string();
+        let data = data.clone();
         let update_res = db
             .interact(move |conn| {
-                diesel::update(dsl::entries)
-                    .filter(dsl::id.eq(&id))
-                    .set(&payload)
-                    .execute(conn)
+                conn.immediate_transaction(|conn| update_entry(conn, &id, None, version, &data))
             })
             .await
             .context(DbInteractSnafu)?;
@@ -318,21 +602,91 @@ impl EntryRepoable for EntryRepo {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
         let vid = vault_id.to_string();
-        let count_res = db
+        db.interact(move |conn| SqliteTx { conn }.count_by_vault(&vid))
+            .await
+            .context(DbInteractSnafu)?
+    }
+
+    async fn mark_used(&self, id: &str) -> Result<()> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let eid = id.to_string();
+        let today = chrono::Utc::now().timestamp();
+        let update_res = db
+            .interact(move |conn| {
+                diesel::update(dsl::entries)
+                    .filter(dsl::id.eq(&eid))
+                    .set(dsl::last_used_at.eq(today))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = update_res.context(DbQuerySnafu {
+            table: "entries".to_string(),
+        })?;
+
+        Ok(())
+    }
+
+    async fn list_due(
+        &self,
+        org_id: &str,
+        params: &ListDueEntriesParams,
+    ) -> Result<Vec<EntryRotationDto>> {
+        let errors = params.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let oid = org_id.to_string();
+        let select_res = db
             .interact(move |conn| {
                 dsl::entries
-                    .filter(dsl::vault_id.eq(vid.as_str()))
-                    .select(count_star())
-                    .get_result::<i64>(conn)
+                    .inner_join(vaults::table)
+                    .filter(vaults::org_id.eq(&oid))
+                    .filter(
+                        dsl::rotation_days
+                            .is_not_null()
+                            .or(vaults::rotation_days.is_not_null())
+                            .or(dsl::expires_at.is_not_null()),
+                    )
+                    .select((
+                        dsl::id,
+                        dsl::vault_id,
+                        vaults::name,
+                        dsl::label,
+                        dsl::secret_changed_at,
+                        dsl::rotation_days,
+                        vaults::rotation_days,
+                        dsl::expires_at,
+                    ))
+                    .load::<EntryRotation>(conn)
             })
             .await
             .context(DbInteractSnafu)?;
 
-        let count = count_res.context(DbQuerySnafu {
+        let rows = select_res.context(DbQuerySnafu {
             table: "entries".to_string(),
         })?;
 
-        Ok(count)
+        let now = chrono::Utc::now().timestamp();
+        let within_days = params.within_days.unwrap_or(ROTATION_WARNING_DAYS);
+        let before = now + within_days * 24 * 60 * 60;
+
+        let mut items: Vec<EntryRotationDto> = rows
+            .into_iter()
+            .filter_map(|row| row.into_dto(now))
+            .filter(|x| x.due_at <= before)
+            .collect();
+
+        items.sort_by(|a, b| a.due_at.cmp(&b.due_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(items)
     }
 
     async fn delete(&self, id: &str) -> Result<()> {
@@ -354,6 +708,306 @@ impl EntryRepoable for EntryRepo {
     }
 }
 
+/// Updates the entry at the given version, optionally only within a vault
+fn update_entry(
+    conn: &mut SqliteConnection,
+    id: &str,
+    vault_id: Option<&str>,
+    version: i32,
+    data: &EntryPayload,
+) -> QueryResult<usize> {
+    let current = dsl::entries
+        .find(id)
+        .select((dsl::vault_id, dsl::cipher_password))
+        .first::<(String, Option<String>)>(conn)
+        .optional()?;
+
+    let Some((current_vault_id, current_password)) = current else {
+        return Ok(0);
+    };
+    if vault_id.is_some_and(|x| x != current_vault_id) {
+        return Ok(0);
+    }
+
+    let today = chrono::Utc::now().timestamp();
+    let payload = UpdateEntry {
+        label: data.label.clone(),
+        cipher_username: data.cipher_username.clone(),
+        cipher_password: data.cipher_password.clone(),
+        cipher_notes: data.cipher_notes.clone(),
+        cipher_extra_notes: data.cipher_extra_notes.clone(),
+        updated_at: today,
+        version: version + 1,
+        // Missing password in the payload keeps the current one
+        secret_changed_at: (data.cipher_password.is_some()
+            && data.cipher_password != current_password)
+            .then_some(today),
+        rotation_days: data.rotation_days.map(|_| data.rotation_days()),
+        expires_at: data.expires_at.map(|_| data.expires_at()),
+    };
+
+    diesel::update(dsl::entries)
+        .filter(dsl::id.eq(id))
+        .filter(dsl::version.eq(version))
+        .set(&payload)
+        .execute(conn)
+}
+
+type BoxedEntryQuery<'a> = entries::BoxedQuery<'a, Sqlite>;
+
+/// Orders by the sort column, the time based id breaks ties
+macro_rules! order_by_sort {
+    ($query:ident, $column:expr, $order:expr) => {
+        match $order {
+            SortOrder::Asc => $query.order(($column.asc(), dsl::id.asc())),
+            SortOrder::Desc => $query.order(($column.desc(), dsl::id.desc())),
+        }
+    };
+}
+
+/// Keeps rows positioned past the cursor in the scan order
+macro_rules! filter_after_cursor {
+    ($query:ident, $column:expr, $value:expr, $id:expr, $order:expr) => {
+        match $order {
+            SortOrder::Asc => $query.filter(
+                $column
+                    .gt($value)
+                    .or($column.eq($value).and(dsl::id.gt($id))),
+            ),
+            SortOrder::Desc => $query.filter(
+                $column
+                    .lt($value)
+                    .or($column.eq($value).and(dsl::id.lt($id))),
+            ),
+        }
+    };
+}
+
+fn sorted_query(
+    mut query: BoxedEntryQuery<'_>,
+    sort: EntrySort,
+    order: SortOrder,
+    cursor: Option<Cursor>,
+) -> BoxedEntryQuery<'_> {
+    match sort {
+        EntrySort::Label => {
+            if let Some(c) = cursor {
+                query = filter_after_cursor!(query, dsl::label, c.value.clone(), c.id, order);
+            }
+            order_by_sort!(query, dsl::label, order)
+        }
+        EntrySort::CreatedAt => {
+            if let Some(c) = cursor {
+                let value = c.value.parse::<i64>().unwrap_or_default();
+                query = filter_after_cursor!(query, dsl::created_at, value, c.id, order);
+            }
+            order_by_sort!(query, dsl::created_at, order)
+        }
+        EntrySort::UpdatedAt => {
+            if let Some(c) = cursor {
+                let value = c.value.parse::<i64>().unwrap_or_default();
+                query = filter_after_cursor!(query, dsl::updated_at, value, c.id, order);
+            }
+            order_by_sort!(query, dsl::updated_at, order)
+        }
+        EntrySort::LastUsed => {
+            if let Some(c) = cursor {
+                let value = c.value.parse::<i64>().unwrap_or_default();
+                query = filter_after_cursor!(query, dsl::last_used_at, value, c.id, order);
+            }
+            order_by_sort!(query, dsl::last_used_at, order)
+        }
+    }
+}
+
+fn entry_cursor(entry: &Entry, sort: EntrySort) -> Cursor {
+    let value = match sort {
+        EntrySort::Label => entry.label.clone(),
+        EntrySort::CreatedAt => entry.created_at.to_string(),
+        EntrySort::UpdatedAt => entry.updated_at.to_string(),
+        EntrySort::LastUsed => entry.last_used_at.to_string(),
+    };
+    Cursor::new(&sort.to_string(), &value, &entry.id)
+}
+
+/// Empty cursor means the start of the listing
+fn decode_cursor(value: Option<&str>, sort: EntrySort) -> Result<Option<Cursor>> {
+    match value {
+        Some(v) if !v.is_empty() => {
+            let cursor = Cursor::decode(v).context(ValidationSnafu {
+                msg: "Invalid pagination cursor".to_string(),
+            })?;
+
+            // Cursors are only valid for the sort they were created with
+            let valid_value = match sort {
+                EntrySort::Label => true,
+                _ => cursor.value.parse::<i64>().is_ok(),
+            };
+            ensure!(
+                cursor.sort == sort.to_string() && valid_value,
+                ValidationSnafu {
+                    msg: "Pagination cursor does not match the sort".to_string(),
+                }
+            );
+
+            Ok(Some(cursor))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Entry operations available inside a transaction
+pub trait EntryTx {
+    fn create(&mut self, entry_dto: EntryDto) -> Result<Entry>;
+
+    fn count_by_vault(&mut self, vault_id: &str) -> Result<i64>;
+
+    /// Updates only when the entry is still in the vault at the given version
+    fn update(
+        &mut self,
+        vault_id: &str,
+        id: &str,
+        version: i32,
+        data: &EntryPayload,
+    ) -> Result<bool>;
+
+    /// Deletes only when the entry is still in the vault at the given version
+    fn delete(&mut self, vault_id: &str, id: &str, version: i32) -> Result<bool>;
+
+    /// Moves the entry at the given version to the target vault with its re-encrypted secrets
+    fn move_to(
+        &mut self,
+        vault_id: &str,
+        id: &str,
+        version: i32,
+        data: &TransferEntryPayload,
+    ) -> Result<Option<Entry>>;
+}
+
+impl EntryTx for SqliteTx<'_> {
+    fn create(&mut self, entry_dto: EntryDto) -> Result<Entry> {
+        let entry: Entry = entry_dto.into();
+
+        diesel::insert_into(entries::table)
+            .values(&entry)
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "entries".to_string(),
+            })?;
+
+        Ok(entry)
+    }
+
+    fn count_by_vault(&mut self, vault_id: &str) -> Result<i64> {
+        dsl::entries
+            .filter(dsl::vault_id.eq(vault_id))
+            .select(count_star())
+            .get_result::<i64>(self.conn)
+            .context(DbQuerySnafu {
+                table: "entries".to_string(),
+            })
+    }
+
+    fn update(
+        &mut self,
+        vault_id: &str,
+        id: &str,
+        version: i32,
+        data: &EntryPayload,
+    ) -> Result<bool> {
+        let affected =
+            update_entry(self.conn, id, Some(vault_id), version, data).context(DbQuerySnafu {
+                table: "entries".to_string(),
+            })?;
+
+        Ok(affected > 0)
+    }
+
+    fn delete(&mut self, vault_id: &str, id: &str, version: i32) -> Result<bool> {
+        let affected = diesel::delete(
+            dsl::entries
+                .filter(dsl::id.eq(id))
+                .filter(dsl::vault_id.eq(vault_id))
+                .filter(dsl::version.eq(version)),
+        )
+        .execute(self.conn)
+        .context(DbQuerySnafu {
+            table: "entries".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+
+    fn move_to(
+        &mut self,
+        vault_id: &str,
+        id: &str,
+        version: i32,
+        data: &TransferEntryPayload,
+    ) -> Result<Option<Entry>> {
+        // Only the encryption changes, the secret itself is the same
+        let affected = diesel::update(
+            dsl::entries
+                .filter(dsl::id.eq(id))
+                .filter(dsl::vault_id.eq(vault_id))
+                .filter(dsl::version.eq(version)),
+        )
+        .set((
+            dsl::vault_id.eq(&data.target_vault_id),
+            dsl::cipher_username.eq(&data.cipher_username),
+            dsl::cipher_password.eq(&data.cipher_password),
+            dsl::cipher_notes.eq(&data.cipher_notes),
+            dsl::cipher_extra_notes.eq(&data.cipher_extra_notes),
+            dsl::updated_at.eq(chrono::Utc::now().timestamp()),
+            dsl::version.eq(version + 1),
+        ))
+        .execute(self.conn)
+        .context(DbQuerySnafu {
+            table: "entries".to_string(),
+        })?;
+
+        if affected == 0 {
+            return Ok(None);
+        }
+
+        dsl::entries
+            .find(id)
+            .select(Entry::as_select())
+            .first::<Entry>(self.conn)
+            .optional()
+            .context(DbQuerySnafu {
+                table: "entries".to_string(),
+            })
+    }
+}
+
+#[cfg(feature = "test")]
+pub const TEST_ENTRY_ID: &str = "0196d1c6f2a87a0f8d0b3e4f5a6b7c8d";
+
+#[cfg(feature = "test")]
+pub fn create_test_entry() -> Entry {
+    use crate::vault::TEST_VAULT_ID;
+    let today = chrono::Utc::now().timestamp();
+
+    Entry {
+        id: TEST_ENTRY_ID.to_string(),
+        vault_id: TEST_VAULT_ID.to_string(),
+        label: "test-entry".to_string(),
+        cipher_username: Some("test-cipher-username".to_string()),
+        cipher_password: Some("test-cipher-password".to_string()),
+        cipher_notes: None,
+        cipher_extra_notes: None,
+        status: "active".to_string(),
+        created_at: today,
+        updated_at: today,
+        last_used_at: today,
+        version: 1,
+        secret_changed_at: today,
+        rotation_days: None,
+        expires_at: None,
+    }
+}
+
 #[cfg(feature = "test")]
 pub struct EntryTestRepo {}
 
@@ -368,23 +1022,242 @@ impl EntryRepoable for EntryTestRepo {
         Ok(PaginatedDto::new(vec![], 1, 10, 0))
     }
 
+    async fn list_by_cursor(
+        &self,
+        _vault_id: &str,
+        params: &ListEntriesParams,
+    ) -> Result<CursorPaginatedDto<Entry>> {
+        let (sort, _) = params.sort_order();
+        let _ = decode_cursor(params.after.as_deref(), sort)?;
+        let _ = decode_cursor(params.before.as_deref(), sort)?;
+
+        let meta = CursorMetaDto {
+            per_page: 10,
+            next_cursor: None,
+            prev_cursor: None,
+            total_records: params.with_total.unwrap_or(false).then_some(0),
+        };
+        Ok(CursorPaginatedDto::new(vec![], meta))
+    }
+
     async fn create(&self, _entry_dto: EntryDto) -> Result<Entry> {
         Err("Not supported".into())
     }
 
-    async fn get(&self, _id: &str) -> Result<Option<Entry>> {
-        Ok(None)
+    async fn get(&self, id: &str) -> Result<Option<Entry>> {
+        let entry = create_test_entry();
+        let entries = vec![entry];
+        let found = entries.into_iter().find(|x| x.id.as_str() == id);
+        Ok(found)
     }
 
-    async fn update(&self, _id: &str, _data: &EntryPayload) -> Result<bool> {
-        Ok(true)
+    async fn update(&self, id: &str, version: i32, _data: &EntryPayload) -> Result<bool> {
+        let entry = self.get(id).await?;
+        Ok(entry.is_some_and(|x| x.version == version))
     }
 
     async fn count_by_vault(&self, _dir_id: &str) -> Result<i64> {
         Ok(0)
     }
 
+    async fn mark_used(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn list_due(
+        &self,
+        _org_id: &str,
+        _params: &ListDueEntriesParams,
+    ) -> Result<Vec<EntryRotationDto>> {
+        Ok(vec![])
+    }
+
     async fn delete(&self, _id: &str) -> Result<()> {
         Ok(())
     }
 }
+
+#[cfg(feature = "test")]
+impl EntryTx for crate::tx::TestTx {
+    fn create(&mut self, _entry_dto: EntryDto) -> Result<Entry> {
+        Err("Not supported".into())
+    }
+
+    fn count_by_vault(&mut self, _vault_id: &str) -> Result<i64> {
+        Ok(0)
+    }
+
+    fn update(
+        &mut self,
+        vault_id: &str,
+        id: &str,
+        version: i32,
+        _data: &EntryPayload,
+    ) -> Result<bool> {
+        let entry = create_test_entry();
+        Ok(entry.vault_id == vault_id && entry.id == id && entry.version == version)
+    }
+
+    fn delete(&mut self, vault_id: &str, id: &str, version: i32) -> Result<bool> {
+        let entry = create_test_entry();
+        Ok(entry.vault_id == vault_id && entry.id == id && entry.version == version)
+    }
+
+    fn move_to(
+        &mut self,
+        _vault_id: &str,
+        _id: &str,
+        _version: i32,
+        _data: &TransferEntryPayload,
+    ) -> Result<Option<Entry>> {
+        Err("Not supported".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
+
+    fn payload_from(entry: &Entry) -> EntryPayload {
+        EntryPayload {
+            label: entry.label.clone(),
+            cipher_username: entry.cipher_username.clone(),
+            cipher_password: entry.cipher_password.clone(),
+            cipher_notes: entry.cipher_notes.clone(),
+            cipher_extra_notes: entry.cipher_extra_notes.clone(),
+            rotation_days: None,
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tx_update_and_delete_within_vault() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let entry = seed.entries[0].clone();
+        let other_vault_id = seed
+            .vaults
+            .iter()
+            .find(|x| x.id != entry.vault_id)
+            .map(|x| x.id.clone())
+            .unwrap();
+        let data = payload_from(&entry);
+
+        let eid = entry.id.clone();
+        let res: Result<(bool, bool, bool, bool)> = db
+            .transaction(move |tx| {
+                let entries = tx.entries();
+                let other = entries.update(&other_vault_id, &eid, 1, &data)?;
+                let updated = entries.update(&entry.vault_id, &eid, 1, &data)?;
+                let stale = entries.delete(&entry.vault_id, &eid, 1)?;
+                let deleted = entries.delete(&entry.vault_id, &eid, 2)?;
+                Ok((other, updated, stale, deleted))
+            })
+            .await;
+        assert_eq!(res.unwrap(), (false, true, false, true));
+
+        assert!(db.entries.get(&entry.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tx_move_to_vault() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let entry = seed.entries[0].clone();
+        let target_vault_id = seed
+            .vaults
+            .iter()
+            .find(|x| x.id != entry.vault_id)
+            .map(|x| x.id.clone())
+            .unwrap();
+        let data = TransferEntryPayload {
+            target_vault_id: target_vault_id.clone(),
+            cipher_username: Some("reencrypted-user".to_string()),
+            cipher_password: Some("reencrypted-pass".to_string()),
+            cipher_notes: None,
+            cipher_extra_notes: None,
+        };
+
+        let (eid, vid) = (entry.id.clone(), entry.vault_id.clone());
+        let res: Result<(Option<Entry>, Option<Entry>)> = db
+            .transaction(move |tx| {
+                let entries = tx.entries();
+                let stale = entries.move_to(&vid, &eid, 2, &data)?;
+                let moved = entries.move_to(&vid, &eid, 1, &data)?;
+                Ok((stale, moved))
+            })
+            .await;
+        let (stale, moved) = res.unwrap();
+        assert!(stale.is_none());
+
+        let moved = moved.unwrap();
+        assert_eq!(moved.vault_id, target_vault_id);
+        assert_eq!(moved.version, 2);
+        assert_eq!(moved.cipher_password, Some("reencrypted-pass".to_string()));
+        assert_eq!(moved.secret_changed_at, entry.secret_changed_at);
+    }
+
+    #[tokio::test]
+    async fn test_secret_changed_only_with_password() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let mut dto: EntryDto = seed.entries[0].clone().into();
+        dto.id = vault::utils::generate_id();
+        dto.secret_changed_at = 1000;
+        let entry = db.entries.create(dto).await.unwrap();
+
+        let mut data = payload_from(&entry);
+        data.label = "Renamed".to_string();
+        data.rotation_days = Some(30);
+        assert!(db.entries.update(&entry.id, 1, &data).await.unwrap());
+
+        let entry = db.entries.get(&entry.id).await.unwrap().unwrap();
+        assert_eq!(entry.secret_changed_at, 1000);
+        assert_eq!(entry.rotation_days, Some(30));
+
+        let items = db
+            .entries
+            .list_due(&seed.org.id, &ListDueEntriesParams::default())
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, entry.id);
+        assert_eq!(items[0].status, RotationStatus::Overdue);
+
+        let mut data = payload_from(&entry);
+        data.cipher_password = Some("rotated".to_string());
+        data.rotation_days = Some(0);
+        assert!(db.entries.update(&entry.id, 2, &data).await.unwrap());
+
+        let entry = db.entries.get(&entry.id).await.unwrap().unwrap();
+        assert!(entry.secret_changed_at > 1000);
+        assert_eq!(entry.rotation_days, None);
+    }
+
+    #[tokio::test]
+    async fn test_list_due_uses_vault_interval() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let params = ListDueEntriesParams {
+            within_days: Some(100),
+        };
+        let items = db.entries.list_due(&seed.org.id, &params).await.unwrap();
+
+        // Only the team vault has a rotation interval
+        assert_eq!(items.len(), 3);
+        assert!(items.iter().all(|x| x.vault_name == "Team"));
+        assert!(items.iter().all(|x| x.rotation_days == Some(90)));
+        assert!(items.iter().all(|x| x.status == RotationStatus::Current));
+
+        let params = ListDueEntriesParams {
+            within_days: Some(1000),
+        };
+        assert!(db.entries.list_due(&seed.org.id, &params).await.is_err());
+    }
+}
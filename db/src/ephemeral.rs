@@ -0,0 +1,52 @@
+use diesel::prelude::*;
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+use snafu::ResultExt;
+
+use crate::Result;
+use crate::db::{DbMapper, DbOptions, create_db_pool, create_pool_mapper};
+use crate::error::{DbConnectionSnafu, DbMigrationSnafu};
+use vault::utils::generate_id;
+
+/// Migrations of `migrations/`, the same ones the diesel CLI applies
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Creates all tables on an empty database
+pub fn apply_schema(conn: &mut SqliteConnection) -> Result<()> {
+    conn.run_pending_migrations(MIGRATIONS)
+        .context(DbMigrationSnafu)?;
+    Ok(())
+}
+
+/// Creates a mapper backed by a fresh in-memory database with the schema applied
+///
+/// The memdb VFS lets every pooled connection share the same database with
+/// regular locking. The data is gone once the mapper is dropped.
+pub fn create_ephemeral_db_mapper() -> Result<DbMapper> {
+    let database_url = format!("file:/ferriseal-{}?vfs=memdb", generate_id());
+
+    // SQLite frees the database when its last connection closes
+    let mut keep_alive = SqliteConnection::establish(&database_url).context(DbConnectionSnafu)?;
+    apply_schema(&mut keep_alive)?;
+
+    let pool = create_db_pool(&database_url, &DbOptions::default());
+    Ok(create_pool_mapper(pool, Some(keep_alive)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ephemeral_databases_are_isolated() {
+        let first = create_ephemeral_db_mapper().unwrap();
+        let second = create_ephemeral_db_mapper().unwrap();
+
+        let new_org = crate::org::NewOrg {
+            name: "Demo".to_string(),
+        };
+        first.orgs.create(&new_org, false).await.unwrap();
+
+        assert_eq!(first.orgs.count().await.unwrap(), 1);
+        assert_eq!(second.orgs.count().await.unwrap(), 0);
+    }
+}
@@ -1,6 +1,6 @@
 use deadpool_diesel::{InteractError, PoolError};
-use dto::role::InvalidRolesError;
-use snafu::{Backtrace, Snafu};
+use dto::role::{InvalidPermissionsError, InvalidRolesError};
+use snafu::{Backtrace, IntoError, Snafu};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -13,12 +13,24 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display("Error connecting to the db: {}", source))]
+    DbConnection {
+        source: diesel::ConnectionError,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Error using the db connection: {}", source))]
     DbInteract {
         source: InteractError,
         backtrace: Backtrace,
     },
 
+    #[snafu(display("Error applying migrations: {}", source))]
+    DbMigration {
+        source: Box<dyn std::error::Error + Send + Sync>,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Error querying {}: {}", table, source))]
     DbQuery {
         table: String,
@@ -41,10 +53,38 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display("{}", source))]
+    InvalidPermissions {
+        source: InvalidPermissionsError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("{}", source))]
+    Cipher {
+        source: cipher::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Backup error: {}", msg))]
+    Backup { msg: String },
+
+    #[snafu(display("Transaction rolled back"))]
+    TxRollback,
+
     #[snafu(display("{}", msg))]
     Whatever { msg: String },
 }
 
+// Required by diesel to run transactions with our error type
+impl From<diesel::result::Error> for Error {
+    fn from(source: diesel::result::Error) -> Self {
+        DbQuerySnafu {
+            table: "transaction".to_string(),
+        }
+        .into_error(source)
+    }
+}
+
 // Allow string slices to be converted to Error
 impl From<&str> for Error {
     fn from(val: &str) -> Self {
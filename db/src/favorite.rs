@@ -0,0 +1,200 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use snafu::ResultExt;
+
+use crate::Result;
+use crate::entry::EntryLink;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
+use crate::schema::entries;
+use crate::schema::entry_favorites::{self, dsl};
+use crate::schema::vaults;
+use dto::entry::EntryLinkDto;
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::entry_favorites)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct NewFavorite {
+    user_id: String,
+    entry_id: String,
+    created_at: i64,
+}
+
+#[async_trait]
+pub trait FavoriteRepoable: Send + Sync {
+    /// Flips the favorite flag and returns the new state
+    async fn toggle(&self, user_id: &str, entry_id: &str) -> Result<bool>;
+
+    async fn is_favorite(&self, user_id: &str, entry_id: &str) -> Result<bool>;
+
+    /// Favorites across all vaults of the org, sorted by label
+    async fn list(&self, user_id: &str, org_id: &str) -> Result<Vec<EntryLinkDto>>;
+}
+
+pub struct FavoriteRepo {
+    db_pool: Pool,
+}
+
+impl FavoriteRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl FavoriteRepoable for FavoriteRepo {
+    async fn toggle(&self, user_id: &str, entry_id: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let data = NewFavorite {
+            user_id: user_id.to_string(),
+            entry_id: entry_id.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        let toggle_res = db
+            .interact(move |conn| {
+                conn.immediate_transaction(|conn| {
+                    let deleted = diesel::delete(
+                        dsl::entry_favorites
+                            .filter(dsl::user_id.eq(&data.user_id))
+                            .filter(dsl::entry_id.eq(&data.entry_id)),
+                    )
+                    .execute(conn)?;
+
+                    if deleted > 0 {
+                        return Ok(false);
+                    }
+
+                    diesel::insert_into(entry_favorites::table)
+                        .values(&data)
+                        .execute(conn)?;
+                    Ok(true)
+                })
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        toggle_res.context(DbQuerySnafu {
+            table: "entry_favorites".to_string(),
+        })
+    }
+
+    async fn is_favorite(&self, user_id: &str, entry_id: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let uid = user_id.to_string();
+        let eid = entry_id.to_string();
+        let count_res = db
+            .interact(move |conn| {
+                dsl::entry_favorites
+                    .filter(dsl::user_id.eq(uid))
+                    .filter(dsl::entry_id.eq(eid))
+                    .select(count_star())
+                    .get_result::<i64>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let count = count_res.context(DbQuerySnafu {
+            table: "entry_favorites".to_string(),
+        })?;
+
+        Ok(count > 0)
+    }
+
+    async fn list(&self, user_id: &str, org_id: &str) -> Result<Vec<EntryLinkDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let uid = user_id.to_string();
+        let oid = org_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::entry_favorites
+                    .inner_join(entries::table.inner_join(vaults::table))
+                    .filter(dsl::user_id.eq(uid))
+                    .filter(vaults::org_id.eq(oid))
+                    .select((
+                        entries::id,
+                        entries::vault_id,
+                        vaults::name,
+                        entries::label,
+                        dsl::created_at,
+                    ))
+                    .order(entries::label.asc())
+                    .load::<EntryLink>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "entry_favorites".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|x| x.into()).collect())
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct FavoriteTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl FavoriteRepoable for FavoriteTestRepo {
+    async fn toggle(&self, _user_id: &str, _entry_id: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn is_favorite(&self, _user_id: &str, _entry_id: &str) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn list(&self, _user_id: &str, _org_id: &str) -> Result<Vec<EntryLinkDto>> {
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
+
+    #[tokio::test]
+    async fn test_toggle_and_list_favorites() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user = seed.user("editor").unwrap();
+        let entry = &seed.entries[0];
+
+        assert!(db.favorites.toggle(&user.id, &entry.id).await.unwrap());
+        assert!(db.favorites.is_favorite(&user.id, &entry.id).await.unwrap());
+
+        let items = db.favorites.list(&user.id, &seed.org.id).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, entry.id);
+        assert_eq!(items[0].vault_name, "Personal");
+
+        // Other orgs never see the entry
+        let items = db.favorites.list(&user.id, &seed.admin_org.id).await;
+        assert!(items.unwrap().is_empty());
+
+        assert!(!db.favorites.toggle(&user.id, &entry.id).await.unwrap());
+        assert!(!db.favorites.is_favorite(&user.id, &entry.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_favorites_removed_with_entry() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user = seed.user("viewer").unwrap();
+        let entry = &seed.entries[1];
+
+        db.favorites.toggle(&user.id, &entry.id).await.unwrap();
+        db.entries.delete(&entry.id).await.unwrap();
+
+        let items = db.favorites.list(&user.id, &seed.org.id).await.unwrap();
+        assert!(items.is_empty());
+    }
+}
@@ -0,0 +1,632 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::prelude::*;
+use diesel::{QueryDsl, SelectableHelper};
+use serde::Deserialize;
+use snafu::{ResultExt, ensure};
+use validator::Validate;
+
+use crate::Result;
+use crate::error::{
+    DbInteractSnafu, DbPoolSnafu, DbQuerySnafu, InvalidRolesSnafu, ValidationSnafu,
+};
+use crate::schema::group_users;
+use crate::schema::groups::{self, dsl};
+use crate::schema::users;
+use dto::group::{GroupDto, GroupMemberDto};
+use dto::role::{Role, to_roles};
+use vault::utils::generate_id;
+use vault::validators::flatten_errors;
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::groups)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct Group {
+    id: String,
+    org_id: String,
+    name: String,
+    roles: String,
+    created_at: i64,
+    updated_at: i64,
+    version: i32,
+}
+
+impl From<Group> for GroupDto {
+    fn from(group: Group) -> Self {
+        let role_list = group
+            .roles
+            .split(",")
+            .map(|item| item.to_string())
+            .collect();
+        let roles = to_roles(role_list).expect("Invalid roles");
+        GroupDto {
+            id: group.id,
+            org_id: group.org_id,
+            name: group.name,
+            roles,
+            created_at: group.created_at,
+            updated_at: group.updated_at,
+            version: group.version,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Queryable)]
+struct GroupMember {
+    group_id: String,
+    user_id: String,
+    username: String,
+    created_at: i64,
+}
+
+impl From<GroupMember> for GroupMemberDto {
+    fn from(member: GroupMember) -> Self {
+        GroupMemberDto {
+            group_id: member.group_id,
+            user_id: member.user_id,
+            username: member.username,
+            created_at: member.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::group_users)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct NewGroupUser {
+    group_id: String,
+    user_id: String,
+    created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct NewGroup {
+    #[validate(length(min = 1, max = 50))]
+    #[validate(custom(function = "vault::validators::anyname"))]
+    pub name: String,
+
+    #[validate(length(min = 1, max = 100))]
+    #[validate(custom(function = "vault::validators::csvname"))]
+    pub roles: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct UpdateGroup {
+    #[validate(length(min = 1, max = 50))]
+    #[validate(custom(function = "vault::validators::anyname"))]
+    pub name: Option<String>,
+
+    #[validate(length(min = 1, max = 100))]
+    #[validate(custom(function = "vault::validators::csvname"))]
+    pub roles: Option<String>,
+}
+
+#[derive(Debug, Clone, AsChangeset)]
+#[diesel(table_name = crate::schema::groups)]
+struct GroupChangeset {
+    name: Option<String>,
+    roles: Option<String>,
+    updated_at: i64,
+}
+
+/// Groups may hold any org role except the system admin
+fn validate_roles(roles: &str) -> Result<()> {
+    let roles: Vec<String> = roles.split(",").map(|item| item.to_string()).collect();
+    let roles = to_roles(roles).context(InvalidRolesSnafu)?;
+
+    ensure!(
+        !roles.contains(&Role::SystemAdmin),
+        ValidationSnafu {
+            msg: "Groups cannot grant the system admin role".to_string(),
+        }
+    );
+    Ok(())
+}
+
+#[async_trait]
+pub trait GroupRepoable: Send + Sync {
+    /// Groups of the org, sorted by name
+    async fn list(&self, org_id: &str) -> Result<Vec<GroupDto>>;
+
+    /// Groups the user belongs to
+    async fn list_by_user(&self, user_id: &str) -> Result<Vec<GroupDto>>;
+
+    async fn create(&self, org_id: &str, data: &NewGroup) -> Result<GroupDto>;
+
+    async fn get(&self, id: &str) -> Result<Option<GroupDto>>;
+
+    /// Updates only when the group is still at the given version
+    async fn update(&self, id: &str, version: i32, data: &UpdateGroup) -> Result<bool>;
+
+    /// Deletes only when the group is still at the given version
+    async fn delete(&self, id: &str, version: i32) -> Result<bool>;
+
+    /// Members of the group, sorted by username
+    async fn list_members(&self, group_id: &str) -> Result<Vec<GroupMemberDto>>;
+
+    /// Adding an existing member does nothing
+    async fn add_member(&self, group_id: &str, user_id: &str) -> Result<()>;
+
+    async fn remove_member(&self, group_id: &str, user_id: &str) -> Result<bool>;
+}
+
+pub struct GroupRepo {
+    db_pool: Pool,
+}
+
+impl GroupRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+
+    async fn find_by_name(&self, org_id: &str, name: &str) -> Result<Option<GroupDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let org_id = org_id.to_string();
+        let name = name.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::groups
+                    .filter(dsl::org_id.eq(org_id))
+                    .filter(dsl::name.eq(name))
+                    .select(Group::as_select())
+                    .first::<Group>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let item = select_res.context(DbQuerySnafu {
+            table: "groups".to_string(),
+        })?;
+
+        Ok(item.map(|item| item.into()))
+    }
+}
+
+#[async_trait]
+impl GroupRepoable for GroupRepo {
+    async fn list(&self, org_id: &str) -> Result<Vec<GroupDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let org_id = org_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::groups
+                    .filter(dsl::org_id.eq(&org_id))
+                    .select(Group::as_select())
+                    .order(dsl::name.asc())
+                    .load::<Group>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "groups".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|item| item.into()).collect())
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Result<Vec<GroupDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let uid = user_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::groups
+                    .inner_join(group_users::table)
+                    .filter(group_users::user_id.eq(uid))
+                    .select(Group::as_select())
+                    .order(dsl::name.asc())
+                    .load::<Group>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "groups".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|item| item.into()).collect())
+    }
+
+    async fn create(&self, org_id: &str, data: &NewGroup) -> Result<GroupDto> {
+        let errors = data.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+        validate_roles(&data.roles)?;
+
+        // Name must be unique within the org
+        let existing = self.find_by_name(org_id, &data.name).await?;
+        ensure!(
+            existing.is_none(),
+            ValidationSnafu {
+                msg: "Group name already exists".to_string(),
+            }
+        );
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let today = chrono::Utc::now().timestamp();
+        let group = Group {
+            id: generate_id(),
+            org_id: org_id.to_string(),
+            name: data.name.clone(),
+            roles: data.roles.clone(),
+            created_at: today,
+            updated_at: today,
+            version: 1,
+        };
+
+        let group_copy = group.clone();
+        let insert_res = db
+            .interact(move |conn| {
+                diesel::insert_into(groups::table)
+                    .values(&group_copy)
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = insert_res.context(DbQuerySnafu {
+            table: "groups".to_string(),
+        })?;
+
+        Ok(group.into())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<GroupDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let gid = id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::groups
+                    .find(gid)
+                    .select(Group::as_select())
+                    .first::<Group>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let item = select_res.context(DbQuerySnafu {
+            table: "groups".to_string(),
+        })?;
+
+        Ok(item.map(|item| item.into()))
+    }
+
+    async fn update(&self, id: &str, version: i32, data: &UpdateGroup) -> Result<bool> {
+        let errors = data.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+        if let Some(roles) = &data.roles {
+            validate_roles(roles)?;
+        }
+
+        // Name must stay unique within the org
+        let group = match &data.name {
+            Some(_) => self.get(id).await?,
+            None => None,
+        };
+        if let (Some(name), Some(group)) = (&data.name, group) {
+            let existing = self.find_by_name(&group.org_id, name).await?;
+            ensure!(
+                existing.is_none_or(|x| x.id == group.id),
+                ValidationSnafu {
+                    msg: "Group name already exists".to_string(),
+                }
+            );
+        }
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let id = id.to_string();
+        let changeset = GroupChangeset {
+            name: data.name.clone(),
+            roles: data.roles.clone(),
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+        let update_res = db
+            .interact(move |conn| {
+                diesel::update(dsl::groups)
+                    .filter(dsl::id.eq(id.as_str()))
+                    .filter(dsl::version.eq(version))
+                    .set((changeset, dsl::version.eq(version + 1)))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = update_res.context(DbQuerySnafu {
+            table: "groups".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let gid = id.to_string();
+        let delete_res = db
+            .interact(move |conn| {
+                diesel::delete(dsl::groups.filter(dsl::id.eq(gid)))
+                    .filter(dsl::version.eq(version))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = delete_res.context(DbQuerySnafu {
+            table: "groups".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+
+    async fn list_members(&self, group_id: &str) -> Result<Vec<GroupMemberDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let gid = group_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                group_users::table
+                    .inner_join(users::table)
+                    .filter(group_users::group_id.eq(gid))
+                    .select((
+                        group_users::group_id,
+                        group_users::user_id,
+                        users::username,
+                        group_users::created_at,
+                    ))
+                    .order(users::username.asc())
+                    .load::<GroupMember>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "group_users".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|x| x.into()).collect())
+    }
+
+    async fn add_member(&self, group_id: &str, user_id: &str) -> Result<()> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let data = NewGroupUser {
+            group_id: group_id.to_string(),
+            user_id: user_id.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        let insert_res = db
+            .interact(move |conn| {
+                diesel::insert_into(group_users::table)
+                    .values(&data)
+                    .on_conflict_do_nothing()
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = insert_res.context(DbQuerySnafu {
+            table: "group_users".to_string(),
+        })?;
+
+        Ok(())
+    }
+
+    async fn remove_member(&self, group_id: &str, user_id: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let gid = group_id.to_string();
+        let uid = user_id.to_string();
+        let delete_res = db
+            .interact(move |conn| {
+                diesel::delete(
+                    group_users::table
+                        .filter(group_users::group_id.eq(gid))
+                        .filter(group_users::user_id.eq(uid)),
+                )
+                .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = delete_res.context(DbQuerySnafu {
+            table: "group_users".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+}
+
+#[cfg(feature = "test")]
+pub const TEST_GROUP_ID: &str = "0197998a4c6f7b1d8e2a3c5f60718293";
+
+#[cfg(feature = "test")]
+pub fn create_test_group() -> GroupDto {
+    use crate::org::TEST_ORG_ID;
+
+    GroupDto {
+        id: TEST_GROUP_ID.to_string(),
+        org_id: TEST_ORG_ID.to_string(),
+        name: "Testers".to_string(),
+        roles: vec![Role::Viewer],
+        created_at: 0,
+        updated_at: 0,
+        version: 1,
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct GroupTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl GroupRepoable for GroupTestRepo {
+    async fn list(&self, org_id: &str) -> Result<Vec<GroupDto>> {
+        let groups = vec![create_test_group()];
+        let filtered = groups
+            .into_iter()
+            .filter(|x| x.org_id.as_str() == org_id)
+            .collect();
+        Ok(filtered)
+    }
+
+    async fn list_by_user(&self, _user_id: &str) -> Result<Vec<GroupDto>> {
+        Ok(vec![])
+    }
+
+    async fn create(&self, _org_id: &str, _data: &NewGroup) -> Result<GroupDto> {
+        Err("Not supported".into())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<GroupDto>> {
+        let groups = vec![create_test_group()];
+        Ok(groups.into_iter().find(|x| x.id.as_str() == id))
+    }
+
+    async fn update(&self, id: &str, version: i32, _data: &UpdateGroup) -> Result<bool> {
+        let group = self.get(id).await?;
+        Ok(group.is_some_and(|x| x.version == version))
+    }
+
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let group = self.get(id).await?;
+        Ok(group.is_some_and(|x| x.version == version))
+    }
+
+    async fn list_members(&self, _group_id: &str) -> Result<Vec<GroupMemberDto>> {
+        Ok(vec![])
+    }
+
+    async fn add_member(&self, _group_id: &str, _user_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn remove_member(&self, _group_id: &str, _user_id: &str) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
+
+    #[tokio::test]
+    async fn test_group_crud() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let data = NewGroup {
+            name: "Support".to_string(),
+            roles: "Editor".to_string(),
+        };
+        let group = db.groups.create(&seed.org.id, &data).await.unwrap();
+        assert_eq!(group.roles, vec![Role::Editor]);
+
+        // Names are unique within the org
+        assert!(db.groups.create(&seed.org.id, &data).await.is_err());
+
+        // System admin cannot be granted through a group
+        let data = NewGroup {
+            name: "Admins".to_string(),
+            roles: "SystemAdmin".to_string(),
+        };
+        assert!(db.groups.create(&seed.org.id, &data).await.is_err());
+
+        let data = UpdateGroup {
+            name: Some("Help Desk".to_string()),
+            roles: Some("Editor,Viewer".to_string()),
+        };
+        assert!(
+            db.groups
+                .update(&group.id, group.version, &data)
+                .await
+                .unwrap()
+        );
+        // Stale version is rejected
+        assert!(
+            !db.groups
+                .update(&group.id, group.version, &data)
+                .await
+                .unwrap()
+        );
+
+        let updated = db.groups.get(&group.id).await.unwrap().unwrap();
+        assert_eq!(updated.name, "Help Desk");
+        assert_eq!(updated.roles, vec![Role::Editor, Role::Viewer]);
+        assert_eq!(updated.version, group.version + 1);
+
+        let items = db.groups.list(&seed.org.id).await.unwrap();
+        assert_eq!(items.len(), 1);
+
+        // Stale version is rejected
+        assert!(!db.groups.delete(&group.id, group.version).await.unwrap());
+        assert!(db.groups.delete(&group.id, updated.version).await.unwrap());
+        assert!(!db.groups.delete(&group.id, updated.version).await.unwrap());
+        assert!(db.groups.get(&group.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_group_members() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let viewer = seed.user("viewer").unwrap();
+        let editor = seed.user("editor").unwrap();
+
+        let data = NewGroup {
+            name: "Support".to_string(),
+            roles: "Editor".to_string(),
+        };
+        let group = db.groups.create(&seed.org.id, &data).await.unwrap();
+
+        db.groups.add_member(&group.id, &viewer.id).await.unwrap();
+        db.groups.add_member(&group.id, &editor.id).await.unwrap();
+        // Adding twice is a no-op
+        db.groups.add_member(&group.id, &viewer.id).await.unwrap();
+
+        let members = db.groups.list_members(&group.id).await.unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].username, "editor");
+
+        let groups = db.groups.list_by_user(&viewer.id).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id, group.id);
+
+        assert!(
+            db.groups
+                .remove_member(&group.id, &viewer.id)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !db.groups
+                .remove_member(&group.id, &viewer.id)
+                .await
+                .unwrap()
+        );
+        assert!(db.groups.list_by_user(&viewer.id).await.unwrap().is_empty());
+
+        // Memberships go away with the group
+        db.groups.delete(&group.id, group.version).await.unwrap();
+        assert!(db.groups.list_by_user(&editor.id).await.unwrap().is_empty());
+    }
+}
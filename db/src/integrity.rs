@@ -0,0 +1,257 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Nullable, Text};
+use snafu::ResultExt;
+
+use crate::Result;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
+use crate::schema::{entries, orgs, users, vaults};
+
+#[derive(Debug, Clone, QueryableByName)]
+struct IntegrityRow {
+    #[diesel(sql_type = Text)]
+    message: String,
+}
+
+#[derive(Debug, Clone, QueryableByName)]
+pub struct ForeignKeyViolation {
+    #[diesel(sql_type = Text)]
+    pub table_name: String,
+
+    #[diesel(sql_type = Nullable<BigInt>)]
+    pub row_id: Option<i64>,
+
+    #[diesel(sql_type = Text)]
+    pub parent: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    /// Problems reported by `PRAGMA integrity_check`, empty when healthy
+    pub integrity_errors: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    pub orphaned_users: Vec<String>,
+    pub orphaned_vaults: Vec<String>,
+    pub orphaned_entries: Vec<String>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.integrity_errors.is_empty()
+            && self.foreign_key_violations.is_empty()
+            && self.orphaned_users.is_empty()
+            && self.orphaned_vaults.is_empty()
+            && self.orphaned_entries.is_empty()
+    }
+}
+
+#[async_trait]
+pub trait IntegrityRepoable: Send + Sync {
+    async fn check(&self) -> Result<IntegrityReport>;
+}
+
+pub struct IntegrityRepo {
+    db_pool: Pool,
+}
+
+impl IntegrityRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl IntegrityRepoable for IntegrityRepo {
+    async fn check(&self) -> Result<IntegrityReport> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let integrity_res = db
+            .interact(move |conn| {
+                diesel::sql_query("SELECT integrity_check AS message FROM pragma_integrity_check")
+                    .load::<IntegrityRow>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let rows = integrity_res.context(DbQuerySnafu {
+            table: "integrity_check".to_string(),
+        })?;
+
+        let integrity_errors: Vec<String> = rows
+            .into_iter()
+            .map(|row| row.message)
+            .filter(|message| message != "ok")
+            .collect();
+
+        let fk_res = db
+            .interact(move |conn| {
+                diesel::sql_query(
+                    "SELECT \"table\" AS table_name, rowid AS row_id, parent
+                    FROM pragma_foreign_key_check",
+                )
+                .load::<ForeignKeyViolation>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let foreign_key_violations = fk_res.context(DbQuerySnafu {
+            table: "foreign_key_check".to_string(),
+        })?;
+
+        let users_res = db
+            .interact(move |conn| {
+                users::table
+                    .filter(users::org_id.ne_all(orgs::table.select(orgs::id)))
+                    .select(users::id)
+                    .load::<String>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let orphaned_users = users_res.context(DbQuerySnafu {
+            table: "users".to_string(),
+        })?;
+
+        let vaults_res = db
+            .interact(move |conn| {
+                vaults::table
+                    .filter(vaults::org_id.ne_all(orgs::table.select(orgs::id)))
+                    .select(vaults::id)
+                    .load::<String>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let orphaned_vaults = vaults_res.context(DbQuerySnafu {
+            table: "vaults".to_string(),
+        })?;
+
+        let entries_res = db
+            .interact(move |conn| {
+                entries::table
+                    .filter(entries::vault_id.ne_all(vaults::table.select(vaults::id)))
+                    .select(entries::id)
+                    .load::<String>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let orphaned_entries = entries_res.context(DbQuerySnafu {
+            table: "entries".to_string(),
+        })?;
+
+        Ok(IntegrityReport {
+            integrity_errors,
+            foreign_key_violations,
+            orphaned_users,
+            orphaned_vaults,
+            orphaned_entries,
+        })
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct IntegrityTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl IntegrityRepoable for IntegrityTestRepo {
+    async fn check(&self) -> Result<IntegrityReport> {
+        Ok(IntegrityReport::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{DbOptions, create_db_pool};
+    use diesel::connection::SimpleConnection;
+
+    const TABLES: &str = "CREATE TABLE orgs (id TEXT PRIMARY KEY);
+        CREATE TABLE users (
+            id TEXT PRIMARY KEY,
+            org_id TEXT NOT NULL,
+            FOREIGN KEY (org_id) REFERENCES orgs(id)
+        );
+        CREATE TABLE vaults (
+            id TEXT PRIMARY KEY,
+            org_id TEXT NOT NULL,
+            FOREIGN KEY (org_id) REFERENCES orgs(id)
+        );
+        CREATE TABLE entries (
+            id TEXT PRIMARY KEY,
+            vault_id TEXT NOT NULL,
+            FOREIGN KEY (vault_id) REFERENCES vaults(id)
+        );";
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_pool_enforces_foreign_keys() {
+        let path = temp_file("fk.sqlite3");
+        let pool = create_db_pool(path.to_str().unwrap(), &DbOptions::default());
+        let db = pool.get().await.unwrap();
+
+        let res = db
+            .interact(|conn| {
+                conn.batch_execute(TABLES)?;
+                conn.batch_execute("INSERT INTO vaults (id, org_id) VALUES ('v1', 'missing')")
+            })
+            .await
+            .unwrap();
+        assert!(res.is_err());
+
+        let journal_mode = db
+            .interact(|conn| {
+                diesel::sql_query("SELECT journal_mode AS message FROM pragma_journal_mode")
+                    .get_result::<IntegrityRow>(conn)
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(journal_mode.message, "wal");
+
+        drop(db);
+        drop(pool);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_check_reports_orphans() {
+        let path = temp_file("orphans.sqlite3");
+
+        // Plain connections leave foreign keys off, just like before
+        let mut conn = SqliteConnection::establish(path.to_str().unwrap()).unwrap();
+        conn.batch_execute(TABLES).unwrap();
+        conn.batch_execute(
+            "INSERT INTO orgs (id) VALUES ('o1');
+            INSERT INTO users (id, org_id) VALUES ('u1', 'o1'), ('u2', 'gone');
+            INSERT INTO vaults (id, org_id) VALUES ('v1', 'o1'), ('v2', 'gone');
+            INSERT INTO entries (id, vault_id) VALUES ('e1', 'v1'), ('e2', 'gone');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let pool = create_db_pool(path.to_str().unwrap(), &DbOptions::default());
+        let repo = IntegrityRepo::new(pool);
+        let report = repo.check().await.unwrap();
+
+        assert!(!report.is_ok());
+        assert!(report.integrity_errors.is_empty());
+        assert_eq!(report.foreign_key_violations.len(), 3);
+        assert_eq!(report.orphaned_users, vec!["u2".to_string()]);
+        assert_eq!(report.orphaned_vaults, vec!["v2".to_string()]);
+        assert_eq!(report.orphaned_entries, vec!["e2".to_string()]);
+
+        drop(repo);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
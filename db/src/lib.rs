@@ -1,8 +1,25 @@
+pub mod api_key;
+pub mod attachment;
+pub mod audit;
+pub mod backup;
 pub mod db;
 pub mod entry;
+pub mod ephemeral;
 pub mod error;
+pub mod favorite;
+pub mod group;
+pub mod integrity;
+pub mod login_attempt;
+pub mod member;
 pub mod org;
+pub mod password_reset;
+pub mod quota;
+pub mod recent;
+pub mod role;
 mod schema;
+pub mod seed;
+pub mod session;
+pub mod tx;
 pub mod user;
 pub mod vault;
 
@@ -0,0 +1,390 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::prelude::*;
+use snafu::ResultExt;
+
+use crate::Result;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
+use crate::schema::login_attempts::{self, dsl};
+use crate::tx::SqliteTx;
+use dto::login_attempt::LoginAttemptDto;
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::login_attempts)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(treat_none_as_null = true)]
+struct LoginAttempt {
+    scope: String,
+    subject: String,
+    failures: i32,
+    first_failed_at: i64,
+    last_failed_at: i64,
+    locked_until: Option<i64>,
+}
+
+impl From<LoginAttempt> for LoginAttemptDto {
+    fn from(attempt: LoginAttempt) -> Self {
+        LoginAttemptDto {
+            scope: attempt.scope,
+            subject: attempt.subject,
+            failures: attempt.failures,
+            first_failed_at: attempt.first_failed_at,
+            last_failed_at: attempt.last_failed_at,
+            locked_until: attempt.locked_until,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginFailure {
+    pub failed_at: i64,
+
+    /// Failures before this time are forgotten and counting starts over
+    pub reset_before: i64,
+}
+
+#[async_trait]
+pub trait LoginAttemptRepoable: Send + Sync {
+    async fn get(&self, scope: &str, subject: &str) -> Result<Option<LoginAttemptDto>>;
+
+    /// Counts one more failure, the result holds the updated count
+    async fn record_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        data: &LoginFailure,
+    ) -> Result<LoginAttemptDto>;
+
+    async fn lock(&self, scope: &str, subject: &str, until: i64) -> Result<bool>;
+
+    /// Forgets all failures, also lifting any lock
+    async fn clear(&self, scope: &str, subject: &str) -> Result<bool>;
+}
+
+pub struct LoginAttemptRepo {
+    db_pool: Pool,
+}
+
+impl LoginAttemptRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl LoginAttemptRepoable for LoginAttemptRepo {
+    async fn get(&self, scope: &str, subject: &str) -> Result<Option<LoginAttemptDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let (scope, subject) = (scope.to_string(), subject.to_string());
+        db.interact(move |conn| SqliteTx { conn }.get(&scope, &subject))
+            .await
+            .context(DbInteractSnafu)?
+    }
+
+    async fn record_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        data: &LoginFailure,
+    ) -> Result<LoginAttemptDto> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let (scope, subject) = (scope.to_string(), subject.to_string());
+        let data = data.clone();
+        db.interact(move |conn| SqliteTx { conn }.record_failure(&scope, &subject, &data))
+            .await
+            .context(DbInteractSnafu)?
+    }
+
+    async fn lock(&self, scope: &str, subject: &str, until: i64) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let (scope, subject) = (scope.to_string(), subject.to_string());
+        db.interact(move |conn| SqliteTx { conn }.lock(&scope, &subject, until))
+            .await
+            .context(DbInteractSnafu)?
+    }
+
+    async fn clear(&self, scope: &str, subject: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let (scope, subject) = (scope.to_string(), subject.to_string());
+        db.interact(move |conn| SqliteTx { conn }.clear(&scope, &subject))
+            .await
+            .context(DbInteractSnafu)?
+    }
+}
+
+/// Login attempt operations available inside a transaction
+pub trait LoginAttemptTx {
+    fn get(&mut self, scope: &str, subject: &str) -> Result<Option<LoginAttemptDto>>;
+
+    /// Counts one more failure, the result holds the updated count
+    fn record_failure(
+        &mut self,
+        scope: &str,
+        subject: &str,
+        data: &LoginFailure,
+    ) -> Result<LoginAttemptDto>;
+
+    fn lock(&mut self, scope: &str, subject: &str, until: i64) -> Result<bool>;
+
+    /// Takes back one counted failure and replaces the lock
+    fn release(&mut self, scope: &str, subject: &str, locked_until: Option<i64>) -> Result<bool>;
+
+    /// Forgets all failures, also lifting any lock
+    fn clear(&mut self, scope: &str, subject: &str) -> Result<bool>;
+}
+
+impl LoginAttemptTx for SqliteTx<'_> {
+    fn get(&mut self, scope: &str, subject: &str) -> Result<Option<LoginAttemptDto>> {
+        let item = dsl::login_attempts
+            .find((scope, subject))
+            .select(LoginAttempt::as_select())
+            .first::<LoginAttempt>(self.conn)
+            .optional()
+            .context(DbQuerySnafu {
+                table: "login_attempts".to_string(),
+            })?;
+
+        Ok(item.map(|x| x.into()))
+    }
+
+    fn record_failure(
+        &mut self,
+        scope: &str,
+        subject: &str,
+        data: &LoginFailure,
+    ) -> Result<LoginAttemptDto> {
+        let attempt = self
+            .conn
+            .transaction(|conn| {
+                let existing = dsl::login_attempts
+                    .find((scope, subject))
+                    .select(LoginAttempt::as_select())
+                    .first::<LoginAttempt>(conn)
+                    .optional()?;
+
+                // Counting starts over once earlier failures are old enough
+                let attempt = match existing {
+                    Some(prev) if prev.last_failed_at >= data.reset_before => LoginAttempt {
+                        failures: prev.failures + 1,
+                        last_failed_at: data.failed_at,
+                        ..prev
+                    },
+                    _ => LoginAttempt {
+                        scope: scope.to_string(),
+                        subject: subject.to_string(),
+                        failures: 1,
+                        first_failed_at: data.failed_at,
+                        last_failed_at: data.failed_at,
+                        locked_until: None,
+                    },
+                };
+
+                diesel::insert_into(login_attempts::table)
+                    .values(&attempt)
+                    .on_conflict((dsl::scope, dsl::subject))
+                    .do_update()
+                    .set(&attempt)
+                    .execute(conn)?;
+
+                Ok::<_, diesel::result::Error>(attempt)
+            })
+            .context(DbQuerySnafu {
+                table: "login_attempts".to_string(),
+            })?;
+
+        Ok(attempt.into())
+    }
+
+    fn lock(&mut self, scope: &str, subject: &str, until: i64) -> Result<bool> {
+        let count = diesel::update(dsl::login_attempts.find((scope, subject)))
+            .set(dsl::locked_until.eq(Some(until)))
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "login_attempts".to_string(),
+            })?;
+
+        Ok(count > 0)
+    }
+
+    fn release(&mut self, scope: &str, subject: &str, locked_until: Option<i64>) -> Result<bool> {
+        let count = diesel::update(dsl::login_attempts.find((scope, subject)))
+            .filter(dsl::failures.gt(0))
+            .set((
+                dsl::failures.eq(dsl::failures - 1),
+                dsl::locked_until.eq(locked_until),
+            ))
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "login_attempts".to_string(),
+            })?;
+
+        Ok(count > 0)
+    }
+
+    fn clear(&mut self, scope: &str, subject: &str) -> Result<bool> {
+        let count = diesel::delete(dsl::login_attempts.find((scope, subject)))
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "login_attempts".to_string(),
+            })?;
+
+        Ok(count > 0)
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct LoginAttemptTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl LoginAttemptRepoable for LoginAttemptTestRepo {
+    async fn get(&self, _scope: &str, _subject: &str) -> Result<Option<LoginAttemptDto>> {
+        Ok(None)
+    }
+
+    async fn record_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        data: &LoginFailure,
+    ) -> Result<LoginAttemptDto> {
+        Ok(LoginAttemptDto {
+            scope: scope.to_string(),
+            subject: subject.to_string(),
+            failures: 1,
+            first_failed_at: data.failed_at,
+            last_failed_at: data.failed_at,
+            locked_until: None,
+        })
+    }
+
+    async fn lock(&self, _scope: &str, _subject: &str, _until: i64) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn clear(&self, _scope: &str, _subject: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(feature = "test")]
+impl LoginAttemptTx for crate::tx::TestTx {
+    fn get(&mut self, _scope: &str, _subject: &str) -> Result<Option<LoginAttemptDto>> {
+        Ok(None)
+    }
+
+    fn record_failure(
+        &mut self,
+        scope: &str,
+        subject: &str,
+        data: &LoginFailure,
+    ) -> Result<LoginAttemptDto> {
+        Ok(LoginAttemptDto {
+            scope: scope.to_string(),
+            subject: subject.to_string(),
+            failures: 1,
+            first_failed_at: data.failed_at,
+            last_failed_at: data.failed_at,
+            locked_until: None,
+        })
+    }
+
+    fn lock(&mut self, _scope: &str, _subject: &str, _until: i64) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn release(
+        &mut self,
+        _scope: &str,
+        _subject: &str,
+        _locked_until: Option<i64>,
+    ) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn clear(&mut self, _scope: &str, _subject: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use dto::login_attempt::USER_SCOPE;
+
+    #[tokio::test]
+    async fn test_record_failures() {
+        let db = create_ephemeral_db_mapper().unwrap();
+
+        let failure = LoginFailure {
+            failed_at: 1000,
+            reset_before: 0,
+        };
+        let attempt = db
+            .login_attempts
+            .record_failure(USER_SCOPE, "alice", &failure)
+            .await
+            .unwrap();
+        assert_eq!(attempt.failures, 1);
+
+        let failure = LoginFailure {
+            failed_at: 1010,
+            reset_before: 0,
+        };
+        let attempt = db
+            .login_attempts
+            .record_failure(USER_SCOPE, "alice", &failure)
+            .await
+            .unwrap();
+        assert_eq!(attempt.failures, 2);
+        assert_eq!(attempt.first_failed_at, 1000);
+
+        assert!(
+            db.login_attempts
+                .lock(USER_SCOPE, "alice", 2000)
+                .await
+                .unwrap()
+        );
+        let attempt = db.login_attempts.get(USER_SCOPE, "alice").await.unwrap();
+        assert_eq!(attempt.unwrap().retry_after(1500), 500);
+
+        // Released failures no longer count, the lock is replaced
+        let released = db
+            .transaction(|tx| tx.login_attempts().release(USER_SCOPE, "alice", None))
+            .await
+            .unwrap();
+        assert!(released);
+        let attempt = db.login_attempts.get(USER_SCOPE, "alice").await.unwrap();
+        let attempt = attempt.unwrap();
+        assert_eq!(attempt.failures, 1);
+        assert_eq!(attempt.retry_after(1500), 0);
+
+        // Old failures are forgotten along with the lock
+        let failure = LoginFailure {
+            failed_at: 5000,
+            reset_before: 4000,
+        };
+        let attempt = db
+            .login_attempts
+            .record_failure(USER_SCOPE, "alice", &failure)
+            .await
+            .unwrap();
+        assert_eq!(attempt.failures, 1);
+        assert!(attempt.locked_until.is_none());
+
+        assert!(db.login_attempts.clear(USER_SCOPE, "alice").await.unwrap());
+        assert!(
+            db.login_attempts
+                .get(USER_SCOPE, "alice")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
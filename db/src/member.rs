@@ -0,0 +1,586 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use validator::Validate;
+
+use crate::Result;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
+use crate::schema::vault_groups;
+use crate::schema::vault_members::{self, dsl};
+use crate::schema::{group_users, groups, users};
+use crate::tx::SqliteTx;
+use dto::vault::{VaultGroupDto, VaultMemberDto, VaultRole};
+
+#[derive(Debug, Clone, Queryable)]
+struct VaultMember {
+    vault_id: String,
+    user_id: String,
+    username: String,
+    role: String,
+    created_at: i64,
+}
+
+impl From<VaultMember> for VaultMemberDto {
+    fn from(member: VaultMember) -> Self {
+        VaultMemberDto {
+            vault_id: member.vault_id,
+            user_id: member.user_id,
+            username: member.username,
+            // Unknown roles fall back to the least access
+            role: VaultRole::try_from(member.role.as_str()).unwrap_or(VaultRole::Viewer),
+            created_at: member.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::vault_members)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct NewVaultMember {
+    vault_id: String,
+    user_id: String,
+    role: String,
+    created_at: i64,
+}
+
+#[derive(Debug, Clone, Queryable)]
+struct VaultGroup {
+    vault_id: String,
+    group_id: String,
+    name: String,
+    role: String,
+    created_at: i64,
+}
+
+impl From<VaultGroup> for VaultGroupDto {
+    fn from(grant: VaultGroup) -> Self {
+        VaultGroupDto {
+            vault_id: grant.vault_id,
+            group_id: grant.group_id,
+            name: grant.name,
+            role: VaultRole::try_from(grant.role.as_str()).unwrap_or(VaultRole::Viewer),
+            created_at: grant.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::vault_groups)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct NewVaultGroup {
+    vault_id: String,
+    group_id: String,
+    role: String,
+    created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct SaveVaultMember {
+    #[validate(custom(function = "vault_role"))]
+    pub role: String,
+}
+
+fn vault_role(value: &str) -> core::result::Result<(), validator::ValidationError> {
+    match VaultRole::try_from(value) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(validator::ValidationError::new("vault_role")),
+    }
+}
+
+#[async_trait]
+pub trait MemberRepoable: Send + Sync {
+    async fn get(&self, vault_id: &str, user_id: &str) -> Result<Option<VaultMemberDto>>;
+
+    /// Members of the vault, sorted by username
+    async fn list(&self, vault_id: &str) -> Result<Vec<VaultMemberDto>>;
+
+    /// Highest role the user has in the vault, directly or through groups
+    async fn effective_role(&self, vault_id: &str, user_id: &str) -> Result<Option<VaultRole>>;
+
+    /// Vaults the user was granted access to, directly or through groups
+    async fn list_vault_ids(&self, user_id: &str) -> Result<Vec<String>>;
+
+    /// Grants access or changes the role of an existing member
+    async fn save(&self, vault_id: &str, user_id: &str, role: &VaultRole) -> Result<()>;
+
+    async fn delete(&self, vault_id: &str, user_id: &str) -> Result<bool>;
+
+    /// Groups granted access to the vault, sorted by name
+    async fn list_groups(&self, vault_id: &str) -> Result<Vec<VaultGroupDto>>;
+
+    /// Grants access to a group or changes its role
+    async fn save_group(&self, vault_id: &str, group_id: &str, role: &VaultRole) -> Result<()>;
+
+    async fn delete_group(&self, vault_id: &str, group_id: &str) -> Result<bool>;
+}
+
+pub struct MemberRepo {
+    db_pool: Pool,
+}
+
+impl MemberRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl MemberRepoable for MemberRepo {
+    async fn get(&self, vault_id: &str, user_id: &str) -> Result<Option<VaultMemberDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let vid = vault_id.to_string();
+        let uid = user_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::vault_members
+                    .inner_join(users::table)
+                    .filter(dsl::vault_id.eq(vid))
+                    .filter(dsl::user_id.eq(uid))
+                    .select((
+                        dsl::vault_id,
+                        dsl::user_id,
+                        users::username,
+                        dsl::role,
+                        dsl::created_at,
+                    ))
+                    .first::<VaultMember>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let member = select_res.context(DbQuerySnafu {
+            table: "vault_members".to_string(),
+        })?;
+
+        Ok(member.map(|x| x.into()))
+    }
+
+    async fn list(&self, vault_id: &str) -> Result<Vec<VaultMemberDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let vid = vault_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::vault_members
+                    .inner_join(users::table)
+                    .filter(dsl::vault_id.eq(vid))
+                    .select((
+                        dsl::vault_id,
+                        dsl::user_id,
+                        users::username,
+                        dsl::role,
+                        dsl::created_at,
+                    ))
+                    .order(users::username.asc())
+                    .load::<VaultMember>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "vault_members".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|x| x.into()).collect())
+    }
+
+    async fn effective_role(&self, vault_id: &str, user_id: &str) -> Result<Option<VaultRole>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let vid = vault_id.to_string();
+        let uid = user_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                let direct = dsl::vault_members
+                    .filter(dsl::vault_id.eq(&vid))
+                    .filter(dsl::user_id.eq(&uid))
+                    .select(dsl::role)
+                    .load::<String>(conn)?;
+
+                let inherited = vault_groups::table
+                    .inner_join(
+                        group_users::table.on(group_users::group_id.eq(vault_groups::group_id)),
+                    )
+                    .filter(vault_groups::vault_id.eq(&vid))
+                    .filter(group_users::user_id.eq(&uid))
+                    .select(vault_groups::role)
+                    .load::<String>(conn)?;
+
+                Ok::<Vec<String>, diesel::result::Error>([direct, inherited].concat())
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let roles = select_res.context(DbQuerySnafu {
+            table: "vault_members".to_string(),
+        })?;
+
+        Ok(roles
+            .iter()
+            .filter_map(|role| VaultRole::try_from(role.as_str()).ok())
+            .max())
+    }
+
+    async fn list_vault_ids(&self, user_id: &str) -> Result<Vec<String>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let uid = user_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                let direct = dsl::vault_members
+                    .filter(dsl::user_id.eq(&uid))
+                    .select(dsl::vault_id)
+                    .load::<String>(conn)?;
+
+                let inherited = vault_groups::table
+                    .inner_join(
+                        group_users::table.on(group_users::group_id.eq(vault_groups::group_id)),
+                    )
+                    .filter(group_users::user_id.eq(&uid))
+                    .select(vault_groups::vault_id)
+                    .load::<String>(conn)?;
+
+                Ok::<Vec<String>, diesel::result::Error>([direct, inherited].concat())
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let mut ids = select_res.context(DbQuerySnafu {
+            table: "vault_members".to_string(),
+        })?;
+
+        // Users may reach the same vault through several grants
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    async fn save(&self, vault_id: &str, user_id: &str, role: &VaultRole) -> Result<()> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let data = NewVaultMember {
+            vault_id: vault_id.to_string(),
+            user_id: user_id.to_string(),
+            role: role.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        let insert_res = db
+            .interact(move |conn| {
+                // Members keep their original grant time when the role changes
+                diesel::insert_into(vault_members::table)
+                    .values(&data)
+                    .on_conflict((dsl::vault_id, dsl::user_id))
+                    .do_update()
+                    .set(dsl::role.eq(&data.role))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = insert_res.context(DbQuerySnafu {
+            table: "vault_members".to_string(),
+        })?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, vault_id: &str, user_id: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let vid = vault_id.to_string();
+        let uid = user_id.to_string();
+        let delete_res = db
+            .interact(move |conn| {
+                diesel::delete(
+                    dsl::vault_members
+                        .filter(dsl::vault_id.eq(vid))
+                        .filter(dsl::user_id.eq(uid)),
+                )
+                .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = delete_res.context(DbQuerySnafu {
+            table: "vault_members".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+
+    async fn list_groups(&self, vault_id: &str) -> Result<Vec<VaultGroupDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let vid = vault_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                vault_groups::table
+                    .inner_join(groups::table)
+                    .filter(vault_groups::vault_id.eq(vid))
+                    .select((
+                        vault_groups::vault_id,
+                        vault_groups::group_id,
+                        groups::name,
+                        vault_groups::role,
+                        vault_groups::created_at,
+                    ))
+                    .order(groups::name.asc())
+                    .load::<VaultGroup>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "vault_groups".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|x| x.into()).collect())
+    }
+
+    async fn save_group(&self, vault_id: &str, group_id: &str, role: &VaultRole) -> Result<()> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let data = NewVaultGroup {
+            vault_id: vault_id.to_string(),
+            group_id: group_id.to_string(),
+            role: role.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        let insert_res = db
+            .interact(move |conn| {
+                diesel::insert_into(vault_groups::table)
+                    .values(&data)
+                    .on_conflict((vault_groups::vault_id, vault_groups::group_id))
+                    .do_update()
+                    .set(vault_groups::role.eq(&data.role))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = insert_res.context(DbQuerySnafu {
+            table: "vault_groups".to_string(),
+        })?;
+
+        Ok(())
+    }
+
+    async fn delete_group(&self, vault_id: &str, group_id: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let vid = vault_id.to_string();
+        let gid = group_id.to_string();
+        let delete_res = db
+            .interact(move |conn| {
+                diesel::delete(
+                    vault_groups::table
+                        .filter(vault_groups::vault_id.eq(vid))
+                        .filter(vault_groups::group_id.eq(gid)),
+                )
+                .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = delete_res.context(DbQuerySnafu {
+            table: "vault_groups".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+}
+
+/// Member operations available inside a transaction
+pub trait MemberTx {
+    /// Grants access or changes the role of an existing member
+    fn save(&mut self, vault_id: &str, user_id: &str, role: &VaultRole) -> Result<()>;
+}
+
+impl MemberTx for SqliteTx<'_> {
+    fn save(&mut self, vault_id: &str, user_id: &str, role: &VaultRole) -> Result<()> {
+        let data = NewVaultMember {
+            vault_id: vault_id.to_string(),
+            user_id: user_id.to_string(),
+            role: role.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        diesel::insert_into(vault_members::table)
+            .values(&data)
+            .on_conflict((dsl::vault_id, dsl::user_id))
+            .do_update()
+            .set(dsl::role.eq(&data.role))
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "vault_members".to_string(),
+            })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct MemberTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl MemberRepoable for MemberTestRepo {
+    async fn get(&self, vault_id: &str, user_id: &str) -> Result<Option<VaultMemberDto>> {
+        Ok(Some(VaultMemberDto {
+            vault_id: vault_id.to_string(),
+            user_id: user_id.to_string(),
+            username: "test".to_string(),
+            role: VaultRole::Manager,
+            created_at: 0,
+        }))
+    }
+
+    async fn list(&self, _vault_id: &str) -> Result<Vec<VaultMemberDto>> {
+        Ok(vec![])
+    }
+
+    async fn effective_role(&self, _vault_id: &str, _user_id: &str) -> Result<Option<VaultRole>> {
+        Ok(Some(VaultRole::Manager))
+    }
+
+    async fn list_vault_ids(&self, _user_id: &str) -> Result<Vec<String>> {
+        Ok(vec![crate::vault::TEST_VAULT_ID.to_string()])
+    }
+
+    async fn save(&self, _vault_id: &str, _user_id: &str, _role: &VaultRole) -> Result<()> {
+        Ok(())
+    }
+
+    async fn delete(&self, _vault_id: &str, _user_id: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn list_groups(&self, _vault_id: &str) -> Result<Vec<VaultGroupDto>> {
+        Ok(vec![])
+    }
+
+    async fn save_group(&self, _vault_id: &str, _group_id: &str, _role: &VaultRole) -> Result<()> {
+        Ok(())
+    }
+
+    async fn delete_group(&self, _vault_id: &str, _group_id: &str) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "test")]
+impl MemberTx for crate::tx::TestTx {
+    fn save(&mut self, _vault_id: &str, _user_id: &str, _role: &VaultRole) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::group::NewGroup;
+    use crate::seed::seed_demo;
+    use dto::vault::VaultRole;
+
+    #[tokio::test]
+    async fn test_save_and_list_members() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let vault = &seed.vaults[0];
+        let user = seed.user("viewer").unwrap();
+
+        // Demo users are granted the vault role closest to their org role
+        let member = db.members.get(&vault.id, &user.id).await.unwrap().unwrap();
+        assert_eq!(member.role, VaultRole::Viewer);
+        assert_eq!(member.username, "viewer");
+
+        db.members
+            .save(&vault.id, &user.id, &VaultRole::Editor)
+            .await
+            .unwrap();
+        let member = db.members.get(&vault.id, &user.id).await.unwrap().unwrap();
+        assert_eq!(member.role, VaultRole::Editor);
+
+        let items = db.members.list(&vault.id).await.unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].username, "editor");
+
+        assert!(db.members.delete(&vault.id, &user.id).await.unwrap());
+        assert!(!db.members.delete(&vault.id, &user.id).await.unwrap());
+        assert!(db.members.get(&vault.id, &user.id).await.unwrap().is_none());
+
+        let ids = db.members.list_vault_ids(&user.id).await.unwrap();
+        assert_eq!(ids, vec![seed.vaults[1].id.clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_members_removed_with_vault() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user = seed.user("editor").unwrap();
+
+        for entry in seed.entries.iter() {
+            db.entries.delete(&entry.id).await.unwrap();
+        }
+        let vault = &seed.vaults[0];
+        assert!(db.vaults.delete(&vault.id, vault.version).await.unwrap());
+
+        let ids = db.members.list_vault_ids(&user.id).await.unwrap();
+        assert_eq!(ids, vec![seed.vaults[1].id.clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_group_grants() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let vault = &seed.vaults[0];
+        let user = seed.user("viewer").unwrap();
+
+        let data = NewGroup {
+            name: "Support".to_string(),
+            roles: "Viewer".to_string(),
+        };
+        let group = db.groups.create(&seed.org.id, &data).await.unwrap();
+        db.groups.add_member(&group.id, &user.id).await.unwrap();
+        db.members
+            .save_group(&vault.id, &group.id, &VaultRole::Editor)
+            .await
+            .unwrap();
+
+        let items = db.members.list_groups(&vault.id).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "Support");
+        assert_eq!(items[0].role, VaultRole::Editor);
+
+        // The highest of the direct and group roles wins
+        let role = db.members.effective_role(&vault.id, &user.id).await;
+        assert_eq!(role.unwrap(), Some(VaultRole::Editor));
+
+        // Group grants keep the vault reachable without a direct grant
+        db.members.delete(&vault.id, &user.id).await.unwrap();
+        db.members
+            .delete(&seed.vaults[1].id, &user.id)
+            .await
+            .unwrap();
+        let ids = db.members.list_vault_ids(&user.id).await.unwrap();
+        assert_eq!(ids, vec![vault.id.clone()]);
+
+        assert!(db.members.delete_group(&vault.id, &group.id).await.unwrap());
+        let role = db.members.effective_role(&vault.id, &user.id).await;
+        assert_eq!(role.unwrap(), None);
+        assert!(
+            db.members
+                .list_vault_ids(&user.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -11,6 +11,7 @@ use validator::Validate;
 use crate::Result;
 use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
 use crate::schema::orgs::{self, dsl};
+use crate::tx::SqliteTx;
 use vault::utils::generate_id;
 
 #[derive(Debug, Clone, Queryable, Selectable, Insertable)]
@@ -21,6 +22,7 @@ pub struct Org {
     pub name: String,
     pub admin: bool,
     pub created_at: i64,
+    pub version: i32,
 }
 
 #[derive(Debug, Clone, Deserialize, Validate)]
@@ -45,6 +47,7 @@ impl From<OrgDto> for Org {
             name: dto.name,
             admin: dto.admin,
             created_at: dto.created_at,
+            version: dto.version,
         }
     }
 }
@@ -56,6 +59,7 @@ impl From<Org> for OrgDto {
             name: org.name,
             admin: org.admin,
             created_at: org.created_at,
+            version: org.version,
         }
     }
 }
@@ -70,11 +74,13 @@ pub trait OrgRepoable: Send + Sync {
 
     async fn get(&self, id: &str) -> Result<Option<OrgDto>>;
 
-    async fn update(&self, id: &str, data: &UpdateOrg) -> Result<bool>;
+    /// Updates only when the org is still at the given version
+    async fn update(&self, id: &str, version: i32, data: &UpdateOrg) -> Result<bool>;
 
     async fn count(&self) -> Result<i64>;
 
-    async fn delete(&self, id: &str) -> Result<()>;
+    /// Deletes only when the org is still at the given version
+    async fn delete(&self, id: &str, version: i32) -> Result<bool>;
 }
 
 pub struct OrgRepo {
@@ -139,54 +145,22 @@ impl OrgRepoable for OrgRepo {
     async fn create(&self, data: &NewOrg, admin: bool) -> Result<Org> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
-        let today = chrono::Utc::now().timestamp();
-        let org = Org {
-            id: generate_id(),
-            name: data.name.clone(),
-            admin,
-            created_at: today,
-        };
-
-        let org_copy = org.clone();
-        let insert_res = db
-            .interact(move |conn| {
-                diesel::insert_into(orgs::table)
-                    .values(&org_copy)
-                    .execute(conn)
-            })
+        let data_copy = data.clone();
+        db.interact(move |conn| SqliteTx { conn }.create(&data_copy, admin))
             .await
-            .context(DbInteractSnafu)?;
-
-        let _ = insert_res.context(DbQuerySnafu {
-            table: "orgs".to_string(),
-        })?;
-
-        Ok(org)
+            .context(DbInteractSnafu)?
     }
 
     async fn get(&self, id: &str) -> Result<Option<OrgDto>> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
         let cid = id.to_string();
-        let select_res = db
-            .interact(move |conn| {
-                dsl::orgs
-                    .find(cid)
-                    .select(Org::as_select())
-                    .first::<Org>(conn)
-                    .optional()
-            })
+        db.interact(move |conn| SqliteTx { conn }.get(&cid))
             .await
-            .context(DbInteractSnafu)?;
-
-        let item = select_res.context(DbQuerySnafu {
-            table: "orgs".to_string(),
-        })?;
-
-        Ok(item.map(|item| item.into()))
+            .context(DbInteractSnafu)?
     }
 
-    async fn update(&self, id: &str, data: &UpdateOrg) -> Result<bool> {
+    async fn update(&self, id: &str, version: i32, data: &UpdateOrg) -> Result<bool> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
         let id = id.to_string();
@@ -195,7 +169,8 @@ impl OrgRepoable for OrgRepo {
             .interact(move |conn| {
                 diesel::update(dsl::orgs)
                     .filter(dsl::id.eq(id.as_str()))
-                    .set(data_copy)
+                    .filter(dsl::version.eq(version))
+                    .set((data_copy, dsl::version.eq(version + 1)))
                     .execute(conn)
             })
             .await
@@ -211,34 +186,87 @@ impl OrgRepoable for OrgRepo {
     async fn count(&self) -> Result<i64> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
-        let count_res = db
-            .interact(move |conn| dsl::orgs.select(count_star()).get_result::<i64>(conn))
+        db.interact(move |conn| SqliteTx { conn }.count())
             .await
-            .context(DbInteractSnafu)?;
-
-        let count = count_res.context(DbQuerySnafu {
-            table: "orgs".to_string(),
-        })?;
-
-        Ok(count)
+            .context(DbInteractSnafu)?
     }
 
-    async fn delete(&self, id: &str) -> Result<()> {
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
         let id = id.to_string();
-        let delete_res = db
-            .interact(move |conn| {
-                diesel::delete(dsl::orgs.filter(dsl::id.eq(id.as_str()))).execute(conn)
-            })
+        db.interact(move |conn| SqliteTx { conn }.delete(&id, version))
             .await
-            .context(DbInteractSnafu)?;
+            .context(DbInteractSnafu)?
+    }
+}
+
+/// Org operations available inside a transaction
+pub trait OrgTx {
+    fn create(&mut self, data: &NewOrg, admin: bool) -> Result<Org>;
+
+    fn get(&mut self, id: &str) -> Result<Option<OrgDto>>;
+
+    fn count(&mut self) -> Result<i64>;
+
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool>;
+}
+
+impl OrgTx for SqliteTx<'_> {
+    fn create(&mut self, data: &NewOrg, admin: bool) -> Result<Org> {
+        let today = chrono::Utc::now().timestamp();
+        let org = Org {
+            id: generate_id(),
+            name: data.name.clone(),
+            admin,
+            created_at: today,
+            version: 1,
+        };
+
+        diesel::insert_into(orgs::table)
+            .values(&org)
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "orgs".to_string(),
+            })?;
+
+        Ok(org)
+    }
+
+    fn get(&mut self, id: &str) -> Result<Option<OrgDto>> {
+        let item = dsl::orgs
+            .find(id)
+            .select(Org::as_select())
+            .first::<Org>(self.conn)
+            .optional()
+            .context(DbQuerySnafu {
+                table: "orgs".to_string(),
+            })?;
 
-        let _ = delete_res.context(DbQuerySnafu {
+        Ok(item.map(|item| item.into()))
+    }
+
+    fn count(&mut self) -> Result<i64> {
+        dsl::orgs
+            .select(count_star())
+            .get_result::<i64>(self.conn)
+            .context(DbQuerySnafu {
+                table: "orgs".to_string(),
+            })
+    }
+
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool> {
+        let affected = diesel::delete(
+            dsl::orgs
+                .filter(dsl::id.eq(id))
+                .filter(dsl::version.eq(version)),
+        )
+        .execute(self.conn)
+        .context(DbQuerySnafu {
             table: "orgs".to_string(),
         })?;
 
-        Ok(())
+        Ok(affected > 0)
     }
 }
 
@@ -262,6 +290,7 @@ pub fn create_test_org() -> Org {
         name: "Test Org".to_string(),
         admin: false,
         created_at: today,
+        version: 1,
     }
 }
 
@@ -273,6 +302,7 @@ pub fn create_test_admin_org() -> Org {
         name: "Test Admin Org".to_string(),
         admin: true,
         created_at: today,
+        version: 1,
     }
 }
 
@@ -284,6 +314,7 @@ pub fn create_test_new_org() -> Org {
         name: "Test New Org".to_string(),
         admin: false,
         created_at: today,
+        version: 1,
     }
 }
 
@@ -318,15 +349,39 @@ impl OrgRepoable for OrgTestRepo {
         Ok(found.map(|x| x.into()))
     }
 
-    async fn update(&self, _id: &str, _data: &UpdateOrg) -> Result<bool> {
-        Ok(true)
+    async fn update(&self, id: &str, version: i32, _data: &UpdateOrg) -> Result<bool> {
+        let org = self.get(id).await?;
+        Ok(org.is_some_and(|x| x.version == version))
     }
 
     async fn count(&self) -> Result<i64> {
         Ok(2)
     }
 
-    async fn delete(&self, _id: &str) -> Result<()> {
-        Ok(())
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let org = self.get(id).await?;
+        Ok(org.is_some_and(|x| x.version == version))
+    }
+}
+
+#[cfg(feature = "test")]
+impl OrgTx for crate::tx::TestTx {
+    fn create(&mut self, _data: &NewOrg, _admin: bool) -> Result<Org> {
+        Ok(create_test_new_org())
+    }
+
+    fn get(&mut self, id: &str) -> Result<Option<OrgDto>> {
+        let orgs = vec![create_test_org(), create_test_admin_org()];
+        let found = orgs.into_iter().find(|x| x.id.as_str() == id);
+        Ok(found.map(|x| x.into()))
+    }
+
+    fn count(&mut self) -> Result<i64> {
+        Ok(2)
+    }
+
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool> {
+        let org = OrgTx::get(self, id)?;
+        Ok(org.is_some_and(|x| x.version == version))
     }
 }
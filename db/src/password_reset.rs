@@ -0,0 +1,296 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::prelude::*;
+use serde::Deserialize;
+use snafu::ResultExt;
+use validator::Validate;
+
+use crate::Result;
+use crate::api_key::hash_secret;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
+use crate::schema::password_resets::{self, dsl};
+use crate::tx::SqliteTx;
+use dto::password_reset::{NewPasswordResetDto, PasswordResetDto};
+use vault::utils::generate_id;
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::password_resets)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct PasswordReset {
+    id: String,
+    org_id: String,
+    user_id: String,
+    token_hash: String,
+    created_by: Option<String>,
+    created_at: i64,
+    expires_at: i64,
+}
+
+impl From<PasswordReset> for PasswordResetDto {
+    fn from(reset: PasswordReset) -> Self {
+        PasswordResetDto {
+            id: reset.id,
+            org_id: reset.org_id,
+            user_id: reset.user_id,
+            created_by: reset.created_by,
+            created_at: reset.created_at,
+            expires_at: reset.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewPasswordReset {
+    pub created_by: Option<String>,
+
+    /// The link can no longer be used after this time
+    pub expires_at: i64,
+}
+
+/// New password chosen by the user through a reset link
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct RedeemPasswordReset {
+    #[validate(length(min = 1, max = 200))]
+    pub token: String,
+
+    #[validate(length(min = 8, max = 60))]
+    pub password: String,
+}
+
+#[async_trait]
+pub trait PasswordResetRepoable: Send + Sync {
+    /// Issues a new link for the user, replacing any pending one
+    ///
+    /// The token is only available from the result.
+    async fn create(
+        &self,
+        org_id: &str,
+        user_id: &str,
+        data: &NewPasswordReset,
+    ) -> Result<NewPasswordResetDto>;
+
+    /// Uses up the link of the token, none when unknown, used or expired
+    async fn redeem(&self, token: &str) -> Result<Option<PasswordResetDto>>;
+}
+
+pub struct PasswordResetRepo {
+    db_pool: Pool,
+}
+
+impl PasswordResetRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepoable for PasswordResetRepo {
+    async fn create(
+        &self,
+        org_id: &str,
+        user_id: &str,
+        data: &NewPasswordReset,
+    ) -> Result<NewPasswordResetDto> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let secret = cipher::random_hex(32);
+        let reset = PasswordReset {
+            id: generate_id(),
+            org_id: org_id.to_string(),
+            user_id: user_id.to_string(),
+            token_hash: hash_secret(&secret),
+            created_by: data.created_by.clone(),
+            created_at: chrono::Utc::now().timestamp(),
+            expires_at: data.expires_at,
+        };
+
+        let reset_copy = reset.clone();
+        let insert_res = db
+            .interact(move |conn| {
+                conn.transaction(|conn| {
+                    // Older links of the user stop working
+                    diesel::delete(dsl::password_resets)
+                        .filter(dsl::user_id.eq(reset_copy.user_id.as_str()))
+                        .execute(conn)?;
+
+                    diesel::insert_into(password_resets::table)
+                        .values(&reset_copy)
+                        .execute(conn)
+                })
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = insert_res.context(DbQuerySnafu {
+            table: "password_resets".to_string(),
+        })?;
+
+        let token = format!("{}.{}", reset.id, secret);
+        Ok(NewPasswordResetDto {
+            reset: reset.into(),
+            token,
+        })
+    }
+
+    async fn redeem(&self, token: &str) -> Result<Option<PasswordResetDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let token = token.to_string();
+        db.interact(move |conn| SqliteTx { conn }.redeem(&token))
+            .await
+            .context(DbInteractSnafu)?
+    }
+}
+
+/// Password reset operations available inside a transaction
+pub trait PasswordResetTx {
+    /// Uses up the link, none when it is unknown, expired or already used
+    fn redeem(&mut self, token: &str) -> Result<Option<PasswordResetDto>>;
+}
+
+impl PasswordResetTx for SqliteTx<'_> {
+    fn redeem(&mut self, token: &str) -> Result<Option<PasswordResetDto>> {
+        let Some((id, secret)) = token.split_once('.') else {
+            return Ok(None);
+        };
+        if !vault::utils::valid_id(id) {
+            return Ok(None);
+        }
+
+        let hash = hash_secret(secret);
+        let today = chrono::Utc::now().timestamp();
+        let item = self
+            .conn
+            .transaction(|conn| {
+                let found = dsl::password_resets
+                    .find(id)
+                    .filter(dsl::token_hash.eq(hash.as_str()))
+                    .filter(dsl::expires_at.gt(today))
+                    .select(PasswordReset::as_select())
+                    .first::<PasswordReset>(conn)
+                    .optional()?;
+
+                // Only one of concurrent uses of the same token wins
+                let deleted = diesel::delete(dsl::password_resets.find(id))
+                    .filter(dsl::token_hash.eq(hash.as_str()))
+                    .execute(conn)?;
+
+                Ok::<_, diesel::result::Error>(found.filter(|_| deleted > 0))
+            })
+            .context(DbQuerySnafu {
+                table: "password_resets".to_string(),
+            })?;
+
+        Ok(item.map(|x| x.into()))
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct PasswordResetTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl PasswordResetRepoable for PasswordResetTestRepo {
+    async fn create(
+        &self,
+        org_id: &str,
+        user_id: &str,
+        data: &NewPasswordReset,
+    ) -> Result<NewPasswordResetDto> {
+        let reset = PasswordResetDto {
+            id: generate_id(),
+            org_id: org_id.to_string(),
+            user_id: user_id.to_string(),
+            created_by: data.created_by.clone(),
+            created_at: chrono::Utc::now().timestamp(),
+            expires_at: data.expires_at,
+        };
+        let token = format!("{}.{}", reset.id, cipher::random_hex(32));
+        Ok(NewPasswordResetDto { reset, token })
+    }
+
+    async fn redeem(&self, _token: &str) -> Result<Option<PasswordResetDto>> {
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "test")]
+impl PasswordResetTx for crate::tx::TestTx {
+    fn redeem(&mut self, _token: &str) -> Result<Option<PasswordResetDto>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
+
+    #[tokio::test]
+    async fn test_redeem_once() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user = &seed.users[0];
+
+        let data = NewPasswordReset {
+            created_by: None,
+            expires_at: chrono::Utc::now().timestamp() + 3600,
+        };
+        let first = db
+            .password_resets
+            .create(&user.org_id, &user.id, &data)
+            .await
+            .unwrap();
+        let second = db
+            .password_resets
+            .create(&user.org_id, &user.id, &data)
+            .await
+            .unwrap();
+
+        // A newer link replaces the older one
+        assert!(
+            db.password_resets
+                .redeem(&first.token)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let redeemed = db.password_resets.redeem(&second.token).await.unwrap();
+        assert_eq!(redeemed.unwrap().user_id, user.id);
+        assert!(
+            db.password_resets
+                .redeem(&second.token)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Expired links are rejected
+        let data = NewPasswordReset {
+            created_by: None,
+            expires_at: chrono::Utc::now().timestamp() - 1,
+        };
+        let expired = db
+            .password_resets
+            .create(&user.org_id, &user.id, &data)
+            .await
+            .unwrap();
+        assert!(
+            db.password_resets
+                .redeem(&expired.token)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            db.password_resets
+                .redeem("not-a-token")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use serde::Deserialize;
+use snafu::{ResultExt, ensure};
+use validator::Validate;
+
+use crate::Result;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu, ValidationSnafu};
+use crate::schema::org_quotas::{self, dsl};
+use crate::schema::{entries, users, vaults};
+use dto::quota::OrgQuotaDto;
+use vault::validators::flatten_errors;
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::org_quotas)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(treat_none_as_null = true)]
+pub struct OrgQuota {
+    pub org_id: String,
+    pub max_vaults: Option<i64>,
+    pub max_users: Option<i64>,
+    pub max_entries_per_vault: Option<i64>,
+    pub updated_at: i64,
+}
+
+/// Replaces all overrides, missing fields reset to the instance default
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+pub struct UpdateOrgQuota {
+    #[validate(range(min = 1, max = 1000000))]
+    pub max_vaults: Option<i64>,
+
+    #[validate(range(min = 1, max = 1000000))]
+    pub max_users: Option<i64>,
+
+    #[validate(range(min = 1, max = 1000000))]
+    pub max_entries_per_vault: Option<i64>,
+}
+
+/// Current counts of everything limited by the quotas
+#[derive(Debug, Clone, Default)]
+pub struct QuotaUsage {
+    pub vaults: i64,
+    pub users: i64,
+    pub entries: i64,
+    pub max_vault_entries: i64,
+}
+
+impl From<OrgQuota> for OrgQuotaDto {
+    fn from(quota: OrgQuota) -> Self {
+        OrgQuotaDto {
+            org_id: quota.org_id,
+            max_vaults: quota.max_vaults,
+            max_users: quota.max_users,
+            max_entries_per_vault: quota.max_entries_per_vault,
+            updated_at: quota.updated_at,
+        }
+    }
+}
+
+#[async_trait]
+pub trait QuotaRepoable: Send + Sync {
+    async fn get(&self, org_id: &str) -> Result<Option<OrgQuotaDto>>;
+
+    async fn set(&self, org_id: &str, data: &UpdateOrgQuota) -> Result<OrgQuotaDto>;
+
+    async fn usage(&self, org_id: &str) -> Result<QuotaUsage>;
+}
+
+pub struct QuotaRepo {
+    db_pool: Pool,
+}
+
+impl QuotaRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl QuotaRepoable for QuotaRepo {
+    async fn get(&self, org_id: &str) -> Result<Option<OrgQuotaDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let oid = org_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::org_quotas
+                    .find(oid)
+                    .select(OrgQuota::as_select())
+                    .first::<OrgQuota>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let item = select_res.context(DbQuerySnafu {
+            table: "org_quotas".to_string(),
+        })?;
+
+        Ok(item.map(|x| x.into()))
+    }
+
+    async fn set(&self, org_id: &str, data: &UpdateOrgQuota) -> Result<OrgQuotaDto> {
+        let errors = data.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let quota = OrgQuota {
+            org_id: org_id.to_string(),
+            max_vaults: data.max_vaults,
+            max_users: data.max_users,
+            max_entries_per_vault: data.max_entries_per_vault,
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+
+        let quota_copy = quota.clone();
+        let upsert_res = db
+            .interact(move |conn| {
+                diesel::insert_into(org_quotas::table)
+                    .values(&quota_copy)
+                    .on_conflict(dsl::org_id)
+                    .do_update()
+                    .set(&quota_copy)
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = upsert_res.context(DbQuerySnafu {
+            table: "org_quotas".to_string(),
+        })?;
+
+        Ok(quota.into())
+    }
+
+    async fn usage(&self, org_id: &str) -> Result<QuotaUsage> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let oid = org_id.to_string();
+        let counts_res = db
+            .interact(move |conn| {
+                let vault_count = vaults::table
+                    .filter(vaults::org_id.eq(&oid))
+                    .select(count_star())
+                    .get_result::<i64>(conn)?;
+
+                let user_count = users::table
+                    .filter(users::org_id.eq(&oid))
+                    .select(count_star())
+                    .get_result::<i64>(conn)?;
+
+                let entry_counts = entries::table
+                    .inner_join(vaults::table)
+                    .filter(vaults::org_id.eq(&oid))
+                    .group_by(entries::vault_id)
+                    .select(count_star())
+                    .load::<i64>(conn)?;
+
+                Ok::<_, diesel::result::Error>((vault_count, user_count, entry_counts))
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let (vaults, users, entry_counts) = counts_res.context(DbQuerySnafu {
+            table: "org_quotas".to_string(),
+        })?;
+
+        Ok(QuotaUsage {
+            vaults,
+            users,
+            entries: entry_counts.iter().sum(),
+            max_vault_entries: entry_counts.into_iter().max().unwrap_or(0),
+        })
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct QuotaTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl QuotaRepoable for QuotaTestRepo {
+    async fn get(&self, _org_id: &str) -> Result<Option<OrgQuotaDto>> {
+        Ok(None)
+    }
+
+    async fn set(&self, org_id: &str, data: &UpdateOrgQuota) -> Result<OrgQuotaDto> {
+        Ok(OrgQuotaDto {
+            org_id: org_id.to_string(),
+            max_vaults: data.max_vaults,
+            max_users: data.max_users,
+            max_entries_per_vault: data.max_entries_per_vault,
+            updated_at: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    async fn usage(&self, _org_id: &str) -> Result<QuotaUsage> {
+        Ok(QuotaUsage::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
+
+    #[tokio::test]
+    async fn test_set_quota_and_usage() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        assert!(db.quotas.get(&seed.org.id).await.unwrap().is_none());
+
+        let data = UpdateOrgQuota {
+            max_vaults: Some(5),
+            max_users: None,
+            max_entries_per_vault: Some(200),
+        };
+        let _ = db.quotas.set(&seed.org.id, &data).await.unwrap();
+
+        // Setting again replaces all overrides
+        let data = UpdateOrgQuota {
+            max_vaults: Some(3),
+            ..Default::default()
+        };
+        let _ = db.quotas.set(&seed.org.id, &data).await.unwrap();
+
+        let quota = db.quotas.get(&seed.org.id).await.unwrap().unwrap();
+        assert_eq!(quota.max_vaults, Some(3));
+        assert_eq!(quota.max_entries_per_vault, None);
+
+        let data = UpdateOrgQuota {
+            max_users: Some(0),
+            ..Default::default()
+        };
+        assert!(db.quotas.set(&seed.org.id, &data).await.is_err());
+
+        let usage = db.quotas.usage(&seed.org.id).await.unwrap();
+        assert_eq!(usage.vaults, 2);
+        assert_eq!(usage.users, 3);
+        assert_eq!(usage.entries, 5);
+        assert_eq!(usage.max_vault_entries, 3);
+    }
+}
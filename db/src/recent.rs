@@ -0,0 +1,153 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use snafu::ResultExt;
+
+use crate::Result;
+use crate::entry::EntryLink;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
+use crate::schema::entries;
+use crate::schema::entry_views::{self, dsl};
+use crate::schema::vaults;
+use dto::entry::EntryLinkDto;
+
+/// Number of recently viewed entries returned per user
+pub const MAX_RECENT_ENTRIES: i64 = 10;
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::entry_views)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct NewView {
+    user_id: String,
+    entry_id: String,
+    viewed_at: i64,
+}
+
+#[async_trait]
+pub trait RecentRepoable: Send + Sync {
+    /// Records that the user viewed the entry just now
+    async fn record(&self, user_id: &str, entry_id: &str) -> Result<()>;
+
+    /// Latest viewed entries across all vaults of the org
+    async fn list(&self, user_id: &str, org_id: &str) -> Result<Vec<EntryLinkDto>>;
+}
+
+pub struct RecentRepo {
+    db_pool: Pool,
+}
+
+impl RecentRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl RecentRepoable for RecentRepo {
+    async fn record(&self, user_id: &str, entry_id: &str) -> Result<()> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let data = NewView {
+            user_id: user_id.to_string(),
+            entry_id: entry_id.to_string(),
+            viewed_at: chrono::Utc::now().timestamp(),
+        };
+
+        let upsert_res = db
+            .interact(move |conn| {
+                diesel::insert_into(entry_views::table)
+                    .values(&data)
+                    .on_conflict((dsl::user_id, dsl::entry_id))
+                    .do_update()
+                    .set(dsl::viewed_at.eq(excluded(dsl::viewed_at)))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = upsert_res.context(DbQuerySnafu {
+            table: "entry_views".to_string(),
+        })?;
+
+        Ok(())
+    }
+
+    async fn list(&self, user_id: &str, org_id: &str) -> Result<Vec<EntryLinkDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let uid = user_id.to_string();
+        let oid = org_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::entry_views
+                    .inner_join(entries::table.inner_join(vaults::table))
+                    .filter(dsl::user_id.eq(uid))
+                    .filter(vaults::org_id.eq(oid))
+                    .select((
+                        entries::id,
+                        entries::vault_id,
+                        vaults::name,
+                        entries::label,
+                        dsl::viewed_at,
+                    ))
+                    .order(dsl::viewed_at.desc())
+                    .limit(MAX_RECENT_ENTRIES)
+                    .load::<EntryLink>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "entry_views".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|x| x.into()).collect())
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct RecentTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl RecentRepoable for RecentTestRepo {
+    async fn record(&self, _user_id: &str, _entry_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn list(&self, _user_id: &str, _org_id: &str) -> Result<Vec<EntryLinkDto>> {
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
+
+    #[tokio::test]
+    async fn test_record_and_list_recents() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user = seed.user("editor").unwrap();
+
+        for entry in seed.entries.iter() {
+            db.recents.record(&user.id, &entry.id).await.unwrap();
+        }
+        // Viewing again only refreshes the timestamp
+        db.recents
+            .record(&user.id, &seed.entries[0].id)
+            .await
+            .unwrap();
+
+        let items = db.recents.list(&user.id, &seed.org.id).await.unwrap();
+        assert_eq!(items.len(), seed.entries.len());
+        assert!(items.windows(2).all(|x| x[0].timestamp >= x[1].timestamp));
+
+        let other = seed.user("viewer").unwrap();
+        let items = db.recents.list(&other.id, &seed.org.id).await.unwrap();
+        assert!(items.is_empty());
+    }
+}
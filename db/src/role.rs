@@ -0,0 +1,632 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::prelude::*;
+use diesel::{QueryDsl, SelectableHelper};
+use serde::Deserialize;
+use snafu::{ResultExt, ensure};
+use validator::Validate;
+
+use crate::Result;
+use crate::error::{
+    DbInteractSnafu, DbPoolSnafu, DbQuerySnafu, InvalidPermissionsSnafu, ValidationSnafu,
+};
+use crate::schema::groups;
+use crate::schema::roles::{self, dsl};
+use crate::schema::users;
+use dto::role::{Permission, Role as RoleName, RoleDto, to_permissions};
+use vault::utils::generate_id;
+use vault::validators::flatten_errors;
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::roles)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct Role {
+    id: String,
+    org_id: String,
+    name: String,
+    permissions: String,
+    created_at: i64,
+    updated_at: i64,
+    version: i32,
+}
+
+impl From<Role> for RoleDto {
+    fn from(role: Role) -> Self {
+        let list = role
+            .permissions
+            .split(",")
+            .map(|item| item.to_string())
+            .collect();
+        let permissions = to_permissions(&list).expect("Invalid permissions");
+        RoleDto {
+            id: role.id,
+            builtin: false,
+            org_id: Some(role.org_id),
+            name: role.name,
+            permissions,
+            created_at: role.created_at,
+            updated_at: role.updated_at,
+            version: role.version,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct NewRole {
+    #[validate(length(min = 1, max = 50))]
+    #[validate(custom(function = "vault::validators::anyname"))]
+    pub name: String,
+
+    /// Comma separated permissions, ex: entries.list,entries.view
+    #[validate(length(min = 1, max = 500))]
+    pub permissions: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct UpdateRole {
+    #[validate(length(min = 1, max = 50))]
+    #[validate(custom(function = "vault::validators::anyname"))]
+    pub name: Option<String>,
+
+    #[validate(length(min = 1, max = 500))]
+    pub permissions: Option<String>,
+}
+
+#[derive(Debug, Clone, AsChangeset)]
+#[diesel(table_name = crate::schema::roles)]
+struct RoleChangeset {
+    name: Option<String>,
+    permissions: Option<String>,
+    updated_at: i64,
+}
+
+/// Custom roles may grant anything but managing the orgs themselves
+pub(crate) fn validate_permissions(permissions: &str) -> Result<()> {
+    let list: Vec<String> = permissions
+        .split(",")
+        .map(|item| item.to_string())
+        .collect();
+    let permissions = to_permissions(&list).context(InvalidPermissionsSnafu)?;
+
+    let assignable = Permission::assignable();
+    let denied: Vec<String> = permissions
+        .iter()
+        .filter(|x| !assignable.contains(x))
+        .map(|x| x.to_string())
+        .collect();
+    ensure!(
+        denied.is_empty(),
+        ValidationSnafu {
+            msg: format!("Permissions not assignable: {}", denied.join(", ")),
+        }
+    );
+    Ok(())
+}
+
+/// Built-in roles are defined in code and keyed by their name
+fn find_builtin(id: &str) -> Option<RoleDto> {
+    RoleName::builtin()
+        .iter()
+        .find(|role| role.to_string() == id)
+        .map(RoleDto::builtin)
+}
+
+#[async_trait]
+pub trait RoleRepoable: Send + Sync {
+    /// Built-in roles followed by the custom roles of the org, sorted by name
+    async fn list(&self, org_id: &str) -> Result<Vec<RoleDto>>;
+
+    /// Custom roles matching the given ids, unknown ids are skipped
+    async fn list_by_ids(&self, ids: &[String]) -> Result<Vec<RoleDto>>;
+
+    async fn create(&self, org_id: &str, data: &NewRole) -> Result<RoleDto>;
+
+    async fn get(&self, id: &str) -> Result<Option<RoleDto>>;
+
+    /// Updates only custom roles still at the given version
+    async fn update(&self, id: &str, version: i32, data: &UpdateRole) -> Result<bool>;
+
+    /// Deletes only custom roles still at the given version, roles still
+    /// assigned to users or groups cannot be deleted
+    async fn delete(&self, id: &str, version: i32) -> Result<bool>;
+}
+
+pub struct RoleRepo {
+    db_pool: Pool,
+}
+
+impl RoleRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Finds the role by name among the built-in and org roles
+    async fn find_by_name(&self, org_id: &str, name: &str) -> Result<Option<RoleDto>> {
+        if let Some(role) = find_builtin(name) {
+            return Ok(Some(role));
+        }
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let org_id = org_id.to_string();
+        let name = name.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::roles
+                    .filter(dsl::org_id.eq(org_id))
+                    .filter(dsl::name.eq(name))
+                    .select(Role::as_select())
+                    .first::<Role>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let item = select_res.context(DbQuerySnafu {
+            table: "roles".to_string(),
+        })?;
+
+        Ok(item.map(|item| item.into()))
+    }
+
+    /// Whether any user or group of the org still holds the role
+    async fn is_assigned(&self, org_id: &str, id: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let org_id = org_id.to_string();
+        let pattern = format!("%{id}%");
+        let count_res = db
+            .interact(move |conn| {
+                let users_count: i64 = users::table
+                    .filter(users::org_id.eq(&org_id))
+                    .filter(users::roles.like(&pattern))
+                    .count()
+                    .get_result(conn)?;
+                let groups_count: i64 = groups::table
+                    .filter(groups::org_id.eq(&org_id))
+                    .filter(groups::roles.like(&pattern))
+                    .count()
+                    .get_result(conn)?;
+                Ok::<i64, diesel::result::Error>(users_count + groups_count)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let count = count_res.context(DbQuerySnafu {
+            table: "roles".to_string(),
+        })?;
+
+        Ok(count > 0)
+    }
+}
+
+#[async_trait]
+impl RoleRepoable for RoleRepo {
+    async fn list(&self, org_id: &str) -> Result<Vec<RoleDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let org_id = org_id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::roles
+                    .filter(dsl::org_id.eq(&org_id))
+                    .select(Role::as_select())
+                    .order(dsl::name.asc())
+                    .load::<Role>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "roles".to_string(),
+        })?;
+
+        let mut roles: Vec<RoleDto> = RoleName::builtin().iter().map(RoleDto::builtin).collect();
+        roles.extend(items.into_iter().map(|item| item.into()));
+        Ok(roles)
+    }
+
+    async fn list_by_ids(&self, ids: &[String]) -> Result<Vec<RoleDto>> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let ids = ids.to_vec();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::roles
+                    .filter(dsl::id.eq_any(ids))
+                    .select(Role::as_select())
+                    .load::<Role>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "roles".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|item| item.into()).collect())
+    }
+
+    async fn create(&self, org_id: &str, data: &NewRole) -> Result<RoleDto> {
+        let errors = data.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+        validate_permissions(&data.permissions)?;
+
+        // Name must be unique within the org, built-in names included
+        let existing = self.find_by_name(org_id, &data.name).await?;
+        ensure!(
+            existing.is_none(),
+            ValidationSnafu {
+                msg: "Role name already exists".to_string(),
+            }
+        );
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let today = chrono::Utc::now().timestamp();
+        let role = Role {
+            id: generate_id(),
+            org_id: org_id.to_string(),
+            name: data.name.clone(),
+            permissions: data.permissions.clone(),
+            created_at: today,
+            updated_at: today,
+            version: 1,
+        };
+
+        let role_copy = role.clone();
+        let insert_res = db
+            .interact(move |conn| {
+                diesel::insert_into(roles::table)
+                    .values(&role_copy)
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = insert_res.context(DbQuerySnafu {
+            table: "roles".to_string(),
+        })?;
+
+        Ok(role.into())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<RoleDto>> {
+        if let Some(role) = find_builtin(id) {
+            return Ok(Some(role));
+        }
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let rid = id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::roles
+                    .find(rid)
+                    .select(Role::as_select())
+                    .first::<Role>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let item = select_res.context(DbQuerySnafu {
+            table: "roles".to_string(),
+        })?;
+
+        Ok(item.map(|item| item.into()))
+    }
+
+    async fn update(&self, id: &str, version: i32, data: &UpdateRole) -> Result<bool> {
+        let errors = data.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+        if let Some(permissions) = &data.permissions {
+            validate_permissions(permissions)?;
+        }
+
+        // Name must stay unique within the org
+        let role = match &data.name {
+            Some(_) => self.get(id).await?,
+            None => None,
+        };
+        if let (
+            Some(name),
+            Some(RoleDto {
+                id: role_id,
+                org_id: Some(org_id),
+                ..
+            }),
+        ) = (&data.name, role)
+        {
+            let existing = self.find_by_name(&org_id, name).await?;
+            ensure!(
+                existing.is_none_or(|x| x.id == role_id),
+                ValidationSnafu {
+                    msg: "Role name already exists".to_string(),
+                }
+            );
+        }
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let id = id.to_string();
+        let changeset = RoleChangeset {
+            name: data.name.clone(),
+            permissions: data.permissions.clone(),
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+        let update_res = db
+            .interact(move |conn| {
+                diesel::update(dsl::roles)
+                    .filter(dsl::id.eq(id.as_str()))
+                    .filter(dsl::version.eq(version))
+                    .set((changeset, dsl::version.eq(version + 1)))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = update_res.context(DbQuerySnafu {
+            table: "roles".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let Some(RoleDto {
+            org_id: Some(org_id),
+            ..
+        }) = self.get(id).await?
+        else {
+            // Built-in roles are never deleted
+            return Ok(false);
+        };
+
+        ensure!(
+            !self.is_assigned(&org_id, id).await?,
+            ValidationSnafu {
+                msg: "Role is still assigned to users or groups".to_string(),
+            }
+        );
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let rid = id.to_string();
+        let delete_res = db
+            .interact(move |conn| {
+                diesel::delete(dsl::roles.filter(dsl::id.eq(rid)))
+                    .filter(dsl::version.eq(version))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = delete_res.context(DbQuerySnafu {
+            table: "roles".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+}
+
+#[cfg(feature = "test")]
+pub const TEST_ROLE_ID: &str = "0197a1c2d3e47f5a8b6c7d8e9fa0b1c2";
+
+#[cfg(feature = "test")]
+pub fn create_test_role() -> RoleDto {
+    use crate::org::TEST_ORG_ID;
+
+    RoleDto {
+        id: TEST_ROLE_ID.to_string(),
+        org_id: Some(TEST_ORG_ID.to_string()),
+        name: "Auditor".to_string(),
+        permissions: vec![Permission::AuditList, Permission::AuditView],
+        builtin: false,
+        created_at: 0,
+        updated_at: 0,
+        version: 1,
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct RoleTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl RoleRepoable for RoleTestRepo {
+    async fn list(&self, org_id: &str) -> Result<Vec<RoleDto>> {
+        let roles = vec![create_test_role()];
+        let filtered = roles
+            .into_iter()
+            .filter(|x| x.org_id.as_deref() == Some(org_id))
+            .collect();
+        Ok(filtered)
+    }
+
+    async fn list_by_ids(&self, ids: &[String]) -> Result<Vec<RoleDto>> {
+        let roles = vec![create_test_role()];
+        Ok(roles.into_iter().filter(|x| ids.contains(&x.id)).collect())
+    }
+
+    async fn create(&self, _org_id: &str, _data: &NewRole) -> Result<RoleDto> {
+        Err("Not supported".into())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<RoleDto>> {
+        let roles = vec![create_test_role()];
+        Ok(roles.into_iter().find(|x| x.id.as_str() == id))
+    }
+
+    async fn update(&self, id: &str, version: i32, _data: &UpdateRole) -> Result<bool> {
+        let role = self.get(id).await?;
+        Ok(role.is_some_and(|x| x.version == version))
+    }
+
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let role = self.get(id).await?;
+        Ok(role.is_some_and(|x| x.version == version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::group::NewGroup;
+    use crate::seed::seed_demo;
+    use dto::role::{Role as RoleName, role_permissions};
+
+    #[tokio::test]
+    async fn test_builtin_roles() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let roles = db.roles.list(&seed.org.id).await.unwrap();
+        assert_eq!(roles.len(), 3);
+
+        let ids: Vec<String> = roles.iter().map(|x| x.id.clone()).collect();
+        assert!(db.roles.list_by_ids(&ids).await.unwrap().is_empty());
+
+        // Defined in code, not stored with the custom roles
+        for role in roles {
+            assert!(role.builtin);
+            let name = RoleName::try_from(role.name.as_str()).unwrap();
+            assert_eq!(role.id, name.to_string());
+            assert_eq!(role.permissions, role_permissions(&name));
+
+            // Built-in roles are read-only
+            let data = UpdateRole {
+                name: None,
+                permissions: Some("entries.view".to_string()),
+            };
+            assert!(
+                !db.roles
+                    .update(&role.id, role.version, &data)
+                    .await
+                    .unwrap()
+            );
+            assert!(!db.roles.delete(&role.id, role.version).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_role_crud() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let data = NewRole {
+            name: "Auditor".to_string(),
+            permissions: "audit.list,audit.view".to_string(),
+        };
+        let role = db.roles.create(&seed.org.id, &data).await.unwrap();
+        assert_eq!(
+            role.permissions,
+            vec![Permission::AuditList, Permission::AuditView]
+        );
+        assert!(!role.builtin);
+
+        // Names are unique, built-in names included
+        assert!(db.roles.create(&seed.org.id, &data).await.is_err());
+        let data = NewRole {
+            name: "Admin".to_string(),
+            permissions: "audit.list".to_string(),
+        };
+        assert!(db.roles.create(&seed.org.id, &data).await.is_err());
+
+        // Unknown and org management permissions are rejected
+        let data = NewRole {
+            name: "Bad".to_string(),
+            permissions: "netflix.watch".to_string(),
+        };
+        assert!(db.roles.create(&seed.org.id, &data).await.is_err());
+        let data = NewRole {
+            name: "Bad".to_string(),
+            permissions: "orgs.create".to_string(),
+        };
+        assert!(db.roles.create(&seed.org.id, &data).await.is_err());
+
+        let data = UpdateRole {
+            name: Some("Auditors".to_string()),
+            permissions: Some("audit.list,audit.view,users.list".to_string()),
+        };
+        assert!(
+            db.roles
+                .update(&role.id, role.version, &data)
+                .await
+                .unwrap()
+        );
+        // Stale version is rejected
+        assert!(
+            !db.roles
+                .update(&role.id, role.version, &data)
+                .await
+                .unwrap()
+        );
+
+        let updated = db.roles.get(&role.id).await.unwrap().unwrap();
+        assert_eq!(updated.name, "Auditors");
+        assert_eq!(updated.permissions.len(), 3);
+        assert_eq!(updated.version, role.version + 1);
+
+        let items = db.roles.list(&seed.org.id).await.unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[3].id, role.id);
+
+        let items = db
+            .roles
+            .list_by_ids(std::slice::from_ref(&role.id))
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+
+        // Stale version is rejected
+        assert!(!db.roles.delete(&role.id, role.version).await.unwrap());
+        assert!(db.roles.delete(&role.id, updated.version).await.unwrap());
+        assert!(!db.roles.delete(&role.id, updated.version).await.unwrap());
+        assert!(db.roles.get(&role.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_assigned_role_delete() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        let data = NewRole {
+            name: "Auditor".to_string(),
+            permissions: "audit.list,audit.view".to_string(),
+        };
+        let role = db.roles.create(&seed.org.id, &data).await.unwrap();
+
+        let data = NewGroup {
+            name: "Auditors".to_string(),
+            roles: format!("Viewer,{}", role.id),
+        };
+        let group = db.groups.create(&seed.org.id, &data).await.unwrap();
+        assert_eq!(
+            group.roles,
+            vec![RoleName::Viewer, RoleName::Custom(role.id.clone())]
+        );
+
+        // Still held by the group
+        assert!(db.roles.delete(&role.id, role.version).await.is_err());
+
+        db.groups.delete(&group.id, group.version).await.unwrap();
+        assert!(db.roles.delete(&role.id, role.version).await.unwrap());
+    }
+}
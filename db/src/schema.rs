@@ -1,5 +1,52 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_keys (id) {
+        id -> Text,
+        org_id -> Text,
+        user_id -> Text,
+        name -> Text,
+        key_hash -> Text,
+        permissions -> Text,
+        vault_id -> Nullable<Text>,
+        allowed_ips -> Nullable<Text>,
+        expires_at -> Nullable<BigInt>,
+        last_used_at -> Nullable<BigInt>,
+        last_used_ip -> Nullable<Text>,
+        created_at -> BigInt,
+        revoked_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    attachments (id) {
+        id -> Text,
+        entry_id -> Text,
+        vault_id -> Text,
+        cipher_filename -> Text,
+        size -> BigInt,
+        sha256 -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    audit_events (id) {
+        id -> Text,
+        org_id -> Nullable<Text>,
+        actor_id -> Nullable<Text>,
+        action -> Text,
+        target_type -> Nullable<Text>,
+        target_id -> Nullable<Text>,
+        ip -> Nullable<Text>,
+        detail -> Nullable<Text>,
+        prev_hash -> Text,
+        hash -> Text,
+        seq -> BigInt,
+        created_at -> BigInt,
+    }
+}
+
 diesel::table! {
     entries (id) {
         id -> Text,
@@ -12,6 +59,68 @@ diesel::table! {
         status -> Text,
         created_at -> BigInt,
         updated_at -> BigInt,
+        last_used_at -> BigInt,
+        version -> Integer,
+        secret_changed_at -> BigInt,
+        rotation_days -> Nullable<Integer>,
+        expires_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    entry_favorites (user_id, entry_id) {
+        user_id -> Text,
+        entry_id -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    entry_views (user_id, entry_id) {
+        user_id -> Text,
+        entry_id -> Text,
+        viewed_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    group_users (group_id, user_id) {
+        group_id -> Text,
+        user_id -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    groups (id) {
+        id -> Text,
+        org_id -> Text,
+        name -> Text,
+        roles -> Text,
+        created_at -> BigInt,
+        updated_at -> BigInt,
+        version -> Integer,
+    }
+}
+
+diesel::table! {
+    login_attempts (scope, subject) {
+        scope -> Text,
+        subject -> Text,
+        failures -> Integer,
+        first_failed_at -> BigInt,
+        last_failed_at -> BigInt,
+        locked_until -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    org_quotas (org_id) {
+        org_id -> Text,
+        max_vaults -> Nullable<BigInt>,
+        max_users -> Nullable<BigInt>,
+        max_entries_per_vault -> Nullable<BigInt>,
+        updated_at -> BigInt,
     }
 }
 
@@ -21,6 +130,54 @@ diesel::table! {
         name -> Text,
         admin -> Bool,
         created_at -> BigInt,
+        version -> Integer,
+    }
+}
+
+diesel::table! {
+    password_resets (id) {
+        id -> Text,
+        org_id -> Text,
+        user_id -> Text,
+        token_hash -> Text,
+        created_by -> Nullable<Text>,
+        created_at -> BigInt,
+        expires_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    roles (id) {
+        id -> Text,
+        org_id -> Text,
+        name -> Text,
+        permissions -> Text,
+        created_at -> BigInt,
+        updated_at -> BigInt,
+        version -> Integer,
+    }
+}
+
+diesel::table! {
+    sessions (id) {
+        id -> Text,
+        org_id -> Text,
+        user_id -> Text,
+        refresh_hash -> Text,
+        ip -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        created_at -> BigInt,
+        last_used_at -> BigInt,
+        expires_at -> BigInt,
+        revoked_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    used_refresh_tokens (refresh_hash) {
+        refresh_hash -> Text,
+        session_id -> Text,
+        used_at -> BigInt,
     }
 }
 
@@ -34,6 +191,26 @@ diesel::table! {
         roles -> Text,
         created_at -> BigInt,
         updated_at -> BigInt,
+        version -> Integer,
+        kind -> Text,
+    }
+}
+
+diesel::table! {
+    vault_groups (vault_id, group_id) {
+        vault_id -> Text,
+        group_id -> Text,
+        role -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    vault_members (vault_id, user_id) {
+        vault_id -> Text,
+        user_id -> Text,
+        role -> Text,
+        created_at -> BigInt,
     }
 }
 
@@ -45,16 +222,59 @@ diesel::table! {
         test_cipher -> Text,
         created_at -> BigInt,
         updated_at -> BigInt,
+        version -> Integer,
+        rotation_days -> Nullable<Integer>,
+        description -> Nullable<Text>,
+        icon -> Nullable<Text>,
+        color -> Nullable<Text>,
     }
 }
 
+diesel::joinable!(api_keys -> orgs (org_id));
+diesel::joinable!(api_keys -> users (user_id));
+diesel::joinable!(api_keys -> vaults (vault_id));
+diesel::joinable!(attachments -> entries (entry_id));
+diesel::joinable!(attachments -> vaults (vault_id));
 diesel::joinable!(entries -> vaults (vault_id));
+diesel::joinable!(entry_favorites -> entries (entry_id));
+diesel::joinable!(entry_favorites -> users (user_id));
+diesel::joinable!(entry_views -> entries (entry_id));
+diesel::joinable!(entry_views -> users (user_id));
+diesel::joinable!(group_users -> groups (group_id));
+diesel::joinable!(group_users -> users (user_id));
+diesel::joinable!(groups -> orgs (org_id));
+diesel::joinable!(org_quotas -> orgs (org_id));
+diesel::joinable!(password_resets -> orgs (org_id));
+diesel::joinable!(password_resets -> users (user_id));
+diesel::joinable!(roles -> orgs (org_id));
+diesel::joinable!(sessions -> orgs (org_id));
+diesel::joinable!(sessions -> users (user_id));
+diesel::joinable!(used_refresh_tokens -> sessions (session_id));
 diesel::joinable!(users -> orgs (org_id));
+diesel::joinable!(vault_groups -> groups (group_id));
+diesel::joinable!(vault_groups -> vaults (vault_id));
+diesel::joinable!(vault_members -> users (user_id));
+diesel::joinable!(vault_members -> vaults (vault_id));
 diesel::joinable!(vaults -> orgs (org_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    api_keys,
+    attachments,
+    audit_events,
     entries,
+    entry_favorites,
+    entry_views,
+    group_users,
+    groups,
+    login_attempts,
+    org_quotas,
     orgs,
+    password_resets,
+    roles,
+    sessions,
+    used_refresh_tokens,
     users,
+    vault_groups,
+    vault_members,
     vaults,
 );
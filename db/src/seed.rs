@@ -0,0 +1,207 @@
+use snafu::ResultExt;
+
+use crate::Result;
+use crate::db::DbMapper;
+use crate::entry::Entry;
+use crate::error::CipherSnafu;
+use crate::org::{NewOrg, Org};
+use crate::user::{NewUser, User};
+use crate::vault::NewVault;
+use dto::entry::EntryDto;
+use dto::user::UserDto;
+use dto::vault::{VaultDto, VaultRole};
+use vault::utils::generate_id;
+
+/// Key used to encrypt the demo entries, never use it for real data
+pub const DEMO_KEY: &str = "ferriseal-demo-key-do-not-use-32";
+
+/// Password shared by all demo users
+pub const DEMO_PASSWORD: &str = "ferriseal-demo";
+
+/// Demo users and their roles, the first one belongs to the admin org
+const DEMO_USERS: &[(&str, &str)] = &[
+    ("admin", "SystemAdmin"),
+    ("orgadmin", "Admin"),
+    ("editor", "Editor"),
+    ("viewer", "Viewer"),
+];
+
+/// Label, username, password and notes
+type DemoEntry = (&'static str, &'static str, &'static str, &'static str);
+
+/// Vault names and their entries
+const DEMO_VAULTS: &[(&str, &[DemoEntry])] = &[
+    (
+        "Personal",
+        &[
+            (
+                "Email",
+                "demo@example.com",
+                "correct-horse-battery",
+                "Main inbox",
+            ),
+            (
+                "Bank",
+                "demo-user",
+                "s3cure-banking!",
+                "Savings and checking",
+            ),
+        ],
+    ),
+    (
+        "Team",
+        &[
+            (
+                "GitHub",
+                "ferriseal-bot",
+                "gh-demo-token",
+                "Deploy keys rotate monthly",
+            ),
+            ("Staging DB", "app", "staging-pass", "Read replica only"),
+            ("Wi-Fi", "office", "guest-network-2025", "Visitors network"),
+        ],
+    ),
+];
+
+/// Everything created by `seed_demo`
+#[derive(Debug, Clone)]
+pub struct DemoSeed {
+    pub admin_org: Org,
+    pub org: Org,
+
+    /// Same order as the demo users, the system admin comes first
+    pub users: Vec<User>,
+
+    pub vaults: Vec<VaultDto>,
+    pub entries: Vec<Entry>,
+}
+
+impl DemoSeed {
+    pub fn user(&self, username: &str) -> Option<&User> {
+        self.users.iter().find(|x| x.username == username)
+    }
+}
+
+/// Fills an empty database with a system admin, a demo org, one user per role
+/// and vaults whose entries are encrypted with `DEMO_KEY`
+pub async fn seed_demo(db: &DbMapper) -> Result<DemoSeed> {
+    let admin_org = db
+        .orgs
+        .create(
+            &NewOrg {
+                name: "system-admin".to_string(),
+            },
+            true,
+        )
+        .await?;
+
+    let org = db
+        .orgs
+        .create(
+            &NewOrg {
+                name: "Demo Org".to_string(),
+            },
+            false,
+        )
+        .await?;
+
+    let mut users: Vec<User> = Vec::with_capacity(DEMO_USERS.len());
+    for (username, roles) in DEMO_USERS {
+        let is_setup = *roles == "SystemAdmin";
+        let org_id = if is_setup { &admin_org.id } else { &org.id };
+        let new_user = NewUser {
+            username: username.to_string(),
+            password: DEMO_PASSWORD.to_string(),
+            roles: roles.to_string(),
+        };
+        users.push(db.users.create(org_id, &new_user, is_setup).await?);
+    }
+
+    let mut vaults: Vec<VaultDto> = Vec::with_capacity(DEMO_VAULTS.len());
+    let mut entries: Vec<Entry> = Vec::new();
+    for (name, items) in DEMO_VAULTS {
+        // Lets clients verify the key before decrypting entries
+        let new_vault = NewVault {
+            name: name.to_string(),
+            test_cipher: encrypt(name)?,
+            // Shared team credentials follow the 90 days rotation rule
+            rotation_days: (*name == "Team").then_some(90),
+        };
+        let vault = db.vaults.create(&org.id, &new_vault).await?;
+
+        for (label, username, password, notes) in items.iter() {
+            let today = chrono::Utc::now().timestamp();
+            let entry = EntryDto {
+                id: generate_id(),
+                vault_id: vault.id.clone(),
+                label: label.to_string(),
+                cipher_username: Some(encrypt(username)?),
+                cipher_password: Some(encrypt(password)?),
+                cipher_notes: Some(encrypt(notes)?),
+                cipher_extra_notes: None,
+                status: "active".to_string(),
+                created_at: today,
+                updated_at: today,
+                last_used_at: today,
+                version: 1,
+                secret_changed_at: today,
+                rotation_days: None,
+                expires_at: None,
+            };
+            entries.push(db.entries.create(entry).await?);
+        }
+
+        // Org users get the vault role closest to their org role
+        for user in users.iter() {
+            let dto: UserDto = user.clone().into();
+            if let Some(role) = VaultRole::from_roles(&dto.roles)
+                && user.org_id == org.id
+            {
+                db.members.save(&vault.id, &user.id, &role).await?;
+            }
+        }
+
+        vaults.push(vault);
+    }
+
+    Ok(DemoSeed {
+        admin_org,
+        org,
+        users,
+        vaults,
+        entries,
+    })
+}
+
+fn encrypt(value: &str) -> Result<String> {
+    cipher::encrypt(DEMO_KEY, value).context(CipherSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+
+    #[tokio::test]
+    async fn test_seed_demo() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+
+        assert_eq!(seed.users.len(), 4);
+        assert_eq!(seed.vaults.len(), 2);
+        assert_eq!(seed.entries.len(), 5);
+        assert_eq!(db.users.count_by_org(&seed.org.id).await.unwrap(), 3);
+
+        let admin = db.users.find_by_username("admin").await.unwrap().unwrap();
+        assert_eq!(admin.org_id, seed.admin_org.id);
+        assert_eq!(admin.roles, "SystemAdmin");
+
+        let vault = db.vaults.get(&seed.vaults[0].id).await.unwrap().unwrap();
+        let name = cipher::decrypt(DEMO_KEY, &vault.test_cipher).unwrap();
+        assert_eq!(name, vault.name);
+
+        let entry = db.entries.get(&seed.entries[0].id).await.unwrap().unwrap();
+        let password = cipher::decrypt(DEMO_KEY, entry.cipher_password.as_ref().unwrap());
+        assert_eq!(password.unwrap(), "correct-horse-battery");
+    }
+}
@@ -0,0 +1,578 @@
+use async_trait::async_trait;
+
+use deadpool_diesel::sqlite::Pool;
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use diesel::{QueryDsl, SelectableHelper};
+use snafu::ResultExt;
+
+use crate::Result;
+use crate::api_key::hash_secret;
+use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
+use crate::schema::sessions::{self, dsl};
+use crate::schema::used_refresh_tokens::{self, dsl as used_dsl};
+use crate::tx::SqliteTx;
+use dto::session::{NewSessionDto, SessionDto};
+use vault::utils::generate_id;
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::sessions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct Session {
+    id: String,
+    org_id: String,
+    user_id: String,
+    refresh_hash: String,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    created_at: i64,
+    last_used_at: i64,
+    expires_at: i64,
+    revoked_at: Option<i64>,
+}
+
+impl From<Session> for SessionDto {
+    fn from(session: Session) -> Self {
+        SessionDto {
+            id: session.id,
+            org_id: session.org_id,
+            user_id: session.user_id,
+            ip: session.ip,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+            revoked_at: session.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::used_refresh_tokens)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct UsedRefreshToken {
+    refresh_hash: String,
+    session_id: String,
+    used_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewSession {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+
+    /// When the refresh token expires unless rotated before
+    pub expires_at: i64,
+}
+
+/// Outcome of exchanging a refresh token
+#[derive(Debug, Clone)]
+pub enum SessionRefresh {
+    /// The token is replaced by the new one
+    Rotated(NewSessionDto),
+
+    /// An already rotated token was used again, the session is revoked
+    Reused(SessionDto),
+
+    /// Unknown, expired or revoked token
+    Invalid,
+}
+
+#[async_trait]
+pub trait SessionRepoable: Send + Sync {
+    /// Active sessions of the user, most recently used first
+    async fn list(&self, user_id: &str) -> Result<Vec<SessionDto>>;
+
+    /// Starts a new session, the refresh token is only available from the result
+    async fn create(&self, org_id: &str, user_id: &str, data: &NewSession)
+    -> Result<NewSessionDto>;
+
+    async fn get(&self, id: &str) -> Result<Option<SessionDto>>;
+
+    /// Exchanges the refresh token for a new one
+    ///
+    /// Presenting any token exchanged before means it leaked, so the whole
+    /// session is revoked.
+    async fn refresh(&self, refresh_token: &str, data: &NewSession) -> Result<SessionRefresh>;
+
+    /// Revoked sessions are kept for the record but can no longer be used
+    async fn revoke(&self, id: &str) -> Result<bool>;
+
+    /// Revokes the sessions of the user except the given one, returns the count
+    async fn revoke_all(&self, user_id: &str, except_id: Option<String>) -> Result<usize>;
+}
+
+pub struct SessionRepo {
+    db_pool: Pool,
+}
+
+impl SessionRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+
+    async fn find(&self, id: &str) -> Result<Option<Session>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let sid = id.to_string();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::sessions
+                    .find(sid)
+                    .select(Session::as_select())
+                    .first::<Session>(conn)
+                    .optional()
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let item = select_res.context(DbQuerySnafu {
+            table: "sessions".to_string(),
+        })?;
+
+        Ok(item)
+    }
+}
+
+#[async_trait]
+impl SessionRepoable for SessionRepo {
+    async fn list(&self, user_id: &str) -> Result<Vec<SessionDto>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let user_id = user_id.to_string();
+        let today = chrono::Utc::now().timestamp();
+        let select_res = db
+            .interact(move |conn| {
+                dsl::sessions
+                    .filter(dsl::user_id.eq(&user_id))
+                    .filter(dsl::revoked_at.is_null())
+                    .filter(dsl::expires_at.gt(today))
+                    .select(Session::as_select())
+                    .order(dsl::last_used_at.desc())
+                    .then_order_by(dsl::id.desc())
+                    .load::<Session>(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let items = select_res.context(DbQuerySnafu {
+            table: "sessions".to_string(),
+        })?;
+
+        Ok(items.into_iter().map(|item| item.into()).collect())
+    }
+
+    async fn create(
+        &self,
+        org_id: &str,
+        user_id: &str,
+        data: &NewSession,
+    ) -> Result<NewSessionDto> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let secret = cipher::random_hex(32);
+        let today = chrono::Utc::now().timestamp();
+        let session = Session {
+            id: generate_id(),
+            org_id: org_id.to_string(),
+            user_id: user_id.to_string(),
+            refresh_hash: hash_secret(&secret),
+            ip: data.ip.clone(),
+            user_agent: data.user_agent.clone(),
+            created_at: today,
+            last_used_at: today,
+            expires_at: data.expires_at,
+            revoked_at: None,
+        };
+
+        let session_copy = session.clone();
+        let insert_res = db
+            .interact(move |conn| {
+                diesel::insert_into(sessions::table)
+                    .values(&session_copy)
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let _ = insert_res.context(DbQuerySnafu {
+            table: "sessions".to_string(),
+        })?;
+
+        let refresh_token = format!("{}.{}", session.id, secret);
+        Ok(NewSessionDto {
+            session: session.into(),
+            refresh_token,
+        })
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<SessionDto>> {
+        let item = self.find(id).await?;
+        Ok(item.map(|item| item.into()))
+    }
+
+    async fn refresh(&self, refresh_token: &str, data: &NewSession) -> Result<SessionRefresh> {
+        let Some((id, secret)) = refresh_token.split_once('.') else {
+            return Ok(SessionRefresh::Invalid);
+        };
+        if !vault::utils::valid_id(id) {
+            return Ok(SessionRefresh::Invalid);
+        }
+
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let sid = id.to_string();
+        let hash = hash_secret(secret);
+        let new_secret = cipher::random_hex(32);
+        let data = data.clone();
+        let today = chrono::Utc::now().timestamp();
+        let refresh_res = db
+            .interact(move |conn| {
+                // Concurrent refreshes with the same token see each other's rotation
+                conn.immediate_transaction(|conn| {
+                    let session = dsl::sessions
+                        .find(&sid)
+                        .select(Session::as_select())
+                        .first::<Session>(conn)
+                        .optional()?;
+                    let Some(session) = session else {
+                        return Ok(SessionRefresh::Invalid);
+                    };
+                    if session.revoked_at.is_some() || session.expires_at <= today {
+                        return Ok(SessionRefresh::Invalid);
+                    }
+
+                    if session.refresh_hash != hash {
+                        // Any token exchanged before, however old, means a copy leaked
+                        let used = used_dsl::used_refresh_tokens
+                            .filter(used_dsl::refresh_hash.eq(&hash))
+                            .filter(used_dsl::session_id.eq(&sid))
+                            .select(count_star())
+                            .get_result::<i64>(conn)?;
+                        if used == 0 {
+                            return Ok(SessionRefresh::Invalid);
+                        }
+
+                        diesel::update(dsl::sessions.find(&sid))
+                            .set(dsl::revoked_at.eq(today))
+                            .execute(conn)?;
+                        let mut session: SessionDto = session.into();
+                        session.revoked_at = Some(today);
+                        return Ok(SessionRefresh::Reused(session));
+                    }
+
+                    let used = UsedRefreshToken {
+                        refresh_hash: hash,
+                        session_id: sid.clone(),
+                        used_at: today,
+                    };
+                    diesel::insert_into(used_refresh_tokens::table)
+                        .values(&used)
+                        .execute(conn)?;
+
+                    let session = Session {
+                        refresh_hash: hash_secret(&new_secret),
+                        ip: data.ip,
+                        user_agent: data.user_agent,
+                        last_used_at: today,
+                        expires_at: data.expires_at,
+                        ..session
+                    };
+                    diesel::update(dsl::sessions.find(&sid))
+                        .set((
+                            dsl::refresh_hash.eq(&session.refresh_hash),
+                            dsl::ip.eq(&session.ip),
+                            dsl::user_agent.eq(&session.user_agent),
+                            dsl::last_used_at.eq(today),
+                            dsl::expires_at.eq(session.expires_at),
+                        ))
+                        .execute(conn)?;
+
+                    let refresh_token = format!("{}.{}", session.id, new_secret);
+                    Ok::<SessionRefresh, diesel::result::Error>(SessionRefresh::Rotated(
+                        NewSessionDto {
+                            session: session.into(),
+                            refresh_token,
+                        },
+                    ))
+                })
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        refresh_res.context(DbQuerySnafu {
+            table: "sessions".to_string(),
+        })
+    }
+
+    async fn revoke(&self, id: &str) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let sid = id.to_string();
+        let today = chrono::Utc::now().timestamp();
+        let update_res = db
+            .interact(move |conn| {
+                diesel::update(dsl::sessions)
+                    .filter(dsl::id.eq(sid.as_str()))
+                    .filter(dsl::revoked_at.is_null())
+                    .set(dsl::revoked_at.eq(today))
+                    .execute(conn)
+            })
+            .await
+            .context(DbInteractSnafu)?;
+
+        let affected = update_res.context(DbQuerySnafu {
+            table: "sessions".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+
+    async fn revoke_all(&self, user_id: &str, except_id: Option<String>) -> Result<usize> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let user_id = user_id.to_string();
+        db.interact(move |conn| SqliteTx { conn }.revoke_all(&user_id, except_id))
+            .await
+            .context(DbInteractSnafu)?
+    }
+}
+
+/// Session operations available inside a transaction
+pub trait SessionTx {
+    fn revoke_all(&mut self, user_id: &str, except_id: Option<String>) -> Result<usize>;
+}
+
+impl SessionTx for SqliteTx<'_> {
+    fn revoke_all(&mut self, user_id: &str, except_id: Option<String>) -> Result<usize> {
+        let except_id = except_id.unwrap_or_default();
+        let today = chrono::Utc::now().timestamp();
+        diesel::update(dsl::sessions)
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::id.ne(except_id.as_str()))
+            .filter(dsl::revoked_at.is_null())
+            .set(dsl::revoked_at.eq(today))
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "sessions".to_string(),
+            })
+    }
+}
+
+#[cfg(feature = "test")]
+pub const TEST_ADMIN_SESSION_ID: &str = "0197a6c2e5fd7a3f9b1d4e8c2a6f0b13";
+
+#[cfg(feature = "test")]
+pub const TEST_SESSION_ID: &str = "0197a6c31f5e7b80a4c6d2e9f1b3a572";
+
+#[cfg(feature = "test")]
+fn create_test_session(id: &str, org_id: &str, user_id: &str) -> SessionDto {
+    let today = chrono::Utc::now().timestamp();
+    SessionDto {
+        id: id.to_string(),
+        org_id: org_id.to_string(),
+        user_id: user_id.to_string(),
+        ip: None,
+        user_agent: None,
+        created_at: today,
+        last_used_at: today,
+        expires_at: today + 86400,
+        revoked_at: None,
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct SessionTestRepo {}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl SessionRepoable for SessionTestRepo {
+    async fn list(&self, _user_id: &str) -> Result<Vec<SessionDto>> {
+        Ok(vec![])
+    }
+
+    async fn create(
+        &self,
+        org_id: &str,
+        user_id: &str,
+        _data: &NewSession,
+    ) -> Result<NewSessionDto> {
+        let session = create_test_session(&generate_id(), org_id, user_id);
+        let refresh_token = format!("{}.{}", session.id, cipher::random_hex(32));
+        Ok(NewSessionDto {
+            session,
+            refresh_token,
+        })
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<SessionDto>> {
+        use crate::org::{TEST_ADMIN_ORG_ID, TEST_ORG_ID};
+        use crate::user::{TEST_ADMIN_USER_ID, TEST_USER_ID};
+
+        let sessions = vec![
+            create_test_session(TEST_ADMIN_SESSION_ID, TEST_ADMIN_ORG_ID, TEST_ADMIN_USER_ID),
+            create_test_session(TEST_SESSION_ID, TEST_ORG_ID, TEST_USER_ID),
+        ];
+        Ok(sessions.into_iter().find(|x| x.id.as_str() == id))
+    }
+
+    async fn refresh(&self, _refresh_token: &str, _data: &NewSession) -> Result<SessionRefresh> {
+        Ok(SessionRefresh::Invalid)
+    }
+
+    async fn revoke(&self, _id: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn revoke_all(&self, _user_id: &str, _except_id: Option<String>) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+#[cfg(feature = "test")]
+impl SessionTx for crate::tx::TestTx {
+    fn revoke_all(&mut self, _user_id: &str, _except_id: Option<String>) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
+
+    fn new_session() -> NewSession {
+        NewSession {
+            ip: Some("10.1.2.3".to_string()),
+            user_agent: Some("Mozilla/5.0".to_string()),
+            expires_at: chrono::Utc::now().timestamp() + 3600,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotation() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user_id = seed.user("viewer").unwrap().id.clone();
+
+        let created = db
+            .sessions
+            .create(&seed.org.id, &user_id, &new_session())
+            .await
+            .unwrap();
+        assert!(
+            created
+                .refresh_token
+                .starts_with(&format!("{}.", created.session.id))
+        );
+
+        let rotated = match db
+            .sessions
+            .refresh(&created.refresh_token, &new_session())
+            .await
+            .unwrap()
+        {
+            SessionRefresh::Rotated(rotated) => rotated,
+            other => panic!("Expected rotation, got {:?}", other),
+        };
+        assert_eq!(rotated.session.id, created.session.id);
+        assert_ne!(rotated.refresh_token, created.refresh_token);
+
+        // Wrong secret or malformed tokens never match
+        let wrong = format!("{}.{}", created.session.id, "0".repeat(64));
+        let result = db.sessions.refresh(&wrong, &new_session()).await.unwrap();
+        assert!(matches!(result, SessionRefresh::Invalid));
+        let result = db.sessions.refresh("garbage", &new_session()).await;
+        assert!(matches!(result.unwrap(), SessionRefresh::Invalid));
+
+        // Using the rotated token again revokes the session
+        let result = db
+            .sessions
+            .refresh(&created.refresh_token, &new_session())
+            .await
+            .unwrap();
+        assert!(matches!(result, SessionRefresh::Reused(_)));
+
+        let session = db.sessions.get(&created.session.id).await.unwrap();
+        assert!(session.unwrap().revoked_at.is_some());
+        let result = db
+            .sessions
+            .refresh(&rotated.refresh_token, &new_session())
+            .await
+            .unwrap();
+        assert!(matches!(result, SessionRefresh::Invalid));
+    }
+
+    #[tokio::test]
+    async fn test_reuse_of_older_token() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user_id = seed.user("viewer").unwrap().id.clone();
+
+        let created = db
+            .sessions
+            .create(&seed.org.id, &user_id, &new_session())
+            .await
+            .unwrap();
+
+        let mut token = created.refresh_token.clone();
+        for _ in 0..3 {
+            token = match db.sessions.refresh(&token, &new_session()).await.unwrap() {
+                SessionRefresh::Rotated(rotated) => rotated.refresh_token,
+                other => panic!("Expected rotation, got {:?}", other),
+            };
+        }
+
+        // Tokens from several rotations back are caught too
+        let result = db
+            .sessions
+            .refresh(&created.refresh_token, &new_session())
+            .await
+            .unwrap();
+        assert!(matches!(result, SessionRefresh::Reused(_)));
+
+        let session = db.sessions.get(&created.session.id).await.unwrap();
+        assert!(session.unwrap().revoked_at.is_some());
+        let result = db.sessions.refresh(&token, &new_session()).await.unwrap();
+        assert!(matches!(result, SessionRefresh::Invalid));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_sessions() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let user_id = seed.user("viewer").unwrap().id.clone();
+
+        let mut ids: Vec<String> = Vec::new();
+        for _ in 0..3 {
+            let created = db
+                .sessions
+                .create(&seed.org.id, &user_id, &new_session())
+                .await
+                .unwrap();
+            ids.push(created.session.id);
+        }
+
+        let count = db
+            .sessions
+            .revoke_all(&user_id, Some(ids[0].clone()))
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let items = db.sessions.list(&user_id).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, ids[0]);
+
+        let now = chrono::Utc::now().timestamp();
+        let kept = db.sessions.get(&ids[0]).await.unwrap().unwrap();
+        assert!(kept.is_active(now));
+        let revoked = db.sessions.get(&ids[1]).await.unwrap().unwrap();
+        assert!(!revoked.is_active(now));
+
+        assert!(db.sessions.revoke(&ids[0]).await.unwrap());
+        assert!(!db.sessions.revoke(&ids[0]).await.unwrap());
+        assert_eq!(db.sessions.revoke_all(&user_id, None).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,322 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use deadpool_diesel::sqlite::Pool;
+use diesel::SqliteConnection;
+use snafu::ResultExt;
+
+use crate::attachment::AttachmentTx;
+use crate::db::DbMapper;
+use crate::entry::EntryTx;
+use crate::error::{DbInteractSnafu, DbPoolSnafu};
+use crate::login_attempt::LoginAttemptTx;
+use crate::member::MemberTx;
+use crate::org::OrgTx;
+use crate::password_reset::PasswordResetTx;
+use crate::session::SessionTx;
+use crate::user::UserTx;
+use crate::vault::VaultTx;
+use crate::{Error, Result};
+
+/// Repository operations sharing a single connection inside a transaction
+pub trait TxScope {
+    fn orgs(&mut self) -> &mut dyn OrgTx;
+
+    fn users(&mut self) -> &mut dyn UserTx;
+
+    fn vaults(&mut self) -> &mut dyn VaultTx;
+
+    fn members(&mut self) -> &mut dyn MemberTx;
+
+    fn entries(&mut self) -> &mut dyn EntryTx;
+
+    fn attachments(&mut self) -> &mut dyn AttachmentTx;
+
+    fn password_resets(&mut self) -> &mut dyn PasswordResetTx;
+
+    fn sessions(&mut self) -> &mut dyn SessionTx;
+
+    fn login_attempts(&mut self) -> &mut dyn LoginAttemptTx;
+}
+
+pub type TxWork = Box<dyn FnOnce(&mut dyn TxScope) -> Result<Box<dyn Any + Send>> + Send>;
+
+#[async_trait]
+pub trait TxRepoable: Send + Sync {
+    /// Runs the work inside `BEGIN IMMEDIATE ... COMMIT`, rolls back on error
+    async fn run(&self, work: TxWork) -> Result<Box<dyn Any + Send>>;
+}
+
+pub struct TxRepo {
+    db_pool: Pool,
+}
+
+impl TxRepo {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+pub(crate) struct SqliteTx<'a> {
+    pub(crate) conn: &'a mut SqliteConnection,
+}
+
+impl TxScope for SqliteTx<'_> {
+    fn orgs(&mut self) -> &mut dyn OrgTx {
+        self
+    }
+
+    fn users(&mut self) -> &mut dyn UserTx {
+        self
+    }
+
+    fn vaults(&mut self) -> &mut dyn VaultTx {
+        self
+    }
+
+    fn members(&mut self) -> &mut dyn MemberTx {
+        self
+    }
+
+    fn entries(&mut self) -> &mut dyn EntryTx {
+        self
+    }
+
+    fn attachments(&mut self) -> &mut dyn AttachmentTx {
+        self
+    }
+
+    fn password_resets(&mut self) -> &mut dyn PasswordResetTx {
+        self
+    }
+
+    fn sessions(&mut self) -> &mut dyn SessionTx {
+        self
+    }
+
+    fn login_attempts(&mut self) -> &mut dyn LoginAttemptTx {
+        self
+    }
+}
+
+#[async_trait]
+impl TxRepoable for TxRepo {
+    async fn run(&self, work: TxWork) -> Result<Box<dyn Any + Send>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        // Immediate transactions take the write lock upfront so that
+        // checks and writes are not interleaved with other writers
+        db.interact(move |conn| {
+            conn.immediate_transaction(|conn| {
+                let mut tx = SqliteTx { conn };
+                work(&mut tx)
+            })
+        })
+        .await
+        .context(DbInteractSnafu)?
+    }
+}
+
+impl DbMapper {
+    /// Runs several repository operations atomically on one connection
+    ///
+    /// Any error returned by the work rolls back the whole transaction.
+    pub async fn transaction<T, E, F>(&self, work: F) -> std::result::Result<T, E>
+    where
+        T: Send + 'static,
+        E: From<Error> + Send + 'static,
+        F: FnOnce(&mut dyn TxScope) -> std::result::Result<T, E> + Send + 'static,
+    {
+        // Keep the caller's error aside while the transaction rolls back
+        let aborted: Arc<Mutex<Option<E>>> = Arc::new(Mutex::new(None));
+        let slot = aborted.clone();
+
+        let res = self
+            .tx
+            .run(Box::new(move |tx| match work(tx) {
+                Ok(value) => Ok(Box::new(value) as Box<dyn Any + Send>),
+                Err(e) => {
+                    *slot.lock().expect("transaction error slot") = Some(e);
+                    Err(Error::TxRollback)
+                }
+            }))
+            .await;
+
+        match res {
+            Ok(value) => Ok(*value
+                .downcast::<T>()
+                .expect("transaction result type must match")),
+            Err(e) => match aborted.lock().expect("transaction error slot").take() {
+                Some(caller_err) => Err(caller_err),
+                None => Err(e.into()),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "test")]
+pub struct TxTestRepo {}
+
+#[cfg(feature = "test")]
+pub(crate) struct TestTx {}
+
+#[cfg(feature = "test")]
+impl TxScope for TestTx {
+    fn orgs(&mut self) -> &mut dyn OrgTx {
+        self
+    }
+
+    fn users(&mut self) -> &mut dyn UserTx {
+        self
+    }
+
+    fn vaults(&mut self) -> &mut dyn VaultTx {
+        self
+    }
+
+    fn members(&mut self) -> &mut dyn MemberTx {
+        self
+    }
+
+    fn entries(&mut self) -> &mut dyn EntryTx {
+        self
+    }
+
+    fn attachments(&mut self) -> &mut dyn AttachmentTx {
+        self
+    }
+
+    fn password_resets(&mut self) -> &mut dyn PasswordResetTx {
+        self
+    }
+
+    fn sessions(&mut self) -> &mut dyn SessionTx {
+        self
+    }
+
+    fn login_attempts(&mut self) -> &mut dyn LoginAttemptTx {
+        self
+    }
+}
+
+#[cfg(feature = "test")]
+#[async_trait]
+impl TxRepoable for TxTestRepo {
+    async fn run(&self, work: TxWork) -> Result<Box<dyn Any + Send>> {
+        let mut tx = TestTx {};
+        work(&mut tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::org::NewOrg;
+    use crate::vault::NewVault;
+    use std::time::Duration;
+
+    /// Error type of a caller, not the one of the db crate
+    #[derive(Debug, PartialEq)]
+    enum QuotaError {
+        LimitReached(i64),
+        Db(String),
+    }
+
+    impl From<Error> for QuotaError {
+        fn from(err: Error) -> Self {
+            QuotaError::Db(err.to_string())
+        }
+    }
+
+    fn new_org(name: &str) -> NewOrg {
+        NewOrg {
+            name: name.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit() {
+        let db = create_ephemeral_db_mapper().unwrap();
+
+        let (first, second) = db
+            .transaction(|tx| {
+                let first = tx.orgs().create(&new_org("First"), false)?;
+                let second = tx.orgs().create(&new_org("Second"), false)?;
+                Ok::<_, Error>((first, second))
+            })
+            .await
+            .unwrap();
+
+        assert!(db.orgs.get(&first.id).await.unwrap().is_some());
+        assert!(db.orgs.get(&second.id).await.unwrap().is_some());
+        assert_eq!(db.orgs.count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_keeps_caller_error() {
+        let db = create_ephemeral_db_mapper().unwrap();
+
+        let res: std::result::Result<(), QuotaError> = db
+            .transaction(|tx| {
+                tx.orgs().create(&new_org("Discarded"), false)?;
+                let count = tx.orgs().count()?;
+                Err(QuotaError::LimitReached(count))
+            })
+            .await;
+
+        // The write is undone and the error is the one the work returned
+        assert_eq!(res, Err(QuotaError::LimitReached(1)));
+        assert_eq!(db.orgs.count().await.unwrap(), 0);
+
+        // Errors of the db crate come through the caller's conversion
+        let res: std::result::Result<(), QuotaError> = db
+            .transaction(|tx| {
+                tx.orgs().create(&new_org("Discarded"), false)?;
+                let vault = NewVault {
+                    name: "Orphan".to_string(),
+                    test_cipher: "cipher".to_string(),
+                    rotation_days: None,
+                };
+                tx.vaults().create("missing-org", &vault)?;
+                Ok(())
+            })
+            .await;
+        assert!(matches!(res, Err(QuotaError::Db(_))));
+        assert_eq!(db.orgs.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_count_limit() {
+        let db = Arc::new(create_ephemeral_db_mapper().unwrap());
+        let limit = 1;
+
+        let create = |name: &'static str| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                db.transaction(move |tx| {
+                    let count = tx.orgs().count()?;
+                    // Give the other task time to run its check in between
+                    std::thread::sleep(Duration::from_millis(100));
+                    if count >= limit {
+                        return Err(QuotaError::LimitReached(count));
+                    }
+                    Ok(tx.orgs().create(&new_org(name), false)?)
+                })
+                .await
+            })
+        };
+
+        let (first, second) = tokio::join!(create("First"), create("Second"));
+        let results = [first.unwrap(), second.unwrap()];
+
+        assert_eq!(results.iter().filter(|x| x.is_ok()).count(), 1);
+        assert!(
+            results
+                .iter()
+                .any(|x| matches!(x, Err(QuotaError::LimitReached(1))))
+        );
+        assert_eq!(db.orgs.count().await.unwrap(), 1);
+    }
+}
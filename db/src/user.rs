@@ -13,6 +13,7 @@ use crate::error::{
     DbInteractSnafu, DbPoolSnafu, DbQuerySnafu, InvalidRolesSnafu, PasswordSnafu, ValidationSnafu,
 };
 use crate::schema::users::{self, dsl};
+use crate::tx::SqliteTx;
 use dto::role::{Role, to_roles};
 use dto::user::UserDto;
 use password::hash_password;
@@ -31,6 +32,14 @@ pub struct User {
     pub roles: String,
     pub created_at: i64,
     pub updated_at: i64,
+    pub version: i32,
+    pub kind: String,
+}
+
+impl User {
+    pub fn is_service_account(&self) -> bool {
+        self.kind == "service"
+    }
 }
 
 impl From<User> for UserDto {
@@ -42,9 +51,11 @@ impl From<User> for UserDto {
             org_id: user.org_id,
             username: user.username,
             status: user.status,
+            kind: user.kind,
             roles,
             created_at: user.created_at,
             updated_at: user.updated_at,
+            version: user.version,
         }
     }
 }
@@ -63,6 +74,18 @@ pub struct NewUser {
     pub roles: String,
 }
 
+/// Service accounts have no usable password and only authenticate with API keys
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct NewServiceAccount {
+    #[validate(length(min = 1, max = 30))]
+    #[validate(custom(function = "vault::validators::alphanumeric"))]
+    pub username: String,
+
+    #[validate(length(min = 1, max = 100))]
+    #[validate(custom(function = "vault::validators::csvname"))]
+    pub roles: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Validate)]
 pub struct UpdateUserStatus {
     #[validate(length(min = 1, max = 10))]
@@ -91,14 +114,14 @@ pub struct ChangeCurrentPassword {
     pub new_password: String,
 }
 
-const MAX_USERS_PER_ORG: i32 = 10;
-
 #[async_trait]
 pub trait UserRepoable: Send + Sync {
     async fn list(&self, org_id: &str) -> Result<Vec<User>>;
 
     async fn create(&self, org_id: &str, data: &NewUser, is_setup: bool) -> Result<User>;
 
+    async fn create_service_account(&self, org_id: &str, data: &NewServiceAccount) -> Result<User>;
+
     async fn get(&self, id: &str) -> Result<Option<User>>;
 
     async fn find_by_username(&self, username: &str) -> Result<Option<User>>;
@@ -111,7 +134,8 @@ pub trait UserRepoable: Send + Sync {
 
     async fn update_password(&self, id: &str, data: &UpdateUserPassword) -> Result<bool>;
 
-    async fn delete(&self, id: &str) -> Result<()>;
+    /// Deletes only when the user is still at the given version
+    async fn delete(&self, id: &str, version: i32) -> Result<bool>;
 }
 
 pub struct UserRepo {
@@ -122,33 +146,14 @@ impl UserRepo {
     pub fn new(db_pool: Pool) -> Self {
         Self { db_pool }
     }
-}
-
-#[async_trait]
-impl UserRepoable for UserRepo {
-    async fn list(&self, org_id: &str) -> Result<Vec<User>> {
-        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
-
-        let org_id = org_id.to_string();
-        let select_res = db
-            .interact(move |conn| {
-                dsl::users
-                    .filter(dsl::org_id.eq(&org_id))
-                    .select(User::as_select())
-                    .order(dsl::username.asc())
-                    .load::<User>(conn)
-            })
-            .await
-            .context(DbInteractSnafu)?;
-
-        let items = select_res.context(DbQuerySnafu {
-            table: "users".to_string(),
-        })?;
-
-        Ok(items)
-    }
 
-    async fn create(&self, org_id: &str, data: &NewUser, is_setup: bool) -> Result<User> {
+    async fn insert(
+        &self,
+        org_id: &str,
+        data: &NewUser,
+        is_setup: bool,
+        kind: &str,
+    ) -> Result<User> {
         let errors = data.validate();
         ensure!(
             errors.is_ok(),
@@ -158,13 +163,6 @@ impl UserRepoable for UserRepo {
         );
 
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
-        let count = self.count_by_org(org_id).await?;
-        ensure!(
-            count < MAX_USERS_PER_ORG as i64,
-            ValidationSnafu {
-                msg: format!("Orgs can only have up to {} users", MAX_USERS_PER_ORG)
-            }
-        );
 
         // Username must be unique
         let existing = self.find_by_username(&data.username).await?;
@@ -203,6 +201,8 @@ impl UserRepoable for UserRepo {
             roles: data_copy.roles,
             created_at: today,
             updated_at: today,
+            version: 1,
+            kind: kind.to_string(),
         };
 
         let user_copy = dir.clone();
@@ -221,27 +221,61 @@ impl UserRepoable for UserRepo {
 
         Ok(dir)
     }
+}
 
-    async fn get(&self, id: &str) -> Result<Option<User>> {
+#[async_trait]
+impl UserRepoable for UserRepo {
+    async fn list(&self, org_id: &str) -> Result<Vec<User>> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
-        let id = id.to_string();
+        let org_id = org_id.to_string();
         let select_res = db
             .interact(move |conn| {
                 dsl::users
-                    .find(&id)
+                    .filter(dsl::org_id.eq(&org_id))
                     .select(User::as_select())
-                    .first::<User>(conn)
-                    .optional()
+                    .order(dsl::username.asc())
+                    .load::<User>(conn)
             })
             .await
             .context(DbInteractSnafu)?;
 
-        let user = select_res.context(DbQuerySnafu {
+        let items = select_res.context(DbQuerySnafu {
             table: "users".to_string(),
         })?;
 
-        Ok(user)
+        Ok(items)
+    }
+
+    async fn create(&self, org_id: &str, data: &NewUser, is_setup: bool) -> Result<User> {
+        self.insert(org_id, data, is_setup, "user").await
+    }
+
+    async fn create_service_account(&self, org_id: &str, data: &NewServiceAccount) -> Result<User> {
+        let errors = data.validate();
+        ensure!(
+            errors.is_ok(),
+            ValidationSnafu {
+                msg: flatten_errors(&errors.unwrap_err()),
+            }
+        );
+
+        // Random password nobody knows, login is rejected for service accounts anyway
+        let data = NewUser {
+            username: data.username.clone(),
+            password: generate_id(),
+            roles: data.roles.clone(),
+        };
+        self.insert(org_id, &data, false, "service").await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<User>> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let id = id.to_string();
+        db.interact(move |conn| SqliteTx { conn }.get(&id))
+            .await
+            .context(DbInteractSnafu)?
     }
 
     async fn find_by_username(&self, username: &str) -> Result<Option<User>> {
@@ -270,21 +304,9 @@ impl UserRepoable for UserRepo {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
         let org_id = org_id.to_string();
-        let count_res = db
-            .interact(move |conn| {
-                dsl::users
-                    .filter(dsl::org_id.eq(&org_id))
-                    .select(count_star())
-                    .get_result::<i64>(conn)
-            })
+        db.interact(move |conn| SqliteTx { conn }.count_by_org(&org_id))
             .await
-            .context(DbInteractSnafu)?;
-
-        let count = count_res.context(DbQuerySnafu {
-            table: "users".to_string(),
-        })?;
-
-        Ok(count)
+            .context(DbInteractSnafu)?
     }
 
     async fn update_status(&self, id: &str, data: &UpdateUserStatus) -> Result<bool> {
@@ -312,7 +334,11 @@ impl UserRepoable for UserRepo {
             .interact(move |conn| {
                 diesel::update(dsl::users)
                     .filter(dsl::id.eq(&id))
-                    .set((dsl::status.eq(&status), dsl::updated_at.eq(today)))
+                    .set((
+                        dsl::status.eq(&status),
+                        dsl::updated_at.eq(today),
+                        dsl::version.eq(dsl::version + 1),
+                    ))
                     .execute(conn)
             })
             .await
@@ -356,7 +382,11 @@ impl UserRepoable for UserRepo {
             .interact(move |conn| {
                 diesel::update(dsl::users)
                     .filter(dsl::id.eq(&id))
-                    .set((dsl::roles.eq(&roles), dsl::updated_at.eq(today)))
+                    .set((
+                        dsl::roles.eq(&roles),
+                        dsl::updated_at.eq(today),
+                        dsl::version.eq(dsl::version + 1),
+                    ))
                     .execute(conn)
             })
             .await
@@ -370,6 +400,59 @@ impl UserRepoable for UserRepo {
     }
 
     async fn update_password(&self, id: &str, data: &UpdateUserPassword) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let id = id.to_string();
+        let data_copy = data.clone();
+        db.interact(move |conn| SqliteTx { conn }.update_password(&id, &data_copy))
+            .await
+            .context(DbInteractSnafu)?
+    }
+
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
+
+        let id = id.to_string();
+        db.interact(move |conn| SqliteTx { conn }.delete(&id, version))
+            .await
+            .context(DbInteractSnafu)?
+    }
+}
+
+/// User operations available inside a transaction
+pub trait UserTx {
+    fn get(&mut self, id: &str) -> Result<Option<User>>;
+
+    fn count_by_org(&mut self, org_id: &str) -> Result<i64>;
+
+    fn update_password(&mut self, id: &str, data: &UpdateUserPassword) -> Result<bool>;
+
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool>;
+}
+
+impl UserTx for SqliteTx<'_> {
+    fn get(&mut self, id: &str) -> Result<Option<User>> {
+        dsl::users
+            .find(id)
+            .select(User::as_select())
+            .first::<User>(self.conn)
+            .optional()
+            .context(DbQuerySnafu {
+                table: "users".to_string(),
+            })
+    }
+
+    fn count_by_org(&mut self, org_id: &str) -> Result<i64> {
+        dsl::users
+            .filter(dsl::org_id.eq(org_id))
+            .select(count_star())
+            .get_result::<i64>(self.conn)
+            .context(DbQuerySnafu {
+                table: "users".to_string(),
+            })
+    }
+
+    fn update_password(&mut self, id: &str, data: &UpdateUserPassword) -> Result<bool> {
         let errors = data.validate();
         ensure!(
             errors.is_ok(),
@@ -378,44 +461,37 @@ impl UserRepoable for UserRepo {
             }
         );
 
-        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
-
-        let id = id.to_string();
         let today = chrono::Utc::now().timestamp();
         let hashed = hash_password(&data.password).context(PasswordSnafu)?;
-        let update_res = db
-            .interact(move |conn| {
-                diesel::update(dsl::users)
-                    .filter(dsl::id.eq(&id))
-                    .set((dsl::password.eq(&hashed), dsl::updated_at.eq(today)))
-                    .execute(conn)
-            })
-            .await
-            .context(DbInteractSnafu)?;
-
-        let affected = update_res.context(DbQuerySnafu {
-            table: "users".to_string(),
-        })?;
+        let affected = diesel::update(dsl::users)
+            .filter(dsl::id.eq(id))
+            .set((
+                dsl::password.eq(&hashed),
+                dsl::updated_at.eq(today),
+                dsl::version.eq(dsl::version + 1),
+            ))
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "users".to_string(),
+            })?;
 
         Ok(affected > 0)
     }
 
-    async fn delete(&self, id: &str) -> Result<()> {
-        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
-
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool> {
         // It is okay to delete user even if there are potential references
         // to created buckets, dirs or files
-        let id = id.to_string();
-        let delete_res = db
-            .interact(move |conn| diesel::delete(dsl::users.filter(dsl::id.eq(&id))).execute(conn))
-            .await
-            .context(DbInteractSnafu)?;
-
-        let _ = delete_res.context(DbQuerySnafu {
+        let affected = diesel::delete(
+            dsl::users
+                .filter(dsl::id.eq(id))
+                .filter(dsl::version.eq(version)),
+        )
+        .execute(self.conn)
+        .context(DbQuerySnafu {
             table: "users".to_string(),
         })?;
 
-        Ok(())
+        Ok(affected > 0)
     }
 }
 
@@ -441,6 +517,8 @@ pub fn create_test_admin_user() -> Result<User> {
         roles: "SystemAdmin".to_string(),
         created_at: today.clone(),
         updated_at: today,
+        version: 1,
+        kind: "user".to_string(),
     })
 }
 
@@ -460,6 +538,8 @@ pub fn create_test_user() -> Result<User> {
         roles: "Admin".to_string(),
         created_at: today.clone(),
         updated_at: today,
+        version: 1,
+        kind: "user".to_string(),
     })
 }
 
@@ -484,6 +564,14 @@ impl UserRepoable for UserTestRepo {
         Err("Not supported".into())
     }
 
+    async fn create_service_account(
+        &self,
+        _org_id: &str,
+        _data: &NewServiceAccount,
+    ) -> Result<User> {
+        Err("Not supported".into())
+    }
+
     async fn get(&self, id: &str) -> Result<Option<User>> {
         let user1 = create_test_admin_user()?;
         let user2 = create_test_user()?;
@@ -517,7 +605,31 @@ impl UserRepoable for UserTestRepo {
         Ok(true)
     }
 
-    async fn delete(&self, _id: &str) -> Result<()> {
-        Ok(())
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let user = self.get(id).await?;
+        Ok(user.is_some_and(|x| x.version == version))
+    }
+}
+
+#[cfg(feature = "test")]
+impl UserTx for crate::tx::TestTx {
+    fn get(&mut self, id: &str) -> Result<Option<User>> {
+        let users = [create_test_admin_user()?, create_test_user()?];
+        Ok(users.into_iter().find(|x| x.id.as_str() == id))
+    }
+
+    fn count_by_org(&mut self, org_id: &str) -> Result<i64> {
+        let users = [create_test_admin_user()?, create_test_user()?];
+        let count = users.iter().filter(|x| x.org_id.as_str() == org_id).count();
+        Ok(count as i64)
+    }
+
+    fn update_password(&mut self, _id: &str, _data: &UpdateUserPassword) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool> {
+        let user = UserTx::get(self, id)?;
+        Ok(user.is_some_and(|x| x.version == version))
     }
 }
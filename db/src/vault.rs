@@ -11,6 +11,7 @@ use validator::Validate;
 use crate::Result;
 use crate::error::{DbInteractSnafu, DbPoolSnafu, DbQuerySnafu};
 use crate::schema::vaults::{self, dsl};
+use crate::tx::SqliteTx;
 use dto::vault::VaultDto;
 use vault::utils::generate_id;
 
@@ -24,6 +25,11 @@ pub struct Vault {
     pub test_cipher: String,
     pub created_at: i64,
     pub updated_at: i64,
+    pub version: i32,
+    pub rotation_days: Option<i32>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Validate)]
@@ -33,6 +39,73 @@ pub struct NewVault {
 
     #[validate(length(min = 1, max = 250))]
     pub test_cipher: String,
+
+    /// Default rotation interval for the vault entries
+    #[validate(range(min = 1, max = 3650))]
+    pub rotation_days: Option<i32>,
+}
+
+/// Vault details update, empty strings clear the optional fields
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct UpdateVault {
+    #[validate(length(min = 1, max = 50))]
+    #[validate(custom(function = "vault::validators::anyname"))]
+    pub name: Option<String>,
+
+    #[validate(length(min = 0, max = 500))]
+    pub description: Option<String>,
+
+    #[validate(length(min = 0, max = 30))]
+    #[validate(custom(function = "icon_name"))]
+    pub icon: Option<String>,
+
+    #[validate(custom(function = "color_code"))]
+    pub color: Option<String>,
+}
+
+impl UpdateVault {
+    fn changeset(&self) -> VaultChangeset {
+        let clearable = |value: &Option<String>| {
+            value.as_ref().map(|x| match x.trim() {
+                "" => None,
+                trimmed => Some(trimmed.to_string()),
+            })
+        };
+
+        VaultChangeset {
+            name: self.name.clone(),
+            description: clearable(&self.description),
+            icon: clearable(&self.icon),
+            color: clearable(&self.color),
+            updated_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, AsChangeset)]
+#[diesel(table_name = crate::schema::vaults)]
+struct VaultChangeset {
+    name: Option<String>,
+
+    /// Outer None keeps the current value, inner None clears it
+    description: Option<Option<String>>,
+    icon: Option<Option<String>>,
+    color: Option<Option<String>>,
+    updated_at: i64,
+}
+
+fn icon_name(value: &str) -> core::result::Result<(), validator::ValidationError> {
+    match value.is_empty() {
+        true => Ok(()),
+        false => vault::validators::sluggable(value),
+    }
+}
+
+fn color_code(value: &str) -> core::result::Result<(), validator::ValidationError> {
+    match value.is_empty() {
+        true => Ok(()),
+        false => vault::validators::hexcolor(value),
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Validate)]
@@ -56,6 +129,11 @@ impl From<VaultDto> for Vault {
             test_cipher: dto.test_cipher,
             created_at: dto.created_at,
             updated_at: dto.updated_at,
+            version: dto.version,
+            rotation_days: dto.rotation_days,
+            description: dto.description,
+            icon: dto.icon,
+            color: dto.color,
         }
     }
 }
@@ -69,6 +147,11 @@ impl From<Vault> for VaultDto {
             test_cipher: vault.test_cipher,
             created_at: vault.created_at,
             updated_at: vault.updated_at,
+            version: vault.version,
+            rotation_days: vault.rotation_days,
+            description: vault.description,
+            icon: vault.icon,
+            color: vault.color,
         }
     }
 }
@@ -81,9 +164,13 @@ pub trait VaultRepoable: Send + Sync {
 
     async fn get(&self, id: &str) -> Result<Option<VaultDto>>;
 
+    /// Updates only when the vault is still at the given version
+    async fn update(&self, id: &str, version: i32, data: &UpdateVault) -> Result<bool>;
+
     async fn count_by_org(&self, org_id: &str) -> Result<i64>;
 
-    async fn delete(&self, id: &str) -> Result<()>;
+    /// Deletes only when the vault is still at the given version
+    async fn delete(&self, id: &str, version: i32) -> Result<bool>;
 
     async fn test_read(&self) -> Result<()>;
 }
@@ -125,32 +212,12 @@ impl VaultRepoable for VaultRepo {
 
     async fn create(&self, org_id: &str, data: &NewVault) -> Result<VaultDto> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
-        let data_copy = data.clone();
-        let today = chrono::Utc::now().timestamp();
-        let vault = Vault {
-            id: generate_id(),
-            org_id: org_id.to_string(),
-            name: data_copy.name,
-            test_cipher: data_copy.test_cipher,
-            created_at: today.clone(),
-            updated_at: today,
-        };
 
-        let vault_copy = vault.clone();
-        let insert_res = db
-            .interact(move |conn| {
-                diesel::insert_into(vaults::table)
-                    .values(&vault_copy)
-                    .execute(conn)
-            })
+        let org_id = org_id.to_string();
+        let data_copy = data.clone();
+        db.interact(move |conn| SqliteTx { conn }.create(&org_id, &data_copy))
             .await
-            .context(DbInteractSnafu)?;
-
-        let _ = insert_res.context(DbQuerySnafu {
-            table: "vaults".to_string(),
-        })?;
-
-        Ok(vault.into())
+            .context(DbInteractSnafu)?
     }
 
     async fn get(&self, id: &str) -> Result<Option<VaultDto>> {
@@ -175,43 +242,45 @@ impl VaultRepoable for VaultRepo {
         Ok(item.map(|item| item.into()))
     }
 
-    async fn count_by_org(&self, org_id: &str) -> Result<i64> {
+    async fn update(&self, id: &str, version: i32, data: &UpdateVault) -> Result<bool> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
-        let cid = org_id.to_string();
-        let count_res = db
+        let id = id.to_string();
+        let changeset = data.changeset();
+        let update_res = db
             .interact(move |conn| {
-                dsl::vaults
-                    .filter(dsl::org_id.eq(cid.as_str()))
-                    .select(count_star())
-                    .get_result::<i64>(conn)
+                diesel::update(dsl::vaults)
+                    .filter(dsl::id.eq(id.as_str()))
+                    .filter(dsl::version.eq(version))
+                    .set((changeset, dsl::version.eq(version + 1)))
+                    .execute(conn)
             })
             .await
             .context(DbInteractSnafu)?;
 
-        let count = count_res.context(DbQuerySnafu {
+        let item = update_res.context(DbQuerySnafu {
             table: "vaults".to_string(),
         })?;
 
-        Ok(count)
+        Ok(item > 0)
     }
 
-    async fn delete(&self, id: &str) -> Result<()> {
+    async fn count_by_org(&self, org_id: &str) -> Result<i64> {
         let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
-        let vault_id = id.to_string();
-        let delete_res = db
-            .interact(move |conn| {
-                diesel::delete(dsl::vaults.filter(dsl::id.eq(vault_id.as_str()))).execute(conn)
-            })
+        let cid = org_id.to_string();
+        db.interact(move |conn| SqliteTx { conn }.count_by_org(&cid))
             .await
-            .context(DbInteractSnafu)?;
+            .context(DbInteractSnafu)?
+    }
 
-        let _ = delete_res.context(DbQuerySnafu {
-            table: "vaults".to_string(),
-        })?;
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let db = self.db_pool.get().await.context(DbPoolSnafu)?;
 
-        Ok(())
+        let vault_id = id.to_string();
+        db.interact(move |conn| SqliteTx { conn }.delete(&vault_id, version))
+            .await
+            .context(DbInteractSnafu)?
     }
 
     async fn test_read(&self) -> Result<()> {
@@ -235,6 +304,67 @@ impl VaultRepoable for VaultRepo {
     }
 }
 
+/// Vault operations available inside a transaction
+pub trait VaultTx {
+    fn create(&mut self, org_id: &str, data: &NewVault) -> Result<VaultDto>;
+
+    fn count_by_org(&mut self, org_id: &str) -> Result<i64>;
+
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool>;
+}
+
+impl VaultTx for SqliteTx<'_> {
+    fn create(&mut self, org_id: &str, data: &NewVault) -> Result<VaultDto> {
+        let today = chrono::Utc::now().timestamp();
+        let vault = Vault {
+            id: generate_id(),
+            org_id: org_id.to_string(),
+            name: data.name.clone(),
+            test_cipher: data.test_cipher.clone(),
+            created_at: today,
+            updated_at: today,
+            version: 1,
+            rotation_days: data.rotation_days,
+            description: None,
+            icon: None,
+            color: None,
+        };
+
+        diesel::insert_into(vaults::table)
+            .values(&vault)
+            .execute(self.conn)
+            .context(DbQuerySnafu {
+                table: "vaults".to_string(),
+            })?;
+
+        Ok(vault.into())
+    }
+
+    fn count_by_org(&mut self, org_id: &str) -> Result<i64> {
+        dsl::vaults
+            .filter(dsl::org_id.eq(org_id))
+            .select(count_star())
+            .get_result::<i64>(self.conn)
+            .context(DbQuerySnafu {
+                table: "vaults".to_string(),
+            })
+    }
+
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool> {
+        let affected = diesel::delete(
+            dsl::vaults
+                .filter(dsl::id.eq(id))
+                .filter(dsl::version.eq(version)),
+        )
+        .execute(self.conn)
+        .context(DbQuerySnafu {
+            table: "vaults".to_string(),
+        })?;
+
+        Ok(affected > 0)
+    }
+}
+
 #[cfg(feature = "test")]
 pub const TEST_VAULT_ID: &'static str = "0196d1bbc22f79c89cdbc8beced0d2f0";
 
@@ -250,6 +380,11 @@ pub fn create_test_vault() -> VaultDto {
         test_cipher: "test-cipher".to_string(),
         created_at: today,
         updated_at: today,
+        version: 1,
+        rotation_days: None,
+        description: None,
+        icon: None,
+        color: None,
     }
 }
 
@@ -280,13 +415,19 @@ impl VaultRepoable for VaultTestRepo {
         Ok(found)
     }
 
+    async fn update(&self, id: &str, version: i32, _data: &UpdateVault) -> Result<bool> {
+        let vault = self.get(id).await?;
+        Ok(vault.is_some_and(|x| x.version == version))
+    }
+
     async fn count_by_org(&self, org_id: &str) -> Result<i64> {
         let vaults = self.list(org_id).await?;
         Ok(vaults.len() as i64)
     }
 
-    async fn delete(&self, _id: &str) -> Result<()> {
-        Ok(())
+    async fn delete(&self, id: &str, version: i32) -> Result<bool> {
+        let vault = self.get(id).await?;
+        Ok(vault.is_some_and(|x| x.version == version))
     }
 
     async fn test_read(&self) -> Result<()> {
@@ -294,28 +435,130 @@ impl VaultRepoable for VaultTestRepo {
     }
 }
 
+#[cfg(feature = "test")]
+impl VaultTx for crate::tx::TestTx {
+    fn create(&mut self, _org_id: &str, _data: &NewVault) -> Result<VaultDto> {
+        Err("No supported".into())
+    }
+
+    fn count_by_org(&mut self, org_id: &str) -> Result<i64> {
+        let vault = create_test_vault();
+        Ok((vault.org_id.as_str() == org_id) as i64)
+    }
+
+    fn delete(&mut self, id: &str, version: i32) -> Result<bool> {
+        let vault = create_test_vault();
+        Ok(vault.id.as_str() == id && vault.version == version)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ephemeral::create_ephemeral_db_mapper;
+    use crate::seed::seed_demo;
 
     #[test]
     fn test_new_vault() {
         let data = NewVault {
             name: "hello-world".to_string(),
             test_cipher: "hello-world".to_string(),
+            rotation_days: None,
         };
         assert!(data.validate().is_ok());
 
         let data = NewVault {
             name: "hello_world".to_string(),
             test_cipher: "".to_string(),
+            rotation_days: None,
         };
         assert!(data.validate().is_err());
 
         let data = NewVault {
             name: "".to_string(),
             test_cipher: "hello-world".to_string(),
+            rotation_days: None,
+        };
+        assert!(data.validate().is_err());
+    }
+
+    #[test]
+    fn test_update_vault() {
+        let data = UpdateVault {
+            name: Some("Team Vault".to_string()),
+            description: Some("".to_string()),
+            icon: Some("user-lock".to_string()),
+            color: Some("#3273dc".to_string()),
+        };
+        assert!(data.validate().is_ok());
+
+        let data = UpdateVault {
+            name: None,
+            description: None,
+            icon: Some("fa user".to_string()),
+            color: None,
         };
         assert!(data.validate().is_err());
+
+        let data = UpdateVault {
+            name: None,
+            description: None,
+            icon: None,
+            color: Some("blue".to_string()),
+        };
+        assert!(data.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_vault_details() {
+        let db = create_ephemeral_db_mapper().unwrap();
+        let seed = seed_demo(&db).await.unwrap();
+        let vault = &seed.vaults[0];
+
+        let data = UpdateVault {
+            name: None,
+            description: Some("Shared logins".to_string()),
+            icon: Some("key".to_string()),
+            color: Some("#00d1b2".to_string()),
+        };
+        assert!(
+            db.vaults
+                .update(&vault.id, vault.version, &data)
+                .await
+                .unwrap()
+        );
+
+        // Stale version is rejected
+        assert!(
+            !db.vaults
+                .update(&vault.id, vault.version, &data)
+                .await
+                .unwrap()
+        );
+
+        let updated = db.vaults.get(&vault.id).await.unwrap().unwrap();
+        assert_eq!(updated.name, vault.name);
+        assert_eq!(updated.description, Some("Shared logins".to_string()));
+        assert_eq!(updated.icon, Some("key".to_string()));
+        assert_eq!(updated.version, vault.version + 1);
+
+        // Empty values clear the field, missing ones are kept
+        let data = UpdateVault {
+            name: None,
+            description: Some("".to_string()),
+            icon: None,
+            color: None,
+        };
+        assert!(
+            db.vaults
+                .update(&vault.id, updated.version, &data)
+                .await
+                .unwrap()
+        );
+
+        let updated = db.vaults.get(&vault.id).await.unwrap().unwrap();
+        assert_eq!(updated.description, None);
+        assert_eq!(updated.icon, Some("key".to_string()));
+        assert_eq!(updated.color, Some("#00d1b2".to_string()));
     }
 }
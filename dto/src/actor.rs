@@ -1,14 +1,19 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use crate::role::{Permission, Role, roles_permissions, to_permissions};
+use crate::api_key::ApiKeyDto;
+use crate::role::{Permission, Role, RoleDto, resolve_permissions, to_permissions};
 use crate::user::UserDto;
+use crate::vault::VaultRole;
 
 #[derive(Clone)]
 pub struct ActorPayload {
     pub id: String,
     pub org_id: String,
     pub scope: String,
+
+    /// Login session of the token, none for API keys
+    pub session_id: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -19,18 +24,22 @@ pub struct Actor {
     pub user: UserDto,
     pub roles: Vec<Role>,
     pub permissions: Vec<Permission>,
+
+    /// Login session of the access token
+    pub session_id: Option<String>,
+
+    /// Set when authenticated with an API key of a service account
+    pub api_key_id: Option<String>,
+
+    /// Only this vault can be accessed when set
+    pub vault_scope: Option<String>,
 }
 
 impl Actor {
-    pub fn new(payload: ActorPayload, user: UserDto) -> Self {
+    /// Custom roles of the user are resolved from the given definitions
+    pub fn new(payload: ActorPayload, user: UserDto, custom_roles: &[RoleDto]) -> Self {
         let roles = user.roles.clone();
-        let permissions: Vec<Permission> = roles_permissions(&roles).into_iter().collect();
-        // Convert to string to allow sorting
-        let mut permissions: Vec<String> = permissions.iter().map(|p| p.to_string()).collect();
-        permissions.sort();
-        // Convert again to Permission enum
-        let permissions: Vec<Permission> =
-            to_permissions(&permissions).expect("Invalid permissions");
+        let permissions = sorted_permissions(&roles, custom_roles);
 
         Actor {
             id: user.id.clone(),
@@ -39,7 +48,34 @@ impl Actor {
             user,
             roles,
             permissions,
+            session_id: payload.session_id,
+            api_key_id: None,
+            vault_scope: None,
+        }
+    }
+
+    /// Same actor with the roles inherited from its groups added to its own
+    pub fn with_group_roles(mut self, group_roles: &[Role], custom_roles: &[RoleDto]) -> Actor {
+        for role in group_roles {
+            if !self.roles.contains(role) {
+                self.roles.push(role.clone());
+            }
         }
+        self.permissions = sorted_permissions(&self.roles, custom_roles);
+        self
+    }
+
+    /// Same actor limited to the permissions and vault of the API key
+    pub fn with_api_key(mut self, api_key: &ApiKeyDto) -> Actor {
+        self.permissions
+            .retain(|permission| api_key.permissions.contains(permission));
+        self.api_key_id = Some(api_key.id.clone());
+        self.vault_scope = api_key.vault_id.clone();
+        self
+    }
+
+    pub fn is_api_key(&self) -> bool {
+        self.api_key_id.is_some()
     }
 
     /// Empty actor for unauthenticated requests
@@ -53,12 +89,17 @@ impl Actor {
                 org_id: "unknown".to_string(),
                 username: "unknown".to_string(),
                 status: "unknown".to_string(),
+                kind: "user".to_string(),
                 roles: vec![],
                 created_at: 0,
                 updated_at: 0,
+                version: 0,
             },
             roles: vec![],
             permissions: vec![],
+            session_id: None,
+            api_key_id: None,
+            vault_scope: None,
         }
     }
 
@@ -80,6 +121,37 @@ impl Actor {
             .all(|permission| self.permissions.contains(permission))
     }
 
+    /// Same actor limited to what the vault role allows within one vault
+    ///
+    /// Entry permissions are the ones both roles share, managers may also
+    /// manage the members of the vault.
+    pub fn for_vault(&self, role: &VaultRole) -> Actor {
+        let allowed = role.permissions();
+        let mut actor = self.clone();
+        actor.permissions.retain(|permission| {
+            let is_entry = matches!(
+                permission,
+                Permission::EntriesCreate
+                    | Permission::EntriesEdit
+                    | Permission::EntriesDelete
+                    | Permission::EntriesList
+                    | Permission::EntriesView
+                    | Permission::EntriesManage
+            );
+            !is_entry || allowed.contains(permission)
+        });
+
+        // API keys never gain permissions they were not issued with
+        if allowed.contains(&Permission::VaultsManage)
+            && !actor.permissions.contains(&Permission::VaultsManage)
+            && !actor.is_api_key()
+        {
+            actor.permissions.push(Permission::VaultsManage);
+            actor.permissions.sort_by_key(|p| p.to_string());
+        }
+        actor
+    }
+
     pub fn is_system_admin(&self) -> bool {
         self.user
             .roles
@@ -89,6 +161,15 @@ impl Actor {
     }
 }
 
+fn sorted_permissions(roles: &Vec<Role>, custom_roles: &[RoleDto]) -> Vec<Permission> {
+    let permissions: Vec<Permission> = resolve_permissions(roles, custom_roles);
+    // Convert to string to allow sorting
+    let mut permissions: Vec<String> = permissions.iter().map(|p| p.to_string()).collect();
+    permissions.sort();
+    // Convert again to Permission enum
+    to_permissions(&permissions).expect("Invalid permissions")
+}
+
 #[derive(Deserialize, Serialize, Validate)]
 pub struct Credentials {
     #[validate(length(min = 1, max = 30))]
@@ -107,6 +188,18 @@ pub struct AuthToken {
 pub struct AuthResponse {
     pub user: UserDto,
     pub token: String,
+
+    /// Single use, exchanged for a new pair of tokens
+    pub refresh_token: String,
+
+    /// Seconds until the access token expires
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, Serialize, Validate)]
+pub struct RefreshCredentials {
+    #[validate(length(min = 1, max = 200))]
+    pub refresh_token: String,
 }
 
 #[cfg(test)]
@@ -130,16 +223,20 @@ mod tests {
                 id: generate_id(),
                 org_id: org_id.clone(),
                 scope: "auth".to_string(),
+                session_id: None,
             },
             UserDto {
                 id: generate_id(),
                 org_id,
                 username: "test".to_string(),
                 status: "active".to_string(),
+                kind: "user".to_string(),
                 roles: vec![Role::Admin],
                 created_at: 0,
                 updated_at: 0,
+                version: 0,
             },
+            &[],
         );
         assert_eq!(actor.has_auth_scope(), true);
         assert_eq!(actor.is_system_admin(), false);
@@ -153,18 +250,187 @@ mod tests {
                 id: generate_id(),
                 org_id: org_id.clone(),
                 scope: "auth".to_string(),
+                session_id: None,
             },
             UserDto {
                 id: generate_id(),
                 org_id,
                 username: "test".to_string(),
                 status: "active".to_string(),
+                kind: "user".to_string(),
                 roles: vec![Role::SystemAdmin],
                 created_at: 0,
                 updated_at: 0,
+                version: 0,
             },
+            &[],
         );
         assert_eq!(actor.has_auth_scope(), true);
         assert_eq!(actor.is_system_admin(), true);
     }
+
+    #[test]
+    fn test_actor_for_vault() {
+        let org_id = generate_id();
+        let actor = Actor::new(
+            ActorPayload {
+                id: generate_id(),
+                org_id: org_id.clone(),
+                scope: "vault".to_string(),
+                session_id: None,
+            },
+            UserDto {
+                id: generate_id(),
+                org_id,
+                username: "test".to_string(),
+                status: "active".to_string(),
+                kind: "user".to_string(),
+                roles: vec![Role::Editor],
+                created_at: 0,
+                updated_at: 0,
+                version: 0,
+            },
+            &[],
+        );
+
+        let viewer = actor.for_vault(&VaultRole::Viewer);
+        assert!(viewer.has_permissions(&vec![Permission::EntriesView]));
+        assert!(!viewer.has_permissions(&vec![Permission::EntriesCreate]));
+
+        // Vault roles never grant more entry permissions than the org role
+        let manager = actor.for_vault(&VaultRole::Manager);
+        assert!(manager.has_permissions(&vec![Permission::EntriesCreate]));
+        assert!(!manager.has_permissions(&vec![Permission::EntriesDelete]));
+        assert!(manager.has_permissions(&vec![Permission::VaultsManage]));
+    }
+
+    #[test]
+    fn test_actor_with_group_roles() {
+        let org_id = generate_id();
+        let actor = Actor::new(
+            ActorPayload {
+                id: generate_id(),
+                org_id: org_id.clone(),
+                scope: "auth".to_string(),
+                session_id: None,
+            },
+            UserDto {
+                id: generate_id(),
+                org_id,
+                username: "test".to_string(),
+                status: "active".to_string(),
+                kind: "user".to_string(),
+                roles: vec![Role::Viewer],
+                created_at: 0,
+                updated_at: 0,
+                version: 0,
+            },
+            &[],
+        );
+        assert!(!actor.has_permissions(&vec![Permission::EntriesCreate]));
+
+        let actor = actor.with_group_roles(&[Role::Editor, Role::Viewer], &[]);
+        assert_eq!(actor.roles, vec![Role::Viewer, Role::Editor]);
+        assert!(actor.has_permissions(&vec![Permission::EntriesCreate]));
+        assert!(!actor.has_permissions(&vec![Permission::EntriesDelete]));
+        // Direct roles of the user are kept as they are
+        assert_eq!(actor.user.roles, vec![Role::Viewer]);
+    }
+
+    #[test]
+    fn test_actor_with_custom_roles() {
+        let org_id = generate_id();
+        let role_id = generate_id();
+        let custom = RoleDto {
+            id: role_id.clone(),
+            org_id: Some(org_id.clone()),
+            name: "Auditor".to_string(),
+            permissions: vec![Permission::AuditList, Permission::AuditView],
+            builtin: false,
+            created_at: 0,
+            updated_at: 0,
+            version: 1,
+        };
+        let actor = Actor::new(
+            ActorPayload {
+                id: generate_id(),
+                org_id: org_id.clone(),
+                scope: "auth".to_string(),
+                session_id: None,
+            },
+            UserDto {
+                id: generate_id(),
+                org_id,
+                username: "test".to_string(),
+                status: "active".to_string(),
+                kind: "user".to_string(),
+                roles: vec![Role::Custom(role_id)],
+                created_at: 0,
+                updated_at: 0,
+                version: 0,
+            },
+            &[custom],
+        );
+        assert_eq!(
+            actor.permissions,
+            vec![Permission::AuditList, Permission::AuditView]
+        );
+        assert!(!actor.has_permissions(&vec![Permission::EntriesView]));
+    }
+
+    #[test]
+    fn test_actor_with_api_key() {
+        let org_id = generate_id();
+        let vault_id = generate_id();
+        let actor = Actor::new(
+            ActorPayload {
+                id: generate_id(),
+                org_id: org_id.clone(),
+                scope: "auth".to_string(),
+                session_id: None,
+            },
+            UserDto {
+                id: generate_id(),
+                org_id: org_id.clone(),
+                username: "deploy".to_string(),
+                status: "active".to_string(),
+                kind: "service".to_string(),
+                roles: vec![Role::Editor],
+                created_at: 0,
+                updated_at: 0,
+                version: 0,
+            },
+            &[],
+        );
+        let api_key = ApiKeyDto {
+            id: generate_id(),
+            org_id,
+            user_id: actor.id.clone(),
+            name: "CI".to_string(),
+            permissions: vec![
+                Permission::EntriesList,
+                Permission::EntriesView,
+                Permission::AuditList,
+            ],
+            vault_id: Some(vault_id.clone()),
+            allowed_ips: vec![],
+            expires_at: None,
+            last_used_at: None,
+            last_used_ip: None,
+            created_at: 0,
+            revoked_at: None,
+        };
+
+        // Key permissions not granted by the roles are ignored
+        let actor = actor.with_api_key(&api_key);
+        assert_eq!(
+            actor.permissions,
+            vec![Permission::EntriesList, Permission::EntriesView]
+        );
+        assert_eq!(actor.vault_scope, Some(vault_id));
+        assert!(actor.is_api_key());
+
+        let manager = actor.for_vault(&VaultRole::Manager);
+        assert!(!manager.has_permissions(&vec![Permission::VaultsManage]));
+    }
 }
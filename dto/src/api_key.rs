@@ -0,0 +1,113 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::role::Permission;
+
+/// Long-lived key of a service account, the secret is never returned back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyDto {
+    pub id: String,
+    pub org_id: String,
+    pub user_id: String,
+    pub name: String,
+    pub permissions: Vec<Permission>,
+
+    /// Only this vault can be accessed when set
+    pub vault_id: Option<String>,
+
+    /// Addresses or CIDR ranges the key may be used from, any when empty
+    pub allowed_ips: Vec<String>,
+
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub last_used_ip: Option<String>,
+    pub created_at: i64,
+    pub revoked_at: Option<i64>,
+}
+
+impl ApiKeyDto {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Whether the key can be used from the client address
+    pub fn allows_ip(&self, ip: Option<&str>) -> bool {
+        if self.allowed_ips.is_empty() {
+            return true;
+        }
+        match ip {
+            Some(ip) => self.allowed_ips.iter().any(|range| ip_in_range(ip, range)),
+            None => false,
+        }
+    }
+}
+
+/// Newly issued key, the only time the full key is available
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewApiKeyDto {
+    pub api_key: ApiKeyDto,
+    pub key: String,
+}
+
+fn parse_range(range: &str) -> Option<(IpAddr, u8)> {
+    let (addr, prefix) = match range.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (range, None),
+    };
+    let addr: IpAddr = addr.trim().parse().ok()?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(prefix) => prefix.trim().parse::<u8>().ok()?,
+        None => max,
+    };
+    if prefix > max {
+        return None;
+    }
+    Some((addr, prefix))
+}
+
+/// Single address or CIDR range such as `10.0.0.0/8`
+pub fn valid_ip_range(range: &str) -> bool {
+    parse_range(range).is_some()
+}
+
+pub fn ip_in_range(ip: &str, range: &str) -> bool {
+    let Ok(ip) = ip.trim().parse::<IpAddr>() else {
+        return false;
+    };
+    let Some((addr, prefix)) = parse_range(range) else {
+        return false;
+    };
+    match (ip, addr) {
+        (IpAddr::V4(ip), IpAddr::V4(addr)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(addr)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_in_range() {
+        assert!(ip_in_range("10.1.2.3", "10.0.0.0/8"));
+        assert!(ip_in_range("10.1.2.3", "10.1.2.3"));
+        assert!(!ip_in_range("11.1.2.3", "10.0.0.0/8"));
+        assert!(ip_in_range("192.168.1.20", "0.0.0.0/0"));
+        assert!(ip_in_range("2001:db8::1", "2001:db8::/32"));
+        assert!(!ip_in_range("10.1.2.3", "2001:db8::/32"));
+        assert!(!ip_in_range("invalid", "10.0.0.0/8"));
+
+        assert!(valid_ip_range("127.0.0.1"));
+        assert!(!valid_ip_range("10.0.0.0/33"));
+        assert!(!valid_ip_range("localhost"));
+    }
+}
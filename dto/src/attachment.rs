@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentDto {
+    pub id: String,
+    pub entry_id: String,
+    pub vault_id: String,
+    pub cipher_filename: String,
+    pub size: i64,
+    pub sha256: String,
+    pub created_at: i64,
+}
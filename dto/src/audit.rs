@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEventDto {
+    pub id: String,
+    pub org_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    pub detail: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+    pub created_at: i64,
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub enum AuditAction {
+    LoginSucceeded,
+    LoginFailed,
+    Logout,
+    RefreshReused,
+    LoginLocked,
+    PasswordReset,
+
+    EntryView,
+    EntryCreate,
+    EntryUpdate,
+    EntryDelete,
+    EntryMove,
+    EntryCopy,
+
+    AttachmentUpload,
+    AttachmentDownload,
+    AttachmentDelete,
+
+    UserCreate,
+    UserUpdateStatus,
+    UserUpdateRoles,
+    UserResetPassword,
+    UserChangePassword,
+    UserDelete,
+    UserUnlock,
+    UserResetLink,
+
+    GroupCreate,
+    GroupUpdate,
+    GroupDelete,
+    GroupAddMember,
+    GroupRemoveMember,
+
+    RoleCreate,
+    RoleUpdate,
+    RoleDelete,
+
+    ServiceAccountCreate,
+    ApiKeyCreate,
+    ApiKeyRevoke,
+
+    SessionRevoke,
+
+    VaultCreate,
+    VaultUpdate,
+    VaultDelete,
+    VaultGrant,
+    VaultRevoke,
+}
+
+impl AuditAction {
+    pub fn all() -> Vec<AuditAction> {
+        vec![
+            AuditAction::LoginSucceeded,
+            AuditAction::LoginFailed,
+            AuditAction::Logout,
+            AuditAction::RefreshReused,
+            AuditAction::LoginLocked,
+            AuditAction::PasswordReset,
+            AuditAction::EntryView,
+            AuditAction::EntryCreate,
+            AuditAction::EntryUpdate,
+            AuditAction::EntryDelete,
+            AuditAction::EntryMove,
+            AuditAction::EntryCopy,
+            AuditAction::AttachmentUpload,
+            AuditAction::AttachmentDownload,
+            AuditAction::AttachmentDelete,
+            AuditAction::UserCreate,
+            AuditAction::UserUpdateStatus,
+            AuditAction::UserUpdateRoles,
+            AuditAction::UserResetPassword,
+            AuditAction::UserChangePassword,
+            AuditAction::UserDelete,
+            AuditAction::UserUnlock,
+            AuditAction::UserResetLink,
+            AuditAction::GroupCreate,
+            AuditAction::GroupUpdate,
+            AuditAction::GroupDelete,
+            AuditAction::GroupAddMember,
+            AuditAction::GroupRemoveMember,
+            AuditAction::RoleCreate,
+            AuditAction::RoleUpdate,
+            AuditAction::RoleDelete,
+            AuditAction::ServiceAccountCreate,
+            AuditAction::ApiKeyCreate,
+            AuditAction::ApiKeyRevoke,
+            AuditAction::SessionRevoke,
+            AuditAction::VaultCreate,
+            AuditAction::VaultUpdate,
+            AuditAction::VaultDelete,
+            AuditAction::VaultGrant,
+            AuditAction::VaultRevoke,
+        ]
+    }
+}
+
+impl core::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            AuditAction::LoginSucceeded => write!(f, "auth.login"),
+            AuditAction::LoginFailed => write!(f, "auth.login_failed"),
+            AuditAction::Logout => write!(f, "auth.logout"),
+            AuditAction::RefreshReused => write!(f, "auth.refresh_reused"),
+            AuditAction::LoginLocked => write!(f, "auth.login_locked"),
+            AuditAction::PasswordReset => write!(f, "auth.password_reset"),
+            AuditAction::EntryView => write!(f, "entries.view"),
+            AuditAction::EntryCreate => write!(f, "entries.create"),
+            AuditAction::EntryUpdate => write!(f, "entries.update"),
+            AuditAction::EntryDelete => write!(f, "entries.delete"),
+            AuditAction::EntryMove => write!(f, "entries.move"),
+            AuditAction::EntryCopy => write!(f, "entries.copy"),
+            AuditAction::AttachmentUpload => write!(f, "attachments.upload"),
+            AuditAction::AttachmentDownload => write!(f, "attachments.download"),
+            AuditAction::AttachmentDelete => write!(f, "attachments.delete"),
+            AuditAction::UserCreate => write!(f, "users.create"),
+            AuditAction::UserUpdateStatus => write!(f, "users.update_status"),
+            AuditAction::UserUpdateRoles => write!(f, "users.update_roles"),
+            AuditAction::UserResetPassword => write!(f, "users.reset_password"),
+            AuditAction::UserChangePassword => write!(f, "users.change_password"),
+            AuditAction::UserDelete => write!(f, "users.delete"),
+            AuditAction::UserUnlock => write!(f, "users.unlock"),
+            AuditAction::UserResetLink => write!(f, "users.reset_link"),
+            AuditAction::GroupCreate => write!(f, "groups.create"),
+            AuditAction::GroupUpdate => write!(f, "groups.update"),
+            AuditAction::GroupDelete => write!(f, "groups.delete"),
+            AuditAction::GroupAddMember => write!(f, "groups.add_member"),
+            AuditAction::GroupRemoveMember => write!(f, "groups.remove_member"),
+            AuditAction::RoleCreate => write!(f, "roles.create"),
+            AuditAction::RoleUpdate => write!(f, "roles.update"),
+            AuditAction::RoleDelete => write!(f, "roles.delete"),
+            AuditAction::ServiceAccountCreate => write!(f, "service_accounts.create"),
+            AuditAction::ApiKeyCreate => write!(f, "api_keys.create"),
+            AuditAction::ApiKeyRevoke => write!(f, "api_keys.revoke"),
+            AuditAction::SessionRevoke => write!(f, "sessions.revoke"),
+            AuditAction::VaultCreate => write!(f, "vaults.create"),
+            AuditAction::VaultUpdate => write!(f, "vaults.update"),
+            AuditAction::VaultDelete => write!(f, "vaults.delete"),
+            AuditAction::VaultGrant => write!(f, "vaults.grant"),
+            AuditAction::VaultRevoke => write!(f, "vaults.revoke"),
+        }
+    }
+}
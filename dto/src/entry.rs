@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::pagination::SortOrder;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntryDto {
     pub id: String,
@@ -12,4 +14,192 @@ pub struct EntryDto {
     pub status: String,
     pub created_at: i64,
     pub updated_at: i64,
+    pub last_used_at: i64,
+    pub version: i32,
+
+    /// Last time the password changed, label or notes edits do not count
+    pub secret_changed_at: i64,
+
+    /// Overrides the vault rotation interval
+    pub rotation_days: Option<i32>,
+
+    pub expires_at: Option<i64>,
+}
+
+impl EntryDto {
+    pub fn rotation_due_at(&self, vault_rotation_days: Option<i32>) -> Option<i64> {
+        rotation_due_at(
+            self.secret_changed_at,
+            self.rotation_days.or(vault_rotation_days),
+            self.expires_at,
+        )
+    }
+
+    /// None when neither the entry nor the vault has a rotation policy
+    pub fn rotation_status(
+        &self,
+        vault_rotation_days: Option<i32>,
+        now: i64,
+    ) -> Option<RotationStatus> {
+        self.rotation_due_at(vault_rotation_days)
+            .map(|due_at| RotationStatus::new(due_at, now))
+    }
+}
+
+/// Entries due within this many days are reported as due soon
+pub const ROTATION_WARNING_DAYS: i64 = 14;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Earliest of the rotation deadline and the expiry date
+pub fn rotation_due_at(
+    secret_changed_at: i64,
+    rotation_days: Option<i32>,
+    expires_at: Option<i64>,
+) -> Option<i64> {
+    let rotate_at = rotation_days.map(|days| secret_changed_at + days as i64 * SECONDS_PER_DAY);
+    match (rotate_at, expires_at) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationStatus {
+    Current,
+    DueSoon,
+    Overdue,
+}
+
+impl RotationStatus {
+    pub fn new(due_at: i64, now: i64) -> Self {
+        if due_at <= now {
+            RotationStatus::Overdue
+        } else if due_at <= now + ROTATION_WARNING_DAYS * SECONDS_PER_DAY {
+            RotationStatus::DueSoon
+        } else {
+            RotationStatus::Current
+        }
+    }
+}
+
+/// Entry that has to be rotated, listed per org
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryRotationDto {
+    pub id: String,
+    pub vault_id: String,
+    pub vault_name: String,
+    pub label: String,
+    pub secret_changed_at: i64,
+
+    /// Interval in effect, either from the entry or the vault
+    pub rotation_days: Option<i32>,
+
+    pub expires_at: Option<i64>,
+    pub due_at: i64,
+    pub status: RotationStatus,
+}
+
+/// Entry reference shown in favorites and recently viewed lists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryLinkDto {
+    pub id: String,
+    pub vault_id: String,
+    pub vault_name: String,
+    pub label: String,
+
+    /// When the entry was favorited or last viewed
+    pub timestamp: i64,
+}
+
+/// Outcome of one operation of a bulk request, in request order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkEntryResultDto {
+    pub index: usize,
+    pub op: String,
+    pub id: String,
+
+    /// Version after the operation, none for deleted entries
+    pub version: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryFavoriteDto {
+    pub entry_id: String,
+    pub favorite: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntrySort {
+    Label,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    LastUsed,
+}
+
+impl EntrySort {
+    /// Labels read naturally from A to Z, timestamps from the most recent
+    pub fn default_order(&self) -> SortOrder {
+        match self {
+            EntrySort::Label => SortOrder::Asc,
+            _ => SortOrder::Desc,
+        }
+    }
+}
+
+impl TryFrom<&str> for EntrySort {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "label" => Ok(EntrySort::Label),
+            "created_at" => Ok(EntrySort::CreatedAt),
+            "updated_at" => Ok(EntrySort::UpdatedAt),
+            "last_used" => Ok(EntrySort::LastUsed),
+            _ => Err(format!("Invalid sort: {value}")),
+        }
+    }
+}
+
+impl core::fmt::Display for EntrySort {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            EntrySort::Label => write!(f, "label"),
+            EntrySort::CreatedAt => write!(f, "created_at"),
+            EntrySort::UpdatedAt => write!(f, "updated_at"),
+            EntrySort::LastUsed => write!(f, "last_used"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_due_at() {
+        let day = SECONDS_PER_DAY;
+        assert_eq!(rotation_due_at(0, None, None), None);
+        assert_eq!(rotation_due_at(0, Some(90), None), Some(90 * day));
+        assert_eq!(rotation_due_at(0, None, Some(5 * day)), Some(5 * day));
+        assert_eq!(rotation_due_at(0, Some(90), Some(5 * day)), Some(5 * day));
+        assert_eq!(rotation_due_at(0, Some(1), Some(5 * day)), Some(day));
+    }
+
+    #[test]
+    fn test_rotation_status() {
+        let now = 1_000 * SECONDS_PER_DAY;
+        assert_eq!(RotationStatus::new(now, now), RotationStatus::Overdue);
+        assert_eq!(
+            RotationStatus::new(now + SECONDS_PER_DAY, now),
+            RotationStatus::DueSoon
+        );
+        assert_eq!(
+            RotationStatus::new(now + 30 * SECONDS_PER_DAY, now),
+            RotationStatus::Current
+        );
+    }
 }
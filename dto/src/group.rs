@@ -0,0 +1,22 @@
+use crate::role::Role;
+use serde::{Deserialize, Serialize};
+
+/// Users of an org that share the same roles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupDto {
+    pub id: String,
+    pub org_id: String,
+    pub name: String,
+    pub roles: Vec<Role>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub version: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMemberDto {
+    pub group_id: String,
+    pub user_id: String,
+    pub username: String,
+    pub created_at: i64,
+}
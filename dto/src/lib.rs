@@ -1,7 +1,15 @@
 pub mod actor;
+pub mod api_key;
+pub mod attachment;
+pub mod audit;
 pub mod entry;
+pub mod group;
+pub mod login_attempt;
 pub mod org;
 pub mod pagination;
+pub mod password_reset;
+pub mod quota;
 pub mod role;
+pub mod session;
 pub mod user;
 pub mod vault;
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Failed logins are tracked per username
+pub const USER_SCOPE: &str = "user";
+
+/// Failed logins are tracked per source IP address
+pub const IP_SCOPE: &str = "ip";
+
+/// Recent failed logins of a username or an IP address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginAttemptDto {
+    pub scope: String,
+    pub subject: String,
+    pub failures: i32,
+    pub first_failed_at: i64,
+    pub last_failed_at: i64,
+
+    /// No login is attempted before this time
+    pub locked_until: Option<i64>,
+}
+
+impl LoginAttemptDto {
+    /// Seconds to wait before the next attempt, zero when not locked
+    pub fn retry_after(&self, now: i64) -> i64 {
+        match self.locked_until {
+            Some(until) if until > now => until - now,
+            _ => 0,
+        }
+    }
+}
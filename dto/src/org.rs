@@ -6,4 +6,5 @@ pub struct OrgDto {
     pub name: String,
     pub admin: bool,
     pub created_at: i64,
+    pub version: i32,
 }
@@ -1,3 +1,4 @@
+use base64::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -35,3 +36,137 @@ impl<T> PaginatedDto<T> {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn reverse(&self) -> Self {
+        match self {
+            SortOrder::Asc => SortOrder::Desc,
+            SortOrder::Desc => SortOrder::Asc,
+        }
+    }
+}
+
+impl TryFrom<&str> for SortOrder {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(format!("Invalid sort order: {value}")),
+        }
+    }
+}
+
+impl core::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            SortOrder::Asc => write!(f, "asc"),
+            SortOrder::Desc => write!(f, "desc"),
+        }
+    }
+}
+
+/// Position of a record in a sorted listing, encoded as an opaque string
+/// for API clients. The record id breaks ties on the sort value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cursor {
+    pub sort: String,
+    pub value: String,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn new(sort: &str, value: &str, id: &str) -> Self {
+        Self {
+            sort: sort.to_string(),
+            value: value.to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let raw = format!("{}:{}:{}", self.sort, self.value, self.id);
+        BASE64_URL_SAFE_NO_PAD.encode(raw.as_bytes())
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        let bytes = BASE64_URL_SAFE_NO_PAD.decode(value.as_bytes()).ok()?;
+        let raw = String::from_utf8(bytes).ok()?;
+
+        // Sort values may contain the separator but sort names and ids do not
+        let (sort, rest) = raw.split_once(':')?;
+        let (sort_value, id) = rest.rsplit_once(':')?;
+        if sort.is_empty() || id.is_empty() {
+            return None;
+        }
+        Some(Self::new(sort, sort_value, id))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CursorMetaDto {
+    pub per_page: i32,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+
+    /// Only computed when requested since it requires a count query
+    pub total_records: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CursorPaginatedDto<T> {
+    pub meta: CursorMetaDto,
+    pub data: Vec<T>,
+}
+
+impl<T> CursorPaginatedDto<T> {
+    pub fn new(records: Vec<T>, meta: CursorMetaDto) -> Self {
+        Self {
+            meta,
+            data: records,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor::new(
+            "created_at",
+            "1749954250",
+            "0196d1c6f2a87a0f8d0b3e4f5a6b7c8d",
+        );
+        let encoded = cursor.encode();
+        assert_eq!(Cursor::decode(&encoded), Some(cursor));
+
+        let cursor = Cursor::new("label", "mail: work", "0196d1c6f2a87a0f8d0b3e4f5a6b7c8d");
+        let encoded = cursor.encode();
+        assert_eq!(Cursor::decode(&encoded), Some(cursor));
+    }
+
+    #[test]
+    fn test_cursor_invalid() {
+        assert_eq!(Cursor::decode(""), None);
+        assert_eq!(Cursor::decode("not a cursor"), None);
+        assert_eq!(
+            Cursor::decode(&BASE64_URL_SAFE_NO_PAD.encode("label:id")),
+            None
+        );
+        assert_eq!(
+            Cursor::decode(&BASE64_URL_SAFE_NO_PAD.encode("label:abc:")),
+            None
+        );
+    }
+}
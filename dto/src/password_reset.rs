@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// Pending password reset link of a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordResetDto {
+    pub id: String,
+    pub org_id: String,
+    pub user_id: String,
+
+    /// Admin who issued the link
+    pub created_by: Option<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+/// Reset link with its token, the only time the token is available
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPasswordResetDto {
+    pub reset: PasswordResetDto,
+    pub token: String,
+}
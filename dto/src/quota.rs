@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// Per-org overrides, None falls back to the instance default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrgQuotaDto {
+    pub org_id: String,
+    pub max_vaults: Option<i64>,
+    pub max_users: Option<i64>,
+    pub max_entries_per_vault: Option<i64>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct UsageDto {
+    pub used: i64,
+    pub limit: i64,
+}
+
+impl UsageDto {
+    pub fn new(used: i64, limit: i64) -> Self {
+        Self { used, limit }
+    }
+
+    pub fn percent(&self) -> i64 {
+        if self.limit <= 0 {
+            return 100;
+        }
+        (self.used * 100 / self.limit).clamp(0, 100)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgUsageDto {
+    pub org_id: String,
+    pub vaults: UsageDto,
+    pub users: UsageDto,
+
+    /// Fullest vault against the per vault limit
+    pub entries_per_vault: UsageDto,
+
+    pub total_entries: i64,
+}
@@ -2,13 +2,34 @@ use snafu::{Snafu, ensure};
 use std::collections::HashSet;
 
 use serde::{Deserialize, Serialize};
+use vault::utils::valid_id;
 
+/// Built-in roles plus the custom roles defined by each org
+///
+/// Custom roles are referenced by their id and only carry permissions once
+/// resolved against their definition, see `resolve_permissions`.
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum Role {
     SystemAdmin,
     Admin,
     Editor,
     Viewer,
+    Custom(String),
+}
+
+impl Role {
+    /// Built-in roles an org may assign to its users
+    pub fn builtin() -> Vec<Role> {
+        vec![Role::Admin, Role::Editor, Role::Viewer]
+    }
+
+    pub fn custom_id(&self) -> Option<&str> {
+        match self {
+            Role::Custom(id) => Some(id.as_str()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Snafu)]
@@ -46,6 +67,12 @@ pub enum Permission {
     EntriesList,
     EntriesView,
     EntriesManage,
+
+    AuditList,
+    AuditView,
+
+    SessionsList,
+    SessionsRevoke,
 }
 
 #[derive(Debug, Snafu)]
@@ -54,6 +81,88 @@ pub struct InvalidPermissionsError {
     permissions: String,
 }
 
+impl Permission {
+    pub fn all() -> Vec<Permission> {
+        vec![
+            Permission::OrgsCreate,
+            Permission::OrgsEdit,
+            Permission::OrgsDelete,
+            Permission::OrgsList,
+            Permission::OrgsView,
+            Permission::OrgsManage,
+            Permission::VaultsCreate,
+            Permission::VaultsEdit,
+            Permission::VaultsDelete,
+            Permission::VaultsList,
+            Permission::VaultsView,
+            Permission::VaultsManage,
+            Permission::UsersCreate,
+            Permission::UsersEdit,
+            Permission::UsersDelete,
+            Permission::UsersList,
+            Permission::UsersView,
+            Permission::UsersManage,
+            Permission::EntriesCreate,
+            Permission::EntriesEdit,
+            Permission::EntriesDelete,
+            Permission::EntriesList,
+            Permission::EntriesView,
+            Permission::EntriesManage,
+            Permission::AuditList,
+            Permission::AuditView,
+            Permission::SessionsList,
+            Permission::SessionsRevoke,
+        ]
+    }
+
+    /// Permissions a custom role may grant
+    ///
+    /// Managing orgs themselves is reserved to the system admin.
+    pub fn assignable() -> Vec<Permission> {
+        Permission::all()
+            .into_iter()
+            .filter(|permission| {
+                !matches!(
+                    permission,
+                    Permission::OrgsCreate
+                        | Permission::OrgsEdit
+                        | Permission::OrgsDelete
+                        | Permission::OrgsManage
+                )
+            })
+            .collect()
+    }
+}
+
+/// Custom role of an org, built-in roles are listed with `builtin` set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleDto {
+    pub id: String,
+    pub org_id: Option<String>,
+    pub name: String,
+    pub permissions: Vec<Permission>,
+    pub builtin: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub version: i32,
+}
+
+impl RoleDto {
+    /// Definition of a built-in role, its permissions come from `role_permissions`
+    pub fn builtin(role: &Role) -> Self {
+        RoleDto {
+            id: role.to_string(),
+            org_id: None,
+            name: role.to_string(),
+            permissions: role_permissions(role),
+            builtin: true,
+            created_at: 0,
+            updated_at: 0,
+            version: 1,
+        }
+    }
+}
+
 impl TryFrom<&str> for Role {
     type Error = String;
 
@@ -63,11 +172,26 @@ impl TryFrom<&str> for Role {
             "Admin" => Ok(Role::Admin),
             "Editor" => Ok(Role::Editor),
             "Viewer" => Ok(Role::Viewer),
+            _ if valid_id(value) => Ok(Role::Custom(value.to_string())),
             _ => Err(format!("Invalid role: {value}")),
         }
     }
 }
 
+impl TryFrom<String> for Role {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Role::try_from(value.as_str())
+    }
+}
+
+impl From<Role> for String {
+    fn from(role: Role) -> Self {
+        role.to_string()
+    }
+}
+
 impl core::fmt::Display for Role {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
@@ -75,6 +199,7 @@ impl core::fmt::Display for Role {
             Role::Admin => write!(f, "Admin"),
             Role::Editor => write!(f, "Editor"),
             Role::Viewer => write!(f, "Viewer"),
+            Role::Custom(id) => write!(f, "{id}"),
         }
     }
 }
@@ -129,6 +254,10 @@ impl TryFrom<&str> for Permission {
             "entries.list" => Ok(Permission::EntriesList),
             "entries.view" => Ok(Permission::EntriesView),
             "entries.manage" => Ok(Permission::EntriesManage),
+            "audit.list" => Ok(Permission::AuditList),
+            "audit.view" => Ok(Permission::AuditView),
+            "sessions.list" => Ok(Permission::SessionsList),
+            "sessions.revoke" => Ok(Permission::SessionsRevoke),
             _ => Err(format!("Invalid permission: {value}")),
         }
     }
@@ -161,6 +290,10 @@ impl core::fmt::Display for Permission {
             Permission::EntriesList => write!(f, "entries.list"),
             Permission::EntriesView => write!(f, "entries.view"),
             Permission::EntriesManage => write!(f, "entries.manage"),
+            Permission::AuditList => write!(f, "audit.list"),
+            Permission::AuditView => write!(f, "audit.view"),
+            Permission::SessionsList => write!(f, "sessions.list"),
+            Permission::SessionsRevoke => write!(f, "sessions.revoke"),
         }
     }
 }
@@ -210,6 +343,10 @@ pub fn role_permissions(role: &Role) -> Vec<Permission> {
             Permission::VaultsList,
             Permission::VaultsView,
             Permission::VaultsManage,
+            Permission::AuditList,
+            Permission::AuditView,
+            Permission::SessionsList,
+            Permission::SessionsRevoke,
         ],
         Role::Admin => vec![
             Permission::OrgsList,
@@ -224,6 +361,10 @@ pub fn role_permissions(role: &Role) -> Vec<Permission> {
             Permission::EntriesList,
             Permission::EntriesView,
             Permission::EntriesManage,
+            Permission::AuditList,
+            Permission::AuditView,
+            Permission::SessionsList,
+            Permission::SessionsRevoke,
         ],
         Role::Editor => vec![
             Permission::OrgsList,
@@ -242,6 +383,8 @@ pub fn role_permissions(role: &Role) -> Vec<Permission> {
             Permission::EntriesList,
             Permission::EntriesView,
         ],
+        // Resolved from the role definition, see `resolve_permissions`
+        Role::Custom(_) => vec![],
     }
 }
 
@@ -256,6 +399,24 @@ pub fn roles_permissions(roles: &Vec<Role>) -> Vec<Permission> {
     permissions.into_iter().collect()
 }
 
+/// Get all permissions for the given roles, including custom roles
+///
+/// Custom roles without a matching definition grant nothing.
+pub fn resolve_permissions(roles: &Vec<Role>, custom_roles: &[RoleDto]) -> Vec<Permission> {
+    let mut permissions: HashSet<Permission> = roles_permissions(roles).into_iter().collect();
+    roles
+        .iter()
+        .filter_map(|role| role.custom_id())
+        .for_each(|id| {
+            if let Some(custom) = custom_roles.iter().find(|x| x.id == id) {
+                custom.permissions.iter().for_each(|p| {
+                    permissions.insert(p.clone());
+                });
+            }
+        });
+    permissions.into_iter().collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,6 +442,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_to_roles_custom() {
+        let id = vault::utils::generate_id();
+        let data = vec!["Editor".to_string(), id.clone()];
+        let roles = to_roles(data).unwrap();
+        assert_eq!(roles, vec![Role::Editor, Role::Custom(id.clone())]);
+        assert_eq!(roles[1].to_string(), id);
+
+        let json = serde_json::to_string(&roles).unwrap();
+        assert_eq!(json, format!("[\"Editor\",\"{id}\"]"));
+        let parsed: Vec<Role> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, roles);
+    }
+
+    #[test]
+    fn test_resolve_permissions() {
+        let id = vault::utils::generate_id();
+        let custom = RoleDto {
+            id: id.clone(),
+            org_id: Some(vault::utils::generate_id()),
+            name: "Auditor".to_string(),
+            permissions: vec![Permission::AuditList, Permission::AuditView],
+            builtin: false,
+            created_at: 0,
+            updated_at: 0,
+            version: 1,
+        };
+
+        let roles = vec![Role::Viewer, Role::Custom(id)];
+        let permissions = resolve_permissions(&roles, &[custom]);
+        assert!(permissions.contains(&Permission::EntriesView));
+        assert!(permissions.contains(&Permission::AuditView));
+
+        // Unknown custom roles grant nothing
+        let permissions = resolve_permissions(&roles, &[]);
+        assert!(!permissions.contains(&Permission::AuditView));
+    }
+
     #[test]
     fn test_to_permissions_valid() {
         let data = vec![
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Login session, access tokens carry its id as the `jti` claim
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDto {
+    pub id: String,
+    pub org_id: String,
+    pub user_id: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: i64,
+    pub last_used_at: i64,
+
+    /// The refresh token can no longer be used after this time
+    pub expires_at: i64,
+    pub revoked_at: Option<i64>,
+}
+
+impl SessionDto {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+/// Session with its refresh token, the only time the token is available
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSessionDto {
+    pub session: SessionDto,
+    pub refresh_token: String,
+}
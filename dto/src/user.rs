@@ -7,7 +7,11 @@ pub struct UserDto {
     pub org_id: String,
     pub username: String,
     pub status: String,
+
+    /// Either "user" or "service" for accounts that only use API keys
+    pub kind: String,
     pub roles: Vec<Role>,
     pub created_at: i64,
     pub updated_at: i64,
+    pub version: i32,
 }
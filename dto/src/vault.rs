@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::role::{Permission, Role};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaultDto {
     pub id: String,
@@ -8,4 +10,127 @@ pub struct VaultDto {
     pub test_cipher: String,
     pub created_at: i64,
     pub updated_at: i64,
+    pub version: i32,
+
+    /// Days between password changes for entries without their own interval
+    pub rotation_days: Option<i32>,
+
+    pub description: Option<String>,
+
+    /// Font Awesome icon name without the `fa-` prefix
+    pub icon: Option<String>,
+
+    /// Accent color in `#rrggbb` form
+    pub color: Option<String>,
+}
+
+/// Access a member has to a single vault, ordered from least to most access
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VaultRole {
+    Viewer,
+    Editor,
+    Manager,
+}
+
+impl VaultRole {
+    pub fn all() -> Vec<VaultRole> {
+        vec![VaultRole::Viewer, VaultRole::Editor, VaultRole::Manager]
+    }
+
+    /// Entry permissions allowed in the vault, managers also manage the members
+    pub fn permissions(&self) -> Vec<Permission> {
+        let mut permissions = vec![Permission::EntriesList, Permission::EntriesView];
+        if *self != VaultRole::Viewer {
+            permissions.push(Permission::EntriesCreate);
+            permissions.push(Permission::EntriesEdit);
+        }
+        if *self == VaultRole::Manager {
+            permissions.push(Permission::EntriesDelete);
+            permissions.push(Permission::EntriesManage);
+            permissions.push(Permission::VaultsManage);
+        }
+        permissions
+    }
+
+    /// Closest vault role to the org wide roles
+    pub fn from_roles(roles: &[Role]) -> Option<VaultRole> {
+        if roles.contains(&Role::Admin) {
+            Some(VaultRole::Manager)
+        } else if roles.contains(&Role::Editor) {
+            Some(VaultRole::Editor)
+        } else if roles.contains(&Role::Viewer) {
+            Some(VaultRole::Viewer)
+        } else {
+            None
+        }
+    }
+}
+
+impl TryFrom<&str> for VaultRole {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "viewer" => Ok(VaultRole::Viewer),
+            "editor" => Ok(VaultRole::Editor),
+            "manager" => Ok(VaultRole::Manager),
+            _ => Err(format!("Invalid vault role: {value}")),
+        }
+    }
+}
+
+impl core::fmt::Display for VaultRole {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            VaultRole::Viewer => write!(f, "viewer"),
+            VaultRole::Editor => write!(f, "editor"),
+            VaultRole::Manager => write!(f, "manager"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultMemberDto {
+    pub vault_id: String,
+    pub user_id: String,
+    pub username: String,
+    pub role: VaultRole,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultGroupDto {
+    pub vault_id: String,
+    pub group_id: String,
+    pub name: String,
+    pub role: VaultRole,
+    pub created_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_role_permissions() {
+        let viewer = VaultRole::Viewer.permissions();
+        assert!(viewer.contains(&Permission::EntriesView));
+        assert!(!viewer.contains(&Permission::EntriesCreate));
+
+        let editor = VaultRole::Editor.permissions();
+        assert!(editor.contains(&Permission::EntriesEdit));
+        assert!(!editor.contains(&Permission::EntriesDelete));
+
+        let manager = VaultRole::Manager.permissions();
+        assert!(manager.contains(&Permission::EntriesDelete));
+        assert!(manager.contains(&Permission::VaultsManage));
+    }
+
+    #[test]
+    fn test_vault_role_from_roles() {
+        let roles = vec![Role::Viewer, Role::Editor];
+        assert_eq!(VaultRole::from_roles(&roles), Some(VaultRole::Editor));
+        assert_eq!(VaultRole::from_roles(&[Role::SystemAdmin]), None);
+    }
 }
@@ -51,7 +51,9 @@ fn error_to_string(error: &ValidationError) -> String {
             _ => "invalid".to_string(),
         },
         "required" => "required".to_string(),
+        "hexcolor" => "must be a hex color like #3273dc".to_string(),
         "sluggable" => "must be composed of alpha-numeric characters or dashes".to_string(),
+        "vault_role" => "must be one of viewer, editor or manager".to_string(),
         _ => "invalid".to_string(),
     }
 }
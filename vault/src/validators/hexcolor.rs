@@ -0,0 +1,30 @@
+use core::result::Result;
+use validator::ValidationError;
+
+/// Accepts colors in the `#rrggbb` form
+pub fn hexcolor(value: &str) -> Result<(), ValidationError> {
+    let Some(digits) = value.strip_prefix('#') else {
+        return Err(ValidationError::new("hexcolor"));
+    };
+
+    match digits.len() == 6 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => Ok(()),
+        false => Err(ValidationError::new("hexcolor")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hexcolor() {
+        assert!(hexcolor("#3273dc").is_ok());
+        assert!(hexcolor("#FFAA00").is_ok());
+        assert!(hexcolor("3273dc").is_err());
+        assert!(hexcolor("#3273d").is_err());
+        assert!(hexcolor("#3273dcff").is_err());
+        assert!(hexcolor("#zz73dc").is_err());
+        assert!(hexcolor("").is_err());
+    }
+}
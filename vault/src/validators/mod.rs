@@ -2,6 +2,7 @@ mod alphanumeric;
 mod anyname;
 mod csvname;
 mod error;
+mod hexcolor;
 mod sluggable;
 mod status;
 mod uuid;
@@ -10,6 +11,7 @@ pub use alphanumeric::alphanumeric;
 pub use anyname::anyname;
 pub use csvname::csvname;
 pub use error::flatten_errors;
+pub use hexcolor::hexcolor;
 pub use sluggable::sluggable;
 pub use status::status;
 pub use uuid::uuid;
@@ -4,7 +4,7 @@
 
     document.body.addEventListener('htmx:beforeSwap', function (evt) {
       // Allow some status codes to be handled
-      const codes = [400, 401, 403, 404, 422, 500];
+      const codes = [400, 401, 403, 404, 409, 422, 500];
 
       if (codes.includes(evt.detail.xhr.status)) {
         evt.detail.shouldSwap = true;
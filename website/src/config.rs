@@ -17,6 +17,7 @@ pub struct AppConfig {
     pub captcha_site_key: String,
     pub captcha_api_key: String,
     pub api_url: String,
+    pub public_url: String,
     pub jwt_secret: String,
     pub ga_tag_id: Option<String>,
 }
@@ -29,6 +30,9 @@ pub struct Config {
     pub captcha_site_key: String,
     pub captcha_api_key: String,
     pub api_url: String,
+
+    /// Address users reach the website at, used for links shared outside it
+    pub public_url: String,
     pub jwt_secret: String,
     pub ga_tag_id: Option<String>,
     pub assets: AssetManifest,
@@ -78,6 +82,12 @@ impl Config {
                 msg: "API URL is required.".to_string()
             }
         );
+        ensure!(
+            config.public_url.starts_with("https://") || config.public_url.starts_with("http://"),
+            ConfigSnafu {
+                msg: "Public URL must be an http or https URL.".to_string()
+            }
+        );
         ensure!(
             config.port > 0,
             ConfigSnafu {
@@ -100,6 +110,7 @@ impl Config {
             captcha_site_key: config.captcha_site_key,
             captcha_api_key: config.captcha_api_key,
             api_url: config.api_url,
+            public_url: config.public_url,
             jwt_secret: config.jwt_secret,
             ga_tag_id: config.ga_tag_id,
             assets,
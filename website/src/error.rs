@@ -23,7 +23,8 @@ pub enum Error {
 
     #[snafu(display("Error parsing config file: {}", source))]
     ConfigParse {
-        source: toml::de::Error,
+        #[snafu(source(from(toml::de::Error, Box::new)))]
+        source: Box<toml::de::Error>,
         backtrace: Backtrace,
     },
 
@@ -145,6 +146,9 @@ pub enum Error {
     #[snafu(display("Invalid username or password"))]
     LoginFailed,
 
+    #[snafu(display("{}", msg))]
+    LoginLocked { msg: String },
+
     #[snafu(display("Login to continue"))]
     LoginRequired,
 
@@ -163,6 +167,15 @@ pub enum Error {
     #[snafu(display("Org not found"))]
     OrgNotFound,
 
+    #[snafu(display("Group not found"))]
+    GroupNotFound,
+
+    #[snafu(display("Role not found"))]
+    RoleNotFound,
+
+    #[snafu(display("{}", msg))]
+    Conflict { msg: String },
+
     #[snafu(display("Stale form data. Refresh the page and try again."))]
     CsrfToken,
 
@@ -208,12 +221,16 @@ impl From<&Error> for StatusCode {
             Error::InvalidPassword => StatusCode::UNAUTHORIZED,
             Error::InactiveUser => StatusCode::UNAUTHORIZED,
             Error::UserNotFound => StatusCode::NOT_FOUND,
+            Error::GroupNotFound => StatusCode::NOT_FOUND,
+            Error::RoleNotFound => StatusCode::NOT_FOUND,
             Error::InvalidRoles { .. } => StatusCode::BAD_REQUEST,
             Error::InvalidPermissions { .. } => StatusCode::BAD_REQUEST,
             Error::LoginFailed { .. } => StatusCode::UNAUTHORIZED,
+            Error::LoginLocked { .. } => StatusCode::TOO_MANY_REQUESTS,
             Error::LoginRequired => StatusCode::UNAUTHORIZED,
             Error::FileNotFound => StatusCode::NOT_FOUND,
             Error::CsrfToken => StatusCode::BAD_REQUEST,
+            Error::Conflict { .. } => StatusCode::CONFLICT,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
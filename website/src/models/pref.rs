@@ -1,14 +1,20 @@
+use dto::entry::EntrySort;
+use dto::pagination::SortOrder;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Pref {
     pub theme: String,
+    pub entry_sort: EntrySort,
+    pub entry_order: SortOrder,
 }
 
 impl Pref {
     pub fn new() -> Self {
         Self {
             theme: String::from("light"),
+            entry_sort: EntrySort::default(),
+            entry_order: EntrySort::default().default_order(),
         }
     }
 }
@@ -4,3 +4,9 @@ use serde::{Deserialize, Serialize};
 pub struct TokenFormData {
     pub token: String,
 }
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct VersionedTokenFormData {
+    pub token: String,
+    pub version: i32,
+}
@@ -9,11 +9,16 @@ use tracing::{Level, info};
 
 use crate::Result;
 use crate::config::Config;
+use crate::services::auth::{ActorCache, RefreshCache};
+use crate::services::jwks::JwksCache;
 use crate::web::all_routes;
 
 #[derive(Clone, FromRef)]
 pub struct AppState {
     pub config: Arc<Config>,
+    pub refresh_cache: RefreshCache,
+    pub jwks_cache: JwksCache,
+    pub actor_cache: ActorCache,
 }
 
 pub async fn run(config: Config) -> Result<()> {
@@ -21,6 +26,9 @@ pub async fn run(config: Config) -> Result<()> {
     let frontend_dir = config.frontend_dir.clone();
     let state = AppState {
         config: Arc::new(config),
+        refresh_cache: RefreshCache::default(),
+        jwks_cache: JwksCache::default(),
+        actor_cache: ActorCache::default(),
     };
 
     let routes_all = Router::new()
@@ -0,0 +1,108 @@
+use core::fmt;
+use reqwest::Client;
+use serde::Deserialize;
+use snafu::ResultExt;
+use urlencoding::encode;
+
+use crate::error::{HttpClientSnafu, HttpResponseParseSnafu};
+use crate::services::handle_response_error;
+use crate::{Error, Result};
+use dto::audit::AuditEventDto;
+use dto::pagination::PaginatedDto;
+
+#[derive(Clone, Deserialize)]
+pub struct AuditParams {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    pub action: Option<String>,
+    pub actor_id: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+}
+
+impl AuditParams {
+    /// Filter query string appended to pagination links
+    pub fn filter_suffix(&self) -> String {
+        let filters = [
+            ("action", &self.action),
+            ("actor_id", &self.actor_id),
+            ("target_type", &self.target_type),
+            ("target_id", &self.target_id),
+        ];
+
+        filters
+            .iter()
+            .filter_map(|(key, value)| match value {
+                Some(v) if !v.is_empty() => Some(format!("&{}={}", key, encode(v))),
+                _ => None,
+            })
+            .collect::<Vec<String>>()
+            .join("")
+    }
+}
+
+impl fmt::Display for AuditParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let page = self.page.unwrap_or(1);
+        let per_page = self.per_page.unwrap_or(20);
+
+        write!(
+            f,
+            "page={}&per_page={}{}",
+            page,
+            per_page,
+            self.filter_suffix()
+        )
+    }
+}
+
+pub async fn list_audit_events(
+    api_url: &str,
+    token: &str,
+    org_id: &str,
+    params: &AuditParams,
+) -> Result<PaginatedDto<AuditEventDto>> {
+    let url = format!("{}/orgs/{}/audit?{}", api_url, org_id, params);
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list audit events. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "audit events", Error::OrgNotFound).await);
+    }
+
+    let events = response
+        .json::<PaginatedDto<AuditEventDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse audit events.".to_string(),
+        })?;
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_params_display() {
+        let params = AuditParams {
+            page: Some(2),
+            per_page: None,
+            action: Some("entries.view".to_string()),
+            actor_id: None,
+            target_type: Some("".to_string()),
+            target_id: Some("a b".to_string()),
+        };
+        assert_eq!(
+            params.to_string(),
+            "page=2&per_page=20&action=entries.view&target_id=a%20b"
+        );
+    }
+}
@@ -1,22 +1,25 @@
 use core::fmt;
 use reqwest::Client;
+use reqwest::header::IF_MATCH;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, ensure};
 use urlencoding::encode;
 
 use crate::config::Config;
-use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu};
-use crate::services::handle_response_error;
+use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu, ValidationSnafu};
 use crate::services::token::verify_csrf_token;
+use crate::services::{handle_response_error, if_match};
 use crate::{Error, Result};
-use dto::entry::EntryDto;
-use dto::pagination::PaginatedDto;
+use dto::entry::{BulkEntryResultDto, EntryDto, EntryFavoriteDto, EntryLinkDto, EntrySort};
+use dto::pagination::{PaginatedDto, SortOrder};
 
 #[derive(Deserialize)]
 pub struct SearchEntriesParams {
     pub keyword: Option<String>,
     pub page: Option<u32>,
     pub per_page: Option<u32>,
+    pub sort: Option<EntrySort>,
+    pub order: Option<SortOrder>,
 }
 impl Default for SearchEntriesParams {
     fn default() -> Self {
@@ -24,14 +27,38 @@ impl Default for SearchEntriesParams {
             keyword: None,
             page: Some(1),
             per_page: Some(10),
+            sort: None,
+            order: None,
         }
     }
 }
 
+impl SearchEntriesParams {
+    /// Query string suffix to keep the search and sort across pages
+    pub fn filter_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if let Some(keyword) = &self.keyword {
+            suffix.push_str(&format!("&keyword={}", encode(keyword)));
+        }
+        if let Some(sort) = &self.sort {
+            suffix.push_str(&format!("&sort={}", sort));
+        }
+        if let Some(order) = &self.order {
+            suffix.push_str(&format!("&order={}", order));
+        }
+        suffix
+    }
+}
+
 impl fmt::Display for SearchEntriesParams {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Ideally, we want an empty string if all fields are None
-        if self.keyword.is_none() && self.page.is_none() && self.per_page.is_none() {
+        if self.keyword.is_none()
+            && self.page.is_none()
+            && self.per_page.is_none()
+            && self.sort.is_none()
+            && self.order.is_none()
+        {
             return write!(f, "");
         }
 
@@ -45,7 +72,15 @@ impl fmt::Display for SearchEntriesParams {
             page,
             per_page,
             encode(keyword)
-        )
+        )?;
+
+        if let Some(sort) = &self.sort {
+            write!(f, "&sort={}", sort)?;
+        }
+        if let Some(order) = &self.order {
+            write!(f, "&order={}", order)?;
+        }
+        Ok(())
     }
 }
 
@@ -57,6 +92,8 @@ pub struct EntryFormData {
     pub cipher_notes: Option<String>,
     pub cipher_extra_notes: Option<String>,
     pub token: String,
+    #[serde(default)]
+    pub version: i32,
 }
 
 #[derive(Clone, Serialize)]
@@ -68,6 +105,68 @@ pub struct EntryData {
     pub cipher_extra_notes: Option<String>,
 }
 
+/// Entry copied to another vault from the entry page
+///
+/// The secrets are sent as stored. Moving is not offered since the browser
+/// cannot re-encrypt them with the key of the target vault yet.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CopyEntryFormData {
+    pub target_vault_id: String,
+    pub cipher_username: Option<String>,
+    pub cipher_password: Option<String>,
+    pub cipher_notes: Option<String>,
+    pub cipher_extra_notes: Option<String>,
+    pub token: String,
+}
+
+#[derive(Clone, Serialize)]
+struct TransferEntryData {
+    target_vault_id: String,
+    cipher_username: Option<String>,
+    cipher_password: Option<String>,
+    cipher_notes: Option<String>,
+    cipher_extra_notes: Option<String>,
+}
+
+/// Entries selected in the listing, sent as repeated `entry=id:version` fields
+#[derive(Clone, Default)]
+pub struct BulkEntriesFormData {
+    pub entries: Vec<(String, i32)>,
+    pub token: String,
+}
+
+impl BulkEntriesFormData {
+    /// Collects the submitted fields, plain form structs only keep the last `entry`
+    pub fn from_pairs(pairs: Vec<(String, String)>) -> Self {
+        let mut form = BulkEntriesFormData::default();
+        for (key, value) in pairs.into_iter() {
+            match key.as_str() {
+                "entry" => {
+                    if let Some((id, version)) = value.split_once(':')
+                        && let Ok(version) = version.parse::<i32>()
+                    {
+                        form.entries.push((id.to_string(), version));
+                    }
+                }
+                "token" => form.token = value,
+                _ => {}
+            }
+        }
+        form
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum BulkEntryOpData {
+    Delete { id: String, version: i32 },
+}
+
+#[derive(Clone, Serialize)]
+struct BulkEntriesData {
+    operations: Vec<BulkEntryOpData>,
+}
+
 pub async fn list_entries(
     api_url: &str,
     token: &str,
@@ -89,6 +188,15 @@ pub async fn list_entries(
     if let Some(keyword) = &params.keyword {
         query.push(("keyword", keyword));
     }
+
+    let sort = params.sort.map(|s| s.to_string());
+    let order = params.order.map(|o| o.to_string());
+    if let Some(sort) = &sort {
+        query.push(("sort", sort));
+    }
+    if let Some(order) = &order {
+        query.push(("order", order));
+    }
     let response = Client::new()
         .get(url)
         .bearer_auth(token)
@@ -219,6 +327,7 @@ pub async fn update_entry(
     let response = Client::new()
         .patch(url)
         .bearer_auth(token)
+        .header(IF_MATCH, if_match(form.version))
         .json(&data)
         .send()
         .await
@@ -246,6 +355,7 @@ pub async fn delete_entry(
     org_id: &str,
     vault_id: &str,
     entry_id: &str,
+    version: i32,
     csrf_token: &str,
 ) -> Result<()> {
     let csrf_result = verify_csrf_token(&csrf_token, &config.jwt_secret)?;
@@ -257,6 +367,7 @@ pub async fn delete_entry(
     let response = Client::new()
         .delete(url)
         .bearer_auth(token)
+        .header(IF_MATCH, if_match(version))
         .send()
         .await
         .context(HttpClientSnafu {
@@ -269,3 +380,258 @@ pub async fn delete_entry(
 
     Ok(())
 }
+
+/// Copies the entry to the target vault, returns the copy
+pub async fn copy_entry(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    entry_id: &str,
+    form: &CopyEntryFormData,
+) -> Result<EntryDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == entry_id, CsrfTokenSnafu);
+    ensure!(
+        !form.target_vault_id.is_empty() && form.target_vault_id != vault_id,
+        ValidationSnafu {
+            msg: "Select another vault.",
+        }
+    );
+
+    let url = format!(
+        "{}/orgs/{}/vaults/{}/entries/{}/copy",
+        &config.api_url, org_id, vault_id, entry_id
+    );
+
+    let data = TransferEntryData {
+        target_vault_id: form.target_vault_id.clone(),
+        cipher_username: form.cipher_username.clone(),
+        cipher_password: form.cipher_password.clone(),
+        cipher_notes: form.cipher_notes.clone(),
+        cipher_extra_notes: form.cipher_extra_notes.clone(),
+    };
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to copy entry. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "entries", Error::EntryNotFound).await);
+    }
+
+    let entry = response
+        .json::<EntryDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse entry.",
+        })?;
+
+    Ok(entry)
+}
+
+async fn bulk_entries(
+    api_url: &str,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    data: &BulkEntriesData,
+) -> Result<Vec<BulkEntryResultDto>> {
+    let url = format!(
+        "{}/orgs/{}/vaults/{}/entries/bulk",
+        api_url, org_id, vault_id
+    );
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .json(data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to update entries. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "entries", Error::VaultNotFound).await);
+    }
+
+    let results =
+        response
+            .json::<Vec<BulkEntryResultDto>>()
+            .await
+            .context(HttpResponseParseSnafu {
+                msg: "Unable to parse bulk entry results.",
+            })?;
+
+    Ok(results)
+}
+
+fn verify_bulk_form(config: &Config, vault_id: &str, form: &BulkEntriesFormData) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == vault_id, CsrfTokenSnafu);
+    ensure!(
+        !form.entries.is_empty(),
+        ValidationSnafu {
+            msg: "Select at least one entry.",
+        }
+    );
+    Ok(())
+}
+
+/// Deletes all selected entries or none of them
+pub async fn delete_entries(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    form: &BulkEntriesFormData,
+) -> Result<usize> {
+    verify_bulk_form(config, vault_id, form)?;
+
+    let data = BulkEntriesData {
+        operations: form
+            .entries
+            .iter()
+            .map(|(id, version)| BulkEntryOpData::Delete {
+                id: id.clone(),
+                version: *version,
+            })
+            .collect(),
+    };
+
+    let results = bulk_entries(&config.api_url, token, org_id, vault_id, &data).await?;
+    Ok(results.len())
+}
+
+pub async fn list_favorite_entries(api_url: &str, token: &str) -> Result<Vec<EntryLinkDto>> {
+    list_entry_links(api_url, token, "favorites").await
+}
+
+pub async fn list_recent_entries(api_url: &str, token: &str) -> Result<Vec<EntryLinkDto>> {
+    list_entry_links(api_url, token, "recents").await
+}
+
+async fn list_entry_links(api_url: &str, token: &str, kind: &str) -> Result<Vec<EntryLinkDto>> {
+    let url = format!("{}/user/{}", api_url, kind);
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: format!("Unable to list {} entries. Try again later.", kind),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "entries", Error::EntryNotFound).await);
+    }
+
+    let items = response
+        .json::<Vec<EntryLinkDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: format!("Unable to parse {} entries.", kind),
+        })?;
+
+    Ok(items)
+}
+
+pub async fn get_entry_favorite(
+    api_url: &str,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    entry_id: &str,
+) -> Result<EntryFavoriteDto> {
+    let url = format!(
+        "{}/orgs/{}/vaults/{}/entries/{}/favorite",
+        api_url, org_id, vault_id, entry_id
+    );
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to get favorite. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "entries", Error::EntryNotFound).await);
+    }
+
+    let favorite = response
+        .json::<EntryFavoriteDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse favorite.",
+        })?;
+
+    Ok(favorite)
+}
+
+pub async fn toggle_entry_favorite(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    entry_id: &str,
+    csrf_token: &str,
+) -> Result<EntryFavoriteDto> {
+    let csrf_result = verify_csrf_token(csrf_token, &config.jwt_secret)?;
+    ensure!(csrf_result == entry_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/vaults/{}/entries/{}/favorite",
+        &config.api_url, org_id, vault_id, entry_id
+    );
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to update favorite. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "entries", Error::EntryNotFound).await);
+    }
+
+    let favorite = response
+        .json::<EntryFavoriteDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse favorite.",
+        })?;
+
+    Ok(favorite)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bulk_form_from_pairs() {
+        let pairs = vec![
+            ("token".to_string(), "csrf".to_string()),
+            ("entry".to_string(), "a1:2".to_string()),
+            ("entry".to_string(), "b2:1".to_string()),
+            ("entry".to_string(), "broken".to_string()),
+            ("target_vault_id".to_string(), "".to_string()),
+            ("action".to_string(), "delete".to_string()),
+        ];
+        let form = BulkEntriesFormData::from_pairs(pairs);
+        assert_eq!(
+            form.entries,
+            vec![("a1".to_string(), 2), ("b2".to_string(), 1)]
+        );
+        assert_eq!(form.token, "csrf");
+    }
+}
@@ -0,0 +1,229 @@
+use reqwest::Client;
+use reqwest::header::IF_MATCH;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, ensure};
+
+use crate::config::Config;
+use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu};
+use crate::services::token::verify_csrf_token;
+use crate::{Error, Result};
+use dto::group::{GroupDto, GroupMemberDto};
+
+use super::{handle_response_error, if_match};
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct NewGroupFormData {
+    pub name: String,
+    pub role: String,
+    pub token: String,
+}
+
+#[derive(Clone, Serialize)]
+struct NewGroupData {
+    name: String,
+    roles: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct DeleteGroupFormData {
+    pub group_id: String,
+    pub version: i32,
+    pub token: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GroupMemberFormData {
+    pub group_id: String,
+    pub user_id: String,
+    pub token: String,
+}
+
+pub async fn list_groups(api_url: &str, token: &str, org_id: &str) -> Result<Vec<GroupDto>> {
+    let url = format!("{}/orgs/{}/groups", api_url, org_id);
+
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list groups. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "groups", Error::OrgNotFound).await);
+    }
+
+    let groups = response
+        .json::<Vec<GroupDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse groups.".to_string(),
+        })?;
+
+    Ok(groups)
+}
+
+pub async fn create_group(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &NewGroupFormData,
+) -> Result<GroupDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!("{}/orgs/{}/groups", &config.api_url, org_id);
+    let data = NewGroupData {
+        name: form.name.clone(),
+        roles: form.role.clone(),
+    };
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to create group. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "groups", Error::OrgNotFound).await);
+    }
+
+    let group = response
+        .json::<GroupDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse group.".to_string(),
+        })?;
+
+    Ok(group)
+}
+
+pub async fn delete_group(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &DeleteGroupFormData,
+) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/groups/{}",
+        &config.api_url, org_id, form.group_id
+    );
+    let response = Client::new()
+        .delete(url)
+        .bearer_auth(token)
+        .header(IF_MATCH, if_match(form.version))
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to delete group. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "groups", Error::GroupNotFound).await);
+    }
+
+    Ok(())
+}
+
+pub async fn list_group_members(
+    api_url: &str,
+    token: &str,
+    org_id: &str,
+    group_id: &str,
+) -> Result<Vec<GroupMemberDto>> {
+    let url = format!("{}/orgs/{}/groups/{}/members", api_url, org_id, group_id);
+
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list group members. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "groups", Error::GroupNotFound).await);
+    }
+
+    let members = response
+        .json::<Vec<GroupMemberDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse group members.".to_string(),
+        })?;
+
+    Ok(members)
+}
+
+pub async fn add_group_member(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &GroupMemberFormData,
+) -> Result<GroupMemberDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/groups/{}/members/{}",
+        &config.api_url, org_id, form.group_id, form.user_id
+    );
+    let response = Client::new()
+        .put(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to add group member. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "groups", Error::UserNotFound).await);
+    }
+
+    let member = response
+        .json::<GroupMemberDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse group member.".to_string(),
+        })?;
+
+    Ok(member)
+}
+
+pub async fn remove_group_member(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &GroupMemberFormData,
+) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/groups/{}/members/{}",
+        &config.api_url, org_id, form.group_id, form.user_id
+    );
+    let response = Client::new()
+        .delete(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to remove group member. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "groups", Error::UserNotFound).await);
+    }
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
+pub mod audit;
 pub mod auth;
 pub mod captcha;
 pub mod entries;
+pub mod groups;
+pub mod jwks;
 pub mod orgs;
+pub mod roles;
+pub mod service_accounts;
+pub mod sessions;
 pub mod token;
 pub mod users;
 pub mod vaults;
@@ -35,12 +41,20 @@ pub async fn handle_response_error(
             msg: format!("You have no permissions to view {}", resource),
         },
         StatusCode::NOT_FOUND => not_found,
+        StatusCode::PRECONDITION_FAILED | StatusCode::PRECONDITION_REQUIRED => Error::Conflict {
+            msg: "This item was changed by someone else since you opened it. Reload to see the latest version.".to_string(),
+        },
         _ => Error::Service {
             msg: "Service error. Try again later.".to_string(),
         },
     }
 }
 
+/// If-Match header value for a resource version
+pub fn if_match(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
 pub async fn parse_response_error(response: reqwest::Response) -> Result<String> {
     let Some(content_type) = response.headers().get("Content-Type") else {
         return Err(Error::Service {
@@ -1,4 +1,5 @@
 use reqwest::Client;
+use reqwest::header::IF_MATCH;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, ensure};
 
@@ -7,13 +8,16 @@ use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu};
 use crate::services::token::verify_csrf_token;
 use crate::{Error, Result};
 use dto::org::OrgDto;
+use dto::quota::OrgUsageDto;
 
-use super::handle_response_error;
+use super::{handle_response_error, if_match};
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct OrgFormSubmitData {
     pub name: String,
     pub token: String,
+    #[serde(default)]
+    pub version: i32,
 }
 
 #[derive(Clone, Serialize)]
@@ -105,6 +109,31 @@ pub async fn get_org(api_url: &str, token: &str, org_id: &str) -> Result<OrgDto>
     Ok(org)
 }
 
+pub async fn get_org_usage(api_url: &str, token: &str, org_id: &str) -> Result<OrgUsageDto> {
+    let url = format!("{}/orgs/{}/usage", api_url, org_id);
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to get org usage. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "orgs", Error::OrgNotFound).await);
+    }
+
+    let usage = response
+        .json::<OrgUsageDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse org usage.",
+        })?;
+
+    Ok(usage)
+}
+
 pub async fn update_org(
     config: &Config,
     token: &str,
@@ -121,6 +150,7 @@ pub async fn update_org(
     let response = Client::new()
         .patch(url)
         .bearer_auth(token)
+        .header(IF_MATCH, if_match(form.version))
         .json(&data)
         .send()
         .await
@@ -146,6 +176,7 @@ pub async fn delete_org(
     config: &Config,
     token: &str,
     org_id: &str,
+    version: i32,
     csrf_token: &str,
 ) -> Result<()> {
     let csrf_result = verify_csrf_token(&csrf_token, &config.jwt_secret)?;
@@ -155,6 +186,7 @@ pub async fn delete_org(
     let response = Client::new()
         .delete(url)
         .bearer_auth(token)
+        .header(IF_MATCH, if_match(version))
         .send()
         .await
         .context(HttpClientSnafu {
@@ -0,0 +1,172 @@
+use reqwest::Client;
+use reqwest::header::IF_MATCH;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, ensure};
+
+use crate::config::Config;
+use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu};
+use crate::services::token::verify_csrf_token;
+use crate::{Error, Result};
+use dto::role::RoleDto;
+
+use super::{handle_response_error, if_match};
+
+/// Permissions are posted as a comma separated list of the checked boxes
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct RoleFormData {
+    pub name: String,
+    pub permissions: String,
+    pub token: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct UpdateRoleFormData {
+    pub role_id: String,
+    pub version: i32,
+    pub name: String,
+    pub permissions: String,
+    pub token: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct DeleteRoleFormData {
+    pub role_id: String,
+    pub version: i32,
+    pub token: String,
+}
+
+#[derive(Clone, Serialize)]
+struct RoleData {
+    name: String,
+    permissions: String,
+}
+
+pub async fn list_roles(api_url: &str, token: &str, org_id: &str) -> Result<Vec<RoleDto>> {
+    let url = format!("{}/orgs/{}/roles", api_url, org_id);
+
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list roles. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "roles", Error::OrgNotFound).await);
+    }
+
+    let roles = response
+        .json::<Vec<RoleDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse roles.".to_string(),
+        })?;
+
+    Ok(roles)
+}
+
+pub async fn create_role(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &RoleFormData,
+) -> Result<RoleDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!("{}/orgs/{}/roles", &config.api_url, org_id);
+    let data = RoleData {
+        name: form.name.clone(),
+        permissions: form.permissions.clone(),
+    };
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to create role. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "roles", Error::OrgNotFound).await);
+    }
+
+    let role = response
+        .json::<RoleDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse role.".to_string(),
+        })?;
+
+    Ok(role)
+}
+
+pub async fn update_role(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &UpdateRoleFormData,
+) -> Result<RoleDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!("{}/orgs/{}/roles/{}", &config.api_url, org_id, form.role_id);
+    let data = RoleData {
+        name: form.name.clone(),
+        permissions: form.permissions.clone(),
+    };
+    let response = Client::new()
+        .patch(url)
+        .bearer_auth(token)
+        .header(IF_MATCH, if_match(form.version))
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to update role. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "roles", Error::RoleNotFound).await);
+    }
+
+    let role = response
+        .json::<RoleDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse role.".to_string(),
+        })?;
+
+    Ok(role)
+}
+
+pub async fn delete_role(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &DeleteRoleFormData,
+) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!("{}/orgs/{}/roles/{}", &config.api_url, org_id, form.role_id);
+    let response = Client::new()
+        .delete(url)
+        .bearer_auth(token)
+        .header(IF_MATCH, if_match(form.version))
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to delete role. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "roles", Error::RoleNotFound).await);
+    }
+
+    Ok(())
+}
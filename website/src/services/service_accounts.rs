@@ -0,0 +1,257 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, ensure};
+
+use crate::config::Config;
+use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu, ValidationSnafu};
+use crate::services::token::verify_csrf_token;
+use crate::{Error, Result};
+use dto::api_key::{ApiKeyDto, NewApiKeyDto};
+use dto::user::UserDto;
+
+use super::handle_response_error;
+
+const READ_PERMISSIONS: &str =
+    "orgs.view,vaults.list,vaults.view,entries.list,entries.view";
+const WRITE_PERMISSIONS: &str =
+    "orgs.view,vaults.list,vaults.view,entries.list,entries.view,entries.create,entries.edit";
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct ServiceAccountFormData {
+    pub username: String,
+    pub role: String,
+    pub token: String,
+}
+
+/// Keys are issued with either read-only or read-write access to entries
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ApiKeyFormData {
+    pub user_id: String,
+    pub name: String,
+    pub access: String,
+
+    /// Empty for all vaults granted to the account
+    pub vault_id: String,
+
+    /// Comma separated addresses or CIDR ranges, empty for any address
+    pub allowed_ips: String,
+
+    /// Empty for keys that never expire
+    pub expires_days: String,
+    pub token: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RevokeApiKeyFormData {
+    pub user_id: String,
+    pub key_id: String,
+    pub token: String,
+}
+
+#[derive(Clone, Serialize)]
+struct ServiceAccountData {
+    username: String,
+    roles: String,
+}
+
+#[derive(Clone, Serialize)]
+struct ApiKeyData {
+    name: String,
+    permissions: String,
+    vault_id: Option<String>,
+    allowed_ips: Option<String>,
+    expires_at: Option<i64>,
+}
+
+pub async fn list_service_accounts(
+    api_url: &str,
+    token: &str,
+    org_id: &str,
+) -> Result<Vec<UserDto>> {
+    let url = format!("{}/orgs/{}/service_accounts", api_url, org_id);
+
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list service accounts. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "service accounts", Error::OrgNotFound).await);
+    }
+
+    let users = response
+        .json::<Vec<UserDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse service accounts.".to_string(),
+        })?;
+
+    Ok(users)
+}
+
+pub async fn create_service_account(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &ServiceAccountFormData,
+) -> Result<UserDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!("{}/orgs/{}/service_accounts", &config.api_url, org_id);
+    let data = ServiceAccountData {
+        username: form.username.clone(),
+        roles: form.role.clone(),
+    };
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to create service account. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "service accounts", Error::OrgNotFound).await);
+    }
+
+    let user = response
+        .json::<UserDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse service account.".to_string(),
+        })?;
+
+    Ok(user)
+}
+
+pub async fn list_api_keys(
+    api_url: &str,
+    token: &str,
+    org_id: &str,
+    user_id: &str,
+) -> Result<Vec<ApiKeyDto>> {
+    let url = format!(
+        "{}/orgs/{}/service_accounts/{}/keys",
+        api_url, org_id, user_id
+    );
+
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list API keys. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "API keys", Error::UserNotFound).await);
+    }
+
+    let keys = response
+        .json::<Vec<ApiKeyDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse API keys.".to_string(),
+        })?;
+
+    Ok(keys)
+}
+
+pub async fn create_api_key(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &ApiKeyFormData,
+) -> Result<NewApiKeyDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let permissions = match form.access.as_str() {
+        "write" => WRITE_PERMISSIONS,
+        _ => READ_PERMISSIONS,
+    };
+    let expires_days = form.expires_days.trim();
+    let expires_at = match expires_days.is_empty() {
+        true => None,
+        false => match expires_days.parse::<i64>() {
+            Ok(days) => Some(chrono::Utc::now().timestamp() + days * 86400),
+            Err(_) => {
+                return ValidationSnafu {
+                    msg: "Expiry must be a number of days.",
+                }
+                .fail();
+            }
+        },
+    };
+    let data = ApiKeyData {
+        name: form.name.clone(),
+        permissions: permissions.to_string(),
+        vault_id: Some(form.vault_id.clone()).filter(|x| !x.is_empty()),
+        allowed_ips: Some(form.allowed_ips.replace(" ", "")).filter(|x| !x.is_empty()),
+        expires_at,
+    };
+
+    let url = format!(
+        "{}/orgs/{}/service_accounts/{}/keys",
+        &config.api_url, org_id, form.user_id
+    );
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to create API key. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "API keys", Error::UserNotFound).await);
+    }
+
+    let created = response
+        .json::<NewApiKeyDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse API key.".to_string(),
+        })?;
+
+    Ok(created)
+}
+
+pub async fn revoke_api_key(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    form: &RevokeApiKeyFormData,
+) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == org_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/service_accounts/{}/keys/{}",
+        &config.api_url, org_id, form.user_id, form.key_id
+    );
+    let response = Client::new()
+        .delete(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to revoke API key. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "API keys", Error::UserNotFound).await);
+    }
+
+    Ok(())
+}
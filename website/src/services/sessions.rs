@@ -0,0 +1,143 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, ensure};
+
+use crate::config::Config;
+use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu};
+use crate::services::token::verify_csrf_token;
+use crate::{Error, Result};
+use dto::session::SessionDto;
+
+use super::handle_response_error;
+
+/// Whose sessions are managed, the current user or a user of the org
+#[derive(Clone)]
+pub enum SessionOwner {
+    Current { user_id: String },
+    OrgUser { org_id: String, user_id: String },
+}
+
+impl SessionOwner {
+    fn api_url(&self, api_url: &str) -> String {
+        match self {
+            SessionOwner::Current { .. } => format!("{}/user/sessions", api_url),
+            SessionOwner::OrgUser { org_id, user_id } => {
+                format!("{}/orgs/{}/users/{}/sessions", api_url, org_id, user_id)
+            }
+        }
+    }
+
+    /// Website path of the sessions widget and its forms
+    pub fn path(&self) -> String {
+        match self {
+            SessionOwner::Current { .. } => "/profile/sessions".to_string(),
+            SessionOwner::OrgUser { org_id, user_id } => {
+                format!("/orgs/{}/users/{}/sessions", org_id, user_id)
+            }
+        }
+    }
+
+    /// Forms of the profile are bound to the user, the others to the org
+    pub fn csrf_subject(&self) -> &str {
+        match self {
+            SessionOwner::Current { user_id } => user_id,
+            SessionOwner::OrgUser { org_id, .. } => org_id,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct RevokeSessionFormData {
+    /// Empty when revoking all other sessions
+    #[serde(default)]
+    pub session_id: String,
+    pub token: String,
+}
+
+pub async fn list_sessions(
+    api_url: &str,
+    token: &str,
+    owner: &SessionOwner,
+) -> Result<Vec<SessionDto>> {
+    let response = Client::new()
+        .get(owner.api_url(api_url))
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list sessions. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "sessions", Error::UserNotFound).await);
+    }
+
+    let sessions = response
+        .json::<Vec<SessionDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse sessions.".to_string(),
+        })?;
+
+    Ok(sessions)
+}
+
+pub async fn revoke_session(
+    config: &Config,
+    token: &str,
+    owner: &SessionOwner,
+    form: &RevokeSessionFormData,
+) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == owner.csrf_subject(), CsrfTokenSnafu);
+
+    let url = format!("{}/{}", owner.api_url(&config.api_url), form.session_id);
+    let response = Client::new()
+        .delete(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to revoke session. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        let not_found = Error::NotFound {
+            msg: "Session not found".to_string(),
+        };
+        return Err(handle_response_error(response, "sessions", not_found).await);
+    }
+
+    Ok(())
+}
+
+/// Revokes all sessions of the owner except the current one
+pub async fn revoke_other_sessions(
+    config: &Config,
+    token: &str,
+    owner: &SessionOwner,
+    form: &RevokeSessionFormData,
+) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == owner.csrf_subject(), CsrfTokenSnafu);
+
+    let action = match owner {
+        SessionOwner::Current { .. } => "revoke_others",
+        SessionOwner::OrgUser { .. } => "revoke_all",
+    };
+    let url = format!("{}/{}", owner.api_url(&config.api_url), action);
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to revoke sessions. Try again later.".to_string(),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "sessions", Error::UserNotFound).await);
+    }
+
+    Ok(())
+}
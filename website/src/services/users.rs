@@ -1,4 +1,5 @@
 use reqwest::Client;
+use reqwest::header::IF_MATCH;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, ensure};
 
@@ -6,9 +7,10 @@ use crate::config::Config;
 use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu, ValidationSnafu};
 use crate::services::token::verify_csrf_token;
 use crate::{Error, Result};
+use dto::password_reset::NewPasswordResetDto;
 use dto::user::UserDto;
 
-use super::handle_response_error;
+use super::{handle_response_error, if_match};
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct NewUserFormData {
@@ -61,6 +63,11 @@ pub struct ResetPasswordData {
     pub password: String,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ResetLinkFormData {
+    pub token: String,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ChangePasswordFormData {
     pub token: String,
@@ -311,6 +318,44 @@ pub async fn reset_user_password(
     Ok(user)
 }
 
+/// Issues a one-time link for the user to choose a new password
+pub async fn create_password_reset(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    user_id: &str,
+    form: &ResetLinkFormData,
+) -> Result<NewPasswordResetDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == user_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/users/{}/reset_link",
+        &config.api_url, org_id, user_id
+    );
+    let response = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to create password reset link. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "users", Error::UserNotFound).await);
+    }
+
+    let created = response
+        .json::<NewPasswordResetDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse password reset information.",
+        })?;
+
+    Ok(created)
+}
+
 pub async fn change_user_password(
     config: &Config,
     token: &str,
@@ -356,6 +401,7 @@ pub async fn delete_user(
     token: &str,
     org_id: &str,
     user_id: &str,
+    version: i32,
     csrf_token: &str,
 ) -> Result<()> {
     let csrf_result = verify_csrf_token(&csrf_token, &config.jwt_secret)?;
@@ -364,6 +410,7 @@ pub async fn delete_user(
     let response = Client::new()
         .delete(url)
         .bearer_auth(token)
+        .header(IF_MATCH, if_match(version))
         .send()
         .await
         .context(HttpClientSnafu {
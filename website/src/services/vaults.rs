@@ -1,4 +1,5 @@
 use reqwest::Client;
+use reqwest::header::IF_MATCH;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, ensure};
 
@@ -6,9 +7,9 @@ use crate::config::Config;
 use crate::error::{CsrfTokenSnafu, HttpClientSnafu, HttpResponseParseSnafu};
 use crate::services::token::verify_csrf_token;
 use crate::{Error, Result};
-use dto::vault::VaultDto;
+use dto::vault::{VaultDto, VaultGroupDto, VaultMemberDto};
 
-use super::handle_response_error;
+use super::{handle_response_error, if_match};
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct NewVaultFormData {
@@ -23,6 +24,47 @@ pub struct NewVaultData {
     pub test_cipher: String,
 }
 
+#[derive(Clone, Deserialize, Serialize)]
+pub struct VaultFormSubmitData {
+    pub name: String,
+    pub description: String,
+    pub icon: String,
+    pub color: String,
+    pub token: String,
+    #[serde(default)]
+    pub version: i32,
+}
+
+#[derive(Clone, Serialize)]
+pub struct VaultSubmitData {
+    pub name: String,
+    pub description: String,
+    pub icon: String,
+    pub color: String,
+}
+
+/// Grants, changes or revokes the access of a user to the vault
+#[derive(Clone, Deserialize, Serialize)]
+pub struct VaultMemberFormData {
+    pub user_id: String,
+    #[serde(default)]
+    pub role: String,
+    pub token: String,
+}
+
+#[derive(Clone, Serialize)]
+struct VaultMemberData {
+    role: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct VaultGroupFormData {
+    pub group_id: String,
+    #[serde(default)]
+    pub role: String,
+    pub token: String,
+}
+
 pub async fn list_vaults(api_url: &str, token: &str, org_id: &str) -> Result<Vec<VaultDto>> {
     let url = format!("{}/orgs/{}/vaults", api_url, org_id);
 
@@ -119,11 +161,54 @@ pub async fn get_vault(
     Ok(vault)
 }
 
+pub async fn update_vault(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    form: &VaultFormSubmitData,
+) -> Result<VaultDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == vault_id, CsrfTokenSnafu);
+
+    let url = format!("{}/orgs/{}/vaults/{}", &config.api_url, org_id, vault_id);
+    let data = VaultSubmitData {
+        name: form.name.clone(),
+        description: form.description.clone(),
+        icon: form.icon.clone(),
+        color: form.color.clone(),
+    };
+    let response = Client::new()
+        .patch(url)
+        .bearer_auth(token)
+        .header(IF_MATCH, if_match(form.version))
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to update vault. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "vaults", Error::VaultNotFound).await);
+    }
+
+    let vault = response
+        .json::<VaultDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse vault information.",
+        })?;
+
+    Ok(vault)
+}
+
 pub async fn delete_vault(
     config: &Config,
     token: &str,
     org_id: &str,
     vault_id: &str,
+    version: i32,
     csrf_token: &str,
 ) -> Result<()> {
     let csrf_result = verify_csrf_token(&csrf_token, &config.jwt_secret)?;
@@ -132,6 +217,7 @@ pub async fn delete_vault(
     let response = Client::new()
         .delete(url)
         .bearer_auth(token)
+        .header(IF_MATCH, if_match(version))
         .send()
         .await
         .context(HttpClientSnafu {
@@ -144,3 +230,205 @@ pub async fn delete_vault(
 
     Ok(())
 }
+
+pub async fn list_vault_members(
+    api_url: &str,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+) -> Result<Vec<VaultMemberDto>> {
+    let url = format!("{}/orgs/{}/vaults/{}/members", api_url, org_id, vault_id);
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list vault members. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "vaults", Error::VaultNotFound).await);
+    }
+
+    let members = response
+        .json::<Vec<VaultMemberDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse vault members.",
+        })?;
+
+    Ok(members)
+}
+
+pub async fn save_vault_member(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    form: &VaultMemberFormData,
+) -> Result<VaultMemberDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == vault_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/vaults/{}/members/{}",
+        &config.api_url, org_id, vault_id, form.user_id
+    );
+    let data = VaultMemberData {
+        role: form.role.clone(),
+    };
+    let response = Client::new()
+        .put(url)
+        .bearer_auth(token)
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to save vault member. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "users", Error::UserNotFound).await);
+    }
+
+    let member = response
+        .json::<VaultMemberDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse vault member.",
+        })?;
+
+    Ok(member)
+}
+
+pub async fn delete_vault_member(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    form: &VaultMemberFormData,
+) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == vault_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/vaults/{}/members/{}",
+        &config.api_url, org_id, vault_id, form.user_id
+    );
+    let response = Client::new()
+        .delete(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to remove vault member. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "users", Error::UserNotFound).await);
+    }
+
+    Ok(())
+}
+
+pub async fn list_vault_groups(
+    api_url: &str,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+) -> Result<Vec<VaultGroupDto>> {
+    let url = format!("{}/orgs/{}/vaults/{}/groups", api_url, org_id, vault_id);
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to list vault groups. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "vaults", Error::VaultNotFound).await);
+    }
+
+    let groups = response
+        .json::<Vec<VaultGroupDto>>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse vault groups.",
+        })?;
+
+    Ok(groups)
+}
+
+pub async fn save_vault_group(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    form: &VaultGroupFormData,
+) -> Result<VaultGroupDto> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == vault_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/vaults/{}/groups/{}",
+        &config.api_url, org_id, vault_id, form.group_id
+    );
+    let data = VaultMemberData {
+        role: form.role.clone(),
+    };
+    let response = Client::new()
+        .put(url)
+        .bearer_auth(token)
+        .json(&data)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to save vault group. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "groups", Error::GroupNotFound).await);
+    }
+
+    let grant = response
+        .json::<VaultGroupDto>()
+        .await
+        .context(HttpResponseParseSnafu {
+            msg: "Unable to parse vault group.",
+        })?;
+
+    Ok(grant)
+}
+
+pub async fn delete_vault_group(
+    config: &Config,
+    token: &str,
+    org_id: &str,
+    vault_id: &str,
+    form: &VaultGroupFormData,
+) -> Result<()> {
+    let csrf_result = verify_csrf_token(&form.token, &config.jwt_secret)?;
+    ensure!(csrf_result == vault_id, CsrfTokenSnafu);
+
+    let url = format!(
+        "{}/orgs/{}/vaults/{}/groups/{}",
+        &config.api_url, org_id, vault_id, form.group_id
+    );
+    let response = Client::new()
+        .delete(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(HttpClientSnafu {
+            msg: "Unable to remove vault group. Try again later.",
+        })?;
+
+    if !response.status().is_success() {
+        return Err(handle_response_error(response, "groups", Error::GroupNotFound).await);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use askama::Template;
+use axum::{
+    Extension,
+    body::Body,
+    extract::{Query, State},
+    response::Response,
+};
+use snafu::ResultExt;
+
+use crate::{
+    Result,
+    ctx::Ctx,
+    error::{ResponseBuilderSnafu, TemplateSnafu},
+    models::{PaginationLinks, Pref, TemplateData, options::SelectOption},
+    run::AppState,
+    services::{
+        audit::{AuditParams, list_audit_events},
+        orgs::get_org,
+    },
+};
+use dto::{actor::Actor, audit::AuditAction, audit::AuditEventDto, org::OrgDto};
+
+use super::{Action, Resource, enforce_policy};
+
+struct AuditEventView {
+    event: AuditEventDto,
+    created: String,
+}
+
+impl From<AuditEventDto> for AuditEventView {
+    fn from(event: AuditEventDto) -> Self {
+        let created = chrono::DateTime::from_timestamp(event.created_at, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_default();
+        Self { event, created }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "pages/audit.html")]
+struct AuditPageTemplate {
+    t: TemplateData,
+    org: OrgDto,
+    base_url: String,
+    breadcrumb_orgs: bool,
+    events: Vec<AuditEventView>,
+    pagination: Option<PaginationLinks>,
+    action: String,
+    actor_id: String,
+    target_type: String,
+    target_id: String,
+    action_options: Vec<SelectOption>,
+}
+
+fn create_action_options() -> Vec<SelectOption> {
+    AuditAction::all()
+        .into_iter()
+        .map(|action| SelectOption {
+            value: action.to_string(),
+            label: action.to_string(),
+        })
+        .collect()
+}
+
+async fn render_audit_page(
+    state: &AppState,
+    pref: &Pref,
+    actor: &Actor,
+    token: &str,
+    org: OrgDto,
+    base_url: String,
+    params: AuditParams,
+) -> Result<Response<Body>> {
+    let mut t = TemplateData::new(state, Some(actor.clone()), pref);
+    t.title = format!("Audit Log - {}", &org.name);
+
+    let listing = list_audit_events(&state.config.api_url, token, &org.id, &params).await?;
+
+    let mut pagination: Option<PaginationLinks> = None;
+    if listing.meta.total_pages > 1 {
+        pagination = Some(PaginationLinks::new(
+            &listing.meta,
+            &base_url,
+            &params.filter_suffix(),
+        ));
+    }
+
+    let tpl = AuditPageTemplate {
+        t,
+        org,
+        base_url,
+        breadcrumb_orgs: actor.is_system_admin(),
+        events: listing.data.into_iter().map(|e| e.into()).collect(),
+        pagination,
+        action: params.action.unwrap_or_default(),
+        actor_id: params.actor_id.unwrap_or_default(),
+        target_type: params.target_type.unwrap_or_default(),
+        target_id: params.target_id.unwrap_or_default(),
+        action_options: create_action_options(),
+    };
+
+    Response::builder()
+        .status(200)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+/// Audit log of the current user's org
+pub async fn audit_page_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(pref): Extension<Pref>,
+    State(state): State<AppState>,
+    Query(params): Query<AuditParams>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Audit, Action::Read)?;
+
+    let token = ctx.token().expect("token is required");
+    let org = get_org(&state.config.api_url, token, &actor.org_id).await?;
+
+    render_audit_page(
+        &state,
+        &pref,
+        actor,
+        token,
+        org,
+        "/audit".to_string(),
+        params,
+    )
+    .await
+}
+
+/// Audit log of any org, for system admins
+pub async fn org_audit_page_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(pref): Extension<Pref>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    Query(params): Query<AuditParams>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Audit, Action::Read)?;
+
+    let token = ctx.token().expect("token is required");
+    let base_url = format!("/orgs/{}/audit", &org.id);
+
+    render_audit_page(&state, &pref, actor, token, org, base_url, params).await
+}
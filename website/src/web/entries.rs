@@ -3,13 +3,17 @@ use axum::extract::Query;
 use axum::http::StatusCode;
 use axum::{Extension, Form, body::Body, extract::State, response::Response};
 use snafu::ResultExt;
+use tower_cookies::{Cookie, Cookies, cookie::time::Duration};
 use urlencoding::encode;
 
 use crate::models::PaginationLinks;
-use crate::models::tokens::TokenFormData;
+use crate::models::tokens::{TokenFormData, VersionedTokenFormData};
 use crate::services::entries::{
-    EntryFormData, SearchEntriesParams, create_entry, delete_entry, list_entries, update_entry,
+    BulkEntriesFormData, CopyEntryFormData, EntryFormData, SearchEntriesParams, copy_entry,
+    create_entry, delete_entries, delete_entry, get_entry_favorite, list_entries,
+    toggle_entry_favorite, update_entry,
 };
+use crate::services::vaults::list_vaults;
 use crate::{
     Error, Result,
     ctx::Ctx,
@@ -17,9 +21,10 @@ use crate::{
     models::{Pref, TemplateData},
     run::AppState,
     services::token::create_csrf_token,
-    web::{Action, Resource, enforce_policy},
+    web::{Action, ENTRY_SORT_COOKIE, Resource, enforce_policy, handle_error_message},
 };
-use dto::entry::EntryDto;
+use dto::entry::{EntryDto, EntrySort, RotationStatus};
+use dto::pagination::SortOrder;
 use dto::vault::VaultDto;
 
 #[derive(Template)]
@@ -28,39 +33,148 @@ struct SearchEntriesTemplate {
     vault: VaultDto,
     entries: Vec<EntryDto>,
     pagination: Option<PaginationLinks>,
+    sort_links: Vec<SortLink>,
     can_create: bool,
+    can_delete: bool,
+    bulk_token: String,
     error_message: Option<String>,
+    now: i64,
+}
+
+/// Badge shown next to entries whose password has to be changed
+struct RotationBadge {
+    label: &'static str,
+    class: &'static str,
+}
+
+impl SearchEntriesTemplate {
+    fn rotation_badge(&self, entry: &EntryDto) -> Option<RotationBadge> {
+        match entry.rotation_status(self.vault.rotation_days, self.now)? {
+            RotationStatus::Overdue => Some(RotationBadge {
+                label: "Rotate now",
+                class: "is-danger",
+            }),
+            RotationStatus::DueSoon => Some(RotationBadge {
+                label: "Rotate soon",
+                class: "is-warning",
+            }),
+            RotationStatus::Current => None,
+        }
+    }
+}
+
+/// Column header that sorts the entries, toggles the order when already active
+struct SortLink {
+    label: String,
+    url: String,
+    active: bool,
+    ascending: bool,
+}
+
+fn create_sort_links(
+    query: &SearchEntriesParams,
+    sort: EntrySort,
+    order: SortOrder,
+) -> Vec<SortLink> {
+    let columns = vec![
+        (EntrySort::Label, "Label"),
+        (EntrySort::CreatedAt, "Created"),
+        (EntrySort::UpdatedAt, "Updated"),
+        (EntrySort::LastUsed, "Last used"),
+    ];
+
+    columns
+        .into_iter()
+        .map(|(column, label)| {
+            let active = column == sort;
+            let next_order = if active {
+                order.reverse()
+            } else {
+                column.default_order()
+            };
+
+            let mut url = format!(
+                "?page=1&per_page={}&sort={}&order={}",
+                query.per_page.unwrap_or(10),
+                column,
+                next_order
+            );
+            if let Some(keyword) = &query.keyword {
+                url.push_str(&format!("&keyword={}", encode(keyword)));
+            }
+
+            SortLink {
+                label: label.to_string(),
+                url,
+                active,
+                ascending: order == SortOrder::Asc,
+            }
+        })
+        .collect()
 }
 
 pub async fn search_entries_handler(
+    cookies: Cookies,
     Extension(ctx): Extension<Ctx>,
+    Extension(pref): Extension<Pref>,
     Extension(vault): Extension<VaultDto>,
     State(state): State<AppState>,
-    Query(query): Query<SearchEntriesParams>,
+    Query(mut query): Query<SearchEntriesParams>,
 ) -> Result<Response<Body>> {
     let actor = ctx.actor().expect("actor is required");
     let _ = enforce_policy(actor, Resource::Entry, Action::Read)?;
 
+    // Remember the chosen sort, otherwise use the last one
+    match query.sort {
+        Some(sort) => {
+            let order = query.order.unwrap_or(sort.default_order());
+            query.order = Some(order);
+
+            let sort_cookie = Cookie::build((ENTRY_SORT_COOKIE, format!("{}:{}", sort, order)))
+                .http_only(true)
+                .max_age(Duration::days(365))
+                .secure(state.config.ssl)
+                .path("/")
+                .build();
+
+            cookies.add(sort_cookie);
+        }
+        None => {
+            query.sort = Some(pref.entry_sort);
+            query.order = Some(pref.entry_order);
+        }
+    }
+
+    let sort = query.sort.unwrap_or_default();
+    let order = query.order.unwrap_or(sort.default_order());
+
     let oid = vault.org_id.clone();
     let vid = vault.id.clone();
+    let token = ctx.token().expect("token is required");
+
+    let can_create = enforce_policy(actor, Resource::Entry, Action::Create).is_ok();
+    let can_delete = enforce_policy(actor, Resource::Entry, Action::Delete).is_ok();
 
     let mut tpl = SearchEntriesTemplate {
         vault,
         entries: Vec::new(),
         pagination: None,
-        can_create: enforce_policy(actor, Resource::Entry, Action::Create).is_ok(),
+        sort_links: create_sort_links(&query, sort, order),
+        can_create,
+        can_delete,
+        bulk_token: create_csrf_token(&vid, &state.config.jwt_secret)?,
         error_message: None,
+        now: chrono::Utc::now().timestamp(),
     };
 
-    let token = ctx.token().expect("token is required");
     match list_entries(&state.config.api_url, token, &oid, &vid, &query).await {
         Ok(entries) => {
-            let mut keyword_param: String = "".to_string();
-            if let Some(keyword) = &query.keyword {
-                keyword_param = format!("&keyword={}", encode(keyword).to_string());
-            }
             tpl.entries = entries.data;
-            tpl.pagination = Some(PaginationLinks::new(&entries.meta, "", &keyword_param));
+            tpl.pagination = Some(PaginationLinks::new(
+                &entries.meta,
+                "",
+                &query.filter_suffix(),
+            ));
 
             Ok(Response::builder()
                 .status(200)
@@ -79,6 +193,31 @@ pub async fn search_entries_handler(
     }
 }
 
+pub async fn post_bulk_entries_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(vault): Extension<VaultDto>,
+    State(state): State<AppState>,
+    Form(pairs): Form<Vec<(String, String)>>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Entry, Action::Delete)?;
+
+    let form = BulkEntriesFormData::from_pairs(pairs);
+    let token = ctx.token().expect("token is required");
+    let config = state.config.clone();
+
+    let result = delete_entries(&config, token, &vault.org_id, &vault.id, &form).await;
+
+    match result {
+        Ok(_) => Ok(Response::builder()
+            .status(200)
+            .header("HX-Redirect", format!("/vaults/{}", &vault.id))
+            .body(Body::from(""))
+            .context(ResponseBuilderSnafu)?),
+        Err(err) => Ok(handle_error_message(&err)),
+    }
+}
+
 #[derive(Template)]
 #[template(path = "pages/new_entry.html")]
 struct NewEntryTemplate {
@@ -122,6 +261,7 @@ pub async fn new_entry_handler(
             cipher_notes: None,
             cipher_extra_notes: None,
             token,
+            version: 0,
         },
         error_message: None,
     };
@@ -156,6 +296,7 @@ pub async fn post_new_entry_handler(
             cipher_notes: None,
             cipher_extra_notes: None,
             token,
+            version: 0,
         },
         error_message: None,
     };
@@ -169,6 +310,7 @@ pub async fn post_new_entry_handler(
         cipher_notes: payload.cipher_notes.clone(),
         cipher_extra_notes: payload.cipher_extra_notes.clone(),
         token: payload.token.clone(),
+        version: 0,
     };
 
     let token = ctx.token().expect("token is required");
@@ -209,6 +351,9 @@ struct EntryTemplate {
     updated: bool,
     can_edit: bool,
     can_delete: bool,
+    can_copy: bool,
+    favorite: bool,
+    payload: TokenFormData,
 }
 
 pub async fn entry_page_handler(
@@ -226,6 +371,18 @@ pub async fn entry_page_handler(
     t.styles = vec![config.assets.gallery_css.clone()];
     t.scripts = vec![config.assets.gallery_js.clone()];
 
+    let auth_token = ctx.token().expect("token is required");
+    let favorite = get_entry_favorite(
+        &config.api_url,
+        auth_token,
+        &vault.org_id,
+        &vault.id,
+        &entry.id,
+    )
+    .await?;
+
+    let token = create_csrf_token(&entry.id, &config.jwt_secret)?;
+
     let tpl = EntryTemplate {
         t,
         vault,
@@ -233,6 +390,54 @@ pub async fn entry_page_handler(
         updated: false,
         can_edit: enforce_policy(actor, Resource::Entry, Action::Update).is_ok(),
         can_delete: enforce_policy(actor, Resource::Entry, Action::Delete).is_ok(),
+        can_copy: enforce_policy(actor, Resource::Entry, Action::Create).is_ok(),
+        favorite: favorite.favorite,
+        payload: TokenFormData { token },
+    };
+
+    Response::builder()
+        .status(200)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+#[derive(Template)]
+#[template(path = "widgets/favorite_entry.html")]
+struct FavoriteEntryTemplate {
+    vault: VaultDto,
+    entry: EntryDto,
+    favorite: bool,
+    payload: TokenFormData,
+}
+
+pub async fn post_favorite_entry_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(entry): Extension<EntryDto>,
+    State(state): State<AppState>,
+    payload: Form<TokenFormData>,
+) -> Result<Response<Body>> {
+    let config = state.config.clone();
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Entry, Action::Read)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let favorite = toggle_entry_favorite(
+        &config,
+        auth_token,
+        &vault.org_id,
+        &vault.id,
+        &entry.id,
+        &payload.token,
+    )
+    .await?;
+
+    let token = create_csrf_token(&entry.id, &config.jwt_secret)?;
+    let tpl = FavoriteEntryTemplate {
+        vault,
+        entry,
+        favorite: favorite.favorite,
+        payload: TokenFormData { token },
     };
 
     Ok(Response::builder()
@@ -249,6 +454,7 @@ struct EditEntryControlsTemplate {
     updated: bool,
     can_edit: bool,
     can_delete: bool,
+    can_copy: bool,
 }
 
 /// Simply re-renders the edit and delete entry controls
@@ -258,7 +464,7 @@ pub async fn edit_entry_controls_handler(
     Extension(entry): Extension<EntryDto>,
 ) -> Result<Response<Body>> {
     let actor = ctx.actor().expect("actor is required");
-    let _ = enforce_policy(actor, Resource::Entry, Action::Update)?;
+    enforce_policy(actor, Resource::Entry, Action::Read)?;
 
     let tpl = EditEntryControlsTemplate {
         vault,
@@ -266,6 +472,7 @@ pub async fn edit_entry_controls_handler(
         updated: false,
         can_edit: enforce_policy(actor, Resource::Entry, Action::Update).is_ok(),
         can_delete: enforce_policy(actor, Resource::Entry, Action::Delete).is_ok(),
+        can_copy: enforce_policy(actor, Resource::Entry, Action::Create).is_ok(),
     };
 
     Ok(Response::builder()
@@ -307,6 +514,7 @@ pub async fn edit_entry_handler(
             cipher_notes: entry.cipher_notes,
             cipher_extra_notes: entry.cipher_extra_notes,
             token,
+            version: entry.version,
         },
         error_message: None,
     };
@@ -317,6 +525,14 @@ pub async fn edit_entry_handler(
         .context(ResponseBuilderSnafu)?)
 }
 
+#[derive(Template)]
+#[template(path = "widgets/entry_conflict.html")]
+struct EntryConflictTemplate {
+    vault: VaultDto,
+    entry: EntryDto,
+    submitted_label: String,
+}
+
 /// Handles the edit entry submission
 pub async fn post_edit_entry_handler(
     Extension(ctx): Extension<Ctx>,
@@ -345,11 +561,13 @@ pub async fn post_edit_entry_handler(
             cipher_notes: None,
             cipher_extra_notes: None,
             token,
+            version: 0,
         },
         error_message: None,
     };
 
     tpl.payload.label = payload.label.clone();
+    tpl.payload.version = payload.version;
 
     let token = ctx.token().expect("token is required");
     let result = update_entry(&config, token, &oid, &vid, &entry_id, &payload).await;
@@ -362,6 +580,7 @@ pub async fn post_edit_entry_handler(
                 updated: true,
                 can_edit: enforce_policy(actor, Resource::Entry, Action::Update).is_ok(),
                 can_delete: enforce_policy(actor, Resource::Entry, Action::Delete).is_ok(),
+                can_copy: enforce_policy(actor, Resource::Entry, Action::Create).is_ok(),
             };
             Ok(Response::builder()
                 .status(200)
@@ -371,6 +590,18 @@ pub async fn post_edit_entry_handler(
         Err(err) => {
             let status;
             match err {
+                Error::Conflict { .. } => {
+                    // Entry from the middleware is the latest version
+                    let tpl = EntryConflictTemplate {
+                        vault,
+                        entry,
+                        submitted_label: payload.label.clone(),
+                    };
+                    return Response::builder()
+                        .status(409)
+                        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+                        .context(ResponseBuilderSnafu);
+                }
                 Error::Validation { msg } => {
                     status = 400;
                     tpl.error_message = Some(msg);
@@ -398,7 +629,7 @@ pub async fn post_edit_entry_handler(
 struct DeleteEntryTemplate {
     vault: VaultDto,
     entry: EntryDto,
-    payload: TokenFormData,
+    payload: VersionedTokenFormData,
     error_message: Option<String>,
 }
 
@@ -414,10 +645,11 @@ pub async fn get_delete_entry_handler(
     let _ = enforce_policy(actor, Resource::Entry, Action::Delete)?;
     let token = create_csrf_token(&entry.id, &config.jwt_secret)?;
 
+    let version = entry.version;
     let tpl = DeleteEntryTemplate {
         vault,
         entry,
-        payload: TokenFormData { token },
+        payload: VersionedTokenFormData { token, version },
         error_message: None,
     };
 
@@ -433,7 +665,7 @@ pub async fn post_delete_entry_handler(
     Extension(vault): Extension<VaultDto>,
     Extension(entry): Extension<EntryDto>,
     State(state): State<AppState>,
-    payload: Form<TokenFormData>,
+    payload: Form<VersionedTokenFormData>,
 ) -> Result<Response<Body>> {
     let config = state.config.clone();
     let actor = ctx.actor().expect("actor is required");
@@ -450,6 +682,7 @@ pub async fn post_delete_entry_handler(
         &vault.org_id,
         &vault.id,
         &entry.id,
+        payload.version,
         &payload.token,
     )
     .await;
@@ -462,8 +695,9 @@ pub async fn post_delete_entry_handler(
             let tpl = DeleteEntryTemplate {
                 vault,
                 entry,
-                payload: TokenFormData {
+                payload: VersionedTokenFormData {
                     token: "".to_string(),
+                    version: 0,
                 },
                 error_message: None,
             };
@@ -478,10 +712,12 @@ pub async fn post_delete_entry_handler(
             let error_message = Some(error_info.message);
 
             // Just render the form on first load or on error
+            // Carries the latest version so a retry confirms the current entry
+            let version = entry.version;
             let tpl = DeleteEntryTemplate {
                 vault,
                 entry,
-                payload: TokenFormData { token },
+                payload: VersionedTokenFormData { token, version },
                 error_message,
             };
 
@@ -492,3 +728,114 @@ pub async fn post_delete_entry_handler(
         }
     }
 }
+
+#[derive(Template)]
+#[template(path = "widgets/move_entry_form.html")]
+struct MoveEntryFormTemplate {
+    vault: VaultDto,
+    entry: EntryDto,
+    payload: CopyEntryFormData,
+    target_vaults: Vec<VaultDto>,
+    error_message: Option<String>,
+}
+
+/// Renders the form to copy the entry to another vault
+pub async fn get_move_entry_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(entry): Extension<EntryDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let config = state.config.clone();
+    let actor = ctx.actor().expect("actor is required");
+
+    enforce_policy(actor, Resource::Entry, Action::Create)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let mut target_vaults = list_vaults(&config.api_url, auth_token, &vault.org_id).await?;
+    target_vaults.retain(|x| x.id != vault.id);
+
+    let token = create_csrf_token(&entry.id, &config.jwt_secret)?;
+    let payload = CopyEntryFormData {
+        target_vault_id: "".to_string(),
+        cipher_username: entry.cipher_username.clone(),
+        cipher_password: entry.cipher_password.clone(),
+        cipher_notes: entry.cipher_notes.clone(),
+        cipher_extra_notes: entry.cipher_extra_notes.clone(),
+        token,
+    };
+
+    let tpl = MoveEntryFormTemplate {
+        vault,
+        entry,
+        payload,
+        target_vaults,
+        error_message: None,
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+/// Copies the entry then redirect to the copy or show error
+pub async fn post_move_entry_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(vault): Extension<VaultDto>,
+    Extension(entry): Extension<EntryDto>,
+    State(state): State<AppState>,
+    payload: Form<CopyEntryFormData>,
+) -> Result<Response<Body>> {
+    let config = state.config.clone();
+    let actor = ctx.actor().expect("actor is required");
+
+    enforce_policy(actor, Resource::Entry, Action::Create)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = copy_entry(
+        &config,
+        auth_token,
+        &vault.org_id,
+        &vault.id,
+        &entry.id,
+        &payload,
+    )
+    .await;
+
+    match result {
+        Ok(copied) => Ok(Response::builder()
+            .status(200)
+            .header(
+                "HX-Redirect",
+                format!("/vaults/{}/entries/{}", &copied.vault_id, &copied.id),
+            )
+            .body(Body::from("".to_string()))
+            .context(ResponseBuilderSnafu)?),
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+
+            let mut target_vaults = list_vaults(&config.api_url, auth_token, &vault.org_id)
+                .await
+                .unwrap_or_default();
+            target_vaults.retain(|x| x.id != vault.id);
+
+            // Render the form again with a fresh token
+            let mut payload = payload.0;
+            payload.token = create_csrf_token(&entry.id, &config.jwt_secret)?;
+
+            let tpl = MoveEntryFormTemplate {
+                vault,
+                entry,
+                payload,
+                target_vaults,
+                error_message: Some(error_info.message),
+            };
+
+            Ok(Response::builder()
+                .status(error_info.status_code)
+                .body(Body::from(tpl.render().context(TemplateSnafu)?))
+                .context(ResponseBuilderSnafu)?)
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use askama::Template;
+use axum::{Extension, Form, body::Body, extract::State, http::StatusCode, response::Response};
+use dto::group::{GroupDto, GroupMemberDto};
+use dto::org::OrgDto;
+use dto::role::{Role, RoleDto};
+use dto::user::UserDto;
+use snafu::ResultExt;
+
+use crate::models::options::SelectOption;
+use crate::services::groups::{
+    DeleteGroupFormData, GroupMemberFormData, NewGroupFormData, add_group_member, create_group,
+    delete_group, list_group_members, list_groups, remove_group_member,
+};
+use crate::services::roles::list_roles;
+use crate::services::users::list_users;
+use crate::web::roles::{create_role_options, role_name};
+use crate::{
+    Result,
+    ctx::Ctx,
+    error::{ErrorInfo, ResponseBuilderSnafu, TemplateSnafu},
+    models::{Pref, TemplateData},
+    run::AppState,
+    services::token::create_csrf_token,
+    web::{Action, Resource, enforce_policy},
+};
+
+#[derive(Template)]
+#[template(path = "pages/groups.html")]
+struct GroupsPageTemplate {
+    t: TemplateData,
+    org: OrgDto,
+}
+
+pub async fn groups_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(pref): Extension<Pref>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Read)?;
+
+    let mut t = TemplateData::new(&state, Some(actor.clone()), &pref);
+    t.title = String::from("Groups");
+
+    let tpl = GroupsPageTemplate { t, org };
+
+    Response::builder()
+        .status(200)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+struct GroupItem {
+    group: GroupDto,
+    members: Vec<GroupMemberDto>,
+
+    /// Org users not yet in the group
+    candidates: Vec<UserDto>,
+}
+
+#[derive(Template)]
+#[template(path = "widgets/groups.html")]
+struct GroupsTemplate {
+    org: OrgDto,
+    groups: Vec<GroupItem>,
+    payload: NewGroupFormData,
+    roles: Vec<RoleDto>,
+    role_options: Vec<SelectOption>,
+    can_create: bool,
+    can_edit: bool,
+    can_delete: bool,
+    error_message: Option<String>,
+}
+
+impl GroupsTemplate {
+    fn role_name(&self, role: &Role) -> String {
+        role_name(role, &self.roles)
+    }
+}
+
+/// Renders the groups of the org with their members
+async fn render_groups(
+    ctx: &Ctx,
+    state: &AppState,
+    org: OrgDto,
+    payload: Option<NewGroupFormData>,
+    error_message: Option<String>,
+    status: StatusCode,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    let auth_token = ctx.token().expect("token is required");
+    let config = state.config.clone();
+
+    let users = list_users(&config.api_url, auth_token, &org.id).await?;
+    let roles = list_roles(&config.api_url, auth_token, &org.id).await?;
+    let mut groups: Vec<GroupItem> = Vec::new();
+    for group in list_groups(&config.api_url, auth_token, &org.id).await? {
+        let members = list_group_members(&config.api_url, auth_token, &org.id, &group.id).await?;
+        let candidates = users
+            .iter()
+            .filter(|x| !members.iter().any(|m| m.user_id == x.id))
+            .cloned()
+            .collect();
+        groups.push(GroupItem {
+            group,
+            members,
+            candidates,
+        });
+    }
+
+    let token = create_csrf_token(&org.id, &config.jwt_secret)?;
+    let payload = match payload {
+        Some(payload) => NewGroupFormData { token, ..payload },
+        None => NewGroupFormData {
+            token,
+            ..Default::default()
+        },
+    };
+
+    let tpl = GroupsTemplate {
+        org,
+        groups,
+        payload,
+        role_options: create_role_options(&roles),
+        roles,
+        can_create: enforce_policy(actor, Resource::User, Action::Create).is_ok(),
+        can_edit: enforce_policy(actor, Resource::User, Action::Update).is_ok(),
+        can_delete: enforce_policy(actor, Resource::User, Action::Delete).is_ok(),
+        error_message,
+    };
+
+    Response::builder()
+        .status(status)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+pub async fn groups_widget_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Read)?;
+
+    render_groups(&ctx, &state, org, None, None, StatusCode::OK).await
+}
+
+pub async fn post_new_group_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<NewGroupFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Create)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = create_group(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_groups(&ctx, &state, org, None, None, StatusCode::OK).await,
+        Err(err) => {
+            // Keep the form values so the user can fix them
+            let error_info = ErrorInfo::from(&err);
+            let payload = Some(payload.0);
+            let error_message = Some(error_info.message);
+            render_groups(
+                &ctx,
+                &state,
+                org,
+                payload,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn post_delete_group_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<DeleteGroupFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Delete)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = delete_group(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_groups(&ctx, &state, org, None, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_groups(
+                &ctx,
+                &state,
+                org,
+                None,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn post_group_member_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<GroupMemberFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Update)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = add_group_member(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_groups(&ctx, &state, org, None, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_groups(
+                &ctx,
+                &state,
+                org,
+                None,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn post_delete_group_member_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<GroupMemberFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Update)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = remove_group_member(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_groups(&ctx, &state, org, None, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_groups(
+                &ctx,
+                &state,
+                org,
+                None,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
@@ -12,9 +12,11 @@ use crate::{
     ctx::Ctx,
     error::{ResponseBuilderSnafu, TemplateSnafu},
     models::TemplateData,
+    services::entries::{list_favorite_entries, list_recent_entries},
     services::vaults::list_vaults,
 };
 use crate::{models::Pref, run::AppState};
+use dto::entry::EntryLinkDto;
 use dto::vault::VaultDto;
 
 use super::{Action, Resource, enforce_policy};
@@ -24,6 +26,9 @@ use super::{Action, Resource, enforce_policy};
 struct IndexTemplate {
     t: TemplateData,
     vaults: Vec<VaultDto>,
+    favorites: Vec<EntryLinkDto>,
+    recents: Vec<EntryLinkDto>,
+    can_view_audit: bool,
 }
 
 pub async fn index_handler(
@@ -45,7 +50,20 @@ pub async fn index_handler(
     let token = ctx.token().expect("token is required");
     let vaults = list_vaults(&state.config.api_url, token, &actor.org_id).await?;
 
-    let tpl = IndexTemplate { t, vaults };
+    let mut favorites: Vec<EntryLinkDto> = Vec::new();
+    let mut recents: Vec<EntryLinkDto> = Vec::new();
+    if enforce_policy(actor, Resource::Entry, Action::Read).is_ok() {
+        favorites = list_favorite_entries(&state.config.api_url, token).await?;
+        recents = list_recent_entries(&state.config.api_url, token).await?;
+    }
+
+    let tpl = IndexTemplate {
+        t,
+        vaults,
+        favorites,
+        recents,
+        can_view_audit: enforce_policy(actor, Resource::Audit, Action::Read).is_ok(),
+    };
 
     Ok(Response::builder()
         .status(200)
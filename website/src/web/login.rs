@@ -4,7 +4,7 @@ use askama::Template;
 use axum::{
     body::Body,
     extract::{Form, Query, State},
-    http::Response,
+    http::{HeaderMap, Response},
     response::{IntoResponse, Redirect},
 };
 use snafu::ResultExt;
@@ -16,14 +16,14 @@ use crate::{
     error::{ResponseBuilderSnafu, TemplateSnafu},
     models::{LoginFormPayload, TemplateData},
     services::{
-        auth::{AuthPayload, authenticate},
+        auth::{AuthPayload, AuthResponse, authenticate},
         captcha::validate_catpcha,
     },
 };
 use crate::{error::ErrorInfo, models::Pref, run::AppState};
 use dto::actor::Actor;
 
-use super::AUTH_TOKEN_COOKIE;
+use super::{AUTH_TOKEN_COOKIE, REFRESH_TOKEN_COOKIE};
 
 #[derive(Template)]
 #[template(path = "pages/login.html")]
@@ -73,6 +73,7 @@ pub async fn login_handler(
 
 pub async fn post_login_handler(
     cookies: Cookies,
+    headers: HeaderMap,
     State(state): State<AppState>,
     Form(login_payload): Form<LoginFormPayload>,
 ) -> impl IntoResponse {
@@ -110,6 +111,8 @@ pub async fn post_login_handler(
     let auth_payload = AuthPayload {
         username: login_payload.username,
         password: login_payload.password,
+        client_ip: client_ip(&headers),
+        user_agent: user_agent(&headers),
     };
     let login_result = authenticate(&config.api_url, auth_payload).await;
     let auth = match login_result {
@@ -119,16 +122,62 @@ pub async fn post_login_handler(
         }
     };
 
+    set_auth_cookies(&cookies, &auth, state.config.ssl);
+
+    Redirect::to("/").into_response()
+}
+
+/// Stores the tokens of a login or refresh
+pub fn set_auth_cookies(cookies: &Cookies, auth: &AuthResponse, ssl: bool) {
     let auth_cookie = Cookie::build((AUTH_TOKEN_COOKIE, auth.token.clone()))
         .http_only(true)
-        .max_age(Duration::weeks(1))
-        .secure(state.config.ssl)
+        .max_age(Duration::seconds(auth.expires_in))
+        .secure(ssl)
+        .path("/")
+        .build();
+
+    // Outlives the access token so that it can be refreshed
+    let refresh_cookie = Cookie::build((REFRESH_TOKEN_COOKIE, auth.refresh_token.clone()))
+        .http_only(true)
+        .max_age(Duration::days(30))
+        .secure(ssl)
         .path("/")
         .build();
 
     cookies.add(auth_cookie);
+    cookies.add(refresh_cookie);
+}
 
-    Redirect::to("/").into_response()
+pub fn remove_auth_cookies(cookies: &Cookies) {
+    cookies.remove(Cookie::new(AUTH_TOKEN_COOKIE, ""));
+    cookies.remove(Cookie::new(REFRESH_TOKEN_COOKIE, ""));
+}
+
+/// Client address as reported by the reverse proxy in front of the website
+///
+/// Only the last `X-Forwarded-For` hop is added by the proxy, the ones before
+/// it come from the client.
+pub fn client_ip(headers: &HeaderMap) -> Option<String> {
+    let real_ip = headers
+        .get("X-Real-IP")
+        .and_then(|value| value.to_str().ok());
+
+    let forwarded = headers
+        .get("X-Forwarded-For")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next());
+
+    real_ip
+        .or(forwarded)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("User-Agent")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
 }
 
 fn handle_error(error: Error) -> Response<Body> {
@@ -1,10 +1,28 @@
-use axum::{http::Response, response::IntoResponse};
-use tower_cookies::{Cookie, Cookies};
+use axum::{Extension, extract::State, http::Response, response::IntoResponse};
+use tower_cookies::Cookies;
+use tracing::error;
 
-use super::AUTH_TOKEN_COOKIE;
+use crate::{
+    ctx::Ctx,
+    run::AppState,
+    services::auth::{forget_token, logout},
+};
 
-pub async fn logout_handler(cookies: Cookies) -> impl IntoResponse {
-    cookies.remove(Cookie::new(AUTH_TOKEN_COOKIE, ""));
+use super::remove_auth_cookies;
+
+pub async fn logout_handler(
+    Extension(ctx): Extension<Ctx>,
+    State(state): State<AppState>,
+    cookies: Cookies,
+) -> impl IntoResponse {
+    // End the session on the API too, not just in this browser
+    if let Some(token) = ctx.token() {
+        forget_token(&state.actor_cache, token).await;
+        if let Err(err) = logout(&state.config.api_url, token).await {
+            error!("Unable to end session: {}", err);
+        }
+    }
+    remove_auth_cookies(&cookies);
 
     Response::builder()
         .status(200)
@@ -1,11 +1,13 @@
 use axum::{
     Extension,
     extract::{Path, Request, State},
+    http::HeaderMap,
     middleware::Next,
     response::{IntoResponse, Redirect, Response},
 };
 use axum_extra::extract::CookieJar;
 use snafu::ensure;
+use tower_cookies::Cookies;
 
 use crate::{
     Error, Result,
@@ -14,20 +16,28 @@ use crate::{
     models::{MyEntryParams, MyVaultParams, OrgParams, Pref, UserParams, VaultParams},
     run::AppState,
     services::{
-        auth::authenticate_token, entries::get_entry, orgs::get_org, users::get_user,
+        auth::{authenticate_token_cached, refresh_auth},
+        entries::get_entry,
+        orgs::get_org,
+        users::get_user,
         vaults::get_vault,
     },
-    web::{Action, Resource, enforce_policy, handle_error},
+    web::{
+        Action, Resource, client_ip, enforce_policy, handle_error, remove_auth_cookies,
+        set_auth_cookies, user_agent,
+    },
 };
+use dto::entry::EntrySort;
+use dto::pagination::SortOrder;
 use dto::vault::VaultDto;
 
-use super::{AUTH_TOKEN_COOKIE, THEME_COOKIE};
+use super::{AUTH_TOKEN_COOKIE, ENTRY_SORT_COOKIE, REFRESH_TOKEN_COOKIE, THEME_COOKIE};
 
 /// Validates auth token but does not require its validity
 pub async fn auth_middleware(
     Extension(pref): Extension<Pref>,
     State(state): State<AppState>,
-    cookies: CookieJar,
+    cookies: Cookies,
     mut req: Request,
     next: Next,
 ) -> Response {
@@ -35,6 +45,9 @@ pub async fn auth_middleware(
     let token = cookies
         .get(AUTH_TOKEN_COOKIE)
         .map(|c| c.value().to_string());
+    let refresh_token = cookies
+        .get(REFRESH_TOKEN_COOKIE)
+        .map(|c| c.value().to_string());
 
     let full_page = req.headers().get("HX-Request").is_none();
 
@@ -43,7 +56,13 @@ pub async fn auth_middleware(
 
     if let Some(token) = token {
         // Validate token
-        let result = authenticate_token(&config.api_url, &token).await;
+        let result = authenticate_token_cached(
+            &config.api_url,
+            &state.jwks_cache,
+            &state.actor_cache,
+            &token,
+        )
+        .await;
 
         let _ = match result {
             Ok(actor) => {
@@ -59,10 +78,56 @@ pub async fn auth_middleware(
         };
     }
 
+    // Expired access tokens are replaced using the refresh token
+    if let (None, Some(refresh_token)) = (&ctx.value, refresh_token) {
+        let result = refresh_session(&state, &cookies, &refresh_token, req.headers()).await;
+
+        match result {
+            Ok(value) => {
+                ctx = Ctx::new(Some(value));
+            }
+            Err(Error::LoginRequired) => {
+                // Session ended, log in again
+                remove_auth_cookies(&cookies);
+            }
+            Err(err) => {
+                return handle_error(&state, None, &pref, ErrorInfo::from(&err), full_page);
+            }
+        }
+    }
+
     req.extensions_mut().insert(ctx);
     next.run(req).await
 }
 
+async fn refresh_session(
+    state: &AppState,
+    cookies: &Cookies,
+    refresh_token: &str,
+    headers: &HeaderMap,
+) -> Result<CtxValue> {
+    let config = state.config.clone();
+    let auth = refresh_auth(
+        &config.api_url,
+        &state.refresh_cache,
+        refresh_token,
+        client_ip(headers),
+        user_agent(headers),
+    )
+    .await?;
+
+    let actor = authenticate_token_cached(
+        &config.api_url,
+        &state.jwks_cache,
+        &state.actor_cache,
+        &auth.token,
+    )
+    .await?;
+    set_auth_cookies(cookies, &auth, config.ssl);
+
+    Ok(CtxValue::new(auth.token, actor))
+}
+
 pub async fn require_auth_middleware(
     Extension(ctx): Extension<Ctx>,
     req: Request,
@@ -201,6 +266,19 @@ pub async fn pref_middleware(cookies: CookieJar, mut req: Request, next: Next) -
         }
     }
 
+    // Stored as sort:order, e.g. label:asc
+    let entry_sort = cookies
+        .get(ENTRY_SORT_COOKIE)
+        .map(|c| c.value().to_string());
+    if let Some(entry_sort) = entry_sort
+        && let Some((sort, order)) = entry_sort.split_once(':')
+        && let Ok(sort) = EntrySort::try_from(sort)
+        && let Ok(order) = SortOrder::try_from(order)
+    {
+        pref.entry_sort = sort;
+        pref.entry_order = order;
+    }
+
     req.extensions_mut().insert(pref);
     next.run(req).await
 }
@@ -1,20 +1,28 @@
+pub mod audit;
 pub mod entries;
 pub mod error;
+pub mod groups;
 pub mod index;
 pub mod login;
 pub mod logout;
 pub mod middleware;
 pub mod my_vault;
 pub mod orgs;
+pub mod password_reset;
 pub mod policies;
 pub mod pref;
 pub mod profile;
+pub mod roles;
 pub mod routes;
+pub mod service_accounts;
+pub mod sessions;
 pub mod users;
 pub mod vaults;
 
 pub const AUTH_TOKEN_COOKIE: &str = "auth_token";
+pub const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
 pub const THEME_COOKIE: &str = "theme";
+pub const ENTRY_SORT_COOKIE: &str = "entry_sort";
 
 pub use error::*;
 pub use index::*;
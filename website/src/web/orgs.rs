@@ -4,8 +4,8 @@ use axum::{Extension, Form, body::Body, extract::State, response::Response};
 use snafu::{ResultExt, ensure};
 
 use crate::error::ForbiddenSnafu;
-use crate::models::tokens::TokenFormData;
-use crate::services::orgs::{create_org, delete_org, update_org};
+use crate::models::tokens::VersionedTokenFormData;
+use crate::services::orgs::{create_org, delete_org, get_org_usage, update_org};
 use crate::{
     Error, Result,
     ctx::Ctx,
@@ -19,6 +19,7 @@ use crate::{
     web::{Action, Resource, enforce_policy},
 };
 use dto::org::OrgDto;
+use dto::quota::OrgUsageDto;
 use dto::role::Permission;
 
 #[derive(Template)]
@@ -119,6 +120,7 @@ pub async fn new_org_handler(
         payload: OrgFormSubmitData {
             name: "".to_string(),
             token,
+            version: 0,
         },
         error_message: None,
     };
@@ -146,6 +148,7 @@ pub async fn post_new_org_handler(
         payload: OrgFormSubmitData {
             name: "".to_string(),
             token,
+            version: 0,
         },
         error_message: None,
     };
@@ -155,6 +158,7 @@ pub async fn post_new_org_handler(
     let payload = OrgFormSubmitData {
         name: payload.name.clone(),
         token: payload.token.clone(),
+        version: payload.version,
     };
 
     let token = ctx.token().expect("token is required");
@@ -191,6 +195,7 @@ pub async fn post_new_org_handler(
 struct OrgPageTemplate {
     t: TemplateData,
     org: OrgDto,
+    usage: OrgUsageDto,
     can_edit: bool,
     can_delete: bool,
     updated: bool,
@@ -207,9 +212,13 @@ pub async fn org_page_handler(
 
     t.title = format!("Org - {}", &org.name);
 
+    let token = ctx.token().expect("token is required");
+    let usage = get_org_usage(&state.config.api_url, token, &org.id).await?;
+
     let tpl = OrgPageTemplate {
         t,
         org,
+        usage,
         can_edit: actor.has_permissions(&vec![Permission::OrgsEdit]),
         can_delete: actor.has_permissions(&vec![Permission::OrgsDelete]),
         updated: false,
@@ -246,6 +255,7 @@ pub async fn edit_org_handler(
         payload: OrgFormSubmitData {
             name: org.name,
             token,
+            version: org.version,
         },
         error_message: None,
     };
@@ -274,6 +284,7 @@ pub async fn post_edit_org_handler(
         payload: OrgFormSubmitData {
             name: "".to_string(),
             token,
+            version: 0,
         },
         error_message: None,
     };
@@ -283,6 +294,7 @@ pub async fn post_edit_org_handler(
     let payload = OrgFormSubmitData {
         name: payload.name.clone(),
         token: payload.token.clone(),
+        version: payload.version,
     };
 
     let token = ctx.token().expect("token is required");
@@ -309,6 +321,7 @@ pub async fn post_edit_org_handler(
             tpl.error_message = Some(error_info.message);
 
             tpl.payload.name = payload.name.clone();
+            tpl.payload.version = payload.version;
 
             Ok(Response::builder()
                 .status(status)
@@ -352,7 +365,7 @@ pub async fn edit_org_controls_handler(
 #[template(path = "widgets/delete_org_form.html")]
 struct DeleteOrgFormTemplate {
     org: OrgDto,
-    payload: TokenFormData,
+    payload: VersionedTokenFormData,
     error_message: Option<String>,
 }
 
@@ -370,7 +383,10 @@ pub async fn delete_org_handler(
 
     let tpl = DeleteOrgFormTemplate {
         org: org.clone(),
-        payload: TokenFormData { token },
+        payload: VersionedTokenFormData {
+            token,
+            version: org.version,
+        },
         error_message: None,
     };
 
@@ -384,7 +400,7 @@ pub async fn post_delete_org_handler(
     Extension(ctx): Extension<Ctx>,
     Extension(org): Extension<OrgDto>,
     State(state): State<AppState>,
-    payload: Form<TokenFormData>,
+    payload: Form<VersionedTokenFormData>,
 ) -> Result<Response<Body>> {
     let config = state.config.clone();
     let actor = ctx.actor().expect("actor is required");
@@ -395,22 +411,26 @@ pub async fn post_delete_org_handler(
 
     let mut tpl = DeleteOrgFormTemplate {
         org: org.clone(),
-        payload: TokenFormData { token },
+        payload: VersionedTokenFormData {
+            token,
+            version: org.version,
+        },
         error_message: None,
     };
 
     let status: StatusCode;
 
     let token = ctx.token().expect("token is required");
-    let result = delete_org(&config, token, &org.id, &payload.token).await;
+    let result = delete_org(&config, token, &org.id, payload.version, &payload.token).await;
 
     match result {
         Ok(_) => {
             // Render same form but trigger a redirect to home
             let tpl = DeleteOrgFormTemplate {
                 org,
-                payload: TokenFormData {
+                payload: VersionedTokenFormData {
                     token: "".to_string(),
+                    version: 0,
                 },
                 error_message: None,
             };
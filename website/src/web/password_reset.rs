@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use askama::Template;
+use axum::{
+    body::Body,
+    extract::{Form, Query, State},
+    http::{Response, StatusCode},
+};
+use snafu::ResultExt;
+
+use crate::{
+    Result,
+    error::{ErrorInfo, ResponseBuilderSnafu, TemplateSnafu},
+    models::{Pref, TemplateData},
+    run::AppState,
+    services::auth::{PasswordResetFormData, redeem_password_reset},
+};
+
+#[derive(Template)]
+#[template(path = "pages/password_reset.html")]
+struct PasswordResetTemplate {
+    t: TemplateData,
+    token: String,
+    done: bool,
+    error_message: Option<String>,
+}
+
+/// Absolute link to the reset page on the configured public address
+///
+/// Never derived from the request, a forged `Host` would send the token to
+/// another site.
+pub(crate) fn password_reset_url(public_url: &str, token: &str) -> String {
+    format!(
+        "{}/password_reset?token={}",
+        public_url.trim_end_matches('/'),
+        urlencoding::encode(token)
+    )
+}
+
+fn render_password_reset(
+    state: &AppState,
+    status: StatusCode,
+    token: String,
+    done: bool,
+    error_message: Option<String>,
+) -> Result<Response<Body>> {
+    let pref = Pref::new();
+    let mut t = TemplateData::new(state, None, &pref);
+    t.title = String::from("Reset Password");
+
+    let tpl = PasswordResetTemplate {
+        t,
+        token,
+        done,
+        error_message,
+    };
+
+    // The token is in the URL, keep it out of caches and referrers
+    Response::builder()
+        .status(status)
+        .header("Cache-Control", "no-store")
+        .header("Referrer-Policy", "no-referrer")
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+pub async fn password_reset_handler(
+    State(state): State<AppState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Response<Body>> {
+    let token = query.get("token").cloned().unwrap_or_default();
+    let error_message = match token.is_empty() {
+        true => Some("Invalid or expired password reset link.".to_string()),
+        false => None,
+    };
+
+    render_password_reset(&state, StatusCode::OK, token, false, error_message)
+}
+
+pub async fn post_password_reset_handler(
+    State(state): State<AppState>,
+    Form(form): Form<PasswordResetFormData>,
+) -> Result<Response<Body>> {
+    let result = redeem_password_reset(&state.config.api_url, &form).await;
+
+    match result {
+        Ok(_) => render_password_reset(&state, StatusCode::OK, form.token, true, None),
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            render_password_reset(
+                &state,
+                error_info.status_code,
+                form.token,
+                false,
+                Some(error_info.message),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_reset_url() {
+        assert_eq!(
+            password_reset_url("https://vault.example.com", "abc.123"),
+            "https://vault.example.com/password_reset?token=abc.123"
+        );
+        assert_eq!(
+            password_reset_url("http://vault.example.com/", "a b"),
+            "http://vault.example.com/password_reset?token=a%20b"
+        );
+    }
+}
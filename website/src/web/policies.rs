@@ -9,6 +9,8 @@ pub enum Resource {
     User,
     Vault,
     Entry,
+    Audit,
+    Session,
 }
 
 pub enum Action {
@@ -24,6 +26,8 @@ pub fn enforce_policy(actor: &Actor, resource: Resource, action: Action) -> Resu
         Resource::Vault => enforce_vaults_permissions(actor, action),
         Resource::User => enforce_users_permissions(actor, action),
         Resource::Entry => enforce_entry_permissions(actor, action),
+        Resource::Audit => enforce_audit_permissions(actor, action),
+        Resource::Session => enforce_session_permissions(actor, action),
     };
 
     match result {
@@ -137,3 +141,38 @@ fn enforce_users_permissions(actor: &Actor, action: Action) -> StdResult<(), &st
     }
     Ok(())
 }
+
+fn enforce_audit_permissions(actor: &Actor, action: Action) -> StdResult<(), &str> {
+    let (permissions, message) = match action {
+        Action::Read => (
+            vec![Permission::AuditList, Permission::AuditView],
+            "You do not have permission to view the audit log.",
+        ),
+        _ => return Err("The audit log is read-only."),
+    };
+
+    if !actor.has_permissions(&permissions) {
+        return Err(message);
+    }
+    Ok(())
+}
+
+/// Sessions of other users, everyone manages their own
+fn enforce_session_permissions(actor: &Actor, action: Action) -> StdResult<(), &str> {
+    let (permissions, message) = match action {
+        Action::Read => (
+            vec![Permission::SessionsList],
+            "You do not have permission to view sessions.",
+        ),
+        Action::Delete => (
+            vec![Permission::SessionsRevoke],
+            "You do not have permission to revoke sessions.",
+        ),
+        _ => return Err("Sessions can only be viewed or revoked."),
+    };
+
+    if !actor.has_permissions(&permissions) {
+        return Err(message);
+    }
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use askama::Template;
+use axum::{Extension, Form, body::Body, extract::State, http::StatusCode, response::Response};
+use dto::org::OrgDto;
+use dto::role::{Permission, Role, RoleDto};
+use snafu::ResultExt;
+
+use crate::models::options::SelectOption;
+use crate::services::roles::{
+    DeleteRoleFormData, RoleFormData, UpdateRoleFormData, create_role, delete_role, list_roles,
+    update_role,
+};
+use crate::{
+    Result,
+    ctx::Ctx,
+    error::{ErrorInfo, ResponseBuilderSnafu, TemplateSnafu},
+    models::{Pref, TemplateData},
+    run::AppState,
+    services::token::create_csrf_token,
+    web::{Action, Resource, enforce_policy},
+};
+
+/// Role choices for users and groups, built-in roles first
+pub(crate) fn create_role_options(roles: &[RoleDto]) -> Vec<SelectOption> {
+    roles
+        .iter()
+        .map(|role| SelectOption {
+            value: role.id.clone(),
+            label: role.name.clone(),
+        })
+        .collect()
+}
+
+/// Name of the role, custom roles are otherwise only known by their id
+pub(crate) fn role_name(role: &Role, roles: &[RoleDto]) -> String {
+    let id = role.to_string();
+    roles
+        .iter()
+        .find(|x| x.id == id)
+        .map(|x| x.name.clone())
+        .unwrap_or(id)
+}
+
+#[derive(Template)]
+#[template(path = "pages/roles.html")]
+struct RolesPageTemplate {
+    t: TemplateData,
+    org: OrgDto,
+}
+
+pub async fn roles_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(pref): Extension<Pref>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Read)?;
+
+    let mut t = TemplateData::new(&state, Some(actor.clone()), &pref);
+    t.title = String::from("Roles");
+
+    let tpl = RolesPageTemplate { t, org };
+
+    Response::builder()
+        .status(200)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+#[derive(Template)]
+#[template(path = "widgets/roles.html")]
+struct RolesTemplate {
+    org: OrgDto,
+    roles: Vec<RoleDto>,
+    payload: RoleFormData,
+    permission_options: Vec<String>,
+    can_create: bool,
+    can_edit: bool,
+    can_delete: bool,
+    error_message: Option<String>,
+}
+
+/// Permissions as a javascript array for the checkboxes
+fn checked_list(permissions: &[String]) -> String {
+    let quoted: Vec<String> = permissions.iter().map(|p| format!("'{p}'")).collect();
+    format!("[{}]", quoted.join(","))
+}
+
+impl RolesTemplate {
+    fn role_checked(&self, role: &RoleDto) -> String {
+        let permissions: Vec<String> = role.permissions.iter().map(|p| p.to_string()).collect();
+        checked_list(&permissions)
+    }
+
+    fn payload_checked(&self) -> String {
+        let permissions: Vec<String> = self
+            .payload
+            .permissions
+            .split(",")
+            .filter(|p| !p.is_empty())
+            .map(|p| p.to_string())
+            .collect();
+        checked_list(&permissions)
+    }
+}
+
+/// Renders the built-in and custom roles of the org
+async fn render_roles(
+    ctx: &Ctx,
+    state: &AppState,
+    org: OrgDto,
+    payload: Option<RoleFormData>,
+    error_message: Option<String>,
+    status: StatusCode,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    let auth_token = ctx.token().expect("token is required");
+    let config = state.config.clone();
+
+    let roles = list_roles(&config.api_url, auth_token, &org.id).await?;
+
+    let token = create_csrf_token(&org.id, &config.jwt_secret)?;
+    let payload = match payload {
+        Some(payload) => RoleFormData { token, ..payload },
+        None => RoleFormData {
+            token,
+            ..Default::default()
+        },
+    };
+
+    let tpl = RolesTemplate {
+        org,
+        roles,
+        payload,
+        permission_options: Permission::assignable()
+            .iter()
+            .map(|p| p.to_string())
+            .collect(),
+        can_create: enforce_policy(actor, Resource::User, Action::Create).is_ok(),
+        can_edit: enforce_policy(actor, Resource::User, Action::Update).is_ok(),
+        can_delete: enforce_policy(actor, Resource::User, Action::Delete).is_ok(),
+        error_message,
+    };
+
+    Response::builder()
+        .status(status)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+pub async fn roles_widget_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Read)?;
+
+    render_roles(&ctx, &state, org, None, None, StatusCode::OK).await
+}
+
+pub async fn post_new_role_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<RoleFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Create)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = create_role(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_roles(&ctx, &state, org, None, None, StatusCode::OK).await,
+        Err(err) => {
+            // Keep the form values so the user can fix them
+            let error_info = ErrorInfo::from(&err);
+            let payload = Some(payload.0);
+            let error_message = Some(error_info.message);
+            render_roles(
+                &ctx,
+                &state,
+                org,
+                payload,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn post_update_role_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<UpdateRoleFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Update)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = update_role(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_roles(&ctx, &state, org, None, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_roles(
+                &ctx,
+                &state,
+                org,
+                None,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn post_delete_role_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<DeleteRoleFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Delete)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = delete_role(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_roles(&ctx, &state, org, None, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_roles(
+                &ctx,
+                &state,
+                org,
+                None,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
@@ -15,10 +15,16 @@ use crate::models::Pref;
 use crate::run::AppState;
 use crate::web::{error_handler, index_handler, login_handler, logout_handler, post_login_handler};
 
+use super::audit::{audit_page_handler, org_audit_page_handler};
 use super::entries::{
     edit_entry_controls_handler, edit_entry_handler, entry_page_handler, get_delete_entry_handler,
-    new_entry_handler, post_delete_entry_handler, post_edit_entry_handler, post_new_entry_handler,
-    search_entries_handler,
+    get_move_entry_handler, new_entry_handler, post_bulk_entries_handler,
+    post_delete_entry_handler, post_edit_entry_handler, post_favorite_entry_handler,
+    post_move_entry_handler, post_new_entry_handler, search_entries_handler,
+};
+use super::groups::{
+    groups_handler, groups_widget_handler, post_delete_group_handler,
+    post_delete_group_member_handler, post_group_member_handler, post_new_group_handler,
 };
 use super::middleware::{
     auth_middleware, entry_middleware, my_vault_middleware, org_middleware, pref_middleware,
@@ -30,19 +36,36 @@ use super::orgs::{
     org_page_handler, orgs_handler, orgs_listing_handler, post_delete_org_handler,
     post_edit_org_handler, post_new_org_handler,
 };
+use super::password_reset::{password_reset_handler, post_password_reset_handler};
 use super::profile::{
     change_user_password_handler, post_change_password_handler, profile_controls_handler,
     profile_page_handler,
 };
+use super::roles::{
+    post_delete_role_handler, post_new_role_handler, post_update_role_handler, roles_handler,
+    roles_widget_handler,
+};
+use super::service_accounts::{
+    post_new_api_key_handler, post_new_service_account_handler, post_revoke_api_key_handler,
+    service_accounts_handler, service_accounts_widget_handler,
+};
+use super::sessions::{
+    post_revoke_other_profile_sessions_handler, post_revoke_profile_session_handler,
+    post_revoke_user_session_handler, post_revoke_user_sessions_handler, profile_sessions_handler,
+    user_sessions_handler,
+};
 use super::users::{
     delete_user_handler, new_user_handler, post_delete_user_handler, post_new_user_handler,
-    post_reset_password_handler, post_update_user_role_handler, post_update_user_status_handler,
-    reset_user_password_handler, update_user_role_handler, update_user_status_handler,
-    user_controls_handler, user_page_handler, users_handler,
+    post_reset_link_handler, post_reset_password_handler, post_update_user_role_handler,
+    post_update_user_status_handler, reset_link_handler, reset_user_password_handler,
+    update_user_role_handler, update_user_status_handler, user_controls_handler, user_page_handler,
+    users_handler,
 };
 use super::vaults::{
-    delete_vault_handler, new_vault_handler, post_delete_vault_handler, post_new_vault_handler,
-    vault_controls_handler, vault_page_handler, vaults_handler,
+    delete_vault_handler, edit_vault_handler, new_vault_handler, post_delete_vault_group_handler,
+    post_delete_vault_handler, post_delete_vault_member_handler, post_edit_vault_handler,
+    post_new_vault_handler, post_vault_group_handler, post_vault_member_handler,
+    vault_controls_handler, vault_members_handler, vault_page_handler, vaults_handler,
 };
 use super::{dark_theme_handler, handle_error, light_theme_handler};
 
@@ -85,10 +108,20 @@ pub fn private_routes(state: AppState) -> Router {
         .route("/prefs/theme/dark", post(dark_theme_handler))
         .route("/profile", get(profile_page_handler))
         .route("/profile/profile_controls", get(profile_controls_handler))
+        .route("/profile/sessions", get(profile_sessions_handler))
+        .route(
+            "/profile/sessions/revoke",
+            post(post_revoke_profile_session_handler),
+        )
+        .route(
+            "/profile/sessions/revoke_others",
+            post(post_revoke_other_profile_sessions_handler),
+        )
         .route(
             "/profile/change_password",
             get(change_user_password_handler).post(post_change_password_handler),
         )
+        .route("/audit", get(audit_page_handler))
         .nest("/orgs", org_routes(state.clone()))
         .nest("/vaults/{vault_id}", my_vault_routes(state.clone()))
         .layer(middleware::map_response_with_state(
@@ -125,7 +158,11 @@ fn org_inner_routes(state: AppState) -> Router<AppState> {
             "/delete",
             get(delete_org_handler).post(post_delete_org_handler),
         )
+        .route("/audit", get(org_audit_page_handler))
         .nest("/users", users_routes(state.clone()))
+        .nest("/groups", groups_routes(state.clone()))
+        .nest("/roles", roles_routes(state.clone()))
+        .nest("/service_accounts", service_accounts_routes(state.clone()))
         .nest("/vaults", vaults_routes(state.clone()))
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
@@ -158,10 +195,20 @@ fn user_inner_routes(state: AppState) -> Router<AppState> {
             "/reset_password",
             get(reset_user_password_handler).post(post_reset_password_handler),
         )
+        .route(
+            "/reset_link",
+            get(reset_link_handler).post(post_reset_link_handler),
+        )
         .route(
             "/delete",
             get(delete_user_handler).post(post_delete_user_handler),
         )
+        .route("/sessions", get(user_sessions_handler))
+        .route("/sessions/revoke", post(post_revoke_user_session_handler))
+        .route(
+            "/sessions/revoke_all",
+            post(post_revoke_user_sessions_handler),
+        )
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             user_middleware,
@@ -169,6 +216,37 @@ fn user_inner_routes(state: AppState) -> Router<AppState> {
         .with_state(state)
 }
 
+fn groups_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(groups_handler).post(post_new_group_handler))
+        .route("/list", get(groups_widget_handler))
+        .route("/delete", post(post_delete_group_handler))
+        .route("/members", post(post_group_member_handler))
+        .route("/members/delete", post(post_delete_group_member_handler))
+        .with_state(state)
+}
+
+fn roles_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(roles_handler).post(post_new_role_handler))
+        .route("/list", get(roles_widget_handler))
+        .route("/update", post(post_update_role_handler))
+        .route("/delete", post(post_delete_role_handler))
+        .with_state(state)
+}
+
+fn service_accounts_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(service_accounts_handler).post(post_new_service_account_handler),
+        )
+        .route("/list", get(service_accounts_widget_handler))
+        .route("/keys", post(post_new_api_key_handler))
+        .route("/keys/revoke", post(post_revoke_api_key_handler))
+        .with_state(state)
+}
+
 fn vaults_routes(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", get(vaults_handler))
@@ -181,10 +259,21 @@ fn vault_inner_routes(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", get(vault_page_handler))
         .route("/edit_controls", get(vault_controls_handler))
+        .route(
+            "/edit",
+            get(edit_vault_handler).post(post_edit_vault_handler),
+        )
         .route(
             "/delete",
             get(delete_vault_handler).post(post_delete_vault_handler),
         )
+        .route(
+            "/members",
+            get(vault_members_handler).post(post_vault_member_handler),
+        )
+        .route("/members/delete", post(post_delete_vault_member_handler))
+        .route("/groups", post(post_vault_group_handler))
+        .route("/groups/delete", post(post_delete_vault_group_handler))
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             vault_middleware,
@@ -196,6 +285,7 @@ fn my_vault_routes(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", get(my_vault_page_handler))
         .route("/search_entries", get(search_entries_handler))
+        .route("/bulk_entries", post(post_bulk_entries_handler))
         .route(
             "/new_entry",
             get(new_entry_handler).post(post_new_entry_handler),
@@ -220,6 +310,11 @@ fn my_entry_inner_routes(state: AppState) -> Router<AppState> {
             "/delete",
             get(get_delete_entry_handler).post(post_delete_entry_handler),
         )
+        .route(
+            "/move",
+            get(get_move_entry_handler).post(post_move_entry_handler),
+        )
+        .route("/favorite", post(post_favorite_entry_handler))
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             entry_middleware,
@@ -230,6 +325,10 @@ fn my_entry_inner_routes(state: AppState) -> Router<AppState> {
 pub fn public_routes(state: AppState) -> Router {
     Router::new()
         .route("/login", get(login_handler).post(post_login_handler))
+        .route(
+            "/password_reset",
+            get(password_reset_handler).post(post_password_reset_handler),
+        )
         .route("/logout", post(logout_handler))
         .layer(middleware::map_response_with_state(
             state.clone(),
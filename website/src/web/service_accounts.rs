@@ -0,0 +1,289 @@
+use askama::Template;
+use axum::{Extension, Form, body::Body, extract::State, http::StatusCode, response::Response};
+use dto::api_key::{ApiKeyDto, NewApiKeyDto};
+use dto::org::OrgDto;
+use dto::role::{Permission, Role, RoleDto};
+use dto::user::UserDto;
+use dto::vault::VaultDto;
+use snafu::ResultExt;
+
+use crate::models::options::SelectOption;
+use crate::services::roles::list_roles;
+use crate::services::service_accounts::{
+    ApiKeyFormData, RevokeApiKeyFormData, ServiceAccountFormData, create_api_key,
+    create_service_account, list_api_keys, list_service_accounts, revoke_api_key,
+};
+use crate::services::vaults::list_vaults;
+use crate::web::roles::{create_role_options, role_name};
+use crate::{
+    Result,
+    ctx::Ctx,
+    error::{ErrorInfo, ResponseBuilderSnafu, TemplateSnafu},
+    models::{Pref, TemplateData},
+    run::AppState,
+    services::token::create_csrf_token,
+    web::{Action, Resource, enforce_policy},
+};
+
+#[derive(Template)]
+#[template(path = "pages/service_accounts.html")]
+struct ServiceAccountsPageTemplate {
+    t: TemplateData,
+    org: OrgDto,
+}
+
+pub async fn service_accounts_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(pref): Extension<Pref>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Read)?;
+
+    let mut t = TemplateData::new(&state, Some(actor.clone()), &pref);
+    t.title = String::from("Service Accounts");
+
+    let tpl = ServiceAccountsPageTemplate { t, org };
+
+    Response::builder()
+        .status(200)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+struct ApiKeyItem {
+    key: ApiKeyDto,
+
+    /// Vault the key is limited to, all granted vaults otherwise
+    scope: String,
+    read_only: bool,
+    status: &'static str,
+    expires: String,
+    last_used: String,
+}
+
+struct AccountItem {
+    account: UserDto,
+    keys: Vec<ApiKeyItem>,
+}
+
+pub(crate) fn format_time(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn create_key_item(key: ApiKeyDto, vaults: &[VaultDto], now: i64) -> ApiKeyItem {
+    let scope = match &key.vault_id {
+        Some(vault_id) => vaults
+            .iter()
+            .find(|x| &x.id == vault_id)
+            .map(|x| x.name.clone())
+            .unwrap_or_else(|| vault_id.clone()),
+        None => "All granted vaults".to_string(),
+    };
+    let status = if key.revoked_at.is_some() {
+        "Revoked"
+    } else if key.is_expired(now) {
+        "Expired"
+    } else {
+        "Active"
+    };
+    ApiKeyItem {
+        scope,
+        read_only: !key.permissions.contains(&Permission::EntriesCreate),
+        status,
+        expires: format_time(key.expires_at),
+        last_used: format_time(key.last_used_at),
+        key,
+    }
+}
+
+#[derive(Template)]
+#[template(path = "widgets/service_accounts.html")]
+struct ServiceAccountsTemplate {
+    org: OrgDto,
+    accounts: Vec<AccountItem>,
+    payload: ServiceAccountFormData,
+    roles: Vec<RoleDto>,
+    role_options: Vec<SelectOption>,
+    vaults: Vec<VaultDto>,
+
+    /// Shown only once, right after the key is issued
+    new_key: Option<NewApiKeyDto>,
+    can_create: bool,
+    can_edit: bool,
+    error_message: Option<String>,
+}
+
+impl ServiceAccountsTemplate {
+    fn role_name(&self, role: &Role) -> String {
+        role_name(role, &self.roles)
+    }
+}
+
+/// Renders the service accounts of the org with their keys
+async fn render_service_accounts(
+    ctx: &Ctx,
+    state: &AppState,
+    org: OrgDto,
+    payload: Option<ServiceAccountFormData>,
+    new_key: Option<NewApiKeyDto>,
+    error_message: Option<String>,
+    status: StatusCode,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    let auth_token = ctx.token().expect("token is required");
+    let config = state.config.clone();
+
+    let roles = list_roles(&config.api_url, auth_token, &org.id).await?;
+    let vaults = list_vaults(&config.api_url, auth_token, &org.id).await?;
+    let now = chrono::Utc::now().timestamp();
+    let mut accounts: Vec<AccountItem> = Vec::new();
+    for account in list_service_accounts(&config.api_url, auth_token, &org.id).await? {
+        let keys = list_api_keys(&config.api_url, auth_token, &org.id, &account.id).await?;
+        let keys = keys
+            .into_iter()
+            .map(|key| create_key_item(key, &vaults, now))
+            .collect();
+        accounts.push(AccountItem { account, keys });
+    }
+
+    let token = create_csrf_token(&org.id, &config.jwt_secret)?;
+    let payload = match payload {
+        Some(payload) => ServiceAccountFormData { token, ..payload },
+        None => ServiceAccountFormData {
+            token,
+            ..Default::default()
+        },
+    };
+
+    let tpl = ServiceAccountsTemplate {
+        org,
+        accounts,
+        payload,
+        role_options: create_role_options(&roles),
+        roles,
+        vaults,
+        new_key,
+        can_create: enforce_policy(actor, Resource::User, Action::Create).is_ok(),
+        can_edit: enforce_policy(actor, Resource::User, Action::Update).is_ok(),
+        error_message,
+    };
+
+    Response::builder()
+        .status(status)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+pub async fn service_accounts_widget_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Read)?;
+
+    render_service_accounts(&ctx, &state, org, None, None, None, StatusCode::OK).await
+}
+
+pub async fn post_new_service_account_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<ServiceAccountFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Create)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = create_service_account(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_service_accounts(&ctx, &state, org, None, None, None, StatusCode::OK).await,
+        Err(err) => {
+            // Keep the form values so the user can fix them
+            let error_info = ErrorInfo::from(&err);
+            let payload = Some(payload.0);
+            let error_message = Some(error_info.message);
+            render_service_accounts(
+                &ctx,
+                &state,
+                org,
+                payload,
+                None,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn post_new_api_key_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<ApiKeyFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Create)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = create_api_key(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(created) => {
+            let new_key = Some(created);
+            render_service_accounts(&ctx, &state, org, None, new_key, None, StatusCode::OK).await
+        }
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_service_accounts(
+                &ctx,
+                &state,
+                org,
+                None,
+                None,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn post_revoke_api_key_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    State(state): State<AppState>,
+    payload: Form<RevokeApiKeyFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::User, Action::Update)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = revoke_api_key(&state.config, auth_token, &org.id, &payload).await;
+
+    match result {
+        Ok(_) => render_service_accounts(&ctx, &state, org, None, None, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_service_accounts(
+                &ctx,
+                &state,
+                org,
+                None,
+                None,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
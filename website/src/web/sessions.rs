@@ -0,0 +1,255 @@
+use askama::Template;
+use axum::{Extension, Form, body::Body, extract::State, http::StatusCode, response::Response};
+use dto::org::OrgDto;
+use dto::session::SessionDto;
+use dto::user::UserDto;
+use snafu::ResultExt;
+
+use crate::services::sessions::{
+    RevokeSessionFormData, SessionOwner, list_sessions, revoke_other_sessions, revoke_session,
+};
+use crate::web::service_accounts::format_time;
+use crate::{
+    Result,
+    ctx::Ctx,
+    error::{ErrorInfo, ResponseBuilderSnafu, TemplateSnafu},
+    run::AppState,
+    services::token::create_csrf_token,
+    web::{Action, Resource, enforce_policy},
+};
+
+struct SessionItem {
+    session: SessionDto,
+    device: String,
+    created: String,
+    last_used: String,
+
+    /// The session the page is being viewed with
+    current: bool,
+}
+
+/// Short description of the browser and system from the user agent
+fn describe_device(user_agent: Option<&str>) -> String {
+    let Some(user_agent) = user_agent.filter(|x| !x.is_empty()) else {
+        return "Unknown device".to_string();
+    };
+
+    // Order matters, most browsers also claim to be Chrome or Safari
+    let browser = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+    ]
+    .iter()
+    .find(|(needle, _)| user_agent.contains(needle))
+    .map(|(_, name)| *name);
+
+    let system = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("CrOS", "ChromeOS"),
+        ("Linux", "Linux"),
+    ]
+    .iter()
+    .find(|(needle, _)| user_agent.contains(needle))
+    .map(|(_, name)| *name);
+
+    match (browser, system) {
+        (Some(browser), Some(system)) => format!("{} on {}", browser, system),
+        (Some(browser), None) => browser.to_string(),
+        (None, Some(system)) => system.to_string(),
+        (None, None) => "Unknown device".to_string(),
+    }
+}
+
+#[derive(Template)]
+#[template(path = "widgets/sessions.html")]
+struct SessionsTemplate {
+    path: String,
+    sessions: Vec<SessionItem>,
+    token: String,
+
+    /// Own sessions keep the current one when revoking the others
+    is_own: bool,
+    can_revoke: bool,
+    error_message: Option<String>,
+}
+
+/// Renders the active sessions of the owner
+async fn render_sessions(
+    ctx: &Ctx,
+    state: &AppState,
+    owner: SessionOwner,
+    error_message: Option<String>,
+    status: StatusCode,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    let auth_token = ctx.token().expect("token is required");
+    let config = state.config.clone();
+
+    let sessions = list_sessions(&config.api_url, auth_token, &owner).await?;
+    let sessions = sessions
+        .into_iter()
+        .map(|session| SessionItem {
+            device: describe_device(session.user_agent.as_deref()),
+            created: format_time(Some(session.created_at)),
+            last_used: format_time(Some(session.last_used_at)),
+            current: actor.session_id.as_ref() == Some(&session.id),
+            session,
+        })
+        .collect();
+
+    let is_own = matches!(owner, SessionOwner::Current { .. });
+    let can_revoke = is_own || enforce_policy(actor, Resource::Session, Action::Delete).is_ok();
+
+    let tpl = SessionsTemplate {
+        path: owner.path(),
+        sessions,
+        token: create_csrf_token(owner.csrf_subject(), &config.jwt_secret)?,
+        is_own,
+        can_revoke,
+        error_message,
+    };
+
+    Response::builder()
+        .status(status)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+/// Renders the sessions again with the error of the failed revocation
+async fn render_revoke_result(
+    ctx: &Ctx,
+    state: &AppState,
+    owner: SessionOwner,
+    result: Result<()>,
+) -> Result<Response<Body>> {
+    match result {
+        Ok(_) => render_sessions(ctx, state, owner, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_sessions(ctx, state, owner, error_message, error_info.status_code).await
+        }
+    }
+}
+
+fn current_owner(ctx: &Ctx) -> SessionOwner {
+    let actor = ctx.actor().expect("actor is required");
+    SessionOwner::Current {
+        user_id: actor.user.id.clone(),
+    }
+}
+
+fn org_user_owner(org: &OrgDto, user: &UserDto) -> SessionOwner {
+    SessionOwner::OrgUser {
+        org_id: org.id.clone(),
+        user_id: user.id.clone(),
+    }
+}
+
+pub async fn profile_sessions_handler(
+    Extension(ctx): Extension<Ctx>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let owner = current_owner(&ctx);
+    render_sessions(&ctx, &state, owner, None, StatusCode::OK).await
+}
+
+pub async fn post_revoke_profile_session_handler(
+    Extension(ctx): Extension<Ctx>,
+    State(state): State<AppState>,
+    payload: Form<RevokeSessionFormData>,
+) -> Result<Response<Body>> {
+    let auth_token = ctx.token().expect("token is required");
+    let owner = current_owner(&ctx);
+    let result = revoke_session(&state.config, auth_token, &owner, &payload).await;
+
+    render_revoke_result(&ctx, &state, owner, result).await
+}
+
+pub async fn post_revoke_other_profile_sessions_handler(
+    Extension(ctx): Extension<Ctx>,
+    State(state): State<AppState>,
+    payload: Form<RevokeSessionFormData>,
+) -> Result<Response<Body>> {
+    let auth_token = ctx.token().expect("token is required");
+    let owner = current_owner(&ctx);
+    let result = revoke_other_sessions(&state.config, auth_token, &owner, &payload).await;
+
+    render_revoke_result(&ctx, &state, owner, result).await
+}
+
+pub async fn user_sessions_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(user): Extension<UserDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Session, Action::Read)?;
+
+    let owner = org_user_owner(&org, &user);
+    render_sessions(&ctx, &state, owner, None, StatusCode::OK).await
+}
+
+pub async fn post_revoke_user_session_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(user): Extension<UserDto>,
+    State(state): State<AppState>,
+    payload: Form<RevokeSessionFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Session, Action::Delete)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let owner = org_user_owner(&org, &user);
+    let result = revoke_session(&state.config, auth_token, &owner, &payload).await;
+
+    render_revoke_result(&ctx, &state, owner, result).await
+}
+
+pub async fn post_revoke_user_sessions_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(user): Extension<UserDto>,
+    State(state): State<AppState>,
+    payload: Form<RevokeSessionFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Session, Action::Delete)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let owner = org_user_owner(&org, &user);
+    let result = revoke_other_sessions(&state.config, auth_token, &owner, &payload).await;
+
+    render_revoke_result(&ctx, &state, owner, result).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_device() {
+        let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:139.0) Gecko/20100101 Firefox/139.0";
+        assert_eq!(describe_device(Some(firefox)), "Firefox on Linux");
+
+        let edge = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/137.0.0.0 Safari/537.36 Edg/137.0.0.0";
+        assert_eq!(describe_device(Some(edge)), "Edge on Windows");
+
+        let safari = "Mozilla/5.0 (iPhone; CPU iPhone OS 18_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.5 Mobile/15E148 Safari/604.1";
+        assert_eq!(describe_device(Some(safari)), "Safari on iOS");
+
+        assert_eq!(describe_device(Some("curl/8.5.0")), "curl");
+        assert_eq!(describe_device(Some("")), "Unknown device");
+        assert_eq!(describe_device(None), "Unknown device");
+    }
+}
@@ -3,13 +3,17 @@ use axum::debug_handler;
 use axum::http::StatusCode;
 use axum::{Extension, Form, body::Body, extract::State, response::Response};
 use dto::org::OrgDto;
-use dto::role::Permission;
+use dto::role::{Permission, Role, RoleDto};
 use dto::user::UserDto;
 use snafu::ResultExt;
 
 use crate::models::options::SelectOption;
-use crate::models::tokens::TokenFormData;
+use crate::models::tokens::VersionedTokenFormData;
+use crate::services::roles::list_roles;
 use crate::services::users::delete_user;
+use crate::web::password_reset::password_reset_url;
+use crate::web::roles::{create_role_options, role_name};
+use crate::web::service_accounts::format_time;
 use crate::{
     Error, Result,
     ctx::Ctx,
@@ -19,8 +23,9 @@ use crate::{
     services::{
         token::create_csrf_token,
         users::{
-            NewUserFormData, ResetPasswordFormData, UserActiveFormData, UserRoleFormData,
-            create_user, list_users, reset_user_password, update_user_roles, update_user_status,
+            NewUserFormData, ResetLinkFormData, ResetPasswordFormData, UserActiveFormData,
+            UserRoleFormData, create_password_reset, create_user, list_users, reset_user_password,
+            update_user_roles, update_user_status,
         },
     },
     web::{Action, Resource, enforce_policy},
@@ -32,6 +37,13 @@ struct UsersPageTemplate {
     t: TemplateData,
     org: OrgDto,
     users: Vec<UserDto>,
+    roles: Vec<RoleDto>,
+}
+
+impl UsersPageTemplate {
+    fn role_name(&self, role: &Role) -> String {
+        role_name(role, &self.roles)
+    }
 }
 
 pub async fn users_handler(
@@ -48,8 +60,14 @@ pub async fn users_handler(
 
     let token = ctx.token().expect("token is required");
     let users = list_users(state.config.api_url.as_str(), token, org.id.as_str()).await?;
+    let roles = list_roles(state.config.api_url.as_str(), token, org.id.as_str()).await?;
 
-    let tpl = UsersPageTemplate { t, org, users };
+    let tpl = UsersPageTemplate {
+        t,
+        org,
+        users,
+        roles,
+    };
 
     Ok(Response::builder()
         .status(200)
@@ -78,23 +96,6 @@ struct NewUserFormTemplate {
     error_message: Option<String>,
 }
 
-fn create_role_options() -> Vec<SelectOption> {
-    vec![
-        SelectOption {
-            value: "Admin".to_string(),
-            label: "Admin".to_string(),
-        },
-        SelectOption {
-            value: "Editor".to_string(),
-            label: "Editor".to_string(),
-        },
-        SelectOption {
-            value: "Viewer".to_string(),
-            label: "Viewer".to_string(),
-        },
-    ]
-}
-
 pub async fn new_user_handler(
     Extension(ctx): Extension<Ctx>,
     Extension(pref): Extension<Pref>,
@@ -112,6 +113,9 @@ pub async fn new_user_handler(
     let token = create_csrf_token("new_user", &config.jwt_secret)?;
     let cid = org.id.clone();
 
+    let auth_token = ctx.token().expect("token is required");
+    let roles = list_roles(&config.api_url, auth_token, &cid).await?;
+
     let tpl = NewUserTemplate {
         t,
         org,
@@ -123,7 +127,7 @@ pub async fn new_user_handler(
             role: "".to_string(),
             token,
         },
-        role_options: create_role_options(),
+        role_options: create_role_options(&roles),
         error_message: None,
     };
 
@@ -147,6 +151,9 @@ pub async fn post_new_user_handler(
     let token = create_csrf_token("new_user", &config.jwt_secret)?;
     let cid = org.id.clone();
 
+    let auth_token = ctx.token().expect("token is required");
+    let roles = list_roles(&config.api_url, auth_token, &cid).await?;
+
     let mut tpl = NewUserFormTemplate {
         org,
         action: format!("/orgs/{}/users/new", cid.as_str()),
@@ -157,7 +164,7 @@ pub async fn post_new_user_handler(
             role: "".to_string(),
             token,
         },
-        role_options: create_role_options(),
+        role_options: create_role_options(&roles),
         error_message: None,
     };
 
@@ -207,9 +214,17 @@ struct UserPageTemplate {
     t: TemplateData,
     org: OrgDto,
     user: UserDto,
+    roles: Vec<RoleDto>,
     updated: bool,
     can_edit: bool,
     can_delete: bool,
+    can_view_sessions: bool,
+}
+
+impl UserPageTemplate {
+    fn role_name(&self, role: &Role) -> String {
+        role_name(role, &self.roles)
+    }
 }
 
 pub async fn user_page_handler(
@@ -224,13 +239,18 @@ pub async fn user_page_handler(
 
     t.title = format!("User - {}", &user.username);
 
+    let token = ctx.token().expect("token is required");
+    let roles = list_roles(&state.config.api_url, token, &org.id).await?;
+
     let tpl = UserPageTemplate {
         t,
         org,
         user,
+        roles,
         updated: false,
         can_edit: actor.has_permissions(&vec![Permission::UsersEdit]),
         can_delete: actor.has_permissions(&vec![Permission::UsersDelete]),
+        can_view_sessions: enforce_policy(actor, Resource::Session, Action::Read).is_ok(),
     };
 
     Ok(Response::builder()
@@ -244,23 +264,35 @@ pub async fn user_page_handler(
 struct UserControlsTemplate {
     org: OrgDto,
     user: UserDto,
+    roles: Vec<RoleDto>,
     updated: bool,
     can_edit: bool,
     can_delete: bool,
 }
 
+impl UserControlsTemplate {
+    fn role_name(&self, role: &Role) -> String {
+        role_name(role, &self.roles)
+    }
+}
+
 pub async fn user_controls_handler(
     Extension(ctx): Extension<Ctx>,
     Extension(org): Extension<OrgDto>,
     Extension(user): Extension<UserDto>,
+    State(state): State<AppState>,
 ) -> Result<Response<Body>> {
     let actor = ctx.actor().expect("actor is required");
 
     let _ = enforce_policy(actor, Resource::User, Action::Update)?;
 
+    let token = ctx.token().expect("token is required");
+    let roles = list_roles(&state.config.api_url, token, &org.id).await?;
+
     let tpl = UserControlsTemplate {
         org,
         user,
+        roles,
         updated: false,
         can_edit: actor.has_permissions(&vec![Permission::UsersEdit]),
         can_delete: actor.has_permissions(&vec![Permission::UsersDelete]),
@@ -354,9 +386,11 @@ pub async fn post_update_user_status_handler(
     match result {
         Ok(updated_user) => {
             // Render back the controls but when updated roles and status
+            let roles = list_roles(&config.api_url, token, &cid).await?;
             let tpl = UserControlsTemplate {
                 org,
                 user: updated_user,
+                roles,
                 updated: true,
                 can_edit: actor.has_permissions(&vec![Permission::UsersEdit]),
                 can_delete: actor.has_permissions(&vec![Permission::UsersDelete]),
@@ -418,6 +452,9 @@ pub async fn update_user_role_handler(
 
     let current_role = user.roles.first().unwrap().to_string();
 
+    let auth_token = ctx.token().expect("token is required");
+    let roles = list_roles(&config.api_url, auth_token, &org.id).await?;
+
     let tpl = UpdateUserRoleTemplate {
         org,
         user,
@@ -425,7 +462,7 @@ pub async fn update_user_role_handler(
             token,
             role: current_role,
         },
-        role_options: create_role_options(),
+        role_options: create_role_options(&roles),
         error_message: None,
     };
 
@@ -453,6 +490,9 @@ pub async fn post_update_user_role_handler(
     let cid = org.id.clone();
     let uid = user.id.clone();
 
+    let auth_token = ctx.token().expect("token is required");
+    let roles = list_roles(&config.api_url, auth_token, &cid).await?;
+
     let mut tpl = UpdateUserRoleTemplate {
         org: org.clone(),
         user,
@@ -460,7 +500,7 @@ pub async fn post_update_user_role_handler(
             token,
             role: payload.role.clone(),
         },
-        role_options: create_role_options(),
+        role_options: create_role_options(&roles),
         error_message: None,
     };
 
@@ -475,9 +515,11 @@ pub async fn post_update_user_role_handler(
     match result {
         Ok(updated_user) => {
             // Render back the controls but when updated roles and status
+            let roles = list_roles(&config.api_url, token, &cid).await?;
             let tpl = UserControlsTemplate {
                 org,
                 user: updated_user,
+                roles,
                 updated: true,
                 can_edit: actor.has_permissions(&vec![Permission::UsersEdit]),
                 can_delete: actor.has_permissions(&vec![Permission::UsersDelete]),
@@ -593,9 +635,11 @@ pub async fn post_reset_password_handler(
 
     match result {
         Ok(_) => {
+            let roles = list_roles(&config.api_url, token, &cid).await?;
             let tpl = UserControlsTemplate {
                 org,
                 user,
+                roles,
                 updated: false,
                 can_edit: actor.has_permissions(&vec![Permission::UsersEdit]),
                 can_delete: actor.has_permissions(&vec![Permission::UsersDelete]),
@@ -633,12 +677,99 @@ pub async fn post_reset_password_handler(
     }
 }
 
+#[derive(Template)]
+#[template(path = "widgets/reset_link_form.html")]
+struct ResetLinkTemplate {
+    org: OrgDto,
+    user: UserDto,
+    payload: ResetLinkFormData,
+
+    /// Shown only once, right after the link is issued
+    link: Option<String>,
+    expires: String,
+    error_message: Option<String>,
+}
+
+pub async fn reset_link_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(user): Extension<UserDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let config = state.config.clone();
+    let actor = ctx.actor().expect("actor is required");
+
+    enforce_policy(actor, Resource::User, Action::Update)?;
+    let token = create_csrf_token(&user.id, &config.jwt_secret)?;
+
+    let tpl = ResetLinkTemplate {
+        org,
+        user,
+        payload: ResetLinkFormData { token },
+        link: None,
+        expires: "".to_string(),
+        error_message: None,
+    };
+
+    Response::builder()
+        .status(200)
+        .header("Content-Type", "text/html")
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+pub async fn post_reset_link_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(user): Extension<UserDto>,
+    State(state): State<AppState>,
+    payload: Form<ResetLinkFormData>,
+) -> Result<Response<Body>> {
+    let config = state.config.clone();
+    let actor = ctx.actor().expect("actor is required");
+
+    enforce_policy(actor, Resource::User, Action::Update)?;
+
+    let token = ctx.token().expect("token is required");
+    let result = create_password_reset(&config, token, &org.id, &user.id, &payload).await;
+
+    let mut tpl = ResetLinkTemplate {
+        org,
+        user: user.clone(),
+        payload: ResetLinkFormData {
+            token: create_csrf_token(&user.id, &config.jwt_secret)?,
+        },
+        link: None,
+        expires: "".to_string(),
+        error_message: None,
+    };
+
+    let status = match result {
+        Ok(created) => {
+            tpl.link = Some(password_reset_url(&config.public_url, &created.token));
+            tpl.expires = format_time(Some(created.reset.expires_at));
+            StatusCode::OK
+        }
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            tpl.error_message = Some(error_info.message);
+            error_info.status_code
+        }
+    };
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "text/html")
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
 #[derive(Template)]
 #[template(path = "widgets/delete_user_form.html")]
 struct DeleteUserFormTemplate {
     org: OrgDto,
     user: UserDto,
-    payload: TokenFormData,
+    payload: VersionedTokenFormData,
     error_message: Option<String>,
 }
 
@@ -655,10 +786,11 @@ pub async fn delete_user_handler(
 
     let token = create_csrf_token(&user.id, &config.jwt_secret)?;
 
+    let version = user.version;
     let tpl = DeleteUserFormTemplate {
         org,
         user,
-        payload: TokenFormData { token },
+        payload: VersionedTokenFormData { token, version },
         error_message: None,
     };
 
@@ -673,7 +805,7 @@ pub async fn post_delete_user_handler(
     Extension(org): Extension<OrgDto>,
     Extension(user): Extension<UserDto>,
     State(state): State<AppState>,
-    payload: Form<TokenFormData>,
+    payload: Form<VersionedTokenFormData>,
 ) -> Result<Response<Body>> {
     let config = state.config.clone();
     let actor = ctx.actor().expect("actor is required");
@@ -685,12 +817,23 @@ pub async fn post_delete_user_handler(
     let mut tpl = DeleteUserFormTemplate {
         org: org.clone(),
         user: user.clone(),
-        payload: TokenFormData { token },
+        payload: VersionedTokenFormData {
+            token,
+            version: user.version,
+        },
         error_message: None,
     };
 
     let token = ctx.token().expect("token is required");
-    let result = delete_user(&config, token, &org.id, &user.id, &payload.token).await;
+    let result = delete_user(
+        &config,
+        token,
+        &org.id,
+        &user.id,
+        payload.version,
+        &payload.token,
+    )
+    .await;
 
     match result {
         Ok(_) => {
@@ -699,8 +842,9 @@ pub async fn post_delete_user_handler(
             let tpl = DeleteUserFormTemplate {
                 org,
                 user,
-                payload: TokenFormData {
+                payload: VersionedTokenFormData {
                     token: "".to_string(),
+                    version: 0,
                 },
                 error_message: None,
             };
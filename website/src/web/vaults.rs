@@ -1,14 +1,22 @@
 use askama::Template;
-use axum::{Extension, Form, body::Body, extract::State, response::Response};
+use axum::{Extension, Form, body::Body, extract::State, http::StatusCode, response::Response};
+use dto::group::GroupDto;
 use dto::org::OrgDto;
 use dto::role::Permission;
-use dto::vault::VaultDto;
+use dto::user::UserDto;
+use dto::vault::{VaultDto, VaultGroupDto, VaultMemberDto, VaultRole};
 use snafu::ResultExt;
 
-use crate::models::tokens::TokenFormData;
-use crate::services::vaults::{NewVaultFormData, create_vault, delete_vault, list_vaults};
+use crate::models::tokens::VersionedTokenFormData;
+use crate::services::groups::list_groups;
+use crate::services::users::list_users;
+use crate::services::vaults::{
+    NewVaultFormData, VaultFormSubmitData, VaultGroupFormData, VaultMemberFormData, create_vault,
+    delete_vault, delete_vault_group, delete_vault_member, list_vault_groups, list_vault_members,
+    list_vaults, save_vault_group, save_vault_member, update_vault,
+};
 use crate::{
-    Result,
+    Error, Result,
     ctx::Ctx,
     error::{ErrorInfo, ResponseBuilderSnafu, TemplateSnafu},
     models::{Pref, TemplateData},
@@ -163,6 +171,8 @@ struct VaultPageTemplate {
     t: TemplateData,
     org: OrgDto,
     vault: VaultDto,
+    updated: bool,
+    can_edit: bool,
     can_delete: bool,
 }
 
@@ -182,6 +192,8 @@ pub async fn vault_page_handler(
         t,
         org,
         vault,
+        updated: false,
+        can_edit: actor.has_permissions(&vec![Permission::VaultsEdit]),
         can_delete: actor.has_permissions(&vec![Permission::VaultsDelete]),
     };
 
@@ -196,6 +208,8 @@ pub async fn vault_page_handler(
 struct VaultControlsTemplate {
     org: OrgDto,
     vault: VaultDto,
+    updated: bool,
+    can_edit: bool,
     can_delete: bool,
 }
 
@@ -211,6 +225,8 @@ pub async fn vault_controls_handler(
     let tpl = VaultControlsTemplate {
         org,
         vault,
+        updated: false,
+        can_edit: actor.has_permissions(&vec![Permission::VaultsEdit]),
         can_delete: actor.has_permissions(&vec![Permission::VaultsDelete]),
     };
 
@@ -221,12 +237,108 @@ pub async fn vault_controls_handler(
         .context(ResponseBuilderSnafu)?)
 }
 
+#[derive(Template)]
+#[template(path = "widgets/edit_vault_form.html")]
+struct EditVaultFormTemplate {
+    org: OrgDto,
+    vault: VaultDto,
+    payload: VaultFormSubmitData,
+    error_message: Option<String>,
+}
+
+pub async fn edit_vault_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(vault): Extension<VaultDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let config = state.config.clone();
+    let actor = ctx.actor().expect("actor is required");
+
+    enforce_policy(actor, Resource::Vault, Action::Update)?;
+
+    let token = create_csrf_token(&vault.id, &config.jwt_secret)?;
+
+    let tpl = EditVaultFormTemplate {
+        org,
+        vault: vault.clone(),
+        payload: VaultFormSubmitData {
+            name: vault.name,
+            description: vault.description.unwrap_or_default(),
+            icon: vault.icon.unwrap_or_default(),
+            color: vault.color.unwrap_or_default(),
+            token,
+            version: vault.version,
+        },
+        error_message: None,
+    };
+
+    Response::builder()
+        .status(200)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+pub async fn post_edit_vault_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(vault): Extension<VaultDto>,
+    State(state): State<AppState>,
+    payload: Form<VaultFormSubmitData>,
+) -> Result<Response<Body>> {
+    let config = state.config.clone();
+    let actor = ctx.actor().expect("actor is required");
+
+    enforce_policy(actor, Resource::Vault, Action::Update)?;
+
+    let token = ctx.token().expect("token is required");
+    let result = update_vault(&config, token, &org.id, &vault.id, &payload).await;
+
+    match result {
+        Ok(updated_vault) => {
+            // Render the controls back together with the updated details
+            let tpl = VaultControlsTemplate {
+                org,
+                vault: updated_vault,
+                updated: true,
+                can_edit: actor.has_permissions(&vec![Permission::VaultsEdit]),
+                can_delete: actor.has_permissions(&vec![Permission::VaultsDelete]),
+            };
+
+            Ok(Response::builder()
+                .status(200)
+                .body(Body::from(tpl.render().context(TemplateSnafu)?))
+                .context(ResponseBuilderSnafu)?)
+        }
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let status: StatusCode = error_info.status_code;
+
+            // Keep the submitted values with a fresh token
+            let mut payload = payload.0;
+            payload.token = create_csrf_token(&vault.id, &config.jwt_secret)?;
+
+            let tpl = EditVaultFormTemplate {
+                org,
+                vault,
+                payload,
+                error_message: Some(error_info.message),
+            };
+
+            Ok(Response::builder()
+                .status(status)
+                .body(Body::from(tpl.render().context(TemplateSnafu)?))
+                .context(ResponseBuilderSnafu)?)
+        }
+    }
+}
+
 #[derive(Template)]
 #[template(path = "widgets/delete_vault_form.html")]
 struct DeleteVaultFormTemplate {
     org: OrgDto,
     vault: VaultDto,
-    payload: TokenFormData,
+    payload: VersionedTokenFormData,
     error_message: Option<String>,
 }
 
@@ -243,10 +355,11 @@ pub async fn delete_vault_handler(
 
     let token = create_csrf_token(&vault.id, &config.jwt_secret)?;
 
+    let version = vault.version;
     let tpl = DeleteVaultFormTemplate {
         org,
         vault,
-        payload: TokenFormData { token },
+        payload: VersionedTokenFormData { token, version },
         error_message: None,
     };
 
@@ -261,7 +374,7 @@ pub async fn post_delete_vault_handler(
     Extension(org): Extension<OrgDto>,
     Extension(vault): Extension<VaultDto>,
     State(state): State<AppState>,
-    payload: Form<TokenFormData>,
+    payload: Form<VersionedTokenFormData>,
 ) -> Result<Response<Body>> {
     let config = state.config.clone();
     let actor = ctx.actor().expect("actor is required");
@@ -273,12 +386,23 @@ pub async fn post_delete_vault_handler(
     let mut tpl = DeleteVaultFormTemplate {
         org: org.clone(),
         vault: vault.clone(),
-        payload: TokenFormData { token },
+        payload: VersionedTokenFormData {
+            token,
+            version: vault.version,
+        },
         error_message: None,
     };
 
     let token = ctx.token().expect("token is required");
-    let result = delete_vault(&config, token, &org.id, &vault.id, &payload.token).await;
+    let result = delete_vault(
+        &config,
+        token,
+        &org.id,
+        &vault.id,
+        payload.version,
+        &payload.token,
+    )
+    .await;
 
     match result {
         Ok(_) => {
@@ -287,8 +411,9 @@ pub async fn post_delete_vault_handler(
             let tpl = DeleteVaultFormTemplate {
                 org,
                 vault,
-                payload: TokenFormData {
+                payload: VersionedTokenFormData {
                     token: "".to_string(),
+                    version: 0,
                 },
                 error_message: None,
             };
@@ -309,3 +434,214 @@ pub async fn post_delete_vault_handler(
         }
     }
 }
+
+#[derive(Template)]
+#[template(path = "widgets/vault_members.html")]
+struct VaultMembersTemplate {
+    org: OrgDto,
+    vault: VaultDto,
+    members: Vec<VaultMemberDto>,
+
+    /// Org users not yet granted access to the vault
+    candidates: Vec<UserDto>,
+    groups: Vec<VaultGroupDto>,
+
+    /// Org groups not yet granted access to the vault
+    group_candidates: Vec<GroupDto>,
+    roles: Vec<VaultRole>,
+    token: String,
+    error_message: Option<String>,
+}
+
+/// Renders the members of the vault, nothing for users who cannot manage them
+async fn render_vault_members(
+    state: &AppState,
+    auth_token: &str,
+    org: OrgDto,
+    vault: VaultDto,
+    error_message: Option<String>,
+    status: StatusCode,
+) -> Result<Response<Body>> {
+    let config = state.config.clone();
+    let members = match list_vault_members(&config.api_url, auth_token, &org.id, &vault.id).await {
+        Ok(members) => members,
+        Err(Error::Forbidden { .. }) => {
+            return Response::builder()
+                .status(StatusCode::OK)
+                .body(Body::from("".to_string()))
+                .context(ResponseBuilderSnafu);
+        }
+        Err(err) => return Err(err),
+    };
+
+    // Vault managers may not be allowed to list the org users
+    let mut candidates = list_users(&config.api_url, auth_token, &org.id)
+        .await
+        .unwrap_or_default();
+    candidates.retain(|x| !members.iter().any(|m| m.user_id == x.id));
+
+    let groups = list_vault_groups(&config.api_url, auth_token, &org.id, &vault.id).await?;
+    let mut group_candidates = list_groups(&config.api_url, auth_token, &org.id)
+        .await
+        .unwrap_or_default();
+    group_candidates.retain(|x| !groups.iter().any(|g| g.group_id == x.id));
+
+    let token = create_csrf_token(&vault.id, &config.jwt_secret)?;
+    let tpl = VaultMembersTemplate {
+        org,
+        vault,
+        members,
+        candidates,
+        groups,
+        group_candidates,
+        roles: VaultRole::all(),
+        token,
+        error_message,
+    };
+
+    Response::builder()
+        .status(status)
+        .body(Body::from(tpl.render().context(TemplateSnafu)?))
+        .context(ResponseBuilderSnafu)
+}
+
+pub async fn vault_members_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(vault): Extension<VaultDto>,
+    State(state): State<AppState>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Vault, Action::Read)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    render_vault_members(&state, auth_token, org, vault, None, StatusCode::OK).await
+}
+
+/// Grants access or changes the role then renders the members again
+pub async fn post_vault_member_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(vault): Extension<VaultDto>,
+    State(state): State<AppState>,
+    payload: Form<VaultMemberFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Vault, Action::Read)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = save_vault_member(&state.config, auth_token, &org.id, &vault.id, &payload).await;
+
+    match result {
+        Ok(_) => render_vault_members(&state, auth_token, org, vault, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_vault_members(
+                &state,
+                auth_token,
+                org,
+                vault,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+/// Revokes the access then renders the members again
+pub async fn post_delete_vault_member_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(vault): Extension<VaultDto>,
+    State(state): State<AppState>,
+    payload: Form<VaultMemberFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Vault, Action::Read)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = delete_vault_member(&state.config, auth_token, &org.id, &vault.id, &payload).await;
+
+    match result {
+        Ok(_) => render_vault_members(&state, auth_token, org, vault, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_vault_members(
+                &state,
+                auth_token,
+                org,
+                vault,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+/// Grants access to a group or changes its role then renders the members again
+pub async fn post_vault_group_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(vault): Extension<VaultDto>,
+    State(state): State<AppState>,
+    payload: Form<VaultGroupFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Vault, Action::Read)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = save_vault_group(&state.config, auth_token, &org.id, &vault.id, &payload).await;
+
+    match result {
+        Ok(_) => render_vault_members(&state, auth_token, org, vault, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_vault_members(
+                &state,
+                auth_token,
+                org,
+                vault,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}
+
+/// Revokes the group access then renders the members again
+pub async fn post_delete_vault_group_handler(
+    Extension(ctx): Extension<Ctx>,
+    Extension(org): Extension<OrgDto>,
+    Extension(vault): Extension<VaultDto>,
+    State(state): State<AppState>,
+    payload: Form<VaultGroupFormData>,
+) -> Result<Response<Body>> {
+    let actor = ctx.actor().expect("actor is required");
+    enforce_policy(actor, Resource::Vault, Action::Read)?;
+
+    let auth_token = ctx.token().expect("token is required");
+    let result = delete_vault_group(&state.config, auth_token, &org.id, &vault.id, &payload).await;
+
+    match result {
+        Ok(_) => render_vault_members(&state, auth_token, org, vault, None, StatusCode::OK).await,
+        Err(err) => {
+            let error_info = ErrorInfo::from(&err);
+            let error_message = Some(error_info.message);
+            render_vault_members(
+                &state,
+                auth_token,
+                org,
+                vault,
+                error_message,
+                error_info.status_code,
+            )
+            .await
+        }
+    }
+}